@@ -1,24 +1,25 @@
 use std::io::{self, Read, Write};
 
 use anyhow::{Context, Result};
+use keychainpgp_core::backend::SecretKeyBackend;
+use keychainpgp_core::mime::{self, MimePart};
+use keychainpgp_core::types::MessageSessionKey;
 use keychainpgp_core::{CryptoEngine, SequoiaEngine};
 use keychainpgp_keys::Keyring;
 use secrecy::ExposeSecret;
 
-pub fn run(passphrase: Option<&str>) -> Result<()> {
+pub fn run(
+    passphrase: Option<&str>,
+    file: Option<&str>,
+    mime: bool,
+    show_session_key: bool,
+) -> Result<()> {
     let engine = SequoiaEngine::new();
-    let keyring = Keyring::open_default()?;
-
-    // Read ciphertext from stdin (limit to 64 MB to prevent memory exhaustion)
-    const MAX_INPUT: u64 = 64 * 1024 * 1024;
-    let mut ciphertext = Vec::new();
-    io::stdin()
-        .take(MAX_INPUT)
-        .read_to_end(&mut ciphertext)
-        .context("failed to read from stdin")?;
+    let keyring = super::open_unlocked_keyring()?;
+    let ciphertext = read_input(file)?;
 
-    if ciphertext.is_empty() {
-        anyhow::bail!("no input data (stdin was empty)");
+    if mime {
+        return run_mime(&engine, &keyring, &ciphertext, passphrase);
     }
 
     // Find own keys and try each
@@ -42,8 +43,15 @@ pub fn run(passphrase: Option<&str>) -> Result<()> {
             Err(_) => continue,
         };
 
-        match engine.decrypt(&ciphertext, secret_key.expose_secret(), passphrase_bytes) {
-            Ok(plaintext) => {
+        match engine.decrypt_revealing_session_key(
+            &ciphertext,
+            secret_key.expose_secret(),
+            passphrase_bytes,
+        ) {
+            Ok((plaintext, session_key)) => {
+                if show_session_key {
+                    eprintln!("Session key: {session_key}");
+                }
                 io::stdout()
                     .write_all(&plaintext)
                     .context("failed to write to stdout")?;
@@ -58,3 +66,194 @@ pub fn run(passphrase: Option<&str>) -> Result<()> {
         own_keys.len()
     )
 }
+
+/// Decrypt a message with a private key held by an agent. Every key in the
+/// keyring is offered, own keys first, since the agent may hold keys the
+/// keyring does not mark as own.
+pub fn run_with_agent(agent: &dyn SecretKeyBackend, file: Option<&str>) -> Result<()> {
+    let engine = SequoiaEngine::new();
+    let keyring = Keyring::open_default()?;
+    let ciphertext = read_input(file)?;
+
+    let mut keys = keyring.list_keys()?;
+    keys.sort_by_key(|k| !k.is_own_key);
+
+    let mut last_error = None;
+    for key_record in &keys {
+        match engine.decrypt_with_backend(&ciphertext, &key_record.pgp_data, agent) {
+            Ok(plaintext) => {
+                io::stdout()
+                    .write_all(&plaintext)
+                    .context("failed to write to stdout")?;
+                return Ok(());
+            }
+            // Stop at a wrong passphrase or when the agent cannot be reached
+            Err(
+                e @ (keychainpgp_core::Error::BadPassphrase | keychainpgp_core::Error::Internal(_)),
+            ) => return Err(e.into()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(anyhow::Error::new(e).context(
+            "decryption failed: the agent holds no key in the keyring that can decrypt this message",
+        )),
+        None => anyhow::bail!("the keyring is empty; import the public key of the agent's key first"),
+    }
+}
+
+/// Decrypt a message with a revealed session key; no keyring access needed.
+pub fn run_with_session_key(session_key: &str, file: Option<&str>) -> Result<()> {
+    let session_key: MessageSessionKey = session_key.parse()?;
+    let ciphertext = read_input(file)?;
+
+    let plaintext = SequoiaEngine::new()
+        .decrypt_with_session_key(&ciphertext, &session_key)
+        .context("decryption with the given session key failed")?;
+    io::stdout()
+        .write_all(&plaintext)
+        .context("failed to write to stdout")?;
+    Ok(())
+}
+
+/// Read ciphertext from the file or stdin (limit to 64 MB to prevent memory exhaustion).
+fn read_input(file: Option<&str>) -> Result<Vec<u8>> {
+    const MAX_INPUT: u64 = 64 * 1024 * 1024;
+    let mut ciphertext = Vec::new();
+    let source = match file {
+        Some(path) if path != "-" => {
+            std::fs::File::open(path)
+                .and_then(|f| f.take(MAX_INPUT).read_to_end(&mut ciphertext))
+                .with_context(|| format!("failed to read {path}"))?;
+            path
+        }
+        _ => {
+            io::stdin()
+                .take(MAX_INPUT)
+                .read_to_end(&mut ciphertext)
+                .context("failed to read from stdin")?;
+            "stdin"
+        }
+    };
+
+    if ciphertext.is_empty() {
+        anyhow::bail!("no input data ({source} was empty)");
+    }
+    Ok(ciphertext)
+}
+
+/// Decrypt and verify a PGP/MIME message, writing the decrypted MIME
+/// message to stdout and a summary to stderr.
+fn run_mime(
+    engine: &SequoiaEngine,
+    keyring: &Keyring,
+    raw: &[u8],
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let message = MimePart::parse(raw).context("failed to parse MIME message")?;
+    if !message.is_pgp_encrypted() && !message.is_pgp_signed() {
+        anyhow::bail!("not a PGP/MIME message (expected multipart/encrypted or multipart/signed)");
+    }
+
+    let keys = keyring.list_keys()?;
+    let signer_keys: Vec<Vec<u8>> = keys.iter().map(|k| k.pgp_data.clone()).collect();
+    let passphrase_bytes = passphrase.map(|p| p.as_bytes());
+
+    let opened = if message.is_pgp_encrypted() {
+        let own_keys: Vec<_> = keys.iter().filter(|k| k.is_own_key).collect();
+        if own_keys.is_empty() {
+            anyhow::bail!(
+                "no private keys found in keyring; generate one with 'keychainpgp generate' first"
+            );
+        }
+
+        let mut last_error = None;
+        let mut decrypted = None;
+        for key_record in &own_keys {
+            let secret_key = match keyring.get_secret_key(&key_record.fingerprint) {
+                Ok(sk) => sk,
+                Err(e) => {
+                    last_error = Some(anyhow::Error::new(e));
+                    continue;
+                }
+            };
+            match mime::decrypt_message(
+                engine,
+                &message,
+                secret_key.expose_secret(),
+                passphrase_bytes,
+                &signer_keys,
+            ) {
+                Ok(opened) => {
+                    decrypted = Some(opened);
+                    break;
+                }
+                // A wrong passphrase will not work for the other keys either
+                Err(e @ keychainpgp_core::Error::BadPassphrase) => return Err(e.into()),
+                Err(e) => last_error = Some(e.into()),
+            }
+        }
+
+        match decrypted {
+            Some(opened) => opened,
+            None => {
+                let context = format!(
+                    "decryption failed: none of the {} private key(s) in the keyring could decrypt this message",
+                    own_keys.len()
+                );
+                return Err(match last_error {
+                    Some(e) => e.context(context),
+                    None => anyhow::anyhow!(context),
+                });
+            }
+        }
+    } else {
+        mime::decrypt_message(engine, &message, &[], None, &signer_keys)?
+    };
+
+    if opened.encrypted {
+        eprintln!("Message was encrypted");
+    }
+    match &opened.signature {
+        Some(sig) if sig.valid => {
+            let fp = sig.signer_fingerprint.as_deref().unwrap_or_default();
+            let name = keys
+                .iter()
+                .find(|k| k.fingerprint.eq_ignore_ascii_case(fp))
+                .and_then(|k| k.name.as_deref())
+                .unwrap_or("(unknown)");
+            eprintln!("Good signature from {name}");
+            eprintln!("Fingerprint: {fp}");
+        }
+        Some(_) => eprintln!("BAD or unknown signature: no key in the keyring verifies it"),
+        None => eprintln!("Message is not signed"),
+    }
+    print_tree(&opened.body, 0);
+
+    io::stdout()
+        .write_all(&opened.to_message_bytes())
+        .context("failed to write to stdout")?;
+    Ok(())
+}
+
+/// Print the structure of a MIME tree to stderr.
+fn print_tree(part: &MimePart, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match part.filename() {
+        Some(name) => eprintln!(
+            "{indent}- {} \"{name}\" ({} bytes)",
+            part.content_type(),
+            part.body.len()
+        ),
+        None if part.is_multipart() => eprintln!("{indent}- {}", part.content_type()),
+        None => eprintln!(
+            "{indent}- {} ({} bytes)",
+            part.content_type(),
+            part.body.len()
+        ),
+    }
+    for child in &part.children {
+        print_tree(child, depth + 1);
+    }
+}
@@ -1,41 +1,79 @@
 use std::io::{self, Read, Write};
 
 use anyhow::{Context, Result};
+use keychainpgp_core::types::RecipientStatus;
 use keychainpgp_core::{CryptoEngine, SequoiaEngine};
 use keychainpgp_keys::Keyring;
+use keychainpgp_keys::groups;
+use keychainpgp_keys::storage::KeyRecord;
 
-pub fn run(recipient_fingerprints: &[String]) -> Result<()> {
+pub fn run(recipient_fingerprints: &[String], hidden_recipients: bool) -> Result<()> {
     let engine = SequoiaEngine::new();
+    engine.set_hidden_recipients(hidden_recipients);
     let keyring = Keyring::open_default()?;
 
-    // Look up recipient public keys (by fingerprint or name/email search)
-    let mut recipient_keys = Vec::new();
+    // Look up recipient public keys (by fingerprint, name/email search, or @group)
+    let mut recipients: Vec<KeyRecord> = Vec::new();
+    let mut add_recipient = |record: KeyRecord| {
+        if !recipients
+            .iter()
+            .any(|r| r.fingerprint == record.fingerprint)
+        {
+            recipients.push(record);
+        }
+    };
     for query in recipient_fingerprints {
-        // Try exact fingerprint first
-        if let Some(record) = keyring.get_key(query)? {
-            recipient_keys.push(record.pgp_data);
+        if let Some(group) = groups::group_reference(query) {
+            let expansion = keyring.expand_group(group)?;
+            for warning in &expansion.warnings {
+                eprintln!("Warning: skipping member of @{group}: {warning}");
+            }
+            if expansion.members.is_empty() {
+                anyhow::bail!("group @{group} has no usable members");
+            }
+            expansion.members.into_iter().for_each(&mut add_recipient);
             continue;
         }
 
-        // Fall back to search
-        let results = keyring.search_keys(query)?;
-        match results.len() {
-            0 => anyhow::bail!("no key found matching '{query}'"),
-            1 => recipient_keys.push(results.into_iter().next().unwrap().pgp_data),
-            n => {
-                eprintln!("Ambiguous recipient '{query}' matched {n} keys:");
-                for r in &results {
-                    let name = r.name.as_deref().unwrap_or("(no name)");
-                    let email = r
-                        .email
-                        .as_deref()
-                        .map(|e| format!(" <{e}>"))
-                        .unwrap_or_default();
-                    eprintln!("  {} {name}{email}", &r.fingerprint[..16]);
-                }
-                anyhow::bail!("specify a more precise recipient (use full fingerprint)");
-            }
+        add_recipient(super::resolve_key(&keyring, query)?);
+    }
+    let recipient_keys: Vec<Vec<u8>> = recipients.iter().map(|r| r.pgp_data.clone()).collect();
+
+    // Pre-flight: point at the exact recipients that cannot be used
+    let mut checks = engine.check_recipients(&recipient_keys);
+    for (check, record) in checks.iter_mut().zip(&recipients) {
+        // Revocations by a designated revoker are only trusted once the
+        // keyring checked them against the revoker's key
+        if record.is_revoked {
+            check.status = RecipientStatus::Revoked;
+        } else if check.status.is_usable()
+            && engine
+                .inspect_key(&record.pgp_data)
+                .is_ok_and(|info| info.possibly_revoked)
+        {
+            let fp = &record.fingerprint;
+            eprintln!(
+                "Warning: {} claims to be revoked by a designated revoker whose key is not in the keyring",
+                &fp[..fp.len().min(16)]
+            );
+        }
+    }
+    let unusable: Vec<_> = checks
+        .into_iter()
+        .filter(|check| !check.status.is_usable())
+        .collect();
+    if !unusable.is_empty() {
+        eprintln!("Cannot encrypt to these recipients:");
+        for check in &unusable {
+            let fp = check.fingerprint.as_deref().unwrap_or("(unknown key)");
+            let uid = check.user_id.as_deref().unwrap_or("(no user ID)");
+            eprintln!("  {} {uid}: {}", &fp[..fp.len().min(16)], check.status);
         }
+        anyhow::bail!(
+            "{} of {} recipient key(s) cannot be used",
+            unusable.len(),
+            recipient_keys.len()
+        );
     }
 
     // Read plaintext from stdin (limit to 64 MB to prevent memory exhaustion)
@@ -58,10 +96,12 @@ pub fn run(recipient_fingerprints: &[String]) -> Result<()> {
         .write_all(&ciphertext)
         .context("failed to write to stdout")?;
 
-    eprintln!(
-        "Encrypted for {} recipient(s).",
-        recipient_fingerprints.len()
-    );
+    eprintln!("Encrypted for {} recipient(s).", recipient_keys.len());
+
+    // Usage tracking is best effort and never fails the encryption
+    for record in &recipients {
+        let _ = keyring.record_key_use(&record.fingerprint);
+    }
 
     Ok(())
 }
@@ -1,37 +1,71 @@
-use anyhow::Result;
-use keychainpgp_core::types::{KeyGenOptions, UserId};
+use anyhow::{Context, Result};
+use keychainpgp_core::mnemonic::generate_mnemonic;
+use keychainpgp_core::types::{Argon2Params, KeyGenOptions, S2kMode, UserId};
 use keychainpgp_core::{CryptoEngine, SequoiaEngine};
-use keychainpgp_keys::Keyring;
 use keychainpgp_keys::storage::KeyRecord;
 use secrecy::{ExposeSecret, SecretBox};
 
-pub fn run(name: &str, email: &str, passphrase: Option<&str>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    name: &str,
+    email: &str,
+    passphrase: Option<&str>,
+    auth_subkey: bool,
+    argon2: bool,
+    adsks: &[String],
+    revokers: &[String],
+    mnemonic: bool,
+) -> Result<()> {
     let engine = SequoiaEngine::new();
+    let keyring = super::open_unlocked_keyring()?;
     let user_id = UserId::new(name, email);
-    let mut options = KeyGenOptions::new(user_id);
+    let mut options = KeyGenOptions::new(user_id).with_auth_subkey(auth_subkey);
+
+    if argon2 {
+        options = options.with_s2k(S2kMode::Argon2(Argon2Params::default()));
+    }
 
     if let Some(pass) = passphrase {
         options = options.with_passphrase(SecretBox::new(Box::new(pass.as_bytes().to_vec())));
     }
 
+    let phrase = mnemonic.then(generate_mnemonic);
+    if let Some(phrase) = &phrase {
+        options = options.with_mnemonic(SecretBox::new(Box::new(phrase.to_string())));
+    }
+
+    for fingerprint in adsks {
+        let adsk = keyring
+            .get_key(fingerprint)?
+            .with_context(|| format!("ADSK key not found: {fingerprint}"))?;
+        options = options.with_adsk(adsk.pgp_data);
+    }
+
+    for fingerprint in revokers {
+        let revoker = keyring
+            .get_key(fingerprint)?
+            .with_context(|| format!("designated revoker key not found: {fingerprint}"))?;
+        options = options.with_revoker(revoker.pgp_data);
+    }
+
     eprintln!("Generating key pair for {name} <{email}>...");
     let key_pair = engine.generate_key_pair(options)?;
 
     // Extract proper metadata from the generated key
     let info = engine.inspect_key(&key_pair.public_key)?;
 
-    let keyring = Keyring::open_default()?;
     let record = KeyRecord {
         fingerprint: key_pair.fingerprint.0.clone(),
         name: Some(name.to_string()),
         email: Some(email.to_string()),
         algorithm: info.algorithm.to_string(),
-        created_at: info.created_at,
-        expires_at: info.expires_at,
+        created_at: info.created_at.clone(),
+        expires_at: info.expires_at.clone(),
         trust_level: 2,
         is_own_key: true,
         is_revoked: info.is_revoked,
         pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
     };
 
     keyring.store_generated_key(record, key_pair.secret_key.expose_secret())?;
@@ -48,6 +82,30 @@ pub fn run(name: &str, email: &str, passphrase: Option<&str>) -> Result<()> {
     eprintln!("Key generated successfully!");
     eprintln!("Fingerprint: {}", key_pair.fingerprint);
     eprintln!("Algorithm:   {}", info.algorithm);
+    for adsk in info.adsks() {
+        eprintln!("ADSK:        {adsk}");
+    }
+    for revoker in &info.designated_revokers {
+        eprintln!("Revoker:     {revoker}");
+    }
+
+    if let Some(phrase) = &phrase {
+        let created = chrono::DateTime::parse_from_rfc3339(&info.created_at)
+            .map(|t| t.timestamp().to_string())
+            .unwrap_or_else(|_| info.created_at.clone());
+        eprintln!();
+        eprintln!("Recovery phrase (write it down and keep it offline; it is not shown again):");
+        let words: Vec<&str> = phrase.split(' ').collect();
+        for (row, chunk) in words.chunks(6).enumerate() {
+            let line: Vec<String> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, word)| format!("{:>2}. {word:<10}", row * 6 + i + 1))
+                .collect();
+            eprintln!("  {}", line.join(" ").trim_end());
+        }
+        eprintln!("Created:     {created} (needed with the phrase to restore the key)");
+    }
 
     Ok(())
 }
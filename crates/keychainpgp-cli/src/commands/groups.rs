@@ -0,0 +1,107 @@
+use anyhow::Result;
+use keychainpgp_keys::Keyring;
+
+/// Group names may be given with or without the `@` used by `encrypt -r`.
+fn group_name(name: &str) -> &str {
+    name.strip_prefix('@').unwrap_or(name)
+}
+
+fn resolve_members(keyring: &Keyring, members: &[String]) -> Result<Vec<String>> {
+    members
+        .iter()
+        .map(|query| Ok(super::resolve_key(keyring, query)?.fingerprint))
+        .collect()
+}
+
+pub fn list() -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let groups = keyring.list_groups()?;
+
+    if groups.is_empty() {
+        eprintln!("No recipient groups. Create one with 'keychainpgp groups create'.");
+        return Ok(());
+    }
+
+    for group in &groups {
+        let count = group.members.len();
+        let noun = if count == 1 { "member" } else { "members" };
+        println!("@{}  ({count} {noun})", group.name);
+    }
+
+    Ok(())
+}
+
+pub fn show(name: &str) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let name = group_name(name);
+    let expansion = keyring.expand_group(name)?;
+
+    for key in &expansion.members {
+        let user_id = key.name.as_deref().unwrap_or("(no name)");
+        let email = key
+            .email
+            .as_deref()
+            .map(|e| format!(" <{e}>"))
+            .unwrap_or_default();
+        println!("{}  {user_id}{email}", key.fingerprint);
+    }
+    for warning in &expansion.warnings {
+        eprintln!("Warning: {warning}");
+    }
+
+    Ok(())
+}
+
+pub fn create(name: &str, members: &[String]) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let name = group_name(name);
+    let members = resolve_members(&keyring, members)?;
+    keyring.create_group(name, &members)?;
+    eprintln!("Group @{name} created with {} member(s).", members.len());
+    Ok(())
+}
+
+pub fn add(name: &str, members: &[String]) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let name = group_name(name);
+    let members = resolve_members(&keyring, members)?;
+    keyring.add_group_members(name, &members)?;
+    eprintln!("Added {} member(s) to @{name}.", members.len());
+    Ok(())
+}
+
+pub fn remove(name: &str, members: &[String]) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let name = group_name(name);
+    // Members whose key is gone can only be named by fingerprint
+    let members = members
+        .iter()
+        .map(|query| match super::resolve_key(&keyring, query) {
+            Ok(record) => Ok(record.fingerprint),
+            Err(_) if query.len() >= 40 => Ok(query.to_uppercase()),
+            Err(e) => Err(e),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    keyring.remove_group_members(name, &members)?;
+    eprintln!("Removed {} member(s) from @{name}.", members.len());
+    Ok(())
+}
+
+pub fn rename(name: &str, new_name: &str) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let (name, new_name) = (group_name(name), group_name(new_name));
+    keyring.rename_group(name, new_name)?;
+    eprintln!("Group @{name} renamed to @{new_name}.");
+    Ok(())
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    let name = group_name(name);
+    if keyring.delete_group(name)? {
+        eprintln!("Group @{name} deleted.");
+    } else {
+        eprintln!("Group not found: @{name}");
+    }
+    Ok(())
+}
@@ -40,6 +40,29 @@ pub fn run(file: &str) -> Result<()> {
         println!("Expires:     never");
     }
 
+    if info.primary_key_offline {
+        println!("Primary key: offline (secret subkeys only)");
+    }
+    if let Some(s2k) = info.s2k {
+        println!("Protection:  {s2k}");
+    }
+    for subkey in &info.subkeys {
+        if let Some(s2k) = subkey.s2k {
+            println!("Subkey:      {} ({s2k})", &subkey.fingerprint);
+        }
+    }
+    for adsk in info.adsks() {
+        println!("ADSK:        {adsk}");
+    }
+    for revoker in &info.designated_revokers {
+        println!("Revoker:     {revoker}");
+    }
+    if info.is_revoked {
+        println!("Revoked:     yes");
+    } else if info.possibly_revoked {
+        println!("Revoked:     possibly (by a designated revoker, not checked)");
+    }
+
     if info.user_ids.is_empty() {
         println!("User IDs:    (none)");
     } else {
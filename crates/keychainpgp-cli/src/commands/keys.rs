@@ -1,9 +1,17 @@
+use std::io::{IsTerminal, Read, Write};
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use keychainpgp_core::mnemonic;
+use keychainpgp_core::paperkey::PaperKey;
+use keychainpgp_core::shamir::{self, Share};
+use keychainpgp_core::types::{Argon2Params, CertInfo, KeyGenOptions, S2kMode, UserId};
 use keychainpgp_core::{CryptoEngine, SequoiaEngine};
-use keychainpgp_keys::Keyring;
 use keychainpgp_keys::storage::KeyRecord;
+use keychainpgp_keys::{AuditQuery, ImportSource, Keyring, TrashedKey, sync};
+use secrecy::{ExposeSecret, SecretBox};
+use zeroize::Zeroizing;
 
 pub fn list() -> Result<()> {
     let keyring = Keyring::open_default()?;
@@ -15,7 +23,7 @@ pub fn list() -> Result<()> {
     }
 
     for key in &keys {
-        print_key_summary(key);
+        print_key_summary(&keyring, key);
         println!();
     }
 
@@ -41,21 +49,13 @@ pub fn import(file: &Path) -> Result<()> {
     };
 
     if info.has_secret_key {
-        let keyring = Keyring::open_default()?;
-        let record = KeyRecord {
-            fingerprint: info.fingerprint.0.clone(),
-            name,
-            email,
-            algorithm: info.algorithm.to_string(),
-            created_at: info.created_at.clone(),
-            expires_at: info.expires_at.clone(),
-            trust_level: 2, // own key = verified
-            is_own_key: true,
-            is_revoked: info.is_revoked,
-            pgp_data: data.clone(),
-        };
-        keyring.store_generated_key(record, &data)?;
+        let keyring = super::open_unlocked_keyring()?;
+        let record = own_key_record(&info, engine.public_key(&data)?);
+        keyring.import_secret_key(record, &data, ImportSource::File)?;
         eprintln!("Secret key imported: {display}");
+        if info.primary_key_offline {
+            eprintln!("Primary key offline: only the subkeys can be used on this device.");
+        }
     } else {
         let keyring = Keyring::open_default()?;
         let record = KeyRecord {
@@ -69,8 +69,9 @@ pub fn import(file: &Path) -> Result<()> {
             is_own_key: false,
             is_revoked: info.is_revoked,
             pgp_data: data,
+            metadata: Default::default(),
         };
-        keyring.import_public_key(record)?;
+        keyring.import_public_key(record, ImportSource::File)?;
         eprintln!("Public key imported: {display}");
     }
 
@@ -78,45 +79,711 @@ pub fn import(file: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn export(fingerprint: &str) -> Result<()> {
+pub fn export(fingerprint: &str, ssh: bool, secret_subkeys: bool) -> Result<()> {
     let keyring = Keyring::open_default()?;
     let record = keyring
         .get_key(fingerprint)?
         .with_context(|| format!("key not found: {fingerprint}"))?;
 
+    if secret_subkeys {
+        super::unlock_keyring(&keyring)?;
+        let secret_key = keyring
+            .get_secret_key(fingerprint)
+            .with_context(|| format!("no secret key found for {fingerprint}"))?;
+        let exported = SequoiaEngine::new()
+            .export_secret_subkeys(secret_key.expose_secret())
+            .context("failed to export secret subkeys")?;
+        keyring.record_secret_export(fingerprint, "secret subkeys")?;
+        print!("{}", String::from_utf8_lossy(&exported));
+        eprintln!("Primary secret key left out; keep the full key offline.");
+        return Ok(());
+    }
+
+    if ssh {
+        let line = keychainpgp_core::ssh::export_ssh_public_key(&record.pgp_data)
+            .context("failed to export SSH public key")?;
+        println!("{line}");
+        return Ok(());
+    }
+
     print!("{}", String::from_utf8_lossy(&record.pgp_data));
     Ok(())
 }
 
+pub fn paper_export(fingerprint: &str, qr_dir: Option<&Path>) -> Result<()> {
+    let keyring = super::open_unlocked_keyring()?;
+    let secret_key = keyring
+        .get_secret_key(fingerprint)
+        .with_context(|| format!("no secret key found for {fingerprint}"))?;
+
+    let paper = PaperKey::from_secret_key(secret_key.expose_secret())
+        .context("failed to extract secret key material")?;
+    keyring.record_secret_export(fingerprint, "paper key")?;
+    print!("{}", paper.to_text());
+
+    if let Some(dir) = qr_dir {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let parts = sync::split_for_qr(paper.as_bytes());
+        for (i, part) in parts.iter().enumerate() {
+            let path = dir.join(format!("paperkey-{:02}-of-{:02}.svg", i + 1, parts.len()));
+            write_private_file(&path, qr_svg(part)?.as_bytes())?;
+        }
+        eprintln!("Wrote {} QR code(s) to {}", parts.len(), dir.display());
+    }
+
+    eprintln!("Keep this backup offline. Anyone with it and your passphrase can use your key.");
+    Ok(())
+}
+
+pub fn paper_restore(file: &str, public_key: Option<&Path>, qr: bool) -> Result<()> {
+    let input = if file == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("failed to read from stdin")?;
+        buf
+    } else {
+        std::fs::read_to_string(file).with_context(|| format!("failed to read {file}"))?
+    };
+
+    let paper = if qr {
+        let parts: Vec<String> = input
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+        let data = sync::reassemble_from_qr(&parts)
+            .map_err(|e| anyhow::anyhow!("failed to reassemble QR codes: {e}"))?;
+        PaperKey::from_bytes(&data)?
+    } else {
+        PaperKey::parse_text(&input)?
+    };
+
+    let public = match public_key {
+        Some(path) => {
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?
+        }
+        None => {
+            let keyring = Keyring::open_default()?;
+            keyring
+                .get_key(paper.fingerprint())?
+                .with_context(|| {
+                    format!(
+                        "public key {} not found in keyring; pass it with --public-key",
+                        paper.fingerprint()
+                    )
+                })?
+                .pgp_data
+        }
+    };
+
+    let secret_key = paper
+        .restore(&public)
+        .context("failed to restore secret key")?;
+    print!("{}", String::from_utf8_lossy(&secret_key));
+    eprintln!("Secret key restored. Import it with 'keychainpgp keys import <file>'.");
+    Ok(())
+}
+
+pub fn split(fingerprint: &str, threshold: u8, count: u8, out_dir: &Path, qr: bool) -> Result<()> {
+    let keyring = super::open_unlocked_keyring()?;
+    let secret_key = keyring
+        .get_secret_key(fingerprint)
+        .with_context(|| format!("no secret key found for {fingerprint}"))?;
+
+    let shares = shamir::split_secret_key(secret_key.expose_secret(), threshold, count)?;
+    keyring.record_secret_export(fingerprint, "recovery shares")?;
+
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("failed to create {}", out_dir.display()))?;
+    for share in &shares {
+        let stem = format!("share-{:02}-of-{:02}", share.index(), count);
+        let path = out_dir.join(format!("{stem}.txt"));
+        write_private_file(&path, share.to_text().as_bytes())?;
+
+        if qr {
+            let parts = sync::split_for_qr(&share.to_bytes());
+            for (i, part) in parts.iter().enumerate() {
+                let path = out_dir.join(format!("{stem}-qr-{:02}.svg", i + 1));
+                write_private_file(&path, qr_svg(part)?.as_bytes())?;
+            }
+        }
+    }
+
+    eprintln!(
+        "Wrote {count} shares to {}; any {threshold} of them recover the key.",
+        out_dir.display()
+    );
+    eprintln!("Give each share to a different person and delete your copies.");
+    Ok(())
+}
+
+pub fn recover(files: &[std::path::PathBuf]) -> Result<()> {
+    let shares = files
+        .iter()
+        .map(|file| {
+            let text = std::fs::read_to_string(file)
+                .with_context(|| format!("failed to read {}", file.display()))?;
+            read_share(&text).with_context(|| format!("invalid share in {}", file.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let secret_key = shamir::recover_secret_key(&shares).context("failed to recover key")?;
+
+    let engine = SequoiaEngine::new();
+    let info = engine.inspect_key(&secret_key)?;
+    let keyring = super::open_unlocked_keyring()?;
+    let record = own_key_record(&info, engine.public_key(&secret_key)?);
+    keyring.import_secret_key(record, &secret_key, ImportSource::Backup)?;
+
+    eprintln!(
+        "Key recovered from {} shares: {}",
+        shares.len(),
+        info.name().unwrap_or(&info.fingerprint.0)
+    );
+    eprintln!("Fingerprint: {}", info.fingerprint);
+    Ok(())
+}
+
+/// Read a recovery phrase: prompt for it on a terminal, otherwise read stdin.
+pub fn read_mnemonic() -> Result<Zeroizing<String>> {
+    let phrase = if std::io::stdin().is_terminal() {
+        Zeroizing::new(rpassword::prompt_password("Recovery phrase: ")?)
+    } else {
+        let mut buf = Zeroizing::new(String::new());
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("failed to read from stdin")?;
+        buf
+    };
+    Ok(mnemonic::normalize_mnemonic(&phrase)?)
+}
+
+pub fn restore_mnemonic(
+    name: &str,
+    email: &str,
+    created: &str,
+    phrase: &str,
+    passphrase: Option<&str>,
+    auth_subkey: bool,
+    argon2: bool,
+) -> Result<()> {
+    let created = parse_creation_time(created)?;
+    let mut options = KeyGenOptions::new(UserId::new(name, email))
+        .with_mnemonic(SecretBox::new(Box::new(phrase.to_string())))
+        .with_creation_time(created)
+        .with_auth_subkey(auth_subkey);
+    if argon2 {
+        options = options.with_s2k(S2kMode::Argon2(Argon2Params::default()));
+    }
+    if let Some(pass) = passphrase {
+        options = options.with_passphrase(SecretBox::new(Box::new(pass.as_bytes().to_vec())));
+    }
+
+    let engine = SequoiaEngine::new();
+    let key_pair = engine.generate_key_pair(options)?;
+    let info = engine.inspect_key(&key_pair.public_key)?;
+    let keyring = super::open_unlocked_keyring()?;
+    keyring.store_generated_key(
+        own_key_record(&info, key_pair.public_key.clone()),
+        key_pair.secret_key.expose_secret(),
+    )?;
+    if let Err(e) =
+        keyring.store_revocation_cert(&key_pair.fingerprint.0, &key_pair.revocation_cert)
+    {
+        eprintln!("Warning: failed to store revocation certificate: {e}");
+    }
+
+    eprintln!("Key restored from recovery phrase: {name} <{email}>");
+    eprintln!("Fingerprint: {}", info.fingerprint);
+    Ok(())
+}
+
+/// Parse a key creation time given as Unix seconds or an RFC 3339 date.
+fn parse_creation_time(value: &str) -> Result<SystemTime> {
+    let secs = match value.trim().parse::<u64>() {
+        Ok(secs) => secs,
+        Err(_) => chrono::DateTime::parse_from_rfc3339(value.trim())
+            .with_context(|| format!("invalid creation time: {value}"))?
+            .timestamp()
+            .try_into()
+            .with_context(|| format!("creation time before 1970: {value}"))?,
+    };
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Parse one share, either as a text block or as scanned QR code contents.
+fn read_share(text: &str) -> Result<Share> {
+    if text.trim_start().starts_with("KCPGP:") {
+        let parts: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+        let data = sync::reassemble_from_qr(&parts)
+            .map_err(|e| anyhow::anyhow!("failed to reassemble QR codes: {e}"))?;
+        Ok(Share::from_bytes(&data)?)
+    } else {
+        Ok(Share::parse_text(text)?)
+    }
+}
+
+/// Build the keyring record for one of the user's own secret keys.
+fn own_key_record(info: &CertInfo, pgp_data: Vec<u8>) -> KeyRecord {
+    KeyRecord {
+        fingerprint: info.fingerprint.0.clone(),
+        name: info.name().map(String::from),
+        email: info.email().map(String::from),
+        algorithm: info.algorithm.to_string(),
+        created_at: info.created_at.clone(),
+        expires_at: info.expires_at.clone(),
+        trust_level: 2, // own key = verified
+        is_own_key: true,
+        is_revoked: info.is_revoked,
+        pgp_data,
+        metadata: Default::default(),
+    }
+}
+
+/// Write secret material to a file only its owner can read.
+fn write_private_file(path: &Path, data: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let write = || {
+        let mut file = options.open(path)?;
+        // The mode only applies to new files; tighten an existing one too
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(data)
+    };
+    write().with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn qr_svg(data: &str) -> Result<String> {
+    Ok(qrcode::QrCode::new(data.as_bytes())
+        .context("QR generation failed")?
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(256, 256)
+        .build())
+}
+
+pub fn passwd(
+    fingerprint: &str,
+    old_passphrase: Option<&str>,
+    new_passphrase: Option<&str>,
+    argon2: bool,
+) -> Result<()> {
+    let engine = SequoiaEngine::new();
+    let keyring = super::open_unlocked_keyring()?;
+    let secret_key = keyring
+        .get_secret_key(fingerprint)
+        .with_context(|| format!("no secret key found for {fingerprint}"))?;
+
+    let s2k = if argon2 {
+        S2kMode::Argon2(Argon2Params::default())
+    } else {
+        S2kMode::Iterated
+    };
+    let updated = engine
+        .change_passphrase(
+            secret_key.expose_secret(),
+            old_passphrase.map(str::as_bytes),
+            new_passphrase.map(str::as_bytes),
+            s2k,
+        )
+        .context("failed to change passphrase")?;
+    keyring.update_secret_key(fingerprint, &updated)?;
+
+    let info = engine.inspect_key(&updated)?;
+    eprintln!("Passphrase changed for {fingerprint}");
+    if let Some(s2k) = info.s2k {
+        eprintln!("Protection: {s2k}");
+    }
+    Ok(())
+}
+
+pub fn add_adsk(fingerprint: &str, adsk_fingerprint: &str, passphrase: Option<&str>) -> Result<()> {
+    let engine = SequoiaEngine::new();
+    let keyring = super::open_unlocked_keyring()?;
+    let secret_key = keyring
+        .get_secret_key(fingerprint)
+        .with_context(|| format!("no secret key found for {fingerprint}"))?;
+    let adsk = keyring
+        .get_key(adsk_fingerprint)?
+        .with_context(|| format!("key not found: {adsk_fingerprint}"))?;
+
+    let updated = engine
+        .add_adsk(
+            secret_key.expose_secret(),
+            passphrase.map(str::as_bytes),
+            &adsk.pgp_data,
+        )
+        .context("failed to add ADSK")?;
+    keyring.update_secret_key(fingerprint, &updated)?;
+    keyring.update_pgp_data(fingerprint, &engine.public_key(&updated)?)?;
+
+    let info = engine.inspect_key(&updated)?;
+    eprintln!("ADSK added to {fingerprint}");
+    for adsk in info.adsks() {
+        eprintln!("ADSK: {adsk}");
+    }
+    eprintln!("Re-publish or re-share your public key so senders encrypt to the ADSK.");
+    Ok(())
+}
+
+pub fn add_revoker(
+    fingerprint: &str,
+    revoker_fingerprint: &str,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let engine = SequoiaEngine::new();
+    let keyring = super::open_unlocked_keyring()?;
+    let secret_key = keyring
+        .get_secret_key(fingerprint)
+        .with_context(|| format!("no secret key found for {fingerprint}"))?;
+    let revoker = keyring
+        .get_key(revoker_fingerprint)?
+        .with_context(|| format!("key not found: {revoker_fingerprint}"))?;
+
+    let updated = engine
+        .add_designated_revoker(
+            secret_key.expose_secret(),
+            passphrase.map(str::as_bytes),
+            &revoker.pgp_data,
+        )
+        .context("failed to add designated revoker")?;
+    keyring.update_secret_key(fingerprint, &updated)?;
+    keyring.update_pgp_data(fingerprint, &engine.public_key(&updated)?)?;
+
+    let info = engine.inspect_key(&updated)?;
+    eprintln!("Designated revoker added to {fingerprint}");
+    for revoker in &info.designated_revokers {
+        eprintln!("Revoker: {revoker}");
+    }
+    eprintln!("Re-publish or re-share your public key so others learn about the revoker.");
+    Ok(())
+}
+
+pub fn revoke_as_revoker(
+    fingerprint: &str,
+    revoker_fingerprint: &str,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let engine = SequoiaEngine::new();
+    let keyring = super::open_unlocked_keyring()?;
+    let target = keyring
+        .get_key(fingerprint)?
+        .with_context(|| format!("key not found: {fingerprint}"))?;
+    let revoker_secret = keyring
+        .get_secret_key(revoker_fingerprint)
+        .with_context(|| format!("no secret key found for {revoker_fingerprint}"))?;
+
+    let revoked = engine
+        .revoke_as_designated_revoker(
+            &target.pgp_data,
+            revoker_secret.expose_secret(),
+            passphrase.map(str::as_bytes),
+        )
+        .context("failed to revoke key")?;
+    keyring.update_pgp_data(fingerprint, &revoked)?;
+    keyring.set_revoked(fingerprint, true)?;
+
+    print!("{}", String::from_utf8_lossy(&revoked));
+    eprintln!("Key {fingerprint} revoked. Publish the key above so others see the revocation.");
+    Ok(())
+}
+
+pub fn master_password() -> Result<()> {
+    let keyring = super::open_unlocked_keyring()?;
+    let new = Zeroizing::new(rpassword::prompt_password("New master password: ")?);
+    let repeated = Zeroizing::new(rpassword::prompt_password("Repeat new master password: ")?);
+    if new != repeated {
+        bail!("master passwords do not match");
+    }
+    keyring.set_master_password(new.as_bytes())?;
+    eprintln!("Master password set; secret keys on disk are now encrypted with it.");
+    Ok(())
+}
+
 pub fn delete(fingerprint: &str) -> Result<()> {
     let keyring = Keyring::open_default()?;
     if keyring.delete_key(fingerprint)? {
-        eprintln!("Key deleted: {fingerprint}");
+        eprintln!("Key moved to the trash: {fingerprint}");
+        eprintln!("Undo with 'keychainpgp keys restore {fingerprint}'.");
     } else {
         eprintln!("Key not found: {fingerprint}");
     }
     Ok(())
 }
 
-pub fn search(query: &str) -> Result<()> {
+pub fn trash(purge: Option<&str>, empty: bool, retention_days: Option<u32>) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+
+    if let Some(days) = retention_days {
+        keyring.set_trash_retention_days(days)?;
+        if days == 0 {
+            eprintln!("Deleted keys are now kept until you purge them.");
+        } else {
+            eprintln!("Deleted keys are now purged after {days} day(s).");
+        }
+        let purged = keyring.purge_expired_trash()?;
+        if purged > 0 {
+            eprintln!("Purged {purged} key(s) past the new retention period.");
+        }
+        return Ok(());
+    }
+    if let Some(query) = purge {
+        let key = resolve_trashed(&keyring, query)?;
+        keyring.purge_key(&key.record.fingerprint)?;
+        eprintln!("Key permanently deleted: {}", key.record.fingerprint);
+        return Ok(());
+    }
+    if empty {
+        let purged = keyring.empty_trash()?;
+        eprintln!("Permanently deleted {purged} key(s).");
+        return Ok(());
+    }
+
+    let trash = keyring.list_trash()?;
+    if trash.is_empty() {
+        eprintln!("The trash is empty.");
+        return Ok(());
+    }
+    for key in &trash {
+        let record = &key.record;
+        let tag = if record.is_own_key { "sec" } else { "pub" };
+        let name = record.name.as_deref().unwrap_or("(no name)");
+        let email = record
+            .email
+            .as_deref()
+            .map(|e| format!(" <{e}>"))
+            .unwrap_or_default();
+        let purge_at = key
+            .purge_at
+            .as_deref()
+            .map(|p| format!(", purged on {}", format_date(p)))
+            .unwrap_or_default();
+        println!(
+            "{tag}   {:<12} deleted {}{purge_at}",
+            record.algorithm,
+            format_date(&key.deleted_at)
+        );
+        println!("      {}", record.fingerprint);
+        println!("      {name}{email}");
+        println!();
+    }
+    Ok(())
+}
+
+pub fn restore(key: &str) -> Result<()> {
     let keyring = Keyring::open_default()?;
-    let results = keyring.search_keys(query)?;
+    let key = resolve_trashed(&keyring, key)?;
+    let fingerprint = &key.record.fingerprint;
+    if keyring.get_key(fingerprint)?.is_some() {
+        bail!(
+            "{fingerprint} was imported again since it was deleted; \
+             purge the old copy with 'keychainpgp keys trash --purge {fingerprint}'"
+        );
+    }
+    keyring.restore_key(fingerprint)?;
+    eprintln!("Key restored: {fingerprint}");
+    Ok(())
+}
 
-    if results.is_empty() {
+pub fn cert_d(dir: Option<&Path>, off: bool, unshare_purged: Option<bool>) -> Result<()> {
+    let mut keyring = Keyring::open_default()?;
+    if let Some(enabled) = unshare_purged {
+        keyring.set_unshare_purged(enabled)?;
+        if enabled {
+            eprintln!("Purged keys will also be removed from the shared directory.");
+        } else {
+            eprintln!("Purged keys will be left in the shared directory.");
+        }
+        if dir.is_none() && !off {
+            return Ok(());
+        }
+    }
+    if off {
+        keyring.stop_cert_d()?;
+        eprintln!("Public keys are no longer shared with other OpenPGP tools.");
+        return Ok(());
+    }
+
+    let path = keyring.use_cert_d(dir)?;
+    let sync = keyring.sync_shared_store()?;
+    eprintln!("Sharing public keys through {}", path.display());
+    eprintln!(
+        "Imported {}, exported {}, updated {} key(s).",
+        sync.imported, sync.exported, sync.updated
+    );
+    Ok(())
+}
+
+/// Find a key in the trash by fingerprint or key ID, or by a fragment of
+/// its name, petname or email.
+fn resolve_trashed(keyring: &Keyring, query: &str) -> Result<TrashedKey> {
+    let handle = query
+        .trim()
+        .trim_start_matches("0x")
+        .replace(' ', "")
+        .to_uppercase();
+    let text = query.to_lowercase();
+    let matches: Vec<TrashedKey> = keyring
+        .list_trash()?
+        .into_iter()
+        .filter(|key| {
+            let record = &key.record;
+            (handle.len() >= 8 && record.fingerprint.ends_with(&handle))
+                || [&record.name, &record.email, &record.metadata.petname]
+                    .into_iter()
+                    .flatten()
+                    .any(|field| field.to_lowercase().contains(&text))
+        })
+        .collect();
+
+    match matches.len() {
+        0 => bail!("no key in the trash matches '{query}'"),
+        1 => Ok(matches.into_iter().next().unwrap()),
+        n => {
+            eprintln!("'{query}' matched {n} keys in the trash:");
+            for key in &matches {
+                let name = key.record.name.as_deref().unwrap_or("(no name)");
+                eprintln!("  {} {name}", &key.record.fingerprint[..16]);
+            }
+            bail!("specify a more precise key (use full fingerprint)");
+        }
+    }
+}
+
+pub fn annotate(
+    key: &str,
+    petname: Option<&str>,
+    notes: Option<&str>,
+    tags: &[String],
+    untags: &[String],
+) -> Result<()> {
+    if petname.is_none() && notes.is_none() && tags.is_empty() && untags.is_empty() {
+        bail!("nothing to change: pass --petname, --notes, --tag or --untag");
+    }
+    let keyring = Keyring::open_default()?;
+    let record = super::resolve_key(&keyring, key)?;
+    let fingerprint = &record.fingerprint;
+
+    // One update, so an invalid tag leaves the petname and notes alone too
+    let metadata = &record.metadata;
+    let untags = untags.iter().map(|t| t.to_lowercase()).collect::<Vec<_>>();
+    let mut new_tags = metadata.tags.clone();
+    new_tags.extend_from_slice(tags);
+    new_tags.retain(|t| !untags.contains(&t.to_lowercase()));
+    keyring.set_key_metadata(
+        fingerprint,
+        petname.or(metadata.petname.as_deref()),
+        notes.or(metadata.notes.as_deref()),
+        &new_tags,
+    )?;
+
+    if let Some(updated) = keyring.get_key(fingerprint)? {
+        print_key_summary(&keyring, &updated);
+    }
+    Ok(())
+}
+
+pub fn audit(
+    key: Option<&str>,
+    kind: Option<&str>,
+    since: Option<&str>,
+    limit: usize,
+) -> Result<()> {
+    let keyring = Keyring::open_default()?;
+    // Deleted keys can only be named by fingerprint
+    let fingerprint = match key {
+        Some(query) => match super::resolve_key(&keyring, query) {
+            Ok(record) => Some(record.fingerprint),
+            Err(_) if query.len() >= 40 => Some(query.to_uppercase()),
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+    let entries = keyring.audit_log(&AuditQuery {
+        fingerprint,
+        kind: kind.map(String::from),
+        since: since.map(String::from),
+        limit: Some(limit),
+    })?;
+
+    if entries.is_empty() {
+        eprintln!("No audit log entries.");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        println!(
+            "{}  {}  {}",
+            entry.timestamp, entry.fingerprint, entry.action
+        );
+    }
+    Ok(())
+}
+
+pub fn search(query: &str, page: usize, per_page: usize) -> Result<()> {
+    if page == 0 || per_page == 0 {
+        bail!("--page and --per-page must be at least 1");
+    }
+    let offset = (page - 1)
+        .checked_mul(per_page)
+        .with_context(|| format!("--page {page} is out of range"))?;
+    let keyring = Keyring::open_default()?;
+    let results = keyring.search_keys_page(query, offset, per_page)?;
+
+    if results.total == 0 {
         eprintln!("No keys match '{query}'.");
         return Ok(());
     }
+    if results.keys.is_empty() {
+        bail!(
+            "--page {page} is past the last result ({} in total)",
+            results.total
+        );
+    }
 
-    for key in &results {
-        print_key_summary(key);
+    for key in &results.keys {
+        print_key_summary(&keyring, key);
         println!();
     }
 
+    let shown = offset + results.keys.len();
+    if results.total > per_page {
+        eprintln!(
+            "Showing {}-{shown} of {} matches.",
+            offset + 1,
+            results.total
+        );
+    }
+    if shown < results.total {
+        eprintln!("Use --page {} for more.", page + 1);
+    }
+
     Ok(())
 }
 
-fn print_key_summary(key: &KeyRecord) {
-    let tag = if key.is_own_key { "sec" } else { "pub" };
+fn print_key_summary(keyring: &Keyring, key: &KeyRecord) {
+    // Like GnuPG, `sec#` marks an own key whose primary key is offline
+    let primary_key_offline = key.is_own_key
+        && keyring
+            .get_secret_key(&key.fingerprint)
+            .ok()
+            .and_then(|sk| SequoiaEngine::new().inspect_key(sk.expose_secret()).ok())
+            .is_some_and(|info| info.primary_key_offline);
+    let tag = match (key.is_own_key, primary_key_offline) {
+        (true, true) => "sec#",
+        (true, false) => "sec",
+        (false, _) => "pub",
+    };
     let name = key.name.as_deref().unwrap_or("(no name)");
     let email = key
         .email
@@ -136,9 +803,41 @@ fn print_key_summary(key: &KeyRecord) {
         .unwrap_or_default();
 
     let revoked = if key.is_revoked { " [REVOKED]" } else { "" };
-    println!("{tag}   {:<12} {trust}{revoked}", key.algorithm);
+    println!("{tag:<6}{:<12} {trust}{revoked}", key.algorithm);
     println!("      {}", key.fingerprint);
     println!("      {name}{email}{expires}");
+
+    // Further User IDs beyond the primary one
+    for (name, email) in keyring
+        .user_ids(&key.fingerprint)
+        .unwrap_or_default()
+        .iter()
+        .skip(1)
+    {
+        let uid = keychainpgp_core::types::UserId {
+            name: name.clone(),
+            email: email.clone(),
+        };
+        println!("      {uid}");
+    }
+
+    let metadata = &key.metadata;
+    if let Some(petname) = &metadata.petname {
+        println!("      petname: {petname}");
+    }
+    if !metadata.tags.is_empty() {
+        println!("      tags: {}", metadata.tags.join(", "));
+    }
+    if let Some(notes) = &metadata.notes {
+        println!("      notes: {notes}");
+    }
+    if let Some(last_used) = &metadata.last_used_at {
+        println!(
+            "      used {} time(s), last on {}",
+            metadata.use_count,
+            format_date(last_used)
+        );
+    }
 }
 
 /// Format an ISO 8601 date to just the date portion for display.
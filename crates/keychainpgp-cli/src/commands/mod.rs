@@ -1,7 +1,115 @@
 pub mod decrypt;
 pub mod encrypt;
 pub mod generate;
+pub mod groups;
 pub mod inspect;
 pub mod keys;
 pub mod sign;
 pub mod verify;
+
+use anyhow::Result;
+use keychainpgp_core::backend::SecretKeyBackend;
+use keychainpgp_keys::Keyring;
+use keychainpgp_keys::storage::KeyRecord;
+use zeroize::Zeroizing;
+
+/// Attempts at the master password before giving up.
+const UNLOCK_ATTEMPTS: u32 = 3;
+
+/// Open the keyring for a command that needs secret keys, unlocking it
+/// first if it is locked behind a master password.
+pub fn open_unlocked_keyring() -> Result<Keyring> {
+    let keyring = Keyring::open_default()?;
+    unlock_keyring(&keyring)?;
+    Ok(keyring)
+}
+
+/// Prompt for the master password if the keyring is locked.
+pub fn unlock_keyring(keyring: &Keyring) -> Result<()> {
+    if !keyring.is_locked() {
+        return Ok(());
+    }
+    let mut attempt = 1;
+    loop {
+        let password = Zeroizing::new(rpassword::prompt_password("Master password: ")?);
+        match keyring.unlock(password.as_bytes()) {
+            Err(keychainpgp_keys::Error::BadMasterPassword) if attempt < UNLOCK_ATTEMPTS => {
+                eprintln!("Incorrect master password, try again.");
+                attempt += 1;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
+/// Find the key a recipient argument names: a primary or subkey
+/// fingerprint, a key ID, or a name or email that matches exactly one key.
+pub fn resolve_key(keyring: &Keyring, query: &str) -> Result<KeyRecord> {
+    // Try exact fingerprint first
+    if let Some(record) = keyring.get_key(query)? {
+        return Ok(record);
+    }
+
+    // Then a subkey fingerprint or a key ID, which several keys can share
+    let owners = keyring.find_by_key_handle(query)?;
+    match owners.len() {
+        0 => {}
+        1 => return Ok(owners.into_iter().next().unwrap()),
+        _ => return Err(ambiguous_recipient(query, &owners)),
+    }
+
+    // Fall back to search
+    let results = keyring.search_keys(query)?;
+    match results.len() {
+        0 => anyhow::bail!("no key found matching '{query}'"),
+        1 => Ok(results.into_iter().next().unwrap()),
+        _ => Err(ambiguous_recipient(query, &results)),
+    }
+}
+
+/// List the keys an ambiguous recipient argument matched.
+fn ambiguous_recipient(query: &str, matches: &[KeyRecord]) -> anyhow::Error {
+    eprintln!(
+        "Ambiguous recipient '{query}' matched {} keys:",
+        matches.len()
+    );
+    for r in matches {
+        let name = r.name.as_deref().unwrap_or("(no name)");
+        let email = r
+            .email
+            .as_deref()
+            .map(|e| format!(" <{e}>"))
+            .unwrap_or_default();
+        eprintln!("  {} {name}{email}", &r.fingerprint[..16]);
+    }
+    anyhow::anyhow!("specify a more precise recipient (use full fingerprint)")
+}
+
+/// Connect to the agent for `--agent`: gpg-agent, or the agent listening on
+/// `socket`. A given passphrase is passed to the agent (loopback pinentry);
+/// otherwise the agent asks for it itself.
+#[cfg(unix)]
+pub fn agent_backend(
+    socket: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<Box<dyn SecretKeyBackend>> {
+    use keychainpgp_keys::agent::AgentBackend;
+    use secrecy::SecretBox;
+
+    let mut agent = match socket {
+        Some(socket) => AgentBackend::new(socket),
+        None => AgentBackend::gpg_agent()?,
+    };
+    if let Some(passphrase) = passphrase {
+        agent = agent.with_passphrase(SecretBox::new(Box::new(passphrase.as_bytes().to_vec())));
+    }
+    Ok(Box::new(agent))
+}
+
+#[cfg(not(unix))]
+pub fn agent_backend(
+    _socket: Option<&str>,
+    _passphrase: Option<&str>,
+) -> Result<Box<dyn SecretKeyBackend>> {
+    anyhow::bail!("--agent is only supported on Unix")
+}
@@ -1,32 +1,64 @@
 use std::io::{self, Read, Write};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
+use keychainpgp_core::backend::SecretKeyBackend;
+use keychainpgp_core::types::SignOptions;
 use keychainpgp_core::{CryptoEngine, SequoiaEngine};
 use keychainpgp_keys::Keyring;
 use secrecy::ExposeSecret;
 
-pub fn run(key_fingerprint: Option<&str>, passphrase: Option<&str>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    key_fingerprint: Option<&str>,
+    passphrase: Option<&str>,
+    agent: Option<&dyn SecretKeyBackend>,
+    notations: &[String],
+    expire_days: Option<u64>,
+    signer_uid: Option<&str>,
+    intended_recipients: &[String],
+) -> Result<()> {
     let engine = SequoiaEngine::new();
     let keyring = Keyring::open_default()?;
+    if agent.is_none() {
+        super::unlock_keyring(&keyring)?;
+    }
 
-    // Find the signing key
-    let secret_key = if let Some(fp) = key_fingerprint {
-        keyring
-            .get_secret_key(fp)
-            .with_context(|| format!("could not retrieve secret key for {fp}"))?
-    } else {
-        // Use the first own key
-        let own_keys = keyring
-            .list_keys()?
-            .into_iter()
-            .filter(|k| k.is_own_key)
-            .collect::<Vec<_>>();
-
-        let first = own_keys
-            .first()
-            .context("no private keys in keyring; generate or import one first")?;
-
-        keyring.get_secret_key(&first.fingerprint)?
+    let expiration = expire_days
+        .map(|days| {
+            days.checked_mul(24 * 60 * 60)
+                .map(Duration::from_secs)
+                .with_context(|| format!("signature lifetime of {days} days is too long"))
+        })
+        .transpose()?;
+    let mut options = SignOptions::new().with_expiration(expiration);
+    for notation in notations {
+        let (name, value) = notation
+            .split_once('=')
+            .with_context(|| format!("notation must have the form NAME=VALUE: {notation}"))?;
+        options = options.with_notation(name, value);
+    }
+    if let Some(uid) = signer_uid {
+        options = options.with_signer_user_id(uid);
+    }
+    for fingerprint in intended_recipients {
+        let recipient = keyring
+            .get_key(fingerprint)?
+            .with_context(|| format!("intended recipient key not found: {fingerprint}"))?;
+        options = options.with_intended_recipient(recipient.pgp_data);
+    }
+
+    // Pick the signing key: the given one or the first own key
+    let fingerprint = match key_fingerprint {
+        Some(fp) => fp.to_string(),
+        None => {
+            keyring
+                .list_keys()?
+                .into_iter()
+                .find(|k| k.is_own_key)
+                .context("no private keys in keyring; generate or import one first")?
+                .fingerprint
+        }
     };
 
     // Read data from stdin (limit to 64 MB to prevent memory exhaustion)
@@ -37,9 +69,26 @@ pub fn run(key_fingerprint: Option<&str>, passphrase: Option<&str>) -> Result<()
         .read_to_end(&mut data)
         .context("failed to read from stdin")?;
 
-    let passphrase_bytes = passphrase.map(|p| p.as_bytes());
-
-    let signed = engine.sign(&data, secret_key.expose_secret(), passphrase_bytes)?;
+    let signed = match agent {
+        // Only the public key is needed; the agent holds the secret
+        Some(agent) => {
+            let record = keyring
+                .get_key(&fingerprint)?
+                .with_context(|| format!("key not found: {fingerprint}"))?;
+            engine.sign_with_backend(&data, &record.pgp_data, agent, &options)?
+        }
+        None => {
+            let secret_key = keyring
+                .get_secret_key(&fingerprint)
+                .with_context(|| format!("could not retrieve secret key for {fingerprint}"))?;
+            engine.sign_with_options(
+                &data,
+                secret_key.expose_secret(),
+                passphrase.map(|p| p.as_bytes()),
+                &options,
+            )?
+        }
+    };
 
     io::stdout()
         .write_all(&signed)
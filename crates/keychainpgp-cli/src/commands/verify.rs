@@ -1,19 +1,13 @@
 use std::io::{self, Read};
 
 use anyhow::{Context, Result};
-use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+use keychainpgp_core::{CryptoEngine, SequoiaEngine, packets};
 use keychainpgp_keys::Keyring;
 
-pub fn run(signer: &str) -> Result<()> {
+pub fn run(signer: Option<&str>) -> Result<()> {
     let engine = SequoiaEngine::new();
     let keyring = Keyring::open_default()?;
 
-    // Find the signer's public key by fingerprint or email
-    let signer_records = keyring.search_keys(signer)?;
-    let signer_record = signer_records
-        .first()
-        .with_context(|| format!("no key found matching '{signer}'"))?;
-
     // Read signed data from stdin (limit to 64 MB to prevent memory exhaustion)
     const MAX_INPUT: u64 = 64 * 1024 * 1024;
     let mut signed_data = Vec::new();
@@ -22,14 +16,58 @@ pub fn run(signer: &str) -> Result<()> {
         .read_to_end(&mut signed_data)
         .context("failed to read from stdin")?;
 
+    let signer_record = match signer {
+        // Find the expected signer's public key by fingerprint or email
+        Some(signer) => keyring
+            .search_keys(signer)?
+            .into_iter()
+            .next()
+            .with_context(|| format!("no key found matching '{signer}'"))?,
+        // Otherwise look up the key that issued the signature
+        None => {
+            let issuers = packets::signature_issuers(&signed_data)?;
+            let mut found = None;
+            for issuer in &issuers {
+                let mut owners = keyring.find_by_key_handle(issuer)?;
+                if owners.len() > 1 {
+                    anyhow::bail!(
+                        "signing key {issuer} belongs to {} keys; name the signer with --signer",
+                        owners.len()
+                    );
+                }
+                if let Some(record) = owners.pop() {
+                    found = Some(record);
+                    break;
+                }
+            }
+            found.with_context(|| match issuers.first() {
+                Some(issuer) => format!("signing key {issuer} is not in the keyring"),
+                None => "the input carries no signature".to_string(),
+            })?
+        }
+    };
+
     match engine.verify(&signed_data, &signer_record.pgp_data) {
         Ok(result) => {
             if result.valid {
+                let _ = keyring.record_key_use(&signer_record.fingerprint);
                 let name = signer_record.name.as_deref().unwrap_or("(unknown)");
                 eprintln!("Good signature from {name}");
                 if let Some(fp) = &result.signer_fingerprint {
                     eprintln!("Fingerprint: {fp}");
                 }
+                if let Some(uid) = &result.signer_user_id {
+                    eprintln!("Signer UID:  {uid}");
+                }
+                if let Some(expires) = &result.expires_at {
+                    eprintln!("Expires:     {expires}");
+                }
+                for notation in &result.notations {
+                    eprintln!("Notation:    {}={}", notation.name, notation.value);
+                }
+                for recipient in &result.intended_recipients {
+                    eprintln!("Recipient:   {recipient}");
+                }
             } else {
                 eprintln!("BAD signature: verification failed");
                 std::process::exit(1);
@@ -36,13 +36,37 @@ enum Commands {
         /// Protect with a passphrase (WARNING: visible in process list; prefer interactive prompt)
         #[arg(long)]
         passphrase: Option<String>,
+
+        /// Also create an authentication subkey (for SSH, see `keys export --ssh`)
+        #[arg(long)]
+        auth_subkey: bool,
+
+        /// Protect the secret key with the memory-hard Argon2 S2K (not readable by older OpenPGP tools)
+        #[arg(long)]
+        argon2: bool,
+
+        /// Fingerprint of a key in the keyring to bind as additional decryption subkey (ADSK)
+        #[arg(long)]
+        adsk: Vec<String>,
+
+        /// Fingerprint of a key in the keyring allowed to revoke the new key (designated revoker)
+        #[arg(long)]
+        revoker: Vec<String>,
+
+        /// Derive the key from a new 24-word recovery phrase, shown once after generation
+        #[arg(long)]
+        mnemonic: bool,
     },
 
     /// Encrypt a message (reads from stdin, writes to stdout)
     Encrypt {
-        /// Recipient key fingerprint(s)
+        /// Recipient key fingerprint(s), or @group for every member of a recipient group
         #[arg(short, long, required = true)]
         recipient: Vec<String>,
+
+        /// Hide recipient key IDs in the message (recipients must try all their keys)
+        #[arg(long)]
+        hidden_recipients: bool,
     },
 
     /// Decrypt a message (reads from stdin, writes to stdout)
@@ -50,6 +74,32 @@ enum Commands {
         /// Passphrase for the private key (WARNING: visible in process list; prefer interactive prompt)
         #[arg(long)]
         passphrase: Option<String>,
+
+        /// Treat the input as a PGP/MIME email (e.g. an .eml file) and output the decrypted MIME message
+        #[arg(long)]
+        mime: bool,
+
+        /// Print the message's session key to stderr, so this one message can be opened without your key
+        #[arg(long, conflicts_with = "mime")]
+        show_session_key: bool,
+
+        /// Decrypt with a session key (as printed by --show-session-key) instead of a private key
+        /// (WARNING: visible in process list)
+        #[arg(long, conflicts_with_all = ["mime", "passphrase"])]
+        session_key: Option<String>,
+
+        /// Decrypt with the private key held by gpg-agent (or the agent at SOCKET); it never
+        /// enters this process
+        #[arg(
+            long,
+            value_name = "SOCKET",
+            require_equals = true,
+            conflicts_with_all = ["mime", "session_key", "show_session_key"]
+        )]
+        agent: Option<Option<String>>,
+
+        /// Path to the input file (reads stdin if omitted or -)
+        file: Option<String>,
     },
 
     /// Sign a message (reads from stdin, writes to stdout)
@@ -61,13 +111,35 @@ enum Commands {
         /// Passphrase for the private key (WARNING: visible in process list; prefer interactive prompt)
         #[arg(long)]
         passphrase: Option<String>,
+
+        /// Add a notation to the signature, e.g. ticket@example.com=OPS-1234 (repeatable)
+        #[arg(long, value_name = "NAME=VALUE")]
+        notation: Vec<String>,
+
+        /// Make the signature expire after this many days
+        #[arg(long, value_name = "DAYS")]
+        expire_days: Option<u64>,
+
+        /// User ID (or email) of the signing key to name as signer
+        #[arg(long)]
+        signer_uid: Option<String>,
+
+        /// Fingerprint of an intended recipient to list in the signature (repeatable)
+        #[arg(long)]
+        intended_recipient: Vec<String>,
+
+        /// Sign with the private key held by gpg-agent (or the agent at SOCKET); it never enters
+        /// this process
+        #[arg(long, value_name = "SOCKET", require_equals = true)]
+        agent: Option<Option<String>>,
     },
 
     /// Verify a signed message (reads from stdin, writes content to stdout)
     Verify {
-        /// Fingerprint or email of the expected signer
+        /// Fingerprint or email of the expected signer (default: the key that
+        /// issued the signature)
         #[arg(long)]
-        signer: String,
+        signer: Option<String>,
     },
 
     /// Inspect a key file and display its metadata
@@ -81,6 +153,68 @@ enum Commands {
         #[command(subcommand)]
         action: KeysAction,
     },
+
+    /// Recipient groups, used as `encrypt -r @name`
+    Groups {
+        #[command(subcommand)]
+        action: GroupsAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum GroupsAction {
+    /// List all recipient groups
+    List,
+
+    /// Show the members of a group, with a warning for each unusable one
+    Show {
+        /// Group name
+        name: String,
+    },
+
+    /// Create a group
+    Create {
+        /// Group name (letters, digits, '-', '_' or '.')
+        name: String,
+
+        /// Members: fingerprints, key IDs, names or emails
+        members: Vec<String>,
+    },
+
+    /// Add members to a group
+    Add {
+        /// Group name
+        name: String,
+
+        /// Members: fingerprints, key IDs, names or emails
+        #[arg(required = true)]
+        members: Vec<String>,
+    },
+
+    /// Remove members from a group
+    Remove {
+        /// Group name
+        name: String,
+
+        /// Members: fingerprints, key IDs, names or emails
+        #[arg(required = true)]
+        members: Vec<String>,
+    },
+
+    /// Rename a group
+    Rename {
+        /// Current group name
+        name: String,
+
+        /// New group name
+        new_name: String,
+    },
+
+    /// Delete a group (member keys are kept)
+    Delete {
+        /// Group name
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -98,18 +232,246 @@ enum KeysAction {
     Export {
         /// Fingerprint of the key to export
         fingerprint: String,
+
+        /// Export the authentication subkey as an OpenSSH public key line
+        #[arg(long)]
+        ssh: bool,
+
+        /// Export the secret subkeys only, with a stub in place of the primary secret key
+        #[arg(long, conflicts_with = "ssh")]
+        secret_subkeys: bool,
+    },
+
+    /// Print a paper backup of the secret parts of one of your keys
+    PaperExport {
+        /// Fingerprint of the key
+        fingerprint: String,
+
+        /// Also write the backup as a series of QR code SVG files into this directory
+        #[arg(long)]
+        qr_dir: Option<std::path::PathBuf>,
+    },
+
+    /// Rebuild a secret key from a paper backup and its public key (writes to stdout)
+    PaperRestore {
+        /// Paper backup text file, or scanned QR code contents with --qr (or - for stdin)
+        file: String,
+
+        /// Public key file (looked up in the keyring if omitted)
+        #[arg(long)]
+        public_key: Option<std::path::PathBuf>,
+
+        /// The input contains scanned QR code contents, one per line
+        #[arg(long)]
+        qr: bool,
     },
 
-    /// Delete a key from the keyring
+    /// Split one of your keys into Shamir shares, any M of which recover it
+    Split {
+        /// Fingerprint of the key
+        fingerprint: String,
+
+        /// Number of shares needed to recover the key (M)
+        #[arg(long, short = 'm')]
+        threshold: u8,
+
+        /// Number of shares to create (N)
+        #[arg(long, short = 'n')]
+        shares: u8,
+
+        /// Directory to write the share files into
+        #[arg(long)]
+        out_dir: std::path::PathBuf,
+
+        /// Also write each share as a series of QR code SVG files
+        #[arg(long)]
+        qr: bool,
+    },
+
+    /// Recover a key from Shamir shares and import it into the keyring
+    Recover {
+        /// Share files: share text blocks or scanned QR code contents, one per line
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+    },
+
+    /// Rebuild a key from its recovery phrase and import it into the keyring
+    ///
+    /// The phrase is prompted for, or read from stdin when it is not a terminal.
+    RestoreMnemonic {
+        /// Display name of the key
+        #[arg(long)]
+        name: String,
+
+        /// Email address of the key
+        #[arg(long)]
+        email: String,
+
+        /// Creation time shown when the key was generated (Unix seconds or RFC 3339)
+        #[arg(long)]
+        created: String,
+
+        /// The key had an authentication subkey
+        #[arg(long)]
+        auth_subkey: bool,
+
+        /// Protect the secret key with the memory-hard Argon2 S2K
+        #[arg(long)]
+        argon2: bool,
+    },
+
+    /// Change the passphrase of one of your keys
+    Passwd {
+        /// Fingerprint of the key
+        fingerprint: String,
+
+        /// Re-protect the key with the memory-hard Argon2 S2K
+        #[arg(long)]
+        argon2: bool,
+    },
+
+    /// Bind another key's encryption subkey to one of your keys as additional decryption subkey (ADSK)
+    AddAdsk {
+        /// Fingerprint of your key
+        fingerprint: String,
+
+        /// Fingerprint of the key receiving a copy of every message (e.g. a company escrow key)
+        adsk: String,
+    },
+
+    /// Allow another key (e.g. the security team's) to revoke one of your keys
+    AddRevoker {
+        /// Fingerprint of your key
+        fingerprint: String,
+
+        /// Fingerprint of the designated revoker
+        revoker: String,
+    },
+
+    /// Revoke a key that names one of your keys as its designated revoker (writes the revoked key to stdout)
+    RevokeAsRevoker {
+        /// Fingerprint of the key to revoke
+        fingerprint: String,
+
+        /// Fingerprint of your key acting as designated revoker
+        #[arg(long = "as")]
+        revoker: String,
+    },
+
+    /// Protect the stored secret keys with a master password, or change it
+    MasterPassword,
+
+    /// Move a key to the trash (undo with `keys restore`)
     Delete {
         /// Fingerprint of the key to delete
         fingerprint: String,
     },
 
-    /// Search keys by name, email, or fingerprint
+    /// List deleted keys, or purge them for good
+    ///
+    /// Deleted keys are purged automatically once they have been in the
+    /// trash for the retention period (30 days unless changed).
+    Trash {
+        /// Permanently delete this key, overwriting its secret key file
+        #[arg(long, value_name = "KEY", conflicts_with = "empty")]
+        purge: Option<String>,
+
+        /// Permanently delete every key in the trash
+        #[arg(long)]
+        empty: bool,
+
+        /// Keep deleted keys for this many days (0 keeps them until purged)
+        #[arg(long, value_name = "DAYS", conflicts_with_all = ["purge", "empty"])]
+        retention_days: Option<u32>,
+    },
+
+    /// Restore a deleted key from the trash
+    Restore {
+        /// Fingerprint, key ID, name or email of the deleted key
+        key: String,
+    },
+
+    /// Share public keys with other OpenPGP tools through an
+    /// `openpgp-cert-d` directory
+    ///
+    /// Keys already in the directory are imported, yours are copied into
+    /// it, and from then on keys you import, generate or update are
+    /// mirrored there. Deleted keys are left for the other tools. Without
+    /// --dir, $PGP_CERT_D or the standard location (e.g.
+    /// ~/.local/share/pgp.cert.d) is used.
+    CertD {
+        /// Certificate directory to share
+        #[arg(long, value_name = "DIR", conflicts_with = "off")]
+        dir: Option<std::path::PathBuf>,
+
+        /// Stop sharing (certificates already shared are left in place)
+        #[arg(long)]
+        off: bool,
+
+        /// Also remove keys from the directory when they are purged from
+        /// the trash. On its own, only changes this setting.
+        #[arg(long, value_name = "BOOL")]
+        unshare_purged: Option<bool>,
+    },
+
+    /// Set your own petname, notes and tags for a key
+    Annotate {
+        /// Fingerprint, key ID, name or email of the key
+        key: String,
+
+        /// Nickname for the key (empty to clear)
+        #[arg(long)]
+        petname: Option<String>,
+
+        /// Notes, e.g. "verified in person at FOSDEM" (empty to clear)
+        #[arg(long)]
+        notes: Option<String>,
+
+        /// Add a tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Remove a tag (repeatable)
+        #[arg(long = "untag", value_name = "TAG")]
+        untags: Vec<String>,
+    },
+
+    /// Search keys by name, petname, email, comment, notes, or fingerprint
+    ///
+    /// Words match as prefixes and tolerate small typos; `tag:NAME` only
+    /// keeps keys with that tag. Exact email matches come first, then own
+    /// and verified keys, then recently used ones.
     Search {
         /// Search query
         query: String,
+
+        /// Page of results to show, starting at 1
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+
+        /// Number of results per page
+        #[arg(long, default_value_t = 20)]
+        per_page: usize,
+    },
+
+    /// Show the audit log: imports, trust changes, revocations, merges,
+    /// secret key exports, deletions, restores and purges, newest first
+    Audit {
+        /// Only show entries for this key
+        key: Option<String>,
+
+        /// Only show one kind of entry (imported, trust_changed, revoked,
+        /// merged, secret_key_exported, deleted, restored, purged)
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Only show entries since this date (e.g. 2026-01-31)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Maximum number of entries to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
     },
 }
 
@@ -153,36 +515,211 @@ fn main() -> anyhow::Result<()> {
             name,
             email,
             passphrase,
+            auth_subkey,
+            argon2,
+            adsk,
+            revoker,
+            mnemonic,
         } => {
             let passphrase =
                 resolve_passphrase(passphrase, "Passphrase (leave empty for no protection): ");
-            commands::generate::run(&name, &email, passphrase.as_deref())?;
+            commands::generate::run(
+                &name,
+                &email,
+                passphrase.as_deref(),
+                auth_subkey,
+                argon2,
+                &adsk,
+                &revoker,
+                mnemonic,
+            )?;
         }
 
-        Commands::Encrypt { recipient } => commands::encrypt::run(&recipient)?,
+        Commands::Encrypt {
+            recipient,
+            hidden_recipients,
+        } => commands::encrypt::run(&recipient, hidden_recipients)?,
 
-        Commands::Decrypt { passphrase } => {
-            let passphrase =
-                resolve_passphrase(passphrase, "Passphrase (leave empty if key has none): ");
-            commands::decrypt::run(passphrase.as_deref())?;
+        Commands::Decrypt {
+            passphrase,
+            mime,
+            show_session_key,
+            session_key,
+            agent,
+            file,
+        } => {
+            if let Some(session_key) = session_key {
+                commands::decrypt::run_with_session_key(&session_key, file.as_deref())?;
+            } else if let Some(socket) = agent {
+                // The agent asks for the passphrase itself unless one is given
+                let agent = commands::agent_backend(socket.as_deref(), passphrase.as_deref())?;
+                commands::decrypt::run_with_agent(&*agent, file.as_deref())?;
+            } else {
+                let passphrase =
+                    resolve_passphrase(passphrase, "Passphrase (leave empty if key has none): ");
+                commands::decrypt::run(
+                    passphrase.as_deref(),
+                    file.as_deref(),
+                    mime,
+                    show_session_key,
+                )?;
+            }
         }
 
-        Commands::Sign { key, passphrase } => {
-            let passphrase =
-                resolve_passphrase(passphrase, "Passphrase (leave empty if key has none): ");
-            commands::sign::run(key.as_deref(), passphrase.as_deref())?;
+        Commands::Sign {
+            key,
+            passphrase,
+            notation,
+            expire_days,
+            signer_uid,
+            intended_recipient,
+            agent,
+        } => {
+            let agent = agent
+                .map(|socket| commands::agent_backend(socket.as_deref(), passphrase.as_deref()))
+                .transpose()?;
+            let passphrase = match agent {
+                // The agent asks for the passphrase itself unless one is given
+                Some(_) => None,
+                None => {
+                    resolve_passphrase(passphrase, "Passphrase (leave empty if key has none): ")
+                }
+            };
+            commands::sign::run(
+                key.as_deref(),
+                passphrase.as_deref(),
+                agent.as_deref(),
+                &notation,
+                expire_days,
+                signer_uid.as_deref(),
+                &intended_recipient,
+            )?;
         }
 
-        Commands::Verify { signer } => commands::verify::run(&signer)?,
+        Commands::Verify { signer } => commands::verify::run(signer.as_deref())?,
 
         Commands::Inspect { file } => commands::inspect::run(&file)?,
 
         Commands::Keys { action } => match action {
             KeysAction::List => commands::keys::list()?,
             KeysAction::Import { file } => commands::keys::import(&file)?,
-            KeysAction::Export { fingerprint } => commands::keys::export(&fingerprint)?,
+            KeysAction::Export {
+                fingerprint,
+                ssh,
+                secret_subkeys,
+            } => commands::keys::export(&fingerprint, ssh, secret_subkeys)?,
+            KeysAction::PaperExport {
+                fingerprint,
+                qr_dir,
+            } => commands::keys::paper_export(&fingerprint, qr_dir.as_deref())?,
+            KeysAction::PaperRestore {
+                file,
+                public_key,
+                qr,
+            } => commands::keys::paper_restore(&file, public_key.as_deref(), qr)?,
+            KeysAction::Split {
+                fingerprint,
+                threshold,
+                shares,
+                out_dir,
+                qr,
+            } => commands::keys::split(&fingerprint, threshold, shares, &out_dir, qr)?,
+            KeysAction::Recover { files } => commands::keys::recover(&files)?,
+            KeysAction::RestoreMnemonic {
+                name,
+                email,
+                created,
+                auth_subkey,
+                argon2,
+            } => {
+                let phrase = commands::keys::read_mnemonic()?;
+                let passphrase = prompt_passphrase("Passphrase (leave empty for no protection): ");
+                commands::keys::restore_mnemonic(
+                    &name,
+                    &email,
+                    &created,
+                    &phrase,
+                    passphrase.as_deref(),
+                    auth_subkey,
+                    argon2,
+                )?;
+            }
+            KeysAction::Passwd {
+                fingerprint,
+                argon2,
+            } => {
+                let old = prompt_passphrase("Current passphrase (leave empty if key has none): ");
+                let new = prompt_passphrase("New passphrase (leave empty for no protection): ");
+                commands::keys::passwd(&fingerprint, old.as_deref(), new.as_deref(), argon2)?;
+            }
+            KeysAction::AddAdsk { fingerprint, adsk } => {
+                let passphrase = prompt_passphrase("Passphrase (leave empty if key has none): ");
+                commands::keys::add_adsk(&fingerprint, &adsk, passphrase.as_deref())?;
+            }
+            KeysAction::AddRevoker {
+                fingerprint,
+                revoker,
+            } => {
+                let passphrase = prompt_passphrase("Passphrase (leave empty if key has none): ");
+                commands::keys::add_revoker(&fingerprint, &revoker, passphrase.as_deref())?;
+            }
+            KeysAction::RevokeAsRevoker {
+                fingerprint,
+                revoker,
+            } => {
+                let passphrase =
+                    prompt_passphrase("Passphrase of your revoker key (leave empty if none): ");
+                commands::keys::revoke_as_revoker(&fingerprint, &revoker, passphrase.as_deref())?;
+            }
+            KeysAction::MasterPassword => commands::keys::master_password()?,
             KeysAction::Delete { fingerprint } => commands::keys::delete(&fingerprint)?,
-            KeysAction::Search { query } => commands::keys::search(&query)?,
+            KeysAction::Trash {
+                purge,
+                empty,
+                retention_days,
+            } => commands::keys::trash(purge.as_deref(), empty, retention_days)?,
+            KeysAction::Restore { key } => commands::keys::restore(&key)?,
+            KeysAction::CertD {
+                dir,
+                off,
+                unshare_purged,
+            } => commands::keys::cert_d(dir.as_deref(), off, unshare_purged)?,
+            KeysAction::Annotate {
+                key,
+                petname,
+                notes,
+                tags,
+                untags,
+            } => commands::keys::annotate(
+                &key,
+                petname.as_deref(),
+                notes.as_deref(),
+                &tags,
+                &untags,
+            )?,
+            KeysAction::Search {
+                query,
+                page,
+                per_page,
+            } => commands::keys::search(&query, page, per_page)?,
+            KeysAction::Audit {
+                key,
+                kind,
+                since,
+                limit,
+            } => commands::keys::audit(key.as_deref(), kind.as_deref(), since.as_deref(), limit)?,
+        },
+
+        Commands::Groups { action } => match action {
+            GroupsAction::List => commands::groups::list()?,
+            GroupsAction::Show { name } => commands::groups::show(&name)?,
+            GroupsAction::Create { name, members } => commands::groups::create(&name, &members)?,
+            GroupsAction::Add { name, members } => commands::groups::add(&name, &members)?,
+            GroupsAction::Remove { name, members } => commands::groups::remove(&name, &members)?,
+            GroupsAction::Rename { name, new_name } => {
+                commands::groups::rename(&name, &new_name)?;
+            }
+            GroupsAction::Delete { name } => commands::groups::delete(&name)?,
         },
     }
 
@@ -0,0 +1,329 @@
+//! Secret key backends: private-key operations performed elsewhere.
+//!
+//! By default the engine needs the secret key material in memory to sign
+//! and decrypt. A [`SecretKeyBackend`] instead receives only the digest to
+//! sign or the encrypted session key, and returns the raw result, so the
+//! secret never enters this process (e.g. it stays in `gpg-agent` or on a
+//! smartcard). The engine does the OpenPGP framing around it, see
+//! [`CryptoEngine::sign_with_backend`](crate::CryptoEngine::sign_with_backend)
+//! and [`CryptoEngine::decrypt_with_backend`](crate::CryptoEngine::decrypt_with_backend).
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use sequoia_openpgp::Cert;
+use sequoia_openpgp::crypto::mpi::{self, MPI, PublicKey};
+use sequoia_openpgp::crypto::{Decryptor, KeyPair, Password, Signer};
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::types::{Curve, HashAlgorithm};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// A key the backend is asked to use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendKey {
+    /// OpenPGP fingerprint of the key or subkey (uppercase hex).
+    pub fingerprint: String,
+    /// GnuPG keygrip of the key (uppercase hex), the name agents use for it.
+    pub keygrip: String,
+}
+
+/// A raw signature made by a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendSignature {
+    /// EdDSA signature values.
+    EdDsa { r: Vec<u8>, s: Vec<u8> },
+    /// RSA signature value.
+    Rsa { s: Vec<u8> },
+}
+
+/// An encrypted session key handed to a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendCiphertext {
+    /// RSA ciphertext value.
+    Rsa { c: Vec<u8> },
+    /// ECDH ephemeral public point and wrapped session key.
+    Ecdh { e: Vec<u8>, wrapped_key: Vec<u8> },
+}
+
+/// What a backend returns for a [`BackendCiphertext`].
+pub enum DecryptedValue {
+    /// ECDH: the shared point; the engine unwraps the session key with it.
+    SharedPoint(Zeroizing<Vec<u8>>),
+    /// RSA: the session key, still in PKCS#1 v1.5 encoding.
+    Padded(Zeroizing<Vec<u8>>),
+    /// RSA: the session key with the padding already removed.
+    Unpadded(Zeroizing<Vec<u8>>),
+}
+
+/// Performs private-key operations without handing out the secret key.
+pub trait SecretKeyBackend: Send + Sync {
+    /// Whether the backend holds the secret of `key`.
+    fn has_key(&self, key: &BackendKey) -> Result<bool>;
+
+    /// Sign `digest` with `key`. `hash_algo` is the OpenPGP ID of the hash
+    /// algorithm that produced the digest (e.g. 10 for SHA-512).
+    fn sign(&self, key: &BackendKey, hash_algo: u8, digest: &[u8]) -> Result<BackendSignature>;
+
+    /// Decrypt an encrypted session key with `key`.
+    fn decrypt(&self, key: &BackendKey, ciphertext: &BackendCiphertext) -> Result<DecryptedValue>;
+}
+
+/// A backend holding unlocked secret keys in memory.
+///
+/// It gives no isolation, since the secrets live in this process; it serves
+/// as the reference implementation for tests and stand-in agents.
+pub struct InMemoryBackend {
+    /// Key pairs by keygrip.
+    keys: Vec<(String, Mutex<KeyPair>)>,
+}
+
+impl InMemoryBackend {
+    /// Load every secret key and subkey of an armored secret key, unlocking
+    /// them with `passphrase` where needed. Offline (stub) keys are skipped.
+    pub fn from_secret_key(secret_key: &[u8], passphrase: Option<&[u8]>) -> Result<Self> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        let password = passphrase.map(Password::from);
+
+        let mut keys = Vec::new();
+        for ka in cert.keys().secret() {
+            let mut key = ka.key().clone();
+            if crate::sequoia_engine::is_secret_stub(&key) {
+                continue;
+            }
+            if key.secret().is_encrypted() {
+                let password = password.as_ref().ok_or(Error::BadPassphrase)?;
+                key = key
+                    .decrypt_secret(password)
+                    .map_err(|_| Error::BadPassphrase)?;
+            }
+            let grip = keygrip(key.mpis())?;
+            let keypair = key
+                .role_into_unspecified()
+                .into_keypair()
+                .map_err(|e| Error::Internal(format!("keypair conversion failed: {e}")))?;
+            keys.push((grip, Mutex::new(keypair)));
+        }
+
+        if keys.is_empty() {
+            return Err(Error::NoSecretKey);
+        }
+        Ok(Self { keys })
+    }
+
+    fn keypair(&self, key: &BackendKey) -> Result<&Mutex<KeyPair>> {
+        self.keys
+            .iter()
+            .find(|(grip, _)| grip.eq_ignore_ascii_case(&key.keygrip))
+            .map(|(_, keypair)| keypair)
+            .ok_or(Error::NoSecretKey)
+    }
+}
+
+impl SecretKeyBackend for InMemoryBackend {
+    fn has_key(&self, key: &BackendKey) -> Result<bool> {
+        Ok(self.keypair(key).is_ok())
+    }
+
+    fn sign(&self, key: &BackendKey, hash_algo: u8, digest: &[u8]) -> Result<BackendSignature> {
+        let mut keypair = self
+            .keypair(key)?
+            .lock()
+            .map_err(|_| Error::Internal("key lock poisoned".into()))?;
+        let signing_error = |e: sequoia_openpgp::anyhow::Error| Error::Signing {
+            reason: e.to_string(),
+        };
+        match keypair
+            .sign(HashAlgorithm::from(hash_algo), digest)
+            .map_err(signing_error)?
+        {
+            mpi::Signature::EdDSA { r, s } => Ok(BackendSignature::EdDsa {
+                r: r.value().to_vec(),
+                s: s.value().to_vec(),
+            }),
+            mpi::Signature::RSA { s } => Ok(BackendSignature::Rsa {
+                s: s.value().to_vec(),
+            }),
+            _ => Err(Error::Signing {
+                reason: "unsupported signature algorithm".into(),
+            }),
+        }
+    }
+
+    fn decrypt(&self, key: &BackendKey, ciphertext: &BackendCiphertext) -> Result<DecryptedValue> {
+        let mut keypair = self
+            .keypair(key)?
+            .lock()
+            .map_err(|_| Error::Internal("key lock poisoned".into()))?;
+        let decryption_error = |e: sequoia_openpgp::anyhow::Error| Error::Decryption {
+            reason: e.to_string(),
+        };
+
+        match ciphertext {
+            BackendCiphertext::Rsa { c } => {
+                let ciphertext = mpi::Ciphertext::RSA { c: MPI::new(c) };
+                let session_key = Decryptor::decrypt(&mut *keypair, &ciphertext, None)
+                    .map_err(decryption_error)?;
+                Ok(DecryptedValue::Unpadded(Zeroizing::new(
+                    session_key.to_vec(),
+                )))
+            }
+            BackendCiphertext::Ecdh { e, .. } => {
+                let scalar = keypair
+                    .secret()
+                    .map(|secret| match secret {
+                        mpi::SecretKeyMaterial::ECDH { scalar } => {
+                            // OpenPGP stores the X25519 scalar big-endian
+                            let mut scalar = scalar.value_padded(32);
+                            scalar.reverse();
+                            <[u8; 32]>::try_from(&scalar[..]).ok().map(Zeroizing::new)
+                        }
+                        _ => None,
+                    })
+                    .ok_or_else(|| Error::Decryption {
+                        reason: "not an X25519 encryption key".into(),
+                    })?;
+                let ephemeral = e.strip_prefix(&[0x40]).unwrap_or(e);
+                let ephemeral = <[u8; 32]>::try_from(ephemeral).map_err(|_| Error::Decryption {
+                    reason: "invalid ephemeral key".into(),
+                })?;
+                let secret = x25519_dalek::StaticSecret::from(*scalar);
+                let shared = secret.diffie_hellman(&x25519_dalek::PublicKey::from(ephemeral));
+                let mut point = Zeroizing::new(Vec::with_capacity(33));
+                point.push(0x40);
+                point.extend_from_slice(shared.as_bytes());
+                Ok(DecryptedValue::SharedPoint(point))
+            }
+        }
+    }
+}
+
+/// Compute the GnuPG keygrip of a public key.
+///
+/// The keygrip is the SHA-1 of the key's parameters as libgcrypt encodes
+/// them; for elliptic curves that includes the curve domain parameters.
+pub(crate) fn keygrip(key: &PublicKey) -> Result<String> {
+    let mut hash = HashAlgorithm::SHA1
+        .context()
+        .map_err(|e| Error::Internal(format!("hash error: {e}")))?
+        .for_digest();
+
+    match key {
+        // libgcrypt hashes the modulus with a leading zero byte
+        PublicKey::RSA { n, .. } => {
+            hash.update(&[0]);
+            hash.update(n.value());
+        }
+        PublicKey::EdDSA { curve, q } | PublicKey::ECDH { curve, q, .. } => {
+            for (name, value) in ["p", "a", "b", "g", "n"]
+                .iter()
+                .zip(curve_parameters(curve)?)
+            {
+                hash_sexp(&mut hash, name, &value);
+            }
+            // Native (0x40-prefixed) points are hashed without the prefix
+            let q = q.value();
+            hash_sexp(&mut hash, "q", q.strip_prefix(&[0x40]).unwrap_or(q));
+        }
+        _ => {
+            return Err(Error::Internal(
+                "no keygrip for this public key algorithm".into(),
+            ));
+        }
+    }
+
+    let digest = hash
+        .into_digest()
+        .map_err(|e| Error::Internal(format!("hash error: {e}")))?;
+    Ok(digest.iter().map(|b| format!("{b:02X}")).collect())
+}
+
+fn hash_sexp(hash: &mut sequoia_openpgp::crypto::hash::Context, name: &str, value: &[u8]) {
+    let _ = write!(hash, "(1:{name}{}:", value.len());
+    hash.update(value);
+    hash.update(b")");
+}
+
+/// Domain parameters p, a, b, g, n of the curves our keys use.
+fn curve_parameters(curve: &Curve) -> Result<[Vec<u8>; 5]> {
+    const P25519: &str = "7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFED";
+    const N25519: &str = "1000000000000000000000000000000014DEF9DEA2F79CD65812631A5CF5D3ED";
+    let hex = match curve {
+        Curve::Ed25519 => [
+            P25519,
+            "01",
+            "2DFC9311D490018C7338BF8688861767FF8FF5B2BEBE27548A14B235ECA6874A",
+            "04216936D3CD6E53FEC0A4E231FDD6DC5C692CC7609525A7B2C9562D608F25D51A\
+             6666666666666666666666666666666666666666666666666666666666666658",
+            N25519,
+        ],
+        Curve::Cv25519 => [
+            P25519,
+            "01DB41",
+            "01",
+            "040000000000000000000000000000000000000000000000000000000000000009\
+             20AE19A1B8A086B4E01EDD2C7748D14C923D4D7E6D7C61B229E9C5A27ECED3D9",
+            N25519,
+        ],
+        other => {
+            return Err(Error::Internal(format!(
+                "no keygrip for keys on curve {other}"
+            )));
+        }
+    };
+    Ok(hex.map(|h| {
+        let bytes = sequoia_openpgp::fmt::hex::decode(h).expect("valid curve parameter");
+        MPI::new(&bytes).value().to_vec()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use secrecy::{ExposeSecret, SecretBox};
+
+    use super::*;
+    use crate::types::{KeyGenOptions, UserId};
+    use crate::{CryptoEngine, SequoiaEngine};
+
+    #[test]
+    fn test_keygrip_matches_gnupg() {
+        let phrase = ["abandon"; 23].join(" ") + " art";
+        let options = KeyGenOptions::new(UserId::new("Grip", "grip@example.com"))
+            .with_mnemonic(SecretBox::new(Box::new(phrase)))
+            .with_creation_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let key_pair = SequoiaEngine::new().generate_key_pair(options).unwrap();
+
+        // As listed by `gpg --with-keygrip` after importing the key
+        let expected = [
+            "213E86B245D7E5D215E5FB8E1428F7C5809CF894",
+            "285F71C26F1CDFF3902BC9E1DAD0FB7E34406E88",
+            "11DF02699AB5F1D306B6F4B21CED465C46805A6D",
+        ];
+        let cert = Cert::from_bytes(&key_pair.public_key).unwrap();
+        let grips: Vec<String> = cert
+            .keys()
+            .map(|ka| keygrip(ka.key().mpis()).unwrap())
+            .collect();
+        assert_eq!(grips, expected);
+
+        let backend =
+            InMemoryBackend::from_secret_key(key_pair.secret_key.expose_secret(), None).unwrap();
+        for grip in expected {
+            let key = BackendKey {
+                fingerprint: String::new(),
+                keygrip: grip.to_lowercase(),
+            };
+            assert!(backend.has_key(&key).unwrap());
+        }
+        let unknown = BackendKey {
+            fingerprint: String::new(),
+            keygrip: "00".repeat(20),
+        };
+        assert!(!backend.has_key(&unknown).unwrap());
+    }
+}
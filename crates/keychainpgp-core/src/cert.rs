@@ -0,0 +1,82 @@
+//! Certificate serialization and merging without a [`CryptoEngine`](crate::CryptoEngine).
+//!
+//! Shared certificate stores such as an `openpgp-cert-d` directory hold
+//! certificates in binary form, and every write merges the new copy into
+//! the one already stored so that signatures added by another tool are kept.
+
+use sequoia_openpgp::Cert;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::serialize::SerializeInto;
+
+use crate::error::{Error, Result};
+
+fn parse(cert_data: &[u8]) -> Result<Cert> {
+    Cert::from_bytes(cert_data).map_err(|e| Error::InvalidArmor {
+        reason: e.to_string(),
+    })
+}
+
+fn serialize(cert: &Cert) -> Result<Vec<u8>> {
+    cert.to_vec()
+        .map_err(|e| Error::Internal(format!("failed to serialize certificate: {e}")))
+}
+
+/// The public part of a certificate, armored or binary, in binary form.
+pub fn to_binary(cert_data: &[u8]) -> Result<Vec<u8>> {
+    serialize(&parse(cert_data)?.strip_secret_key_material())
+}
+
+/// Merge two copies of the same certificate into one, in binary form,
+/// keeping every User ID, subkey and signature of both. Secret key material
+/// is dropped. Fails if the copies belong to different certificates.
+pub fn merge(existing: &[u8], update: &[u8]) -> Result<Vec<u8>> {
+    let existing = parse(existing)?;
+    let update = parse(update)?;
+    if existing.fingerprint() != update.fingerprint() {
+        return Err(Error::InvalidArmor {
+            reason: format!(
+                "cannot merge certificate {} into {}",
+                update.fingerprint().to_hex(),
+                existing.fingerprint().to_hex()
+            ),
+        });
+    }
+    let merged = existing
+        .strip_secret_key_material()
+        .merge_public(update)
+        .map_err(|e| Error::Internal(format!("failed to merge certificates: {e}")))?;
+    serialize(&merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{KeyGenOptions, UserId};
+    use crate::{CryptoEngine, SequoiaEngine};
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn test_merge_keeps_both_copies() {
+        let engine = SequoiaEngine::new();
+        let alice = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let bob = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let binary = to_binary(&alice.public_key).unwrap();
+        assert!(!binary.starts_with(b"-----"));
+        // Secret key material never reaches the binary form
+        let from_secret = to_binary(alice.secret_key.expose_secret()).unwrap();
+        assert!(!engine.inspect_key(&from_secret).unwrap().has_secret_key);
+
+        let merged = merge(&binary, &alice.public_key).unwrap();
+        let info = engine.inspect_key(&merged).unwrap();
+        assert_eq!(info.fingerprint, alice.fingerprint);
+        assert!(merge(&binary, &bob.public_key).is_err());
+    }
+}
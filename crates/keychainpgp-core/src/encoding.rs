@@ -0,0 +1,179 @@
+//! Small text encodings shared by the MIME and export modules.
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes to base64 (standard alphabet with padding, no line breaks).
+#[must_use]
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = u32::from(chunk[0]);
+        let b1 = u32::from(chunk.get(1).copied().unwrap_or(0));
+        let b2 = u32::from(chunk.get(2).copied().unwrap_or(0));
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        result.push(BASE64_CHARS[((triple >> 18) & 0x3F) as usize] as char);
+        result.push(BASE64_CHARS[((triple >> 12) & 0x3F) as usize] as char);
+        if chunk.len() > 1 {
+            result.push(BASE64_CHARS[((triple >> 6) & 0x3F) as usize] as char);
+        } else {
+            result.push('=');
+        }
+        if chunk.len() > 2 {
+            result.push(BASE64_CHARS[(triple & 0x3F) as usize] as char);
+        } else {
+            result.push('=');
+        }
+    }
+    result
+}
+
+/// Encode bytes to base64, wrapping lines at `width` characters with CRLF.
+#[must_use]
+pub fn base64_encode_wrapped(data: &[u8], width: usize) -> String {
+    let encoded = base64_encode(data);
+    encoded
+        .as_bytes()
+        .chunks(width.max(4))
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// Decode base64 data, ignoring any ASCII whitespace (line breaks included).
+pub fn base64_decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    fn val(c: u8) -> Result<u32, String> {
+        match c {
+            b'A'..=b'Z' => Ok(u32::from(c - b'A')),
+            b'a'..=b'z' => Ok(u32::from(c - b'a' + 26)),
+            b'0'..=b'9' => Ok(u32::from(c - b'0' + 52)),
+            b'+' => Ok(62),
+            b'/' => Ok(63),
+            b'=' => Ok(0),
+            _ => Err(format!("invalid base64 character: {}", c as char)),
+        }
+    }
+
+    let data: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if data.len() % 4 != 0 {
+        return Err("invalid base64 length".into());
+    }
+
+    let mut result = Vec::with_capacity(data.len() / 4 * 3);
+    for chunk in data.chunks(4) {
+        let triple = (val(chunk[0])? << 18)
+            | (val(chunk[1])? << 12)
+            | (val(chunk[2])? << 6)
+            | val(chunk[3])?;
+        result.push((triple >> 16) as u8);
+        if chunk[2] != b'=' {
+            result.push((triple >> 8) as u8);
+        }
+        if chunk[3] != b'=' {
+            result.push(triple as u8);
+        }
+    }
+    Ok(result)
+}
+
+/// Decode quoted-printable data (RFC 2045 §6.7).
+///
+/// Malformed escape sequences are passed through unchanged, as recommended
+/// by the RFC for robust decoders.
+#[must_use]
+pub fn quoted_printable_decode(data: &[u8]) -> Vec<u8> {
+    fn hex(c: u8) -> Option<u8> {
+        match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            _ => None,
+        }
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != b'=' {
+            out.push(data[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line break: "=" followed by CRLF or LF
+        if data[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if data[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let (Some(hi), Some(lo)) = (
+            data.get(i + 1).copied().and_then(hex),
+            data.get(i + 2).copied().and_then(hex),
+        ) {
+            out.push((hi << 4) | lo);
+            i += 3;
+        } else {
+            out.push(b'=');
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Encode bytes as uppercase hex.
+#[must_use]
+pub fn hex_encode(data: &[u8]) -> String {
+    use std::fmt::Write;
+    data.iter()
+        .fold(String::with_capacity(data.len() * 2), |mut s, b| {
+            let _ = write!(s, "{b:02X}");
+            s
+        })
+}
+
+/// Decode a hex string (case-insensitive, whitespace ignored).
+pub fn hex_decode(input: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = input.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        return Err("odd number of hex digits".into());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let s = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+            u8::from_str_radix(s, 16).map_err(|_| format!("invalid hex digits: {s}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_round_trip() {
+        let data = b"Hello, KeychainPGP!\x00\xFF\x80";
+        let encoded = base64_encode(data);
+        assert_eq!(base64_decode(encoded.as_bytes()).unwrap(), data);
+
+        let wrapped = base64_encode_wrapped(&[0xAB; 200], 76);
+        assert!(wrapped.lines().all(|l| l.trim_end().len() <= 76));
+        assert_eq!(base64_decode(wrapped.as_bytes()).unwrap(), vec![0xAB; 200]);
+    }
+
+    #[test]
+    fn test_quoted_printable_decode() {
+        let decoded = quoted_printable_decode(b"caf=C3=A9 soft=\r\nbreak =ZZ");
+        assert_eq!(decoded, "café softbreak =ZZ".as_bytes());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let data = [0x00, 0x7F, 0xAB, 0xFF];
+        assert_eq!(hex_encode(&data), "007FABFF");
+        assert_eq!(hex_decode("00 7f ab FF").unwrap(), data);
+        assert!(hex_decode("ABC").is_err());
+    }
+}
@@ -1,5 +1,9 @@
+use crate::backend::SecretKeyBackend;
 use crate::error::Result;
-use crate::types::{CertInfo, GeneratedKeyPair, KeyGenOptions, VerifyResult};
+use crate::types::{
+    CertInfo, GeneratedKeyPair, KeyGenOptions, MessageSessionKey, RecipientCheck, S2kMode,
+    SignOptions, VerifyResult,
+};
 
 /// Trait abstracting all OpenPGP cryptographic operations.
 ///
@@ -12,14 +16,103 @@ pub trait CryptoEngine: Send + Sync {
     /// key material in ASCII-armored form.
     fn generate_key_pair(&self, options: KeyGenOptions) -> Result<GeneratedKeyPair>;
 
+    /// Change the passphrase protecting a secret key.
+    ///
+    /// - `secret_key`: ASCII-armored secret key.
+    /// - `old_passphrase`: Current passphrase, if the key is protected.
+    /// - `new_passphrase`: New passphrase, or `None` to remove protection.
+    /// - `s2k`: S2K scheme to protect the key with (e.g. upgrade to Argon2).
+    ///
+    /// Returns the re-protected ASCII-armored secret key.
+    fn change_passphrase(
+        &self,
+        secret_key: &[u8],
+        old_passphrase: Option<&[u8]>,
+        new_passphrase: Option<&[u8]>,
+        s2k: S2kMode,
+    ) -> Result<Vec<u8>>;
+
+    /// Bind an additional decryption subkey (ADSK) to an own key.
+    ///
+    /// - `secret_key`: ASCII-armored secret key to extend.
+    /// - `passphrase`: Optional passphrase if the secret key is protected.
+    /// - `adsk_key`: ASCII-armored public key whose newest encryption subkey
+    ///   becomes the ADSK, e.g. a company escrow key.
+    ///
+    /// Senders honoring ADSKs then also encrypt every message to that subkey.
+    /// Returns the updated ASCII-armored secret key.
+    fn add_adsk(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        adsk_key: &[u8],
+    ) -> Result<Vec<u8>>;
+
+    /// Export a secret key with only its subkeys' secret material.
+    ///
+    /// The primary secret key is replaced by a GnuPG-style stub, so the
+    /// result can sign and decrypt day to day while the certification-capable
+    /// primary key stays offline.
+    fn export_secret_subkeys(&self, secret_key: &[u8]) -> Result<Vec<u8>>;
+
+    /// Name another key as designated revoker of an own key.
+    ///
+    /// - `secret_key`: ASCII-armored secret key to extend.
+    /// - `passphrase`: Optional passphrase if the secret key is protected.
+    /// - `revoker_key`: ASCII-armored public key allowed to revoke it, e.g.
+    ///   the security team's key.
+    ///
+    /// Returns the updated ASCII-armored secret key.
+    fn add_designated_revoker(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        revoker_key: &[u8],
+    ) -> Result<Vec<u8>>;
+
+    /// Revoke someone else's key as its designated revoker.
+    ///
+    /// - `target_key`: ASCII-armored public key to revoke; it must name the
+    ///   revoker as designated revoker.
+    /// - `revoker_secret_key`: ASCII-armored secret key of the revoker.
+    /// - `passphrase`: Optional passphrase if the revoker key is protected.
+    ///
+    /// Returns the ASCII-armored target certificate including the revocation,
+    /// ready to be imported or published.
+    fn revoke_as_designated_revoker(
+        &self,
+        target_key: &[u8],
+        revoker_secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>>;
+
+    /// Check a revocation of a key by one of its designated revokers.
+    ///
+    /// - `key`: ASCII-armored public key carrying the revocation.
+    /// - `revoker_keys`: ASCII-armored public keys of designated revokers
+    ///   known to the caller. Keys that are not designated revokers of
+    ///   `key` are ignored.
+    ///
+    /// Returns whether a revocation signature verifies against one of them.
+    fn verify_designated_revocation(&self, key: &[u8], revoker_keys: &[Vec<u8>]) -> Result<bool>;
+
     /// Encrypt plaintext for the given recipients.
     ///
     /// - `plaintext`: The raw message bytes to encrypt.
     /// - `recipient_keys`: ASCII-armored public keys of the recipients.
     ///
-    /// Returns the ASCII-armored OpenPGP encrypted message.
+    /// Returns the ASCII-armored OpenPGP encrypted message. Additional
+    /// decryption subkeys bound to a recipient are encrypted to as well.
+    ///
+    /// Fails if any recipient key is unusable, naming the key and the reason.
     fn encrypt(&self, plaintext: &[u8], recipient_keys: &[Vec<u8>]) -> Result<Vec<u8>>;
 
+    /// Check, before encrypting, whether each recipient key can be used.
+    ///
+    /// Returns one entry per key, in order, so callers can point at the exact
+    /// contact that is expired, revoked or otherwise unusable.
+    fn check_recipients(&self, recipient_keys: &[Vec<u8>]) -> Vec<RecipientCheck>;
+
     /// Decrypt an OpenPGP message using the provided secret key.
     ///
     /// - `ciphertext`: ASCII-armored (or binary) OpenPGP message.
@@ -34,6 +127,28 @@ pub trait CryptoEngine: Send + Sync {
         passphrase: Option<&[u8]>,
     ) -> Result<Vec<u8>>;
 
+    /// Decrypt an OpenPGP message and also return its session key.
+    ///
+    /// Same as [`CryptoEngine::decrypt`], but reveals the session key so the
+    /// message can later be decrypted with
+    /// [`CryptoEngine::decrypt_with_session_key`] by someone who does not hold
+    /// the secret key.
+    fn decrypt_revealing_session_key(
+        &self,
+        ciphertext: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, MessageSessionKey)>;
+
+    /// Decrypt an OpenPGP message using a previously revealed session key.
+    ///
+    /// No secret key is needed; the session key only opens this one message.
+    fn decrypt_with_session_key(
+        &self,
+        ciphertext: &[u8],
+        session_key: &MessageSessionKey,
+    ) -> Result<Vec<u8>>;
+
     /// Create a cleartext signature of the given data.
     ///
     /// - `data`: The raw bytes to sign.
@@ -43,20 +158,84 @@ pub trait CryptoEngine: Send + Sync {
     /// Returns the ASCII-armored cleartext signed message.
     fn sign(&self, data: &[u8], secret_key: &[u8], passphrase: Option<&[u8]>) -> Result<Vec<u8>>;
 
+    /// Sign data like [`CryptoEngine::sign`], adding the notations, signature
+    /// expiration, signer's User ID and intended recipients in `options`.
+    fn sign_with_options(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>>;
+
+    /// Sign data like [`CryptoEngine::sign_with_options`], with the private-key
+    /// operation done by `backend`.
+    ///
+    /// - `public_key`: ASCII-armored public key (certificate) of the signer;
+    ///   its first signing-capable key held by the backend is used.
+    fn sign_with_backend(
+        &self,
+        data: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>>;
+
+    /// Decrypt a message like [`CryptoEngine::decrypt`], with the private-key
+    /// operation done by `backend`.
+    ///
+    /// - `public_key`: ASCII-armored public key (certificate) of the
+    ///   recipient; every encryption key of it held by the backend is tried.
+    fn decrypt_with_backend(
+        &self,
+        ciphertext: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+    ) -> Result<Vec<u8>>;
+
     /// Verify a cleartext-signed or inline-signed OpenPGP message.
     ///
     /// - `signed_data`: The signed message (cleartext or inline).
     /// - `signer_key`: ASCII-armored public key of the expected signer.
     ///
-    /// Returns verification result including validity and signer fingerprint.
+    /// Returns verification result including validity, signer fingerprint and
+    /// the options the signature was made with (see [`SignOptions`]).
     fn verify(&self, signed_data: &[u8], signer_key: &[u8]) -> Result<VerifyResult>;
 
+    /// Create a detached signature over the given data.
+    ///
+    /// The signature uses SHA-512 and is returned ASCII-armored. Used for
+    /// PGP/MIME `multipart/signed` bodies, where the signed content travels
+    /// separately from the signature.
+    fn sign_detached(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>>;
+
+    /// Verify a detached signature over the given data.
+    ///
+    /// - `data`: The exact bytes that were signed.
+    /// - `signature`: ASCII-armored (or binary) detached signature.
+    /// - `signer_key`: ASCII-armored public key of the expected signer.
+    fn verify_detached(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        signer_key: &[u8],
+    ) -> Result<VerifyResult>;
+
     /// Parse a key (public or secret) and extract metadata.
     fn inspect_key(&self, key_data: &[u8]) -> Result<CertInfo>;
 
     /// Armor a key (public or secret) back into ASCII format.
     fn armor_key(&self, key_data: &[u8]) -> Result<String>;
 
+    /// Return the ASCII-armored public certificate of a key, dropping any
+    /// secret key material.
+    fn public_key(&self, key_data: &[u8]) -> Result<Vec<u8>>;
+
     /// Encrypt data symmetrically with a passphrase (SKESK).
     ///
     /// Creates an OpenPGP message encrypted with a symmetric key derived from
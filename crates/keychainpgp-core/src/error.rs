@@ -5,6 +5,10 @@ pub enum Error {
     #[error("failed to generate key pair: {reason}")]
     KeyGeneration { reason: String },
 
+    /// Updating an existing key (e.g. binding a new subkey) failed.
+    #[error("failed to update key: {reason}")]
+    KeyUpdate { reason: String },
+
     /// Encryption failed.
     #[error("failed to encrypt message: {reason}")]
     Encryption { reason: String },
@@ -33,6 +37,22 @@ pub enum Error {
     #[error("invalid ASCII armor: {reason}")]
     InvalidArmor { reason: String },
 
+    /// A MIME message could not be parsed or is not valid PGP/MIME.
+    #[error("invalid MIME message: {reason}")]
+    InvalidMime { reason: String },
+
+    /// A printed paper key backup could not be read.
+    #[error("invalid paper key: {reason}")]
+    InvalidPaperKey { reason: String },
+
+    /// A Shamir key share is damaged or does not fit the other shares.
+    #[error("invalid key share: {reason}")]
+    InvalidShare { reason: String },
+
+    /// A recovery phrase is malformed or has a bad checksum.
+    #[error("invalid recovery phrase: {reason}")]
+    InvalidMnemonic { reason: String },
+
     /// A key has expired.
     #[error("key expired on {expiration}")]
     KeyExpired { expiration: String },
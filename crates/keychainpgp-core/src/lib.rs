@@ -8,6 +8,15 @@
 //! - Message signing and signature verification
 //! - Key inspection (metadata extraction)
 //! - ASCII armor serialization/deserialization
+//! - PGP/MIME (RFC 3156) message composition and parsing
+//! - OpenSSH public key export for authentication subkeys
+//! - Printable paper key backups of secret key material
+//! - Shamir M-of-N splitting of secret keys for shared recovery
+//! - Deterministic key generation from a BIP39 recovery phrase
+//! - Signing and decryption through external secret key backends
+//! - Reading signature issuers and message recipients without keys
+//! - Binary serialization and merging of certificates for shared stores
+//! - A deterministic mock engine for tests (`mock` feature)
 //!
 //! All operations are abstracted behind the [`CryptoEngine`] trait,
 //! with a concrete implementation backed by Sequoia-PGP.
@@ -15,13 +24,26 @@
 //! This crate performs no I/O. All functions operate on in-memory data.
 
 pub mod armor;
+pub mod backend;
+pub mod cert;
+pub mod encoding;
 pub mod engine;
 pub mod error;
+pub mod mime;
+pub mod mnemonic;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod packets;
+pub mod paperkey;
 pub mod sequoia_engine;
+pub mod shamir;
+pub mod ssh;
 pub mod types;
 
 pub use engine::CryptoEngine;
 pub use error::{Error, Result};
+#[cfg(any(test, feature = "mock"))]
+pub use mock::MockEngine;
 pub use sequoia_engine::SequoiaEngine;
 
 /// Fill a buffer with cryptographically secure random bytes.
@@ -0,0 +1,701 @@
+//! PGP/MIME (RFC 3156) message composition and parsing.
+//!
+//! Builds `multipart/encrypted` and `multipart/signed` bodies from plaintext
+//! MIME parts, and takes incoming RFC 822 / MIME messages apart so they can
+//! be decrypted and verified. All operations work on in-memory data.
+
+use crate::encoding::{base64_decode, base64_encode_wrapped, hex_encode, quoted_printable_decode};
+use crate::engine::CryptoEngine;
+use crate::error::{Error, Result};
+use crate::types::VerifyResult;
+
+/// Maximum nesting depth accepted when parsing multipart bodies.
+const MAX_DEPTH: usize = 32;
+
+/// A single MIME entity: headers plus either a leaf body or child parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimePart {
+    /// Header fields in their original order, with folded lines unfolded.
+    pub headers: Vec<(String, String)>,
+    /// Decoded body of a leaf part (empty for multipart entities).
+    pub body: Vec<u8>,
+    /// Child parts of a multipart entity.
+    pub children: Vec<MimePart>,
+    /// The exact bytes this part was parsed from, used to reproduce signed
+    /// content byte-for-byte.
+    raw: Option<Vec<u8>>,
+}
+
+impl MimePart {
+    /// Create a `text/plain` part encoded safely for signing.
+    #[must_use]
+    pub fn text(text: &str) -> Self {
+        let seven_bit = text.is_ascii() && text.lines().all(|l| l.len() <= 76);
+        let encoding = if seven_bit { "7bit" } else { "base64" };
+        Self::leaf(
+            "text/plain; charset=utf-8",
+            encoding,
+            text.as_bytes().to_vec(),
+        )
+    }
+
+    /// Create a base64-encoded attachment part.
+    #[must_use]
+    pub fn attachment(filename: &str, content_type: &str, data: Vec<u8>) -> Self {
+        let filename = filename.replace(['"', '\\', '\r', '\n'], "_");
+        Self::leaf(
+            &format!("{content_type}; name=\"{filename}\""),
+            "base64",
+            data,
+        )
+        .with_header(
+            "Content-Disposition",
+            &format!("attachment; filename=\"{filename}\""),
+        )
+    }
+
+    /// Create a multipart entity (e.g. `mixed`) with a random boundary.
+    #[must_use]
+    pub fn multipart(subtype: &str, children: Vec<MimePart>) -> Self {
+        Self::multipart_with_params(subtype, "", children)
+    }
+
+    fn multipart_with_params(subtype: &str, params: &str, children: Vec<MimePart>) -> Self {
+        let mut random = [0u8; 12];
+        crate::crypto_random(&mut random);
+        let boundary = format!("=-kcpgp-{}", hex_encode(&random));
+        Self {
+            headers: vec![(
+                "Content-Type".into(),
+                format!("multipart/{subtype}{params}; boundary=\"{boundary}\""),
+            )],
+            body: Vec::new(),
+            children,
+            raw: None,
+        }
+    }
+
+    fn leaf(content_type: &str, encoding: &str, body: Vec<u8>) -> Self {
+        Self {
+            headers: vec![
+                ("Content-Type".into(), content_type.into()),
+                ("Content-Transfer-Encoding".into(), encoding.into()),
+            ],
+            body,
+            children: Vec::new(),
+            raw: None,
+        }
+    }
+
+    /// Append a header field (e.g. `From`, `Subject`, `MIME-Version`).
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self.raw = None;
+        self
+    }
+
+    /// Return the first header with the given name (case-insensitive).
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Return the lowercased media type, defaulting to `text/plain`.
+    #[must_use]
+    pub fn content_type(&self) -> String {
+        self.header("Content-Type")
+            .and_then(|v| v.split(';').next())
+            .map(|t| t.trim().to_ascii_lowercase())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| "text/plain".into())
+    }
+
+    /// Return a `Content-Type` parameter (e.g. `boundary`, `protocol`).
+    #[must_use]
+    pub fn content_type_param(&self, name: &str) -> Option<String> {
+        header_param(self.header("Content-Type")?, name)
+    }
+
+    /// Return the attachment file name, if any.
+    #[must_use]
+    pub fn filename(&self) -> Option<String> {
+        self.header("Content-Disposition")
+            .and_then(|v| header_param(v, "filename"))
+            .or_else(|| self.content_type_param("name"))
+    }
+
+    /// Whether this entity is a `multipart/*` container.
+    #[must_use]
+    pub fn is_multipart(&self) -> bool {
+        self.content_type().starts_with("multipart/")
+    }
+
+    /// Whether this entity is a PGP/MIME `multipart/encrypted` body.
+    #[must_use]
+    pub fn is_pgp_encrypted(&self) -> bool {
+        self.content_type() == "multipart/encrypted"
+            && self
+                .content_type_param("protocol")
+                .is_some_and(|p| p.eq_ignore_ascii_case("application/pgp-encrypted"))
+    }
+
+    /// Whether this entity is a PGP/MIME `multipart/signed` body.
+    #[must_use]
+    pub fn is_pgp_signed(&self) -> bool {
+        self.content_type() == "multipart/signed"
+            && self
+                .content_type_param("protocol")
+                .is_some_and(|p| p.eq_ignore_ascii_case("application/pgp-signature"))
+    }
+
+    /// Parse an RFC 822 message or MIME entity.
+    pub fn parse(raw: &[u8]) -> Result<Self> {
+        parse_part(raw, 0)
+    }
+
+    /// Serialize this entity with CRLF line endings.
+    ///
+    /// Parsed parts are reproduced byte-for-byte so that signatures over
+    /// them remain valid.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if let Some(raw) = &self.raw {
+            return raw.clone();
+        }
+
+        let mut out = Vec::new();
+        for (name, value) in &self.headers {
+            out.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        out.extend_from_slice(b"\r\n");
+
+        if self.is_multipart() {
+            let boundary = self.content_type_param("boundary").unwrap_or_default();
+            for child in &self.children {
+                out.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+                out.extend_from_slice(&child.to_bytes());
+                out.extend_from_slice(b"\r\n");
+            }
+            out.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+        } else if self
+            .header("Content-Transfer-Encoding")
+            .is_some_and(|e| e.trim().eq_ignore_ascii_case("base64"))
+        {
+            out.extend_from_slice(base64_encode_wrapped(&self.body, 76).as_bytes());
+            out.extend_from_slice(b"\r\n");
+        } else {
+            out.extend_from_slice(&to_crlf(&self.body));
+        }
+        out
+    }
+}
+
+/// Result of opening a PGP/MIME message.
+#[derive(Debug, Clone)]
+pub struct MimeDecryption {
+    /// Header fields of the outer message (From, To, Subject, ...).
+    pub headers: Vec<(String, String)>,
+    /// The decrypted (and unwrapped) MIME tree.
+    pub body: MimePart,
+    /// Whether the message was `multipart/encrypted`.
+    pub encrypted: bool,
+    /// Signature verification result if the content was `multipart/signed`.
+    pub signature: Option<VerifyResult>,
+}
+
+impl MimeDecryption {
+    /// Reassemble a readable message: the outer non-MIME headers followed by
+    /// the decrypted entity.
+    #[must_use]
+    pub fn to_message_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, value) in &self.headers {
+            if !name.to_ascii_lowercase().starts_with("content-") {
+                out.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+            }
+        }
+        out.extend_from_slice(&self.body.to_bytes());
+        out
+    }
+}
+
+/// Wrap a MIME part in a PGP/MIME `multipart/encrypted` body.
+///
+/// To sign and encrypt, pass the output of [`compose_signed`] as `part`.
+pub fn compose_encrypted(
+    engine: &dyn CryptoEngine,
+    part: &MimePart,
+    recipient_keys: &[Vec<u8>],
+) -> Result<MimePart> {
+    let ciphertext = engine.encrypt(&part.to_bytes(), recipient_keys)?;
+
+    let control = MimePart::leaf(
+        "application/pgp-encrypted",
+        "7bit",
+        b"Version: 1\r\n".to_vec(),
+    )
+    .with_header("Content-Description", "PGP/MIME version identification");
+    let payload = MimePart::leaf(
+        "application/octet-stream; name=\"encrypted.asc\"",
+        "7bit",
+        ciphertext,
+    )
+    .with_header("Content-Description", "OpenPGP encrypted message")
+    .with_header("Content-Disposition", "inline; filename=\"encrypted.asc\"");
+
+    Ok(MimePart::multipart_with_params(
+        "encrypted",
+        "; protocol=\"application/pgp-encrypted\"",
+        vec![control, payload],
+    ))
+}
+
+/// Wrap a MIME part in a PGP/MIME `multipart/signed` body.
+pub fn compose_signed(
+    engine: &dyn CryptoEngine,
+    part: &MimePart,
+    secret_key: &[u8],
+    passphrase: Option<&[u8]>,
+) -> Result<MimePart> {
+    // Re-parse so the signed child keeps exactly the bytes that were signed.
+    let content = MimePart::parse(&part.to_bytes())?;
+    let raw = content.raw.as_deref().unwrap_or_default();
+    let signature = engine.sign_detached(raw, secret_key, passphrase)?;
+
+    let signature_part = MimePart::leaf(
+        "application/pgp-signature; name=\"signature.asc\"",
+        "7bit",
+        signature,
+    )
+    .with_header("Content-Description", "OpenPGP digital signature")
+    .with_header(
+        "Content-Disposition",
+        "attachment; filename=\"signature.asc\"",
+    );
+
+    Ok(MimePart::multipart_with_params(
+        "signed",
+        "; micalg=pgp-sha512; protocol=\"application/pgp-signature\"",
+        vec![content, signature_part],
+    ))
+}
+
+/// Decrypt and verify a parsed PGP/MIME message.
+///
+/// - `secret_key`/`passphrase`: used when the message is `multipart/encrypted`.
+/// - `signer_keys`: candidate public keys for `multipart/signed` content;
+///   the first one that verifies is reported.
+pub fn decrypt_message(
+    engine: &dyn CryptoEngine,
+    message: &MimePart,
+    secret_key: &[u8],
+    passphrase: Option<&[u8]>,
+    signer_keys: &[Vec<u8>],
+) -> Result<MimeDecryption> {
+    if !message.is_pgp_encrypted() && !message.is_pgp_signed() {
+        return Err(Error::InvalidMime {
+            reason: "message is neither multipart/encrypted nor multipart/signed".into(),
+        });
+    }
+
+    let encrypted = message.is_pgp_encrypted();
+    let mut body = if encrypted {
+        let payload = message
+            .children
+            .iter()
+            .find(|c| c.content_type() == "application/octet-stream")
+            .ok_or_else(|| Error::InvalidMime {
+                reason: "multipart/encrypted has no application/octet-stream part".into(),
+            })?;
+        let plaintext = engine.decrypt(&payload.body, secret_key, passphrase)?;
+        MimePart::parse(&plaintext)?
+    } else {
+        message.clone()
+    };
+
+    let mut signature = None;
+    if body.is_pgp_signed() {
+        let (content, result) = verify_signed(engine, &body, signer_keys)?;
+        body = content;
+        signature = Some(result);
+    }
+
+    Ok(MimeDecryption {
+        headers: message.headers.clone(),
+        body,
+        encrypted,
+        signature,
+    })
+}
+
+/// Verify a `multipart/signed` entity, returning the signed content.
+fn verify_signed(
+    engine: &dyn CryptoEngine,
+    part: &MimePart,
+    signer_keys: &[Vec<u8>],
+) -> Result<(MimePart, VerifyResult)> {
+    let [content, signature] = part.children.as_slice() else {
+        return Err(Error::InvalidMime {
+            reason: "multipart/signed must have exactly two parts".into(),
+        });
+    };
+
+    // RFC 3156 §5: the signed data is the canonical (CRLF) form of the
+    // first part, exactly as transmitted.
+    let signed_bytes = to_crlf(content.raw.as_deref().unwrap_or_default());
+
+    let mut result = VerifyResult::default();
+    for key in signer_keys {
+        if let Ok(r) = engine.verify_detached(&signed_bytes, &signature.body, key) {
+            if r.valid {
+                result = r;
+                break;
+            }
+        }
+    }
+
+    Ok((content.clone(), result))
+}
+
+fn parse_part(raw: &[u8], depth: usize) -> Result<MimePart> {
+    if depth > MAX_DEPTH {
+        return Err(Error::InvalidMime {
+            reason: "multipart nesting too deep".into(),
+        });
+    }
+
+    let (header_bytes, body) = split_header_body(raw);
+    let headers = parse_headers(header_bytes);
+    let mut part = MimePart {
+        headers,
+        body: Vec::new(),
+        children: Vec::new(),
+        raw: Some(raw.to_vec()),
+    };
+
+    if part.is_multipart() {
+        let boundary = part
+            .content_type_param("boundary")
+            .ok_or_else(|| Error::InvalidMime {
+                reason: "multipart entity without boundary".into(),
+            })?;
+        part.children = split_multipart(body, &boundary)?
+            .into_iter()
+            .map(|child| parse_part(child, depth + 1))
+            .collect::<Result<_>>()?;
+    } else {
+        let encoding = part
+            .header("Content-Transfer-Encoding")
+            .map(|e| e.trim().to_ascii_lowercase());
+        part.body = match encoding.as_deref() {
+            Some("base64") => base64_decode(body).map_err(|reason| Error::InvalidMime {
+                reason: format!("bad base64 body: {reason}"),
+            })?,
+            Some("quoted-printable") => quoted_printable_decode(body),
+            _ => body.to_vec(),
+        };
+    }
+
+    Ok(part)
+}
+
+/// Split an entity at the first empty line.
+fn split_header_body(raw: &[u8]) -> (&[u8], &[u8]) {
+    // An entity starting with an empty line has no headers.
+    if raw.starts_with(b"\r\n") {
+        return (&[], &raw[2..]);
+    }
+    if raw.starts_with(b"\n") {
+        return (&[], &raw[1..]);
+    }
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i..].starts_with(b"\r\n\r\n") {
+            return (&raw[..i], &raw[i + 4..]);
+        }
+        if raw[i..].starts_with(b"\n\n") {
+            return (&raw[..i], &raw[i + 2..]);
+        }
+        if raw[i..].starts_with(b"\n\r\n") {
+            return (&raw[..i], &raw[i + 3..]);
+        }
+        i += 1;
+    }
+    (raw, &[])
+}
+
+fn parse_headers(data: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(data);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            // Continuation of a folded header
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+/// Extract a parameter value from a structured header such as
+/// `multipart/signed; protocol="application/pgp-signature"`.
+fn header_param(value: &str, name: &str) -> Option<String> {
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            ';' if !in_quotes => params.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    params.push(current);
+
+    params.iter().skip(1).find_map(|param| {
+        let (key, val) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| val.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Split a multipart body into the raw bytes of each child part.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<&'a [u8]>> {
+    let delimiter = format!("--{boundary}");
+    let mut parts = Vec::new();
+    let mut current_start: Option<usize> = None;
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let line_end = body[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(body.len(), |i| pos + i + 1);
+        let line = trim_line_end(&body[pos..line_end]);
+
+        if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+            let closing = rest.starts_with(b"--");
+            if rest.iter().all(u8::is_ascii_whitespace) || closing {
+                if let Some(start) = current_start {
+                    // The line break before the delimiter belongs to it.
+                    let mut end = pos;
+                    if end > start && body[end - 1] == b'\n' {
+                        end -= 1;
+                        if end > start && body[end - 1] == b'\r' {
+                            end -= 1;
+                        }
+                    }
+                    parts.push(&body[start..end.max(start)]);
+                }
+                if closing {
+                    return Ok(parts);
+                }
+                current_start = Some(line_end);
+            }
+        }
+        pos = line_end;
+    }
+
+    Err(Error::InvalidMime {
+        reason: format!("missing closing boundary \"{delimiter}--\""),
+    })
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let mut end = line.len();
+    while end > 0 && matches!(line[end - 1], b'\r' | b'\n') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Convert bare LF line endings to CRLF.
+fn to_crlf(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 32);
+    let mut prev = 0u8;
+    for &b in data {
+        if b == b'\n' && prev != b'\r' {
+            out.push(b'\r');
+        }
+        out.push(b);
+        prev = b;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SequoiaEngine;
+    use crate::types::{KeyGenOptions, UserId};
+    use secrecy::ExposeSecret;
+
+    fn sample_message() -> MimePart {
+        MimePart::multipart(
+            "mixed",
+            vec![
+                MimePart::text("Quarterly numbers attached.\n"),
+                MimePart::attachment("report.csv", "text/csv", b"q,revenue\n1,100\n".to_vec()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_parse_headers_and_params() {
+        let raw = b"From: Alice <alice@example.com>\r\nSubject: folded\r\n  subject\r\n\
+            Content-Type: multipart/signed; micalg=pgp-sha512;\r\n\
+            \tprotocol=\"application/pgp-signature\"; boundary=\"b;1\"\r\n\r\n\
+            preamble\r\n--b;1\r\nContent-Type: text/plain\r\n\r\nhi\r\n\
+            --b;1\r\nContent-Type: application/pgp-signature\r\n\r\nsig\r\n--b;1--\r\n";
+        let part = MimePart::parse(raw).unwrap();
+
+        assert_eq!(part.header("subject"), Some("folded subject"));
+        assert!(part.is_pgp_signed());
+        assert_eq!(part.content_type_param("boundary").as_deref(), Some("b;1"));
+        assert_eq!(part.children.len(), 2);
+        assert_eq!(part.children[0].body, b"hi");
+        assert_eq!(part.children[1].body, b"sig");
+    }
+
+    #[test]
+    fn test_compose_and_parse_round_trip() {
+        let message = sample_message();
+        let parsed = MimePart::parse(&message.to_bytes()).unwrap();
+
+        assert_eq!(parsed.content_type(), "multipart/mixed");
+        assert_eq!(parsed.children.len(), 2);
+        assert_eq!(parsed.children[0].body, b"Quarterly numbers attached.\r\n");
+        assert_eq!(parsed.children[1].filename().as_deref(), Some("report.csv"));
+        assert_eq!(parsed.children[1].body, b"q,revenue\n1,100\n");
+    }
+
+    #[test]
+    fn test_encrypted_signed_round_trip() {
+        let engine = SequoiaEngine::new();
+        let alice = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let bob = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let signed = compose_signed(
+            &engine,
+            &sample_message(),
+            alice.secret_key.expose_secret(),
+            None,
+        )
+        .unwrap();
+        let encrypted = compose_encrypted(&engine, &signed, &[bob.public_key.clone()])
+            .unwrap()
+            .with_header("Subject", "Report")
+            .with_header("MIME-Version", "1.0");
+
+        let message = MimePart::parse(&encrypted.to_bytes()).unwrap();
+        assert!(message.is_pgp_encrypted());
+
+        let opened = decrypt_message(
+            &engine,
+            &message,
+            bob.secret_key.expose_secret(),
+            None,
+            &[bob.public_key.clone(), alice.public_key.clone()],
+        )
+        .unwrap();
+
+        assert!(opened.encrypted);
+        let signature = opened.signature.clone().unwrap();
+        assert!(signature.valid);
+        assert_eq!(
+            signature.signer_fingerprint.as_deref(),
+            Some(alice.fingerprint.0.as_str())
+        );
+        assert_eq!(opened.body.children[1].body, b"q,revenue\n1,100\n");
+        assert!(
+            String::from_utf8_lossy(&opened.to_message_bytes()).starts_with("Subject: Report\r\n")
+        );
+    }
+
+    #[test]
+    fn test_signed_verifies_with_lf_line_endings() {
+        let engine = SequoiaEngine::new();
+        let alice = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+
+        let signed = compose_signed(
+            &engine,
+            &MimePart::text("line one\nline two\n"),
+            alice.secret_key.expose_secret(),
+            None,
+        )
+        .unwrap();
+        // Mail stored on disk frequently has its CRLFs converted to LF.
+        let lf_only: Vec<u8> = signed
+            .to_bytes()
+            .into_iter()
+            .filter(|&b| b != b'\r')
+            .collect();
+        let message = MimePart::parse(&lf_only).unwrap();
+
+        let opened =
+            decrypt_message(&engine, &message, &[], None, &[alice.public_key.clone()]).unwrap();
+        assert!(!opened.encrypted);
+        assert!(opened.signature.unwrap().valid);
+    }
+
+    #[test]
+    fn test_tampered_signed_content_fails() {
+        let engine = SequoiaEngine::new();
+        let alice = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+
+        let signed = compose_signed(
+            &engine,
+            &MimePart::text("pay 100 EUR\n"),
+            alice.secret_key.expose_secret(),
+            None,
+        )
+        .unwrap();
+        let tampered = String::from_utf8(signed.to_bytes())
+            .unwrap()
+            .replace("pay 100 EUR", "pay 900 EUR");
+        let message = MimePart::parse(tampered.as_bytes()).unwrap();
+
+        let opened =
+            decrypt_message(&engine, &message, &[], None, &[alice.public_key.clone()]).unwrap();
+        assert!(!opened.signature.unwrap().valid);
+    }
+
+    #[test]
+    fn test_plain_message_rejected() {
+        let engine = SequoiaEngine::new();
+        let message = MimePart::parse(b"Subject: hi\r\n\r\nplain body\r\n").unwrap();
+        assert!(matches!(
+            decrypt_message(&engine, &message, &[], None, &[]),
+            Err(Error::InvalidMime { .. })
+        ));
+    }
+}
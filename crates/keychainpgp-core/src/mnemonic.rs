@@ -0,0 +1,141 @@
+//! Recovery phrases for deterministic key generation.
+//!
+//! A BIP39 mnemonic (24 English words) is stretched into a 64-byte seed, and
+//! the secret of each key in a certificate is derived from that seed under
+//! its own label. Together with the key creation time, the phrase rebuilds
+//! the same keys, and so the same fingerprint, on any device.
+
+use bip39::{Language, Mnemonic};
+use sequoia_openpgp::types::HashAlgorithm;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// Number of words in a generated recovery phrase (256 bits of entropy).
+pub const WORD_COUNT: usize = 24;
+
+/// Domain separation prefix for every derived key secret.
+const DERIVATION_CONTEXT: &[u8] = b"KeychainPGP mnemonic key v1";
+
+/// Label of the Ed25519 primary (certification) key.
+pub(crate) const PRIMARY_KEY: &str = "primary";
+/// Label of the Ed25519 signing subkey.
+pub(crate) const SIGNING_KEY: &str = "signing";
+/// Label of the X25519 encryption subkey.
+pub(crate) const ENCRYPTION_KEY: &str = "encryption";
+/// Label of the Ed25519 authentication subkey.
+pub(crate) const AUTHENTICATION_KEY: &str = "authentication";
+
+/// Generate a new random recovery phrase.
+#[must_use]
+pub fn generate_mnemonic() -> Zeroizing<String> {
+    let mut entropy = Zeroizing::new([0u8; WORD_COUNT / 3 * 4]);
+    crate::crypto_random(entropy.as_mut());
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy.as_ref())
+        .expect("entropy length matches the word count");
+    Zeroizing::new(mnemonic.to_string())
+}
+
+/// Check a recovery phrase and return it in canonical form: lowercase words
+/// separated by single spaces.
+///
+/// Fails if a word is not in the BIP39 English word list, the word count is
+/// wrong, or the checksum does not match (e.g. a mistyped word).
+pub fn normalize_mnemonic(phrase: &str) -> Result<Zeroizing<String>> {
+    parse(phrase).map(|mnemonic| Zeroizing::new(mnemonic.to_string()))
+}
+
+/// Seed from which the secrets of all keys of one certificate are derived.
+pub(crate) struct KeySeed(Zeroizing<[u8; 64]>);
+
+impl KeySeed {
+    /// Stretch a recovery phrase into a seed (BIP39, empty passphrase).
+    pub(crate) fn from_mnemonic(phrase: &str) -> Result<Self> {
+        Ok(Self(Zeroizing::new(parse(phrase)?.to_seed_normalized(""))))
+    }
+
+    /// Derive the 32-byte secret of the key with the given label.
+    pub(crate) fn derive(&self, label: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let mut ctx = HashAlgorithm::SHA256
+            .context()
+            .map_err(|e| Error::Internal(format!("hash error: {e}")))?
+            .for_digest();
+        ctx.update(DERIVATION_CONTEXT);
+        ctx.update(&[0]);
+        ctx.update(label.as_bytes());
+        ctx.update(&[0]);
+        ctx.update(self.0.as_ref());
+        ctx.into_digest()
+            .map(Zeroizing::new)
+            .map_err(|e| Error::Internal(format!("hash error: {e}")))
+    }
+}
+
+fn parse(phrase: &str) -> Result<Mnemonic> {
+    let words = Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    Mnemonic::parse_in(Language::English, words.as_str()).map_err(|e| Error::InvalidMnemonic {
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_normalize() {
+        let phrase = generate_mnemonic();
+        assert_eq!(phrase.split(' ').count(), WORD_COUNT);
+
+        let messy = format!("  {}\n", phrase.to_uppercase().replace(' ', "   "));
+        assert_eq!(*normalize_mnemonic(&messy).unwrap(), *phrase);
+    }
+
+    #[test]
+    fn test_rejects_bad_phrases() {
+        let phrase = generate_mnemonic();
+        let mut words: Vec<&str> = phrase.split(' ').collect();
+
+        // Unknown word
+        words[3] = "keychainpgp";
+        let err = normalize_mnemonic(&words.join(" ")).unwrap_err();
+        assert!(matches!(err, Error::InvalidMnemonic { .. }));
+
+        // Known words, bad checksum
+        let err = normalize_mnemonic(&["abandon"; WORD_COUNT].join(" ")).unwrap_err();
+        assert!(matches!(err, Error::InvalidMnemonic { .. }));
+
+        // Wrong word count
+        let err = normalize_mnemonic(&words[4..9].join(" ")).unwrap_err();
+        assert!(matches!(err, Error::InvalidMnemonic { .. }));
+    }
+
+    #[test]
+    fn test_derivation_is_deterministic_and_labelled() {
+        let phrase = generate_mnemonic();
+        let a = KeySeed::from_mnemonic(&phrase).unwrap();
+        let b = KeySeed::from_mnemonic(&phrase).unwrap();
+
+        assert_eq!(
+            *a.derive(PRIMARY_KEY).unwrap(),
+            *b.derive(PRIMARY_KEY).unwrap()
+        );
+        assert_eq!(a.derive(PRIMARY_KEY).unwrap().len(), 32);
+        assert_ne!(
+            *a.derive(PRIMARY_KEY).unwrap(),
+            *a.derive(SIGNING_KEY).unwrap()
+        );
+
+        let other = KeySeed::from_mnemonic(&generate_mnemonic()).unwrap();
+        assert_ne!(
+            *a.derive(PRIMARY_KEY).unwrap(),
+            *other.derive(PRIMARY_KEY).unwrap()
+        );
+    }
+}
@@ -0,0 +1,1338 @@
+//! A fake [`CryptoEngine`] for tests.
+//!
+//! [`MockEngine`] implements every engine operation on a small readable text
+//! format instead of real OpenPGP, so tests of code built on the engine (the
+//! keyring, CLI and UI commands) run without slow key generation. Its keys
+//! are deterministic: a fresh engine generating the same keys in the same
+//! order hands out the same fingerprints. Failures can be scripted per
+//! operation to exercise error paths, and every call is recorded.
+//!
+//! Only available with the `mock` feature. The output is armored like
+//! OpenPGP data but can only be read back by `MockEngine`.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use secrecy::{ExposeSecret, SecretBox};
+use sequoia_openpgp::types::HashAlgorithm;
+
+use crate::backend::{BackendKey, SecretKeyBackend};
+use crate::encoding::{hex_decode, hex_encode};
+use crate::engine::CryptoEngine;
+use crate::error::{Error, Result};
+use crate::mnemonic::KeySeed;
+use crate::types::{
+    CertInfo, Fingerprint, GeneratedKeyPair, KeyAlgorithm, KeyCapability, KeyGenOptions,
+    MessageSessionKey, RecipientCheck, RecipientStatus, S2kMode, S2kType, SignOptions,
+    SignatureNotation, SubkeyInfo, UserId, VerifyResult,
+};
+
+/// Creation time of generated keys, and the engine's clock, unless set
+/// otherwise: 2024-01-01T00:00:00Z.
+pub const MOCK_EPOCH: u64 = 1_704_067_200;
+
+/// Expiration date reported by a scripted [`MockFailure::KeyExpired`].
+const SCRIPTED_EXPIRATION: &str = "2024-01-01T00:00:00+00:00";
+
+/// An engine operation, one per [`CryptoEngine`] method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockOperation {
+    GenerateKeyPair,
+    ChangePassphrase,
+    AddAdsk,
+    ExportSecretSubkeys,
+    AddDesignatedRevoker,
+    RevokeAsDesignatedRevoker,
+    VerifyDesignatedRevocation,
+    Encrypt,
+    CheckRecipients,
+    Decrypt,
+    DecryptRevealingSessionKey,
+    DecryptWithSessionKey,
+    Sign,
+    SignWithOptions,
+    SignWithBackend,
+    DecryptWithBackend,
+    Verify,
+    SignDetached,
+    VerifyDetached,
+    InspectKey,
+    ArmorKey,
+    PublicKey,
+    EncryptSymmetric,
+}
+
+/// An error a test can make an operation fail with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockFailure {
+    /// [`Error::BadPassphrase`].
+    BadPassphrase,
+    /// [`Error::KeyExpired`], expired on [`MOCK_EPOCH`].
+    KeyExpired,
+    /// [`Error::NoSecretKey`].
+    NoSecretKey,
+}
+
+impl MockFailure {
+    fn to_error(self) -> Error {
+        match self {
+            Self::BadPassphrase => Error::BadPassphrase,
+            Self::KeyExpired => Error::KeyExpired {
+                expiration: SCRIPTED_EXPIRATION.into(),
+            },
+            Self::NoSecretKey => Error::NoSecretKey,
+        }
+    }
+}
+
+/// A recorded call to the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// The operation called.
+    pub operation: MockOperation,
+    /// Fingerprints of the keys passed in, in argument order. Keys that do
+    /// not parse are left out.
+    pub keys: Vec<String>,
+}
+
+struct ScriptedFailure {
+    operation: MockOperation,
+    failure: MockFailure,
+    once: bool,
+}
+
+#[derive(Default)]
+struct State {
+    calls: Vec<MockCall>,
+    failures: Vec<ScriptedFailure>,
+}
+
+/// A deterministic fake [`CryptoEngine`] for tests.
+///
+/// Passphrases, recipients, expiration and revocation are checked like the
+/// real engine does, so e.g. decrypting with the wrong key fails with
+/// [`Error::NoSecretKey`]. Expiration is judged against the engine's own
+/// clock, which stands still at [`MOCK_EPOCH`] until [`MockEngine::set_time`].
+pub struct MockEngine {
+    now: AtomicU64,
+    generated: AtomicU64,
+    state: Mutex<State>,
+}
+
+impl Default for MockEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockEngine {
+    /// Create a mock engine with its clock at [`MOCK_EPOCH`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            now: AtomicU64::new(MOCK_EPOCH),
+            generated: AtomicU64::new(0),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Set the engine's clock, used for expiration checks.
+    pub fn set_time(&self, time: SystemTime) {
+        self.now.store(unix_secs(time), Ordering::Relaxed);
+    }
+
+    /// Make the next call of `operation` fail with `failure`.
+    pub fn fail_next(&self, operation: MockOperation, failure: MockFailure) {
+        self.script(operation, failure, true);
+    }
+
+    /// Make every call of `operation` fail with `failure`, until
+    /// [`MockEngine::clear_failures`].
+    pub fn fail_always(&self, operation: MockOperation, failure: MockFailure) {
+        self.script(operation, failure, false);
+    }
+
+    /// Remove all scripted failures.
+    pub fn clear_failures(&self) {
+        self.lock().failures.clear();
+    }
+
+    /// Return all calls made so far, oldest first.
+    #[must_use]
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.lock().calls.clone()
+    }
+
+    /// Return how often `operation` has been called.
+    #[must_use]
+    pub fn call_count(&self, operation: MockOperation) -> usize {
+        self.lock()
+            .calls
+            .iter()
+            .filter(|call| call.operation == operation)
+            .count()
+    }
+
+    /// Forget the calls recorded so far.
+    pub fn clear_calls(&self) {
+        self.lock().calls.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn script(&self, operation: MockOperation, failure: MockFailure, once: bool) {
+        self.lock().failures.push(ScriptedFailure {
+            operation,
+            failure,
+            once,
+        });
+    }
+
+    /// Record a call and return the failure scripted for it, if any.
+    fn enter(&self, operation: MockOperation, keys: &[&[u8]]) -> Option<MockFailure> {
+        let keys = keys
+            .iter()
+            .filter_map(|key| MockKey::parse(key).ok())
+            .map(|key| key.fingerprint)
+            .collect();
+        let mut state = self.lock();
+        state.calls.push(MockCall { operation, keys });
+        let index = state
+            .failures
+            .iter()
+            .position(|f| f.operation == operation)?;
+        let failure = state.failures[index].failure;
+        if state.failures[index].once {
+            state.failures.remove(index);
+        }
+        Some(failure)
+    }
+
+    /// Like [`MockEngine::enter`], returning the scripted failure as error.
+    fn begin(&self, operation: MockOperation, keys: &[&[u8]]) -> Result<()> {
+        match self.enter(operation, keys) {
+            Some(failure) => Err(failure.to_error()),
+            None => Ok(()),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::Relaxed)
+    }
+
+    fn recipient_status(&self, key: &MockKey) -> RecipientStatus {
+        if key.revoked {
+            RecipientStatus::Revoked
+        } else if let Some(expired_at) = self.expired(key) {
+            RecipientStatus::Expired { expired_at }
+        } else {
+            RecipientStatus::Usable
+        }
+    }
+
+    /// The expiration time (RFC 3339) of `key`, if it has expired.
+    fn expired(&self, key: &MockKey) -> Option<String> {
+        key.expires.filter(|&t| t <= self.now()).map(rfc3339)
+    }
+
+    /// Check that `key` can be used to sign.
+    fn signing_key(&self, secret_key: &[u8], passphrase: Option<&[u8]>) -> Result<MockKey> {
+        let key = MockKey::parse_secret(secret_key, |reason| Error::Signing { reason })?;
+        key.unlock(passphrase)?;
+        if key.revoked {
+            return Err(Error::KeyRevoked);
+        }
+        if let Some(expiration) = self.expired(&key) {
+            return Err(Error::KeyExpired { expiration });
+        }
+        Ok(key)
+    }
+
+    fn decrypt_message(
+        &self,
+        ciphertext: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<MockMessage> {
+        let key = MockKey::parse_secret(secret_key, |reason| Error::Decryption { reason })?;
+        let message = MockMessage::parse(ciphertext)?;
+        if !message.recipients.contains(&key.fingerprint) {
+            return Err(Error::NoSecretKey);
+        }
+        key.unlock(passphrase)?;
+        Ok(message)
+    }
+
+    fn signed_message(
+        &self,
+        data: &[u8],
+        signer: &MockKey,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>> {
+        if let Some(uid) = &options.signer_user_id
+            && !signer
+                .user_ids
+                .iter()
+                .any(|u| u == uid || parse_user_id(u).email.as_deref() == Some(uid.as_str()))
+        {
+            return Err(Error::Signing {
+                reason: format!("'{uid}' is not a User ID of the signing key"),
+            });
+        }
+        let mut fields = vec![("Signer", signer.fingerprint.clone())];
+        for notation in &options.notations {
+            fields.push(("Notation", format!("{}={}", notation.name, notation.value)));
+        }
+        if let Some(expiration) = options.expiration {
+            fields.push(("Expires", (self.now() + expiration.as_secs()).to_string()));
+        }
+        if let Some(uid) = &options.signer_user_id {
+            fields.push(("Signer-User-ID", uid.clone()));
+        }
+        for recipient in &options.intended_recipients {
+            let recipient = MockKey::parse(recipient).map_err(|reason| Error::Signing {
+                reason: format!("invalid intended recipient key: {reason}"),
+            })?;
+            fields.push(("Intended-Recipient", recipient.fingerprint));
+        }
+        fields.push(("Digest", digest(&[data])));
+        fields.push(("Data", hex_encode(data)));
+        Ok(armor("SIGNED MESSAGE", &fields))
+    }
+
+    fn check_signature(&self, fields: &Fields, data: &[u8], signer_key: &[u8]) -> VerifyResult {
+        let signer = fields.get("Signer").unwrap_or_default();
+        let valid = MockKey::parse(signer_key).is_ok_and(|key| key.fingerprint == signer)
+            && fields.get("Digest") == Some(digest(&[data]).as_str());
+        let expires = fields.get("Expires").and_then(|t| t.parse::<u64>().ok());
+        VerifyResult {
+            valid: valid && expires.is_none_or(|t| t > self.now()),
+            signer_fingerprint: Some(signer.to_string()),
+            notations: fields
+                .all("Notation")
+                .filter_map(|n| n.split_once('='))
+                .map(|(name, value)| SignatureNotation {
+                    name: name.into(),
+                    value: value.into(),
+                })
+                .collect(),
+            expires_at: expires.map(rfc3339),
+            signer_user_id: fields.get("Signer-User-ID").map(String::from),
+            intended_recipients: fields.all("Intended-Recipient").map(String::from).collect(),
+        }
+    }
+}
+
+impl CryptoEngine for MockEngine {
+    fn generate_key_pair(&self, options: KeyGenOptions) -> Result<GeneratedKeyPair> {
+        self.begin(MockOperation::GenerateKeyPair, &[])?;
+        let user_id = options.user_id.to_openpgp_string();
+        let created = options.creation_time.map_or(MOCK_EPOCH, unix_secs);
+        let fingerprint = match &options.mnemonic {
+            Some(phrase) => {
+                if options.algorithm != KeyAlgorithm::Ed25519 {
+                    return Err(Error::KeyGeneration {
+                        reason: "recovery phrases are only supported for Ed25519 keys".into(),
+                    });
+                }
+                let seed = KeySeed::from_mnemonic(phrase.expose_secret())?;
+                let secret = seed.derive("mock")?;
+                fingerprint_of(&[secret.as_slice(), &created.to_be_bytes()])
+            }
+            None => {
+                let n = self.generated.fetch_add(1, Ordering::Relaxed);
+                fingerprint_of(&[user_id.as_bytes(), &n.to_be_bytes()])
+            }
+        };
+
+        let parse_all = |keys: &[Vec<u8>]| {
+            keys.iter()
+                .map(|k| MockKey::parse(k).map(|k| k.fingerprint))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|reason| Error::KeyGeneration { reason })
+        };
+        let key = MockKey {
+            secret: true,
+            fingerprint: fingerprint.clone(),
+            user_ids: vec![user_id],
+            algorithm: options.algorithm,
+            created,
+            expires: options.expiration.map(|d| created + d.as_secs()),
+            auth_subkey: options.auth_subkey,
+            adsks: parse_all(&options.adsks)?,
+            revokers: parse_all(&options.revokers)?,
+            revoked: false,
+            passphrase: options
+                .passphrase
+                .as_ref()
+                .map(|p| hex_encode(p.expose_secret())),
+            s2k: s2k_name(options.s2k).into(),
+            primary_offline: false,
+        };
+
+        let public_key = key.public().to_armor();
+        let revocation_cert = MockKey {
+            revoked: true,
+            ..key.public()
+        }
+        .to_armor();
+        Ok(GeneratedKeyPair {
+            public_key,
+            secret_key: SecretBox::new(Box::new(key.to_armor())),
+            fingerprint: Fingerprint::new(fingerprint),
+            revocation_cert,
+        })
+    }
+
+    fn change_passphrase(
+        &self,
+        secret_key: &[u8],
+        old_passphrase: Option<&[u8]>,
+        new_passphrase: Option<&[u8]>,
+        s2k: S2kMode,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::ChangePassphrase, &[secret_key])?;
+        let mut key = MockKey::parse_secret(secret_key, |reason| Error::KeyUpdate { reason })?;
+        key.unlock(old_passphrase)?;
+        key.passphrase = new_passphrase.map(hex_encode);
+        key.s2k = s2k_name(s2k).into();
+        Ok(key.to_armor())
+    }
+
+    fn add_adsk(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        adsk_key: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::AddAdsk, &[secret_key, adsk_key])?;
+        let mut key = MockKey::parse_secret(secret_key, |reason| Error::KeyUpdate { reason })?;
+        let adsk = MockKey::parse(adsk_key).map_err(|reason| Error::KeyUpdate { reason })?;
+        key.unlock_primary(passphrase)?;
+        if !key.adsks.contains(&adsk.fingerprint) {
+            key.adsks.push(adsk.fingerprint);
+        }
+        Ok(key.to_armor())
+    }
+
+    fn export_secret_subkeys(&self, secret_key: &[u8]) -> Result<Vec<u8>> {
+        self.begin(MockOperation::ExportSecretSubkeys, &[secret_key])?;
+        let mut key = MockKey::parse_secret(secret_key, |reason| Error::KeyUpdate { reason })?;
+        key.primary_offline = true;
+        Ok(key.to_armor())
+    }
+
+    fn add_designated_revoker(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        revoker_key: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.begin(
+            MockOperation::AddDesignatedRevoker,
+            &[secret_key, revoker_key],
+        )?;
+        let mut key = MockKey::parse_secret(secret_key, |reason| Error::KeyUpdate { reason })?;
+        let revoker = MockKey::parse(revoker_key).map_err(|reason| Error::KeyUpdate { reason })?;
+        key.unlock_primary(passphrase)?;
+        if !key.revokers.contains(&revoker.fingerprint) {
+            key.revokers.push(revoker.fingerprint);
+        }
+        Ok(key.to_armor())
+    }
+
+    fn revoke_as_designated_revoker(
+        &self,
+        target_key: &[u8],
+        revoker_secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        self.begin(
+            MockOperation::RevokeAsDesignatedRevoker,
+            &[target_key, revoker_secret_key],
+        )?;
+        let target = MockKey::parse(target_key).map_err(|reason| Error::KeyUpdate { reason })?;
+        let revoker =
+            MockKey::parse_secret(revoker_secret_key, |reason| Error::KeyUpdate { reason })?;
+        revoker.unlock_primary(passphrase)?;
+        if !target.revokers.contains(&revoker.fingerprint) {
+            return Err(Error::KeyUpdate {
+                reason: format!(
+                    "{} is not a designated revoker of {}",
+                    revoker.fingerprint, target.fingerprint
+                ),
+            });
+        }
+        Ok(MockKey {
+            revoked: true,
+            ..target.public()
+        }
+        .to_armor())
+    }
+
+    fn verify_designated_revocation(&self, key: &[u8], revoker_keys: &[Vec<u8>]) -> Result<bool> {
+        let mut keys = vec![key];
+        keys.extend(revoker_keys.iter().map(Vec::as_slice));
+        self.begin(MockOperation::VerifyDesignatedRevocation, &keys)?;
+        let target = MockKey::parse(key).map_err(|reason| Error::InvalidArmor { reason })?;
+        // Mock revocations carry no signature: any known revoker vouches
+        Ok(target.revoked
+            && revoker_keys
+                .iter()
+                .filter_map(|k| MockKey::parse(k).ok())
+                .any(|revoker| target.revokers.contains(&revoker.fingerprint)))
+    }
+
+    fn encrypt(&self, plaintext: &[u8], recipient_keys: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let keys: Vec<&[u8]> = recipient_keys.iter().map(Vec::as_slice).collect();
+        self.begin(MockOperation::Encrypt, &keys)?;
+        if recipient_keys.is_empty() {
+            return Err(Error::NoRecipients);
+        }
+        let mut recipients = Vec::new();
+        for key in recipient_keys {
+            let key = MockKey::parse(key).map_err(|reason| Error::Encryption {
+                reason: format!("invalid recipient key: {reason}"),
+            })?;
+            let status = self.recipient_status(&key);
+            if !status.is_usable() {
+                return Err(Error::Encryption {
+                    reason: format!("cannot encrypt to {}: {status}", key.fingerprint),
+                });
+            }
+            for fp in std::iter::once(&key.fingerprint).chain(&key.adsks) {
+                if !recipients.contains(fp) {
+                    recipients.push(fp.clone());
+                }
+            }
+        }
+        Ok(MockMessage::new(plaintext, recipients, None).to_armor())
+    }
+
+    fn check_recipients(&self, recipient_keys: &[Vec<u8>]) -> Vec<RecipientCheck> {
+        let keys: Vec<&[u8]> = recipient_keys.iter().map(Vec::as_slice).collect();
+        // This operation cannot fail, so a scripted failure shows in the
+        // status of every key instead.
+        let failure = self.enter(MockOperation::CheckRecipients, &keys);
+        recipient_keys
+            .iter()
+            .map(|key| match MockKey::parse(key) {
+                Ok(key) => RecipientCheck {
+                    status: match failure {
+                        Some(MockFailure::KeyExpired) => RecipientStatus::Expired {
+                            expired_at: SCRIPTED_EXPIRATION.into(),
+                        },
+                        Some(failure) => RecipientStatus::Invalid {
+                            reason: failure.to_error().to_string(),
+                        },
+                        None => self.recipient_status(&key),
+                    },
+                    user_id: key.user_ids.first().cloned(),
+                    fingerprint: Some(key.fingerprint),
+                },
+                Err(reason) => RecipientCheck {
+                    fingerprint: None,
+                    user_id: None,
+                    status: RecipientStatus::Invalid { reason },
+                },
+            })
+            .collect()
+    }
+
+    fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::Decrypt, &[secret_key])?;
+        Ok(self
+            .decrypt_message(ciphertext, secret_key, passphrase)?
+            .data)
+    }
+
+    fn decrypt_revealing_session_key(
+        &self,
+        ciphertext: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, MessageSessionKey)> {
+        self.begin(MockOperation::DecryptRevealingSessionKey, &[secret_key])?;
+        let message = self.decrypt_message(ciphertext, secret_key, passphrase)?;
+        let session_key = message.session_key.clone();
+        Ok((message.data, session_key))
+    }
+
+    fn decrypt_with_session_key(
+        &self,
+        ciphertext: &[u8],
+        session_key: &MessageSessionKey,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::DecryptWithSessionKey, &[])?;
+        let message = MockMessage::parse(ciphertext)?;
+        if message.session_key.key != session_key.key {
+            return Err(Error::Decryption {
+                reason: "session key does not fit this message".into(),
+            });
+        }
+        Ok(message.data)
+    }
+
+    fn sign(&self, data: &[u8], secret_key: &[u8], passphrase: Option<&[u8]>) -> Result<Vec<u8>> {
+        self.begin(MockOperation::Sign, &[secret_key])?;
+        let signer = self.signing_key(secret_key, passphrase)?;
+        self.signed_message(data, &signer, &SignOptions::new())
+    }
+
+    fn sign_with_options(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::SignWithOptions, &[secret_key])?;
+        let signer = self.signing_key(secret_key, passphrase)?;
+        self.signed_message(data, &signer, options)
+    }
+
+    fn sign_with_backend(
+        &self,
+        data: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::SignWithBackend, &[public_key])?;
+        let signer = MockKey::parse(public_key).map_err(|reason| Error::Signing { reason })?;
+        if !backend.has_key(&signer.backend_key())? {
+            return Err(Error::NoSecretKey);
+        }
+        self.signed_message(data, &signer, options)
+    }
+
+    fn decrypt_with_backend(
+        &self,
+        ciphertext: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::DecryptWithBackend, &[public_key])?;
+        let key = MockKey::parse(public_key).map_err(|reason| Error::Decryption { reason })?;
+        let message = MockMessage::parse(ciphertext)?;
+        if !message.recipients.contains(&key.fingerprint) || !backend.has_key(&key.backend_key())? {
+            return Err(Error::NoSecretKey);
+        }
+        Ok(message.data)
+    }
+
+    fn verify(&self, signed_data: &[u8], signer_key: &[u8]) -> Result<VerifyResult> {
+        self.begin(MockOperation::Verify, &[signer_key])?;
+        let fields =
+            dearmor(signed_data, "SIGNED MESSAGE").ok_or_else(|| Error::VerificationFailed {
+                reason: "invalid signed data: not a mock signed message".into(),
+            })?;
+        let data = decode_data(&fields).map_err(|reason| Error::VerificationFailed { reason })?;
+        Ok(self.check_signature(&fields, &data, signer_key))
+    }
+
+    fn sign_detached(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        self.begin(MockOperation::SignDetached, &[secret_key])?;
+        let signer = self.signing_key(secret_key, passphrase)?;
+        Ok(armor(
+            "SIGNATURE",
+            &[("Signer", signer.fingerprint), ("Digest", digest(&[data]))],
+        ))
+    }
+
+    fn verify_detached(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        signer_key: &[u8],
+    ) -> Result<VerifyResult> {
+        self.begin(MockOperation::VerifyDetached, &[signer_key])?;
+        let fields = dearmor(signature, "SIGNATURE").ok_or_else(|| Error::VerificationFailed {
+            reason: "invalid signature: not a mock signature".into(),
+        })?;
+        Ok(self.check_signature(&fields, data, signer_key))
+    }
+
+    fn inspect_key(&self, key_data: &[u8]) -> Result<CertInfo> {
+        self.begin(MockOperation::InspectKey, &[key_data])?;
+        let key = MockKey::parse(key_data).map_err(|reason| Error::InvalidArmor { reason })?;
+        Ok(key.info())
+    }
+
+    fn armor_key(&self, key_data: &[u8]) -> Result<String> {
+        self.begin(MockOperation::ArmorKey, &[key_data])?;
+        let key = MockKey::parse(key_data).map_err(|reason| Error::InvalidArmor { reason })?;
+        String::from_utf8(key.to_armor()).map_err(|e| Error::Internal(e.to_string()))
+    }
+
+    fn public_key(&self, key_data: &[u8]) -> Result<Vec<u8>> {
+        self.begin(MockOperation::PublicKey, &[key_data])?;
+        let key = MockKey::parse(key_data).map_err(|reason| Error::InvalidArmor { reason })?;
+        Ok(key.public().to_armor())
+    }
+
+    fn encrypt_symmetric(&self, plaintext: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+        self.begin(MockOperation::EncryptSymmetric, &[])?;
+        Ok(MockMessage::new(plaintext, Vec::new(), Some(digest(&[passphrase]))).to_armor())
+    }
+}
+
+/// A parsed mock key. Secret keys carry their passphrase in the clear.
+#[derive(Clone)]
+struct MockKey {
+    secret: bool,
+    fingerprint: String,
+    user_ids: Vec<String>,
+    algorithm: KeyAlgorithm,
+    created: u64,
+    expires: Option<u64>,
+    auth_subkey: bool,
+    adsks: Vec<String>,
+    revokers: Vec<String>,
+    revoked: bool,
+    passphrase: Option<String>,
+    s2k: String,
+    primary_offline: bool,
+}
+
+impl MockKey {
+    fn parse(data: &[u8]) -> std::result::Result<Self, String> {
+        let (secret, fields) = match dearmor(data, "PRIVATE KEY BLOCK") {
+            Some(fields) => (true, fields),
+            None => (
+                false,
+                dearmor(data, "PUBLIC KEY BLOCK").ok_or("not a mock key")?,
+            ),
+        };
+        let number = |name: &str| -> std::result::Result<Option<u64>, String> {
+            fields
+                .get(name)
+                .map(|v| v.parse().map_err(|_| format!("bad {name} '{v}'")))
+                .transpose()
+        };
+        let algorithm = match fields.get("Algorithm") {
+            Some("Ed25519") => KeyAlgorithm::Ed25519,
+            Some(other) => other
+                .strip_prefix("RSA-")
+                .and_then(|bits| bits.parse().ok())
+                .map(KeyAlgorithm::Rsa)
+                .ok_or_else(|| format!("bad Algorithm '{other}'"))?,
+            None => return Err("missing Algorithm".into()),
+        };
+        Ok(Self {
+            secret,
+            fingerprint: fields
+                .get("Fingerprint")
+                .ok_or("missing Fingerprint")?
+                .into(),
+            user_ids: fields.all("User-ID").map(String::from).collect(),
+            algorithm,
+            created: number("Created")?.ok_or("missing Created")?,
+            expires: number("Expires")?,
+            auth_subkey: fields.flag("Auth-Subkey"),
+            adsks: fields.all("ADSK").map(String::from).collect(),
+            revokers: fields.all("Revoker").map(String::from).collect(),
+            revoked: fields.flag("Revoked"),
+            passphrase: fields.get("Passphrase").map(String::from),
+            s2k: fields.get("S2K").unwrap_or("Iterated").into(),
+            primary_offline: fields.flag("Primary-Offline"),
+        })
+    }
+
+    fn parse_secret(data: &[u8], error: impl Fn(String) -> Error) -> Result<Self> {
+        let key = Self::parse(data).map_err(&error)?;
+        if !key.secret {
+            return Err(error("not a secret key".into()));
+        }
+        Ok(key)
+    }
+
+    fn unlock(&self, passphrase: Option<&[u8]>) -> Result<()> {
+        match &self.passphrase {
+            Some(expected) if passphrase.map(hex_encode).as_ref() != Some(expected) => {
+                Err(Error::BadPassphrase)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Unlock the primary key, needed to change what the key binds.
+    fn unlock_primary(&self, passphrase: Option<&[u8]>) -> Result<()> {
+        if self.primary_offline {
+            return Err(Error::NoSecretKey);
+        }
+        self.unlock(passphrase)
+    }
+
+    fn public(&self) -> Self {
+        Self {
+            secret: false,
+            passphrase: None,
+            primary_offline: false,
+            ..self.clone()
+        }
+    }
+
+    fn backend_key(&self) -> BackendKey {
+        BackendKey {
+            fingerprint: self.fingerprint.clone(),
+            keygrip: self.fingerprint.clone(),
+        }
+    }
+
+    fn info(&self) -> CertInfo {
+        let s2k = self.secret.then(|| match self.passphrase {
+            None => S2kType::Unprotected,
+            Some(_) if self.s2k == "Argon2" => S2kType::Argon2,
+            Some(_) => S2kType::Iterated,
+        });
+        let algorithm = match self.algorithm {
+            KeyAlgorithm::Ed25519 => "EdDSA",
+            KeyAlgorithm::Rsa(_) => "RSA",
+        };
+        let subkey = |label: &str, capability| SubkeyInfo {
+            fingerprint: fingerprint_of(&[self.fingerprint.as_bytes(), label.as_bytes()]),
+            algorithm: algorithm.into(),
+            created_at: rfc3339(self.created),
+            expires_at: self.expires.map(rfc3339),
+            capabilities: vec![capability],
+            is_revoked: false,
+            s2k,
+        };
+        let mut subkeys = vec![
+            subkey("sign", KeyCapability::Sign),
+            subkey("encrypt", KeyCapability::Encrypt),
+        ];
+        if self.auth_subkey {
+            subkeys.push(subkey("auth", KeyCapability::Authenticate));
+        }
+        subkeys.extend(self.adsks.iter().map(|fp| SubkeyInfo {
+            fingerprint: fp.clone(),
+            s2k: None,
+            ..subkey("adsk", KeyCapability::AdditionalDecryption)
+        }));
+        CertInfo {
+            fingerprint: Fingerprint::new(&self.fingerprint),
+            user_ids: self.user_ids.iter().map(|u| parse_user_id(u)).collect(),
+            algorithm: self.algorithm,
+            created_at: rfc3339(self.created),
+            expires_at: self.expires.map(rfc3339),
+            has_secret_key: self.secret,
+            is_revoked: self.revoked,
+            s2k: if self.primary_offline {
+                Some(S2kType::Other)
+            } else {
+                s2k
+            },
+            primary_key_offline: self.primary_offline,
+            subkeys,
+            designated_revokers: self.revokers.clone(),
+            possibly_revoked: false,
+        }
+    }
+
+    fn to_armor(&self) -> Vec<u8> {
+        let algorithm = match self.algorithm {
+            KeyAlgorithm::Ed25519 => "Ed25519".to_string(),
+            KeyAlgorithm::Rsa(bits) => format!("RSA-{bits}"),
+        };
+        let mut fields = vec![("Fingerprint", self.fingerprint.clone())];
+        fields.extend(self.user_ids.iter().map(|u| ("User-ID", u.clone())));
+        fields.push(("Algorithm", algorithm));
+        fields.push(("Created", self.created.to_string()));
+        if let Some(expires) = self.expires {
+            fields.push(("Expires", expires.to_string()));
+        }
+        if self.auth_subkey {
+            fields.push(("Auth-Subkey", "yes".into()));
+        }
+        fields.extend(self.adsks.iter().map(|fp| ("ADSK", fp.clone())));
+        fields.extend(self.revokers.iter().map(|fp| ("Revoker", fp.clone())));
+        if self.revoked {
+            fields.push(("Revoked", "yes".into()));
+        }
+        if self.secret {
+            if let Some(passphrase) = &self.passphrase {
+                fields.push(("Passphrase", passphrase.clone()));
+            }
+            fields.push(("S2K", self.s2k.clone()));
+            if self.primary_offline {
+                fields.push(("Primary-Offline", "yes".into()));
+            }
+            armor("PRIVATE KEY BLOCK", &fields)
+        } else {
+            armor("PUBLIC KEY BLOCK", &fields)
+        }
+    }
+}
+
+/// A parsed mock encrypted message.
+struct MockMessage {
+    recipients: Vec<String>,
+    passphrase_digest: Option<String>,
+    session_key: MessageSessionKey,
+    data: Vec<u8>,
+}
+
+impl MockMessage {
+    fn new(data: &[u8], recipients: Vec<String>, passphrase_digest: Option<String>) -> Self {
+        let key = digest(&[b"session key", data]);
+        Self {
+            recipients,
+            passphrase_digest,
+            session_key: MessageSessionKey {
+                algorithm: Some(9),
+                key: hex_decode(&key).unwrap_or_default(),
+            },
+            data: data.to_vec(),
+        }
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let invalid = |reason: String| Error::Decryption {
+            reason: format!("invalid message: {reason}"),
+        };
+        let fields =
+            dearmor(data, "MESSAGE").ok_or_else(|| invalid("not a mock message".into()))?;
+        let session_key = fields
+            .get("Session-Key")
+            .ok_or_else(|| invalid("missing Session-Key".into()))?
+            .parse()?;
+        Ok(Self {
+            recipients: fields.all("Recipient").map(String::from).collect(),
+            passphrase_digest: fields.get("Passphrase").map(String::from),
+            session_key,
+            data: decode_data(&fields).map_err(invalid)?,
+        })
+    }
+
+    fn to_armor(&self) -> Vec<u8> {
+        let mut fields: Vec<_> = self
+            .recipients
+            .iter()
+            .map(|fp| ("Recipient", fp.clone()))
+            .collect();
+        if let Some(passphrase) = &self.passphrase_digest {
+            fields.push(("Passphrase", passphrase.clone()));
+        }
+        fields.push(("Session-Key", self.session_key.to_string()));
+        fields.push(("Data", hex_encode(&self.data)));
+        armor("MESSAGE", &fields)
+    }
+}
+
+/// The `Name: value` lines of a mock armor block.
+struct Fields(Vec<(String, String)>);
+
+impl Fields {
+    fn get<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        self.all(name).next()
+    }
+
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some("yes")
+    }
+}
+
+fn armor(label: &str, fields: &[(&str, String)]) -> Vec<u8> {
+    let mut out = format!("-----BEGIN PGP {label}-----\nComment: KeychainPGP mock\n\n");
+    for (name, value) in fields {
+        out.push_str(&format!("Mock-{name}: {value}\n"));
+    }
+    out.push_str(&format!("-----END PGP {label}-----\n"));
+    out.into_bytes()
+}
+
+fn dearmor(data: &[u8], label: &str) -> Option<Fields> {
+    let text = std::str::from_utf8(data).ok()?;
+    let begin = format!("-----BEGIN PGP {label}-----");
+    let end = format!("-----END PGP {label}-----");
+    let body = &text[text.find(&begin)? + begin.len()..];
+    let body = &body[..body.find(&end)?];
+    let fields: Vec<_> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("Mock-")?.split_once(": "))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    (!fields.is_empty()).then_some(Fields(fields))
+}
+
+fn decode_data(fields: &Fields) -> std::result::Result<Vec<u8>, String> {
+    hex_decode(fields.get("Data").ok_or("missing Data")?)
+}
+
+/// Uppercase hex SHA-256 digest of the concatenated parts.
+fn digest(parts: &[&[u8]]) -> String {
+    let mut ctx = HashAlgorithm::SHA256
+        .context()
+        .expect("SHA-256 is supported")
+        .for_digest();
+    for part in parts {
+        ctx.update(part);
+    }
+    hex_encode(&ctx.into_digest().expect("SHA-256 is supported"))
+}
+
+/// A v4-sized (40 hex digit) fingerprint derived from the parts.
+fn fingerprint_of(parts: &[&[u8]]) -> String {
+    digest(parts)[..40].to_string()
+}
+
+fn parse_user_id(user_id: &str) -> UserId {
+    match user_id.rsplit_once('<') {
+        Some((name, email)) => {
+            let name = name.trim();
+            UserId {
+                name: (!name.is_empty()).then(|| name.to_string()),
+                email: Some(email.trim_end_matches('>').to_string()),
+            }
+        }
+        None => UserId {
+            name: Some(user_id.to_string()),
+            email: None,
+        },
+    }
+}
+
+fn s2k_name(s2k: S2kMode) -> &'static str {
+    match s2k {
+        S2kMode::Iterated => "Iterated",
+        S2kMode::Argon2(_) => "Argon2",
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn rfc3339(secs: u64) -> String {
+    chrono::DateTime::<chrono::Utc>::from(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+        .to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCiphertext, BackendSignature, DecryptedValue};
+
+    fn generate(engine: &MockEngine, email: &str, passphrase: Option<&str>) -> GeneratedKeyPair {
+        let mut options = KeyGenOptions::new(UserId::new("Test", email));
+        if let Some(p) = passphrase {
+            options = options.with_passphrase(SecretBox::new(Box::new(p.as_bytes().to_vec())));
+        }
+        engine.generate_key_pair(options).unwrap()
+    }
+
+    #[test]
+    fn test_keys_are_deterministic() {
+        let first = generate(&MockEngine::new(), "alice@example.com", None);
+        let again = generate(&MockEngine::new(), "alice@example.com", None);
+        assert_eq!(first.fingerprint, again.fingerprint);
+        assert_eq!(first.public_key, again.public_key);
+
+        let engine = MockEngine::new();
+        let a = generate(&engine, "alice@example.com", None);
+        let b = generate(&engine, "alice@example.com", None);
+        assert_ne!(a.fingerprint, b.fingerprint);
+
+        let info = engine.inspect_key(&a.public_key).unwrap();
+        assert_eq!(info.fingerprint, a.fingerprint);
+        assert_eq!(info.email(), Some("alice@example.com"));
+        assert_eq!(info.created_at, "2024-01-01T00:00:00+00:00");
+        assert!(!info.has_secret_key);
+        assert!(
+            engine
+                .inspect_key(a.secret_key.expose_secret())
+                .unwrap()
+                .has_secret_key
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", Some("pw"));
+        let bob = generate(&engine, "bob@example.com", None);
+
+        let ciphertext = engine
+            .encrypt(b"hello", std::slice::from_ref(&alice.public_key))
+            .unwrap();
+        let secret = alice.secret_key.expose_secret();
+        assert_eq!(
+            engine.decrypt(&ciphertext, secret, Some(b"pw")).unwrap(),
+            b"hello"
+        );
+        assert!(matches!(
+            engine.decrypt(&ciphertext, secret, Some(b"wrong")),
+            Err(Error::BadPassphrase)
+        ));
+        assert!(matches!(
+            engine.decrypt(&ciphertext, bob.secret_key.expose_secret(), None),
+            Err(Error::NoSecretKey)
+        ));
+
+        let (_, session_key) = engine
+            .decrypt_revealing_session_key(&ciphertext, secret, Some(b"pw"))
+            .unwrap();
+        assert_eq!(
+            engine
+                .decrypt_with_session_key(&ciphertext, &session_key)
+                .unwrap(),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", None);
+        let bob = generate(&engine, "bob@example.com", None);
+        let secret = alice.secret_key.expose_secret();
+
+        let options = SignOptions::new()
+            .with_notation("ticket@example.com", "OPS-1")
+            .with_signer_user_id("alice@example.com");
+        let signed = engine
+            .sign_with_options(b"data", secret, None, &options)
+            .unwrap();
+        let result = engine.verify(&signed, &alice.public_key).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.notations[0].value, "OPS-1");
+        assert_eq!(result.signer_user_id.as_deref(), Some("alice@example.com"));
+        assert!(!engine.verify(&signed, &bob.public_key).unwrap().valid);
+
+        let signature = engine.sign_detached(b"data", secret, None).unwrap();
+        assert!(
+            engine
+                .verify_detached(b"data", &signature, &alice.public_key)
+                .unwrap()
+                .valid
+        );
+        assert!(
+            !engine
+                .verify_detached(b"other", &signature, &alice.public_key)
+                .unwrap()
+                .valid
+        );
+    }
+
+    #[test]
+    fn test_expiration_follows_engine_clock() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", None);
+        let keys = vec![alice.public_key.clone()];
+        assert!(engine.check_recipients(&keys)[0].status.is_usable());
+
+        engine.set_time(UNIX_EPOCH + std::time::Duration::from_secs(MOCK_EPOCH * 2));
+        assert!(matches!(
+            engine.check_recipients(&keys)[0].status,
+            RecipientStatus::Expired { .. }
+        ));
+        assert!(matches!(
+            engine.encrypt(b"x", &keys),
+            Err(Error::Encryption { .. })
+        ));
+        assert!(matches!(
+            engine.sign(b"x", alice.secret_key.expose_secret(), None),
+            Err(Error::KeyExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scripted_failures() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", None);
+        let secret = alice.secret_key.expose_secret();
+
+        engine.fail_next(MockOperation::Sign, MockFailure::BadPassphrase);
+        assert!(matches!(
+            engine.sign(b"x", secret, None),
+            Err(Error::BadPassphrase)
+        ));
+        assert!(engine.sign(b"x", secret, None).is_ok());
+
+        engine.fail_always(MockOperation::Encrypt, MockFailure::KeyExpired);
+        for _ in 0..2 {
+            assert!(matches!(
+                engine.encrypt(b"x", std::slice::from_ref(&alice.public_key)),
+                Err(Error::KeyExpired { .. })
+            ));
+        }
+        engine.fail_next(MockOperation::CheckRecipients, MockFailure::KeyExpired);
+        assert!(matches!(
+            engine.check_recipients(std::slice::from_ref(&alice.public_key))[0].status,
+            RecipientStatus::Expired { .. }
+        ));
+
+        engine.clear_failures();
+        let ciphertext = engine
+            .encrypt(b"x", std::slice::from_ref(&alice.public_key))
+            .unwrap();
+        engine.fail_next(MockOperation::Decrypt, MockFailure::NoSecretKey);
+        assert!(matches!(
+            engine.decrypt(&ciphertext, secret, None),
+            Err(Error::NoSecretKey)
+        ));
+    }
+
+    #[test]
+    fn test_calls_are_recorded() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", None);
+        let bob = generate(&engine, "bob@example.com", None);
+        engine.clear_calls();
+
+        engine
+            .encrypt(b"x", &[alice.public_key.clone(), bob.public_key.clone()])
+            .unwrap();
+        engine.fail_next(MockOperation::InspectKey, MockFailure::NoSecretKey);
+        engine.inspect_key(&alice.public_key).unwrap_err();
+
+        assert_eq!(
+            engine.calls(),
+            vec![
+                MockCall {
+                    operation: MockOperation::Encrypt,
+                    keys: vec![alice.fingerprint.0.clone(), bob.fingerprint.0.clone()],
+                },
+                MockCall {
+                    operation: MockOperation::InspectKey,
+                    keys: vec![alice.fingerprint.0.clone()],
+                },
+            ]
+        );
+        assert_eq!(engine.call_count(MockOperation::Encrypt), 1);
+        assert_eq!(engine.call_count(MockOperation::Decrypt), 0);
+    }
+
+    #[test]
+    fn test_key_updates() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", Some("old"));
+        let escrow = generate(&engine, "escrow@example.com", None);
+        let revoker = generate(&engine, "security@example.com", None);
+
+        let secret = engine
+            .change_passphrase(
+                alice.secret_key.expose_secret(),
+                Some(b"old"),
+                Some(b"new"),
+                S2kMode::Argon2(Default::default()),
+            )
+            .unwrap();
+        assert_eq!(
+            engine.inspect_key(&secret).unwrap().s2k,
+            Some(S2kType::Argon2)
+        );
+        let secret = engine
+            .add_adsk(&secret, Some(b"new"), &escrow.public_key)
+            .unwrap();
+        let secret = engine
+            .add_designated_revoker(&secret, Some(b"new"), &revoker.public_key)
+            .unwrap();
+
+        // Messages to Alice also go to her ADSK.
+        let public = engine.public_key(&secret).unwrap();
+        let ciphertext = engine.encrypt(b"x", &[public.clone()]).unwrap();
+        assert_eq!(
+            engine
+                .decrypt(&ciphertext, escrow.secret_key.expose_secret(), None)
+                .unwrap(),
+            b"x"
+        );
+
+        let revoked = engine
+            .revoke_as_designated_revoker(&public, revoker.secret_key.expose_secret(), None)
+            .unwrap();
+        assert!(engine.inspect_key(&revoked).unwrap().is_revoked);
+        assert!(
+            engine
+                .revoke_as_designated_revoker(&public, escrow.secret_key.expose_secret(), None)
+                .is_err()
+        );
+
+        let offline = engine.export_secret_subkeys(&secret).unwrap();
+        assert!(engine.inspect_key(&offline).unwrap().primary_key_offline);
+        assert!(matches!(
+            engine.add_adsk(&offline, Some(b"new"), &escrow.public_key),
+            Err(Error::NoSecretKey)
+        ));
+    }
+
+    struct FakeBackend(String);
+
+    impl SecretKeyBackend for FakeBackend {
+        fn has_key(&self, key: &BackendKey) -> Result<bool> {
+            Ok(key.fingerprint == self.0)
+        }
+
+        fn sign(&self, _: &BackendKey, _: u8, _: &[u8]) -> Result<BackendSignature> {
+            unreachable!()
+        }
+
+        fn decrypt(&self, _: &BackendKey, _: &BackendCiphertext) -> Result<DecryptedValue> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_backend_operations() {
+        let engine = MockEngine::new();
+        let alice = generate(&engine, "alice@example.com", None);
+        let bob = generate(&engine, "bob@example.com", None);
+        let backend = FakeBackend(alice.fingerprint.0.clone());
+
+        let signed = engine
+            .sign_with_backend(b"x", &alice.public_key, &backend, &SignOptions::new())
+            .unwrap();
+        assert!(engine.verify(&signed, &alice.public_key).unwrap().valid);
+        assert!(matches!(
+            engine.sign_with_backend(b"x", &bob.public_key, &backend, &SignOptions::new()),
+            Err(Error::NoSecretKey)
+        ));
+
+        let ciphertext = engine
+            .encrypt(b"x", std::slice::from_ref(&alice.public_key))
+            .unwrap();
+        assert_eq!(
+            engine
+                .decrypt_with_backend(&ciphertext, &alice.public_key, &backend)
+                .unwrap(),
+            b"x"
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_keys_are_reproducible() {
+        let phrase = || SecretBox::new(Box::new(format!("{}art", "abandon ".repeat(23))));
+        let options =
+            || KeyGenOptions::new(UserId::new("Test", "t@example.com")).with_mnemonic(phrase());
+        let a = MockEngine::new().generate_key_pair(options()).unwrap();
+        let engine = MockEngine::new();
+        generate(&engine, "other@example.com", None);
+        let b = engine.generate_key_pair(options()).unwrap();
+        assert_eq!(a.fingerprint, b.fingerprint);
+    }
+}
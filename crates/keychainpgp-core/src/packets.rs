@@ -0,0 +1,127 @@
+//! Lightweight inspection of OpenPGP messages without any key material.
+//!
+//! These helpers read the key handles a message refers to, so callers can
+//! look up the right certificate (by primary or subkey fingerprint or key ID)
+//! before attempting to verify or decrypt.
+
+use sequoia_openpgp::parse::{PacketParser, PacketParserResult, Parse};
+use sequoia_openpgp::{KeyHandle, KeyID, Packet};
+
+use crate::error::{Error, Result};
+
+/// Return the issuer fingerprints and key IDs of every signature in a
+/// signed message (inline, cleartext or detached), as uppercase hex.
+///
+/// Handles from the signatures themselves come first, fingerprints before
+/// key IDs; one-pass signature key IDs follow. Duplicates are removed.
+pub fn signature_issuers(signed_data: &[u8]) -> Result<Vec<String>> {
+    let mut issuers = Vec::new();
+    let mut one_pass_issuers = Vec::new();
+    walk(signed_data, |packet| match packet {
+        Packet::Signature(sig) => {
+            let mut handles = sig.get_issuers();
+            // Fingerprints are more precise than key IDs
+            handles.sort_by_key(|h| !matches!(h, KeyHandle::Fingerprint(_)));
+            for handle in handles {
+                push_unique(&mut issuers, handle.to_hex());
+            }
+        }
+        Packet::OnePassSig(ops) => one_pass_issuers.push(ops.issuer().to_hex()),
+        _ => {}
+    })?;
+    for issuer in one_pass_issuers {
+        push_unique(&mut issuers, issuer);
+    }
+    Ok(issuers)
+}
+
+/// Return the key IDs an encrypted message is addressed to, as uppercase
+/// hex. Anonymous (wildcard) recipients are skipped.
+pub fn message_recipients(ciphertext: &[u8]) -> Result<Vec<String>> {
+    let mut recipients = Vec::new();
+    walk(ciphertext, |packet| {
+        if let Packet::PKESK(pkesk) = packet {
+            if let Some(handle) = pkesk.recipient() {
+                let id = KeyID::from(handle);
+                if !id.is_wildcard() {
+                    push_unique(&mut recipients, id.to_hex());
+                }
+            }
+        }
+    })?;
+    Ok(recipients)
+}
+
+/// Visit every packet, descending into compressed data but not into
+/// encrypted containers.
+fn walk(data: &[u8], mut visit: impl FnMut(&Packet)) -> Result<()> {
+    let invalid = |e: sequoia_openpgp::anyhow::Error| Error::InvalidArmor {
+        reason: e.to_string(),
+    };
+    let mut ppr = PacketParser::from_bytes(data).map_err(invalid)?;
+    while let PacketParserResult::Some(pp) = ppr {
+        visit(&pp.packet);
+        ppr = pp.recurse().map_err(invalid)?.1;
+    }
+    Ok(())
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CryptoEngine;
+    use crate::sequoia_engine::SequoiaEngine;
+    use crate::types::{KeyCapability, KeyGenOptions, UserId};
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn test_signature_issuers_and_recipients() {
+        let engine = SequoiaEngine::new();
+        let key = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let info = engine.inspect_key(&key.public_key).unwrap();
+        let subkey = |capability: KeyCapability| {
+            info.subkeys
+                .iter()
+                .find(|s| s.capabilities.contains(&capability))
+                .unwrap()
+                .fingerprint
+                .to_uppercase()
+        };
+
+        // Messages are signed by the signing subkey, not the primary key
+        let signed = engine
+            .sign(b"hello", key.secret_key.expose_secret(), None)
+            .unwrap();
+        let signing_subkey = subkey(KeyCapability::Sign);
+        let issuers = signature_issuers(&signed).unwrap();
+        assert_eq!(issuers[0], signing_subkey);
+        assert!(issuers.contains(&signing_subkey[24..].to_string()));
+
+        let ciphertext = engine.encrypt(b"hello", &[key.public_key.clone()]).unwrap();
+        let recipients = message_recipients(&ciphertext).unwrap();
+        assert_eq!(
+            recipients,
+            vec![subkey(KeyCapability::Encrypt)[24..].to_string()]
+        );
+
+        engine.set_hidden_recipients(true);
+        let hidden = engine.encrypt(b"hello", &[key.public_key.clone()]).unwrap();
+        assert!(message_recipients(&hidden).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_garbage_is_rejected() {
+        assert!(signature_issuers(b"not a pgp message").is_err());
+    }
+}
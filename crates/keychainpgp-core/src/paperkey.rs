@@ -0,0 +1,447 @@
+//! Printable "paper key" backups of secret keys.
+//!
+//! Like GnuPG's `paperkey`, only the secret parts of each key packet are
+//! exported; everything that can be recovered from the public certificate
+//! is left out. The result is short enough to print and type back in, and
+//! every line carries a CRC-24 so typing mistakes are caught line by line.
+//!
+//! Restoring requires the printed secret bits plus the public certificate.
+
+use std::fmt::Write as _;
+
+use sequoia_openpgp::cert::amalgamation::key::PrimaryKey;
+use sequoia_openpgp::packet::Key;
+use sequoia_openpgp::packet::key::{KeyParts, KeyRole};
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::serialize::{MarshalInto, SerializeInto};
+use sequoia_openpgp::{Cert, Packet, PacketPile};
+use zeroize::Zeroizing;
+
+use crate::encoding::{hex_decode, hex_encode};
+use crate::error::{Error, Result};
+
+/// Version of the binary paper key layout.
+const FORMAT_VERSION: u8 = 0;
+
+/// Number of data bytes printed per line.
+const BYTES_PER_LINE: usize = 16;
+
+/// The secret parts of every key in a certificate, ready to be printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PaperKey {
+    /// Fingerprint of the primary key (hex).
+    fingerprint: String,
+    /// Binary layout: format version, then for each key its version,
+    /// fingerprint and the secret tail of its packet body.
+    data: Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for PaperKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PaperKey")
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PaperKey {
+    /// Extract the secret key material from a secret key (TSK).
+    pub fn from_secret_key(secret_key: &[u8]) -> Result<Self> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert.is_tsk() {
+            return Err(Error::NoSecretKey);
+        }
+
+        let mut data = Zeroizing::new(vec![FORMAT_VERSION]);
+        for ka in cert.keys().secret() {
+            let key = ka.key();
+            let full = Zeroizing::new(key_body(key)?);
+            let public = key_body(key.parts_as_public())?;
+            let secret = full.get(public.len()..).unwrap_or_default();
+            if !full.starts_with(&public) || secret.is_empty() {
+                return Err(Error::Internal(
+                    "unexpected secret key packet layout".into(),
+                ));
+            }
+
+            let fp = key.fingerprint();
+            let fp = fp.as_bytes();
+            data.push(key.version());
+            data.push(fp.len() as u8);
+            data.extend_from_slice(fp);
+            data.extend_from_slice(&(secret.len() as u16).to_be_bytes());
+            data.extend_from_slice(secret);
+        }
+
+        Ok(Self {
+            fingerprint: cert.fingerprint().to_hex(),
+            data,
+        })
+    }
+
+    /// Rebuild a paper key from its raw bytes (e.g. reassembled from QR codes).
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let entries = parse_entries(data)?;
+        let fingerprint = entries
+            .first()
+            .map(|(_, fp, _)| hex_encode(fp))
+            .ok_or_else(|| invalid("paper key contains no keys"))?;
+        Ok(Self {
+            fingerprint,
+            data: Zeroizing::new(data.to_vec()),
+        })
+    }
+
+    /// Fingerprint of the primary key this paper key belongs to.
+    #[must_use]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The raw paper key bytes, e.g. for encoding as a series of QR codes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Render the printable text form.
+    ///
+    /// Each data line is `NNN: <hex bytes> <CRC-24>`, where the CRC covers
+    /// the line number and the bytes of that line. The last line holds the
+    /// CRC-24 of all data.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# KeychainPGP paper key backup\n");
+        let _ = writeln!(out, "# Primary key fingerprint: {}", self.fingerprint);
+        out.push_str("# Contains only the secret parts of the key. To restore, you also\n");
+        out.push_str("# need the public key:\n");
+        out.push_str("#   keychainpgp keys paper-restore --public-key <public.asc> <this file>\n");
+        out.push_str("# Each line ends with a CRC-24 of its line number and bytes.\n");
+        out.push_str("# The last line is a CRC-24 of all data.\n\n");
+
+        let mut line_no = 0u16;
+        for chunk in self.data.chunks(BYTES_PER_LINE) {
+            line_no += 1;
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(
+                out,
+                "{line_no:3}: {hex:<width$} {:06X}",
+                line_crc(line_no, chunk),
+                width = BYTES_PER_LINE * 3 - 1
+            );
+        }
+        let _ = writeln!(out, "{:3}: {:06X}", line_no + 1, crc24(&self.data));
+        out
+    }
+
+    /// Parse the printable text form, checking every line checksum.
+    ///
+    /// Lines starting with `#` and blank lines are ignored. Errors name the
+    /// offending line so a typo can be found and corrected.
+    pub fn parse_text(text: &str) -> Result<Self> {
+        let lines: Vec<(u16, &str)> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let (num, rest) = l
+                    .split_once(':')
+                    .ok_or_else(|| invalid(format!("malformed line: {l}")))?;
+                let num = num
+                    .trim()
+                    .parse::<u16>()
+                    .map_err(|_| invalid(format!("bad line number: {l}")))?;
+                Ok((num, rest.trim()))
+            })
+            .collect::<Result<_>>()?;
+
+        let Some((&(total_no, total_line), data_lines)) = lines.split_last() else {
+            return Err(invalid("no data lines found"));
+        };
+
+        let mut data = Zeroizing::new(Vec::new());
+        for (expected, &(line_no, line)) in (1u16..).zip(data_lines) {
+            if line_no != expected {
+                return Err(invalid(format!(
+                    "line {expected} is missing (found line {line_no})"
+                )));
+            }
+            let (bytes_hex, crc_hex) = line
+                .rsplit_once(' ')
+                .ok_or_else(|| invalid(format!("line {line_no}: missing checksum")))?;
+            let bytes = Zeroizing::new(
+                hex_decode(bytes_hex).map_err(|e| invalid(format!("line {line_no}: {e}")))?,
+            );
+            let crc = parse_crc(crc_hex)
+                .ok_or_else(|| invalid(format!("line {line_no}: bad checksum field")))?;
+            if crc != line_crc(line_no, &bytes) {
+                return Err(invalid(format!(
+                    "line {line_no}: checksum mismatch, check for typos"
+                )));
+            }
+            data.extend_from_slice(&bytes);
+        }
+
+        if total_no != data_lines.len() as u16 + 1 {
+            return Err(invalid(format!("line {total_no} is out of sequence")));
+        }
+        let total = parse_crc(total_line)
+            .ok_or_else(|| invalid(format!("line {total_no}: bad checksum field")))?;
+        if total != crc24(&data) {
+            return Err(invalid("total checksum mismatch, lines may be missing"));
+        }
+
+        Self::from_bytes(&data)
+    }
+
+    /// Rebuild the full secret key from these secret bits and the public
+    /// certificate. Returns the ASCII-armored secret key.
+    pub fn restore(&self, public_cert: &[u8]) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(public_cert).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert
+            .fingerprint()
+            .to_hex()
+            .eq_ignore_ascii_case(&self.fingerprint)
+        {
+            return Err(invalid(format!(
+                "paper key belongs to {}, not to the given public key {}",
+                self.fingerprint,
+                cert.fingerprint().to_hex()
+            )));
+        }
+
+        let mut packets: Vec<Packet> = Vec::new();
+        for (version, fp, secret) in parse_entries(&self.data)? {
+            let ka = cert
+                .keys()
+                .find(|ka| ka.key().fingerprint().as_bytes() == fp)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "public key has no (sub)key with fingerprint {}",
+                        hex_encode(fp)
+                    ))
+                })?;
+            if ka.key().version() != version {
+                return Err(invalid("key version mismatch"));
+            }
+
+            let mut body = key_body(ka.key())?;
+            body.extend_from_slice(secret);
+            // Tag 5 = secret key, tag 7 = secret subkey
+            let tag = if ka.primary() { 5 } else { 7 };
+            let packet = PacketPile::from_bytes(&new_format_packet(tag, &body))
+                .ok()
+                .and_then(|pile| pile.into_children().next())
+                .filter(|p| matches!(p, Packet::SecretKey(_) | Packet::SecretSubkey(_)))
+                .ok_or_else(|| invalid("secret key material is corrupt"))?;
+            packets.push(packet);
+        }
+
+        let (cert, _) = cert
+            .insert_packets(packets)
+            .map_err(|e| Error::Internal(format!("failed to merge secret keys: {e}")))?;
+        SerializeInto::to_vec(&cert.as_tsk().armored())
+            .map_err(|e| Error::Internal(format!("serialize error: {e}")))
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidPaperKey {
+        reason: reason.into(),
+    }
+}
+
+/// Serialize the body of a key packet (without packet header).
+fn key_body<P: KeyParts, R: KeyRole>(key: &Key<P, R>) -> Result<Vec<u8>> {
+    match key {
+        Key::V4(k) => k.to_vec(),
+        Key::V6(k) => k.to_vec(),
+        _ => return Err(Error::Internal("unsupported key version".into())),
+    }
+    .map_err(|e| Error::Internal(format!("serialize error: {e}")))
+}
+
+/// Frame a packet body with a new-format OpenPGP packet header.
+fn new_format_packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xC0 | tag];
+    let len = body.len();
+    if len < 192 {
+        out.push(len as u8);
+    } else if len < 8384 {
+        let len = len - 192;
+        out.push(((len >> 8) + 192) as u8);
+        out.push(len as u8);
+    } else {
+        out.push(0xFF);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+/// One key in the binary layout: `(version, fingerprint, secret)`.
+type Entry<'a> = (u8, &'a [u8], &'a [u8]);
+
+/// Split the binary layout into its per-key entries.
+fn parse_entries(data: &[u8]) -> Result<Vec<Entry<'_>>> {
+    let (&format, mut rest) = data
+        .split_first()
+        .ok_or_else(|| invalid("paper key is empty"))?;
+    if format != FORMAT_VERSION {
+        return Err(invalid(format!("unsupported paper key format {format}")));
+    }
+
+    let truncated = || invalid("paper key data is truncated");
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let [version, fp_len, tail @ ..] = rest else {
+            return Err(truncated());
+        };
+        let (fp, tail) = tail
+            .split_at_checked(usize::from(*fp_len))
+            .ok_or_else(truncated)?;
+        let (len, tail) = tail.split_at_checked(2).ok_or_else(truncated)?;
+        let len = usize::from(u16::from_be_bytes([len[0], len[1]]));
+        let (secret, tail) = tail.split_at_checked(len).ok_or_else(truncated)?;
+        entries.push((*version, fp, secret));
+        rest = tail;
+    }
+    Ok(entries)
+}
+
+/// CRC-24 as used by OpenPGP ASCII armor (RFC 9580 §6.1).
+pub(crate) fn crc24(data: &[u8]) -> u32 {
+    const INIT: u32 = 0x00B7_04CE;
+    const POLY: u32 = 0x0186_4CFB;
+    let mut crc = INIT;
+    for &b in data {
+        crc ^= u32::from(b) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+    crc & 0x00FF_FFFF
+}
+
+/// Line checksum: CRC-24 over the line number and the line's bytes, so
+/// swapped lines are detected as well as typos.
+fn line_crc(line_no: u16, bytes: &[u8]) -> u32 {
+    let mut buf = line_no.to_be_bytes().to_vec();
+    buf.extend_from_slice(bytes);
+    crc24(&buf)
+}
+
+fn parse_crc(field: &str) -> Option<u32> {
+    (field.len() == 6)
+        .then(|| u32::from_str_radix(field, 16).ok())
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{KeyGenOptions, UserId};
+    use crate::{CryptoEngine, SequoiaEngine};
+    use secrecy::ExposeSecret;
+
+    fn generate(passphrase: Option<&[u8]>) -> crate::types::GeneratedKeyPair {
+        let mut options = KeyGenOptions::new(UserId::new("Paper", "paper@example.com"));
+        if let Some(p) = passphrase {
+            options = options.with_passphrase(secrecy::SecretBox::new(Box::new(p.to_vec())));
+        }
+        SequoiaEngine::new().generate_key_pair(options).unwrap()
+    }
+
+    #[test]
+    fn test_crc24_known_value() {
+        // CRC-24 of the empty string is the initial value
+        assert_eq!(crc24(b""), 0x00B7_04CE);
+        assert_eq!(crc24(b"123456789"), 0x0021_CF02);
+    }
+
+    #[test]
+    fn test_paper_key_text_round_trip_restores_key() {
+        let engine = SequoiaEngine::new();
+        let kp = generate(Some(b"paper-pass"));
+
+        let paper = PaperKey::from_secret_key(kp.secret_key.expose_secret()).unwrap();
+        assert_eq!(paper.fingerprint(), kp.fingerprint.0);
+        // Far smaller than the armored secret key
+        assert!(paper.as_bytes().len() * 3 < kp.secret_key.expose_secret().len());
+
+        let text = paper.to_text();
+        let parsed = PaperKey::parse_text(&text).unwrap();
+        assert_eq!(parsed, paper);
+
+        let restored = parsed.restore(&kp.public_key).unwrap();
+        let info = engine.inspect_key(&restored).unwrap();
+        assert!(info.has_secret_key);
+
+        let ciphertext = engine
+            .encrypt(b"cold storage", std::slice::from_ref(&kp.public_key))
+            .unwrap();
+        let plaintext = engine
+            .decrypt(&ciphertext, &restored, Some(b"paper-pass"))
+            .unwrap();
+        assert_eq!(plaintext, b"cold storage");
+    }
+
+    #[test]
+    fn test_paper_key_typo_reports_line() {
+        let kp = generate(None);
+        let text = PaperKey::from_secret_key(kp.secret_key.expose_secret())
+            .unwrap()
+            .to_text();
+
+        // Flip one hex digit on data line 2
+        let tampered: String = text
+            .lines()
+            .map(|l| {
+                if l.trim_start().starts_with("2:") {
+                    let mut chars: Vec<char> = l.chars().collect();
+                    chars[5] = if chars[5] == '0' { '1' } else { '0' };
+                    chars.into_iter().collect()
+                } else {
+                    l.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let err = PaperKey::parse_text(&tampered).unwrap_err().to_string();
+        assert!(err.contains("line 2"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_paper_key_wrong_public_key_rejected() {
+        let kp = generate(None);
+        let other = generate(None);
+        let paper = PaperKey::from_secret_key(kp.secret_key.expose_secret()).unwrap();
+
+        assert!(matches!(
+            paper.restore(&other.public_key),
+            Err(Error::InvalidPaperKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_paper_key_requires_secret_key() {
+        let kp = generate(None);
+        assert!(matches!(
+            PaperKey::from_secret_key(&kp.public_key),
+            Err(Error::NoSecretKey)
+        ));
+    }
+}
@@ -1,29 +1,38 @@
 use std::io::Write;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use sequoia_openpgp::Cert;
 use sequoia_openpgp::cert::prelude::*;
-use sequoia_openpgp::crypto::SessionKey;
+use sequoia_openpgp::crypto::{Password, S2K, SessionKey, mpi};
+use sequoia_openpgp::packet::Key;
+use sequoia_openpgp::packet::key::{KeyRole, PublicParts, UnspecifiedRole};
 use sequoia_openpgp::parse::Parse;
 use sequoia_openpgp::parse::stream::*;
 use sequoia_openpgp::policy::StandardPolicy;
 use sequoia_openpgp::serialize::Marshal;
 use sequoia_openpgp::serialize::stream::*;
-use sequoia_openpgp::types::{KeyFlags, PublicKeyAlgorithm};
+use sequoia_openpgp::types::{
+    AEADAlgorithm, HashAlgorithm, KeyFlags, PublicKeyAlgorithm, RevocationKey, SymmetricAlgorithm,
+};
+use sequoia_openpgp::{Cert, KeyHandle};
 
 use secrecy::ExposeSecret;
 
+use crate::backend::{
+    BackendCiphertext, BackendKey, BackendSignature, DecryptedValue, SecretKeyBackend,
+};
 use crate::engine::CryptoEngine;
 use crate::error::{Error, Result};
 use crate::types::{
     CertInfo, Fingerprint, GeneratedKeyPair, KeyAlgorithm, KeyCapability, KeyGenOptions,
-    SubkeyInfo, UserId, VerifyResult,
+    MessageSessionKey, RecipientCheck, RecipientStatus, S2kMode, S2kType, SignOptions,
+    SignatureNotation, SubkeyInfo, UserId, VerifyResult,
 };
 
 /// Sequoia-PGP backed implementation of [`CryptoEngine`].
 pub struct SequoiaEngine {
     policy: StandardPolicy<'static>,
     include_armor_headers: AtomicBool,
+    hidden_recipients: AtomicBool,
 }
 
 impl SequoiaEngine {
@@ -33,6 +42,7 @@ impl SequoiaEngine {
         Self {
             policy: StandardPolicy::new(),
             include_armor_headers: AtomicBool::new(true),
+            hidden_recipients: AtomicBool::new(false),
         }
     }
 
@@ -41,6 +51,98 @@ impl SequoiaEngine {
         self.include_armor_headers.store(enabled, Ordering::Relaxed);
     }
 
+    /// Enable or disable hidden recipients in encrypted output.
+    ///
+    /// When enabled, every PKESK carries the wildcard (anonymous) key ID
+    /// instead of the recipient's key ID, so the ciphertext does not reveal
+    /// who it is for. Recipients then have to try each of their secret keys.
+    pub fn set_hidden_recipients(&self, enabled: bool) {
+        self.hidden_recipients.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Work out whether a certificate can be encrypted to, using the same
+    /// key selection as [`CryptoEngine::encrypt`].
+    fn recipient_status(&self, cert: &Cert) -> RecipientStatus {
+        let valid_cert = match cert.with_policy(&self.policy, None) {
+            Ok(v) => v,
+            Err(e) => {
+                return RecipientStatus::PolicyRejected {
+                    reason: e.to_string(),
+                };
+            }
+        };
+        // A revocation by a designated revoker cannot be checked here, and
+        // anyone can claim to be one; only verified revocations count
+        if matches!(
+            valid_cert.revocation_status(),
+            sequoia_openpgp::types::RevocationStatus::Revoked(_)
+        ) {
+            return RecipientStatus::Revoked;
+        }
+        let rfc3339 = |t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339();
+        if let Err(e) = valid_cert.alive() {
+            return match valid_cert.primary_key().key_expiration_time() {
+                Some(t) => RecipientStatus::Expired {
+                    expired_at: rfc3339(t),
+                },
+                None => RecipientStatus::PolicyRejected {
+                    reason: e.to_string(),
+                },
+            };
+        }
+
+        let candidates: Vec<_> = valid_cert
+            .keys()
+            .revoked(false)
+            .for_transport_encryption()
+            .for_storage_encryption()
+            .collect();
+        if candidates.is_empty() {
+            // Encryption subkeys whose algorithm or binding the policy rejects
+            // are filtered out above; look for them without the policy.
+            let rejected = cert.keys().subkeys().find_map(|ka| {
+                let flags = ka.self_signatures().next()?.key_flags()?;
+                if !(flags.for_transport_encryption() || flags.for_storage_encryption()) {
+                    return None;
+                }
+                ka.with_policy(&self.policy, None).err()
+            });
+            return match rejected {
+                Some(e) => RecipientStatus::PolicyRejected {
+                    reason: e.to_string(),
+                },
+                None => RecipientStatus::NoEncryptionSubkey,
+            };
+        }
+
+        if !candidates.iter().any(|ka| ka.alive().is_ok()) {
+            let expired_at = candidates
+                .iter()
+                .filter_map(|ka| ka.key_expiration_time())
+                .max();
+            return match expired_at {
+                Some(t) => RecipientStatus::Expired {
+                    expired_at: rfc3339(t),
+                },
+                None => RecipientStatus::NoEncryptionSubkey,
+            };
+        }
+
+        if !candidates
+            .iter()
+            .any(|ka| ka.alive().is_ok() && ka.key().pk_algo().is_supported())
+        {
+            return RecipientStatus::PolicyRejected {
+                reason: format!(
+                    "unsupported encryption algorithm {}",
+                    candidates[0].key().pk_algo()
+                ),
+            };
+        }
+
+        RecipientStatus::Usable
+    }
+
     /// Create an armor writer, optionally including promotional headers.
     fn armor_writer<'a, W: Write + Send + Sync + 'a>(
         &self,
@@ -72,6 +174,258 @@ impl SequoiaEngine {
         }
     }
 
+    /// Serialize a certificate with its secret key material, ASCII-armored.
+    fn armored_secret_key(&self, cert: &Cert) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        {
+            let mut writer = self
+                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::SecretKey)
+                .map_err(|e| Error::Internal(format!("armor error: {e}")))?;
+            cert.as_tsk()
+                .serialize(&mut writer)
+                .map_err(|e| Error::Internal(format!("serialize error: {e}")))?;
+            writer
+                .finalize()
+                .map_err(|e| Error::Internal(format!("finalize error: {e}")))?;
+        }
+
+        Ok(output)
+    }
+
+    /// Serialize the public parts of a certificate, ASCII-armored.
+    fn armored_public_key(&self, cert: &Cert) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        {
+            let mut writer = self
+                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::PublicKey)
+                .map_err(|e| Error::Internal(format!("armor error: {e}")))?;
+            cert.serialize(&mut writer)
+                .map_err(|e| Error::Internal(format!("serialize error: {e}")))?;
+            writer
+                .finalize()
+                .map_err(|e| Error::Internal(format!("finalize error: {e}")))?;
+        }
+
+        Ok(output)
+    }
+
+    /// Bind the newest encryption subkey of `adsk_key` to `cert` as an
+    /// additional decryption subkey, signed by the unlocked `primary` key.
+    fn bind_adsk(
+        &self,
+        cert: Cert,
+        primary: &mut sequoia_openpgp::crypto::KeyPair,
+        adsk_key: &[u8],
+    ) -> Result<Cert> {
+        use sequoia_openpgp::packet::signature::SignatureBuilder;
+        use sequoia_openpgp::types::SignatureType;
+
+        let adsk_cert = Cert::from_bytes(adsk_key).map_err(|e| Error::KeyUpdate {
+            reason: format!("invalid ADSK key: {e}"),
+        })?;
+        if adsk_cert.fingerprint() == cert.fingerprint() {
+            return Err(Error::KeyUpdate {
+                reason: "a key cannot be its own ADSK".into(),
+            });
+        }
+
+        let status = self.recipient_status(&adsk_cert);
+        if !status.is_usable() {
+            return Err(Error::KeyUpdate {
+                reason: format!(
+                    "cannot use {} as ADSK: {status}",
+                    describe_recipient(&adsk_cert, &self.policy)
+                ),
+            });
+        }
+
+        let valid_adsk =
+            adsk_cert
+                .with_policy(&self.policy, None)
+                .map_err(|e| Error::KeyUpdate {
+                    reason: format!("ADSK policy check failed: {e}"),
+                })?;
+        let ka = valid_adsk
+            .keys()
+            .supported()
+            .alive()
+            .revoked(false)
+            .for_transport_encryption()
+            .for_storage_encryption()
+            .max_by_key(|ka| ka.key().creation_time())
+            .ok_or_else(|| Error::KeyUpdate {
+                reason: "ADSK key has no usable encryption subkey".into(),
+            })?;
+
+        let subkey = ka.key().clone().role_into_subordinate();
+        let binding = SignatureBuilder::new(SignatureType::SubkeyBinding)
+            .set_key_flags(adsk_flags())
+            .and_then(|b| b.set_key_expiration_time(&subkey, ka.key_expiration_time()))
+            .and_then(|b| b.sign_subkey_binding(primary, cert.primary_key().key(), &subkey))
+            .map_err(|e| Error::KeyUpdate {
+                reason: format!("failed to sign ADSK binding: {e}"),
+            })?;
+
+        let (cert, _) = cert
+            .insert_packets(vec![sequoia_openpgp::Packet::from(subkey), binding.into()])
+            .map_err(|e| Error::KeyUpdate {
+                reason: e.to_string(),
+            })?;
+
+        Ok(cert)
+    }
+
+    /// Run the decryptor with `helper`, returning the plaintext and the
+    /// session key that opened it.
+    fn decrypt_with_helper(
+        &self,
+        ciphertext: &[u8],
+        helper: DecryptHelper<'_>,
+    ) -> Result<(Vec<u8>, MessageSessionKey)> {
+        let mut decryptor = DecryptorBuilder::from_bytes(ciphertext)
+            .map_err(|e| Error::Decryption {
+                reason: format!("invalid ciphertext: {e}"),
+            })?
+            .with_policy(&self.policy, None, helper)
+            .map_err(|e| {
+                backend_error(e, |e| Error::Decryption {
+                    reason: format!("decryption failed: {e}"),
+                })
+            })?;
+
+        let mut plaintext = Vec::new();
+        std::io::copy(&mut decryptor, &mut plaintext).map_err(|e| Error::Decryption {
+            reason: format!("read error: {e}"),
+        })?;
+
+        let session_key = decryptor
+            .into_helper()
+            .session_key
+            .take()
+            .ok_or_else(|| Error::Internal("session key was not captured".into()))?;
+        Ok((plaintext, session_key))
+    }
+
+    /// Write `data` as an armored, inline-signed message.
+    fn write_signed_message<S>(
+        &self,
+        data: &[u8],
+        signer: S,
+        template: sequoia_openpgp::packet::signature::SignatureBuilder,
+        recipients: &[Cert],
+    ) -> Result<Vec<u8>>
+    where
+        S: sequoia_openpgp::crypto::Signer + Send + Sync,
+    {
+        let mut output = Vec::new();
+        {
+            let mut armored_writer = self
+                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::Message)
+                .map_err(|e| Error::Signing {
+                    reason: format!("armor error: {e}"),
+                })?;
+
+            let message = Message::new(&mut armored_writer);
+            let mut signer =
+                Signer::with_template(message, signer, template).map_err(|e| Error::Signing {
+                    reason: format!("signer error: {e}"),
+                })?;
+            for recipient in recipients {
+                signer = signer.add_intended_recipient(recipient);
+            }
+            let message = signer.build().map_err(|e| Error::Signing {
+                reason: format!("signer error: {e}"),
+            })?;
+            let mut message = LiteralWriter::new(message)
+                .build()
+                .map_err(|e| Error::Signing {
+                    reason: format!("literal writer error: {e}"),
+                })?;
+
+            message.write_all(data).map_err(|e| Error::Signing {
+                reason: format!("write error: {e}"),
+            })?;
+            message.finalize().map_err(|e| {
+                backend_error(e, |e| Error::Signing {
+                    reason: format!("finalize error: {e}"),
+                })
+            })?;
+
+            armored_writer.finalize().map_err(|e| Error::Signing {
+                reason: format!("armor finalize error: {e}"),
+            })?;
+        }
+
+        Ok(output)
+    }
+
+    /// Find and unlock a signing-capable secret key in `secret_key`.
+    fn signing_keypair(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<sequoia_openpgp::crypto::KeyPair> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::Signing {
+            reason: format!("invalid secret key: {e}"),
+        })?;
+
+        let valid_cert = cert
+            .with_policy(&self.policy, None)
+            .map_err(|e| Error::Signing {
+                reason: format!("key policy check failed: {e}"),
+            })?;
+
+        // Find a signing-capable secret key
+        let mut keypair = None;
+
+        // Try unencrypted secret keys first
+        if let Some(ka) = valid_cert
+            .keys()
+            .supported()
+            .alive()
+            .revoked(false)
+            .for_signing()
+            .unencrypted_secret()
+            .next()
+        {
+            keypair = Some(
+                ka.key()
+                    .clone()
+                    .into_keypair()
+                    .map_err(|e| Error::Signing {
+                        reason: format!("keypair conversion failed: {e}"),
+                    })?,
+            );
+        }
+
+        // Try with passphrase
+        if keypair.is_none() {
+            if let Some(passphrase) = passphrase {
+                let password = sequoia_openpgp::crypto::Password::from(passphrase);
+                for ka in valid_cert
+                    .keys()
+                    .supported()
+                    .alive()
+                    .revoked(false)
+                    .for_signing()
+                    .secret()
+                {
+                    let key = ka.key().clone();
+                    if let Ok(decrypted) = key.decrypt_secret(&password) {
+                        if let Ok(kp) = decrypted.into_keypair() {
+                            keypair = Some(kp);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        keypair.ok_or_else(|| Error::Signing {
+            reason: "no signing-capable secret key found".into(),
+        })
+    }
+
     /// Parse decrypted backup bytes into individual certificates.
     ///
     /// Returns a list of `(public_key_armored, secret_key_armored, CertInfo)` for
@@ -322,6 +676,68 @@ impl Default for SequoiaEngine {
     }
 }
 
+/// Key flag bit of an additional decryption subkey (ADSK), 0x0400 in the
+/// key flags subpacket. Sequoia has no named helper for it.
+const ADSK_KEY_FLAG: usize = 10;
+
+/// Key flags of an additional decryption subkey.
+fn adsk_flags() -> KeyFlags {
+    KeyFlags::empty().set(ADSK_KEY_FLAG)
+}
+
+/// Revocations of `valid_cert` whose issuer subpacket names one of its
+/// designated revokers. The issuer is only a claim: the signature can be
+/// checked against the revoker's certificate alone, see
+/// [`CryptoEngine::verify_designated_revocation`].
+fn designated_revocations<'a>(
+    valid_cert: &ValidCert<'a>,
+) -> Vec<&'a sequoia_openpgp::packet::Signature> {
+    let sequoia_openpgp::types::RevocationStatus::CouldBe(revocations) =
+        valid_cert.revocation_status()
+    else {
+        return Vec::new();
+    };
+    let revokers: Vec<KeyHandle> = valid_cert
+        .revocation_keys()
+        .map(|rk| KeyHandle::from(rk.revoker().1))
+        .collect();
+    revocations
+        .into_iter()
+        .filter(|rev| {
+            rev.get_issuers()
+                .iter()
+                .any(|issuer| revokers.iter().any(|r| issuer.aliases(r)))
+        })
+        .collect()
+}
+
+/// Unlock the primary key of `cert`, e.g. to sign new subkey bindings.
+fn primary_keypair(
+    cert: &Cert,
+    passphrase: Option<&Password>,
+) -> Result<sequoia_openpgp::crypto::KeyPair> {
+    let key = cert
+        .primary_key()
+        .key()
+        .clone()
+        .parts_into_secret()
+        .map_err(|_| Error::NoSecretKey)?;
+    if is_secret_stub(&key) {
+        return Err(Error::KeyUpdate {
+            reason: "the primary key is offline; use the copy that holds it".into(),
+        });
+    }
+    let key = if key.secret().is_encrypted() {
+        let password = passphrase.ok_or(Error::BadPassphrase)?;
+        key.decrypt_secret(password)
+            .map_err(|_| Error::BadPassphrase)?
+    } else {
+        key
+    };
+    key.into_keypair()
+        .map_err(|e| Error::Internal(format!("keypair conversion failed: {e}")))
+}
+
 /// Parse a Sequoia User ID component value into our UserId type.
 fn parse_user_id(uid: &sequoia_openpgp::packet::UserID) -> UserId {
     // Sequoia gives us the raw User ID string, typically "Name <email>"
@@ -364,65 +780,442 @@ fn map_algorithm(algo: PublicKeyAlgorithm, key_size: Option<usize>) -> KeyAlgori
     }
 }
 
-impl CryptoEngine for SequoiaEngine {
-    fn generate_key_pair(&self, options: KeyGenOptions) -> Result<GeneratedKeyPair> {
-        let user_id = options.user_id.to_openpgp_string();
+/// Re-protect every secret key in `cert` with `new_passphrase` using `s2k`.
+///
+/// Encrypted keys are first unlocked with `old_passphrase`. A `None` new
+/// passphrase leaves the secret key material unprotected.
+fn protect_secret_keys(
+    cert: Cert,
+    old_passphrase: Option<&Password>,
+    new_passphrase: Option<&Password>,
+    s2k: S2kMode,
+) -> Result<Cert> {
+    let mut packets: Vec<sequoia_openpgp::Packet> = Vec::new();
+
+    for ka in cert.keys().secret() {
+        let mut key = ka.key().clone();
+        if is_secret_stub(&key) {
+            continue;
+        }
+        if key.secret().is_encrypted() {
+            let password = old_passphrase.ok_or(Error::BadPassphrase)?;
+            key = key
+                .decrypt_secret(password)
+                .map_err(|_| Error::BadPassphrase)?;
+        }
 
-        let mut builder = match options.algorithm {
-            KeyAlgorithm::Ed25519 => CertBuilder::new()
-                .add_userid(user_id)
-                .set_cipher_suite(CipherSuite::Cv25519)
-                .add_signing_subkey()
-                .add_subkey(
-                    KeyFlags::empty().set_transport_encryption(),
-                    options.expiration,
-                    None,
-                ),
-            KeyAlgorithm::Rsa(bits) => {
-                let suite = match bits {
-                    3072 => CipherSuite::RSA3k,
-                    _ => CipherSuite::RSA4k,
-                };
-                CertBuilder::new()
-                    .add_userid(user_id)
-                    .set_cipher_suite(suite)
-                    .add_signing_subkey()
-                    .add_subkey(
-                        KeyFlags::empty().set_transport_encryption(),
-                        options.expiration,
-                        None,
-                    )
+        if let Some(password) = new_passphrase {
+            key = match s2k {
+                S2kMode::Iterated => key.encrypt_secret(password),
+                S2kMode::Argon2(params) => {
+                    let mut salt = [0u8; 16];
+                    crate::crypto_random(&mut salt);
+                    let (key, mut secret) = key.take_secret();
+                    secret
+                        .encrypt_in_place_with(
+                            &key,
+                            S2K::Argon2 {
+                                salt,
+                                t: params.passes,
+                                p: params.parallelism,
+                                m: params.memory_exponent,
+                            },
+                            SymmetricAlgorithm::AES256,
+                            Some(AEADAlgorithm::OCB),
+                            password,
+                        )
+                        .map(|()| key.add_secret(secret).0)
+                }
             }
-        };
+            .map_err(|e| Error::Internal(format!("failed to protect secret key: {e}")))?;
+        }
+
+        packets.push(if ka.primary() {
+            key.role_into_primary().into()
+        } else {
+            key.role_into_subordinate().into()
+        });
+    }
+
+    cert.insert_packets(packets)
+        .map(|(cert, _)| cert)
+        .map_err(|e| Error::Internal(format!("failed to update secret keys: {e}")))
+}
+
+/// Determine how a key's secret material is protected, if it has any.
+fn s2k_type<P, R>(key: &sequoia_openpgp::packet::Key<P, R>) -> Option<S2kType>
+where
+    P: sequoia_openpgp::packet::key::KeyParts,
+    R: sequoia_openpgp::packet::key::KeyRole,
+{
+    use sequoia_openpgp::packet::key::SecretKeyMaterial;
+
+    #[allow(deprecated)]
+    match key.optional_secret()? {
+        SecretKeyMaterial::Unencrypted(_) => Some(S2kType::Unprotected),
+        SecretKeyMaterial::Encrypted(e) => Some(match e.s2k() {
+            S2K::Argon2 { .. } => S2kType::Argon2,
+            S2K::Iterated { .. } => S2kType::Iterated,
+            S2K::Salted { .. } => S2kType::Salted,
+            S2K::Simple { .. } => S2kType::Simple,
+            _ => S2kType::Other,
+        }),
+    }
+}
 
-        if let Some(expiration) = options.expiration {
-            builder = builder.set_validity_period(expiration);
+/// Whether a key carries a GnuPG-style secret key stub (`gnu-dummy` S2K)
+/// instead of real secret key material, i.e. the key is kept offline.
+pub(crate) fn is_secret_stub<P, R>(key: &sequoia_openpgp::packet::Key<P, R>) -> bool
+where
+    P: sequoia_openpgp::packet::key::KeyParts,
+    R: sequoia_openpgp::packet::key::KeyRole,
+{
+    use sequoia_openpgp::packet::key::SecretKeyMaterial;
+
+    #[allow(deprecated)]
+    match key.optional_secret() {
+        Some(SecretKeyMaterial::Encrypted(e)) => {
+            matches!(e.s2k(), S2K::Private { tag: 101, .. })
         }
+        _ => false,
+    }
+}
 
-        if let Some(ref passphrase) = options.passphrase {
-            builder = builder.set_password(Some(sequoia_openpgp::crypto::Password::from(
-                passphrase.expose_secret().as_slice(),
-            )));
+/// Build the signature template for `options` and parse the intended
+/// recipients' certificates.
+fn signature_template(
+    secret_key: &[u8],
+    options: &SignOptions,
+) -> Result<(
+    sequoia_openpgp::packet::signature::SignatureBuilder,
+    Vec<Cert>,
+)> {
+    use sequoia_openpgp::packet::signature::SignatureBuilder;
+    use sequoia_openpgp::packet::signature::subpacket::NotationDataFlags;
+    use sequoia_openpgp::types::SignatureType;
+
+    let signing_error = |e: sequoia_openpgp::anyhow::Error| Error::Signing {
+        reason: e.to_string(),
+    };
+
+    let mut template = SignatureBuilder::new(SignatureType::Binary);
+
+    for notation in &options.notations {
+        if !notation.name.contains('@') {
+            return Err(Error::Signing {
+                reason: format!(
+                    "notation name must have the form name@domain: {}",
+                    notation.name
+                ),
+            });
         }
+        template = template
+            .add_notation(
+                &notation.name,
+                notation.value.as_bytes(),
+                NotationDataFlags::empty().set_human_readable(),
+                false,
+            )
+            .map_err(signing_error)?;
+    }
 
-        let (cert, revocation) = builder.generate().map_err(|e| Error::KeyGeneration {
-            reason: e.to_string(),
+    if let Some(expiration) = options.expiration {
+        template = template
+            .set_signature_validity_period(expiration)
+            .map_err(signing_error)?;
+    }
+
+    if let Some(wanted) = &options.signer_user_id {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::Signing {
+            reason: format!("invalid secret key: {e}"),
         })?;
+        let userid = cert
+            .userids()
+            .map(|ua| ua.userid())
+            .find(|uid| {
+                String::from_utf8_lossy(uid.value()) == wanted.as_str()
+                    || parse_user_id(uid).email.as_deref() == Some(wanted.as_str())
+            })
+            .ok_or_else(|| Error::Signing {
+                reason: format!("the signing key has no user ID {wanted}"),
+            })?;
+        template = template
+            .set_signers_user_id(userid.value())
+            .map_err(signing_error)?;
+    }
 
-        let fingerprint = Fingerprint::new(cert.fingerprint().to_hex());
+    let recipients = options
+        .intended_recipients
+        .iter()
+        .map(|key| {
+            Cert::from_bytes(key).map_err(|e| Error::Signing {
+                reason: format!("invalid intended recipient key: {e}"),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-        // Serialize public key (certificate)
-        let mut public_key = Vec::new();
-        {
-            let uid_str = cert
-                .userids()
-                .next()
-                .map(|u| u.userid().to_string())
-                .unwrap_or_default();
-            let fp_hex = cert.fingerprint().to_hex();
-            let extra: Vec<(&str, &str)> = vec![("Comment", &uid_str), ("Fingerprint", &fp_hex)];
-            let mut writer = self
-                .armor_writer_with_extra(
+    Ok((template, recipients))
+}
+
+/// Build an Ed25519/X25519 certificate whose key secrets are derived from a
+/// recovery phrase seed.
+///
+/// Mirrors the layout of [`CertBuilder`] with the Cv25519 suite: a
+/// certification-only primary key, a signing subkey, an encryption subkey
+/// and optionally an authentication subkey. All keys are created at
+/// `creation_time`, so the fingerprint only depends on the phrase and time;
+/// the binding signatures are made at `signature_time`, so a restored copy
+/// supersedes the self-signatures of the original.
+fn seeded_cert(
+    seed: &crate::mnemonic::KeySeed,
+    user_id: &str,
+    creation_time: std::time::SystemTime,
+    signature_time: std::time::SystemTime,
+    validity: Option<std::time::Duration>,
+    auth_subkey: bool,
+    revokers: Vec<RevocationKey>,
+) -> sequoia_openpgp::Result<(Cert, sequoia_openpgp::packet::Signature)> {
+    use sequoia_openpgp::Packet;
+    use sequoia_openpgp::packet::key::{Key4, PrimaryRole, SecretParts, SubordinateRole};
+    use sequoia_openpgp::packet::signature::SignatureBuilder;
+    use sequoia_openpgp::packet::{Key, UserID};
+    use sequoia_openpgp::types::{Features, ReasonForRevocation, SignatureType};
+
+    use crate::mnemonic::{AUTHENTICATION_KEY, ENCRYPTION_KEY, PRIMARY_KEY, SIGNING_KEY};
+
+    let primary: Key<SecretParts, PrimaryRole> =
+        Key4::import_secret_ed25519(&seed.derive(PRIMARY_KEY)?, creation_time)?.into();
+    let mut signer = primary.clone().into_keypair()?;
+
+    // Self-signature metadata, as CertBuilder puts it on the direct key
+    // signature and the user ID binding.
+    let metadata = |sig_type: SignatureType| -> sequoia_openpgp::Result<SignatureBuilder> {
+        SignatureBuilder::new(sig_type)
+            .set_hash_algo(HashAlgorithm::SHA512)
+            .set_signature_creation_time(signature_time)?
+            .set_features(Features::sequoia())?
+            .set_key_flags(KeyFlags::empty().set_certification())?
+            .set_key_validity_period(validity)?
+            .set_preferred_hash_algorithms(vec![HashAlgorithm::SHA512, HashAlgorithm::SHA256])?
+            .set_preferred_symmetric_algorithms(vec![
+                SymmetricAlgorithm::AES256,
+                SymmetricAlgorithm::AES128,
+            ])
+    };
+
+    let mut direct = metadata(SignatureType::DirectKey)?;
+    for revoker in revokers {
+        direct = direct.add_revocation_key(revoker)?;
+    }
+    let direct = direct.sign_direct_key(&mut signer, primary.parts_as_public())?;
+
+    let userid = UserID::from(user_id);
+    let userid_binding = metadata(SignatureType::PositiveCertification)?
+        .set_primary_userid(true)?
+        .sign_userid_binding(&mut signer, primary.parts_as_public(), &userid)?;
+
+    let mut packets: Vec<Packet> = vec![
+        primary.clone().into(),
+        direct.into(),
+        userid.into(),
+        userid_binding.into(),
+    ];
+
+    let mut subkeys = vec![
+        (SIGNING_KEY, KeyFlags::empty().set_signing()),
+        (ENCRYPTION_KEY, KeyFlags::empty().set_transport_encryption()),
+    ];
+    if auth_subkey {
+        subkeys.push((AUTHENTICATION_KEY, KeyFlags::empty().set_authentication()));
+    }
+
+    for (label, flags) in subkeys {
+        let secret = seed.derive(label)?;
+        let subkey: Key<SecretParts, SubordinateRole> = if flags.for_transport_encryption() {
+            Key4::import_secret_cv25519(&secret, None, None, creation_time)?.into()
+        } else {
+            Key4::import_secret_ed25519(&secret, creation_time)?.into()
+        };
+
+        let mut binding = SignatureBuilder::new(SignatureType::SubkeyBinding)
+            .set_hash_algo(HashAlgorithm::SHA512)
+            .set_signature_creation_time(signature_time)?
+            .set_key_flags(flags.clone())?
+            .set_key_validity_period(validity)?;
+        if flags.for_signing() {
+            let backsig = SignatureBuilder::new(SignatureType::PrimaryKeyBinding)
+                .set_hash_algo(HashAlgorithm::SHA512)
+                .set_signature_creation_time(signature_time)?
+                .sign_primary_key_binding(
+                    &mut subkey.clone().into_keypair()?,
+                    primary.parts_as_public(),
+                    subkey.parts_as_public(),
+                )?;
+            binding = binding.set_embedded_signature(backsig)?;
+        }
+        let binding = binding.sign_subkey_binding(
+            &mut signer,
+            primary.parts_as_public(),
+            subkey.parts_as_public(),
+        )?;
+
+        packets.push(subkey.into());
+        packets.push(binding.into());
+    }
+
+    let cert = Cert::from_packets(packets.into_iter())?;
+    let revocation = CertRevocationBuilder::new()
+        .set_signature_creation_time(signature_time)?
+        .set_reason_for_revocation(ReasonForRevocation::Unspecified, b"Unspecified")?
+        .build(&mut signer, &cert, None)?;
+
+    Ok((cert, revocation))
+}
+
+impl CryptoEngine for SequoiaEngine {
+    fn generate_key_pair(&self, options: KeyGenOptions) -> Result<GeneratedKeyPair> {
+        let user_id = options.user_id.to_openpgp_string();
+
+        let revokers = options
+            .revokers
+            .iter()
+            .map(|key| {
+                Cert::from_bytes(key)
+                    .map(|revoker| RevocationKey::from(&revoker))
+                    .map_err(|e| Error::KeyGeneration {
+                        reason: format!("invalid designated revoker key: {e}"),
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Whole seconds, as stored in OpenPGP packets
+        let now = std::time::UNIX_EPOCH
+            + std::time::Duration::from_secs(chrono::Utc::now().timestamp().max(0) as u64);
+        let creation_time = options.creation_time.map(|t| {
+            let secs = t
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)
+        });
+
+        // The expiration counts from now, but OpenPGP stores it relative to
+        // the key creation time, which may be in the past.
+        let validity = options.expiration.map(|expiration| {
+            expiration
+                + creation_time
+                    .and_then(|t| now.duration_since(t).ok())
+                    .unwrap_or_default()
+        });
+
+        let password = options
+            .passphrase
+            .as_ref()
+            .map(|p| Password::from(p.expose_secret().as_slice()));
+
+        let (mut cert, revocation) = if let Some(phrase) = &options.mnemonic {
+            if options.algorithm != KeyAlgorithm::Ed25519 {
+                return Err(Error::KeyGeneration {
+                    reason: "keys from a recovery phrase must use Ed25519".into(),
+                });
+            }
+            let creation_time = creation_time.unwrap_or(now);
+            let seed = crate::mnemonic::KeySeed::from_mnemonic(phrase.expose_secret())?;
+            let (cert, revocation) = seeded_cert(
+                &seed,
+                &user_id,
+                creation_time,
+                now.max(creation_time),
+                validity,
+                options.auth_subkey,
+                revokers,
+            )
+            .map_err(|e| Error::KeyGeneration {
+                reason: e.to_string(),
+            })?;
+            let cert = match &password {
+                Some(password) => protect_secret_keys(cert, None, Some(password), options.s2k)
+                    .map_err(|e| Error::KeyGeneration {
+                        reason: e.to_string(),
+                    })?,
+                None => cert,
+            };
+            (cert, revocation)
+        } else {
+            let mut builder = match options.algorithm {
+                KeyAlgorithm::Ed25519 => CertBuilder::new()
+                    .add_userid(user_id)
+                    .set_cipher_suite(CipherSuite::Cv25519)
+                    .add_signing_subkey()
+                    .add_subkey(KeyFlags::empty().set_transport_encryption(), validity, None),
+                KeyAlgorithm::Rsa(bits) => {
+                    let suite = match bits {
+                        3072 => CipherSuite::RSA3k,
+                        _ => CipherSuite::RSA4k,
+                    };
+                    CertBuilder::new()
+                        .add_userid(user_id)
+                        .set_cipher_suite(suite)
+                        .add_signing_subkey()
+                        .add_subkey(KeyFlags::empty().set_transport_encryption(), validity, None)
+                }
+            };
+
+            if options.auth_subkey {
+                builder = builder.add_authentication_subkey();
+            }
+
+            if !revokers.is_empty() {
+                builder = builder.set_revocation_keys(revokers);
+            }
+
+            if let Some(validity) = validity {
+                builder = builder.set_validity_period(validity);
+            }
+
+            if let Some(time) = creation_time {
+                builder = builder.set_creation_time(time);
+            }
+
+            if options.s2k == S2kMode::Iterated {
+                builder = builder.set_password(password.clone());
+            }
+
+            let (mut cert, revocation) = builder.generate().map_err(|e| Error::KeyGeneration {
+                reason: e.to_string(),
+            })?;
+
+            if options.s2k != S2kMode::Iterated && password.is_some() {
+                cert = protect_secret_keys(cert, None, password.as_ref(), options.s2k).map_err(
+                    |e| Error::KeyGeneration {
+                        reason: e.to_string(),
+                    },
+                )?;
+            }
+            (cert, revocation)
+        };
+
+        if !options.adsks.is_empty() {
+            let mut primary = primary_keypair(&cert, password.as_ref())?;
+            for adsk_key in &options.adsks {
+                cert = self.bind_adsk(cert, &mut primary, adsk_key)?;
+            }
+        }
+
+        let fingerprint = Fingerprint::new(cert.fingerprint().to_hex());
+
+        // Serialize public key (certificate)
+        let mut public_key = Vec::new();
+        {
+            let uid_str = cert
+                .userids()
+                .next()
+                .map(|u| u.userid().to_string())
+                .unwrap_or_default();
+            let fp_hex = cert.fingerprint().to_hex();
+            let extra: Vec<(&str, &str)> = vec![("Comment", &uid_str), ("Fingerprint", &fp_hex)];
+            let mut writer = self
+                .armor_writer_with_extra(
                     &mut public_key,
                     sequoia_openpgp::armor::Kind::PublicKey,
                     &extra,
@@ -499,6 +1292,227 @@ impl CryptoEngine for SequoiaEngine {
         })
     }
 
+    fn change_passphrase(
+        &self,
+        secret_key: &[u8],
+        old_passphrase: Option<&[u8]>,
+        new_passphrase: Option<&[u8]>,
+        s2k: S2kMode,
+    ) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert.is_tsk() {
+            return Err(Error::NoSecretKey);
+        }
+
+        let old = old_passphrase.map(Password::from);
+        let new = new_passphrase.map(Password::from);
+        let cert = protect_secret_keys(cert, old.as_ref(), new.as_ref(), s2k)?;
+
+        self.armored_secret_key(&cert)
+    }
+
+    fn add_adsk(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        adsk_key: &[u8],
+    ) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert.is_tsk() {
+            return Err(Error::NoSecretKey);
+        }
+
+        let password = passphrase.map(Password::from);
+        let mut primary = primary_keypair(&cert, password.as_ref())?;
+        let cert = self.bind_adsk(cert, &mut primary, adsk_key)?;
+
+        self.armored_secret_key(&cert)
+    }
+
+    fn export_secret_subkeys(&self, secret_key: &[u8]) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert.keys().subkeys().any(|ka| ka.key().has_secret()) {
+            return Err(Error::NoSecretKey);
+        }
+
+        let primary = cert.fingerprint();
+        let mut output = Vec::new();
+        {
+            let mut writer = self
+                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::SecretKey)
+                .map_err(|e| Error::Internal(format!("armor error: {e}")))?;
+            cert.as_tsk()
+                .set_filter(move |key| key.fingerprint() != primary)
+                .emit_secret_key_stubs(true)
+                .serialize(&mut writer)
+                .map_err(|e| Error::Internal(format!("serialize error: {e}")))?;
+            writer
+                .finalize()
+                .map_err(|e| Error::Internal(format!("finalize error: {e}")))?;
+        }
+
+        Ok(output)
+    }
+
+    fn add_designated_revoker(
+        &self,
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        revoker_key: &[u8],
+    ) -> Result<Vec<u8>> {
+        use sequoia_openpgp::packet::signature::SignatureBuilder;
+        use sequoia_openpgp::types::SignatureType;
+
+        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !cert.is_tsk() {
+            return Err(Error::NoSecretKey);
+        }
+        let revoker = Cert::from_bytes(revoker_key).map_err(|e| Error::KeyUpdate {
+            reason: format!("invalid designated revoker key: {e}"),
+        })?;
+        if revoker.fingerprint() == cert.fingerprint() {
+            return Err(Error::KeyUpdate {
+                reason: "a key cannot be its own designated revoker".into(),
+            });
+        }
+
+        let valid_cert = cert
+            .with_policy(&self.policy, None)
+            .map_err(|e| Error::KeyUpdate {
+                reason: format!("key policy check failed: {e}"),
+            })?;
+        if valid_cert
+            .revocation_keys()
+            .any(|rk| rk.revoker().1 == &revoker.fingerprint())
+        {
+            return Err(Error::KeyUpdate {
+                reason: format!(
+                    "{} is already a designated revoker",
+                    describe_recipient(&revoker, &self.policy)
+                ),
+            });
+        }
+
+        // Supersede the current direct key signature, keeping its other
+        // subpackets (key flags, expiration, preferences) intact.
+        let builder = match valid_cert.direct_key_signature() {
+            Ok(sig) => SignatureBuilder::from(sig.clone()),
+            Err(_) => SignatureBuilder::new(SignatureType::DirectKey),
+        };
+
+        let password = passphrase.map(Password::from);
+        let mut primary = primary_keypair(&cert, password.as_ref())?;
+        let sig = builder
+            .add_revocation_key(RevocationKey::from(&revoker))
+            .and_then(|b| b.sign_direct_key(&mut primary, cert.primary_key().key()))
+            .map_err(|e| Error::KeyUpdate {
+                reason: format!("failed to sign designated revoker: {e}"),
+            })?;
+
+        let (cert, _) = cert.insert_packets(sig).map_err(|e| Error::KeyUpdate {
+            reason: e.to_string(),
+        })?;
+
+        self.armored_secret_key(&cert)
+    }
+
+    fn revoke_as_designated_revoker(
+        &self,
+        target_key: &[u8],
+        revoker_secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        use sequoia_openpgp::types::ReasonForRevocation;
+
+        let target = Cert::from_bytes(target_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        let revoker = Cert::from_bytes(revoker_secret_key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        if !revoker.is_tsk() {
+            return Err(Error::NoSecretKey);
+        }
+
+        let valid_target =
+            target
+                .with_policy(&self.policy, None)
+                .map_err(|e| Error::KeyUpdate {
+                    reason: format!("key policy check failed: {e}"),
+                })?;
+        if !valid_target
+            .revocation_keys()
+            .any(|rk| rk.revoker().1 == &revoker.fingerprint())
+        {
+            return Err(Error::KeyUpdate {
+                reason: format!(
+                    "{} is not a designated revoker of {}",
+                    describe_recipient(&revoker, &self.policy),
+                    describe_recipient(&target, &self.policy)
+                ),
+            });
+        }
+
+        let password = passphrase.map(Password::from);
+        let mut signer = primary_keypair(&revoker, password.as_ref())?;
+        let revocation = CertRevocationBuilder::new()
+            .set_reason_for_revocation(
+                ReasonForRevocation::Unspecified,
+                b"Revoked by designated revoker",
+            )
+            .and_then(|b| b.build(&mut signer, &target, None))
+            .map_err(|e| Error::KeyUpdate {
+                reason: format!("failed to sign revocation: {e}"),
+            })?;
+
+        let (target, _) = target
+            .insert_packets(revocation)
+            .map_err(|e| Error::KeyUpdate {
+                reason: e.to_string(),
+            })?;
+
+        self.armored_public_key(&target)
+    }
+
+    fn verify_designated_revocation(&self, key: &[u8], revoker_keys: &[Vec<u8>]) -> Result<bool> {
+        let target = Cert::from_bytes(key).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+        let Ok(valid_target) = target.with_policy(&self.policy, None) else {
+            return Ok(false);
+        };
+        let revocations = designated_revocations(&valid_target);
+        if revocations.is_empty() {
+            return Ok(false);
+        }
+        let revokers: Vec<Cert> = revoker_keys
+            .iter()
+            .filter_map(|k| Cert::from_bytes(k).ok())
+            .filter(|revoker| {
+                valid_target
+                    .revocation_keys()
+                    .any(|rk| rk.revoker().1 == &revoker.fingerprint())
+            })
+            .collect();
+        Ok(revocations.iter().any(|rev| {
+            revokers.iter().any(|revoker| {
+                rev.verify_primary_key_revocation(
+                    revoker.primary_key().key(),
+                    target.primary_key().key(),
+                )
+                .is_ok()
+            })
+        }))
+    }
+
     fn encrypt(&self, plaintext: &[u8], recipient_keys: &[Vec<u8>]) -> Result<Vec<u8>> {
         if recipient_keys.is_empty() {
             return Err(Error::NoRecipients);
@@ -513,8 +1527,19 @@ impl CryptoEngine for SequoiaEngine {
             })
             .collect::<Result<Vec<_>>>()?;
 
+        let hidden = self.hidden_recipients.load(Ordering::Relaxed);
         let mut recipients: Vec<Recipient> = Vec::new();
         for cert in &certs {
+            let status = self.recipient_status(cert);
+            if !status.is_usable() {
+                return Err(Error::Encryption {
+                    reason: format!(
+                        "cannot encrypt to {}: {status}",
+                        describe_recipient(cert, &self.policy)
+                    ),
+                });
+            }
+
             let valid_cert =
                 cert.with_policy(&self.policy, None)
                     .map_err(|e| Error::Encryption {
@@ -528,8 +1553,19 @@ impl CryptoEngine for SequoiaEngine {
                 .revoked(false)
                 .for_transport_encryption()
                 .for_storage_encryption()
+                .key_flags(adsk_flags())
             {
-                recipients.push(key.into());
+                let recipient = Recipient::from(key);
+                let recipient = if hidden {
+                    recipient
+                        .set_key_handle(None)
+                        .map_err(|e| Error::Encryption {
+                            reason: format!("failed to hide recipient: {e}"),
+                        })?
+                } else {
+                    recipient
+                };
+                recipients.push(recipient);
             }
         }
 
@@ -577,21 +1613,87 @@ impl CryptoEngine for SequoiaEngine {
         Ok(output)
     }
 
+    fn check_recipients(&self, recipient_keys: &[Vec<u8>]) -> Vec<RecipientCheck> {
+        recipient_keys
+            .iter()
+            .map(|key| match Cert::from_bytes(key) {
+                Ok(cert) => RecipientCheck {
+                    fingerprint: Some(cert.fingerprint().to_hex()),
+                    user_id: primary_user_id(&cert, &self.policy),
+                    status: self.recipient_status(&cert),
+                },
+                Err(e) => RecipientCheck {
+                    fingerprint: None,
+                    user_id: None,
+                    status: RecipientStatus::Invalid {
+                        reason: e.to_string(),
+                    },
+                },
+            })
+            .collect()
+    }
+
     fn decrypt(
         &self,
         ciphertext: &[u8],
         secret_key: &[u8],
         passphrase: Option<&[u8]>,
     ) -> Result<Vec<u8>> {
+        self.decrypt_revealing_session_key(ciphertext, secret_key, passphrase)
+            .map(|(plaintext, _)| plaintext)
+    }
+
+    fn decrypt_revealing_session_key(
+        &self,
+        ciphertext: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, MessageSessionKey)> {
         let cert = Cert::from_bytes(secret_key).map_err(|e| Error::Decryption {
             reason: format!("invalid secret key: {e}"),
         })?;
 
-        let helper = DecryptHelper {
-            policy: &self.policy,
-            cert,
-            passphrase,
-        };
+        self.decrypt_with_helper(
+            ciphertext,
+            DecryptHelper {
+                policy: &self.policy,
+                cert,
+                passphrase,
+                backend: None,
+                session_key: None,
+            },
+        )
+    }
+
+    fn decrypt_with_backend(
+        &self,
+        ciphertext: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+    ) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(public_key).map_err(|e| Error::Decryption {
+            reason: format!("invalid public key: {e}"),
+        })?;
+
+        self.decrypt_with_helper(
+            ciphertext,
+            DecryptHelper {
+                policy: &self.policy,
+                cert,
+                passphrase: None,
+                backend: Some(backend),
+                session_key: None,
+            },
+        )
+        .map(|(plaintext, _)| plaintext)
+    }
+
+    fn decrypt_with_session_key(
+        &self,
+        ciphertext: &[u8],
+        session_key: &MessageSessionKey,
+    ) -> Result<Vec<u8>> {
+        let helper = SessionKeyHelper { session_key };
 
         let mut decryptor = DecryptorBuilder::from_bytes(ciphertext)
             .map_err(|e| Error::Decryption {
@@ -611,87 +1713,123 @@ impl CryptoEngine for SequoiaEngine {
     }
 
     fn sign(&self, data: &[u8], secret_key: &[u8], passphrase: Option<&[u8]>) -> Result<Vec<u8>> {
-        let cert = Cert::from_bytes(secret_key).map_err(|e| Error::Signing {
-            reason: format!("invalid secret key: {e}"),
-        })?;
+        self.sign_with_options(data, secret_key, passphrase, &SignOptions::default())
+    }
 
+    fn sign_with_options(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>> {
+        let signer_keypair = self.signing_keypair(secret_key, passphrase)?;
+        let (template, recipients) = signature_template(secret_key, options)?;
+        self.write_signed_message(data, signer_keypair, template, &recipients)
+    }
+
+    fn sign_with_backend(
+        &self,
+        data: &[u8],
+        public_key: &[u8],
+        backend: &dyn SecretKeyBackend,
+        options: &SignOptions,
+    ) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(public_key).map_err(|e| Error::Signing {
+            reason: format!("invalid public key: {e}"),
+        })?;
         let valid_cert = cert
             .with_policy(&self.policy, None)
             .map_err(|e| Error::Signing {
                 reason: format!("key policy check failed: {e}"),
             })?;
 
-        // Find a signing-capable secret key
-        let mut keypair = None;
-
-        // Try unencrypted secret keys first
-        if let Some(ka) = valid_cert
+        let mut signer = None;
+        for ka in valid_cert
             .keys()
             .supported()
             .alive()
             .revoked(false)
             .for_signing()
-            .unencrypted_secret()
-            .next()
         {
-            keypair = Some(
-                ka.key()
-                    .clone()
-                    .into_keypair()
-                    .map_err(|e| Error::Signing {
-                        reason: format!("keypair conversion failed: {e}"),
-                    })?,
-            );
-        }
-
-        // Try with passphrase
-        if keypair.is_none() {
-            if let Some(passphrase) = passphrase {
-                let password = sequoia_openpgp::crypto::Password::from(passphrase);
-                for ka in valid_cert
-                    .keys()
-                    .supported()
-                    .alive()
-                    .revoked(false)
-                    .for_signing()
-                    .secret()
-                {
-                    let key = ka.key().clone();
-                    if let Ok(decrypted) = key.decrypt_secret(&password) {
-                        if let Ok(kp) = decrypted.into_keypair() {
-                            keypair = Some(kp);
-                            break;
-                        }
-                    }
-                }
+            let keypair = BackendKeyPair::new(ka.key(), backend)?;
+            if backend.has_key(&keypair.key)? {
+                signer = Some(keypair);
+                break;
             }
         }
+        let signer = signer.ok_or(Error::NoSecretKey)?;
 
-        let signer_keypair = keypair.ok_or_else(|| Error::Signing {
-            reason: "no signing-capable secret key found".into(),
+        let (template, recipients) = signature_template(public_key, options)?;
+        self.write_signed_message(data, signer, template, &recipients)
+    }
+
+    fn verify(&self, signed_data: &[u8], signer_key: &[u8]) -> Result<VerifyResult> {
+        let signer_cert = Cert::from_bytes(signer_key).map_err(|e| Error::VerificationFailed {
+            reason: format!("invalid signer key: {e}"),
+        })?;
+
+        let signer_fp = signer_cert.fingerprint().to_hex();
+
+        let helper = VerifyHelper {
+            policy: &self.policy,
+            cert: signer_cert,
+            result: None,
+        };
+
+        let mut verifier = VerifierBuilder::from_bytes(signed_data)
+            .map_err(|e| Error::VerificationFailed {
+                reason: format!("invalid signed data: {e}"),
+            })?
+            .with_policy(&self.policy, None, helper)
+            .map_err(|e| Error::VerificationFailed {
+                reason: format!("verification setup failed: {e}"),
+            })?;
+
+        // Consume the verified content
+        let mut content = Vec::new();
+        std::io::copy(&mut verifier, &mut content).map_err(|e| Error::VerificationFailed {
+            reason: format!("read error: {e}"),
         })?;
 
+        let helper = verifier.into_helper();
+
+        Ok(helper.result.unwrap_or(VerifyResult {
+            valid: false,
+            signer_fingerprint: Some(signer_fp),
+            ..Default::default()
+        }))
+    }
+
+    fn sign_detached(
+        &self,
+        data: &[u8],
+        secret_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        let signer_keypair = self.signing_keypair(secret_key, passphrase)?;
+
         let mut output = Vec::new();
         {
             let mut armored_writer = self
-                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::Message)
+                .armor_writer(&mut output, sequoia_openpgp::armor::Kind::Signature)
                 .map_err(|e| Error::Signing {
                     reason: format!("armor error: {e}"),
                 })?;
 
             let message = Message::new(&mut armored_writer);
-            let message = Signer::new(message, signer_keypair)
+            let mut message = Signer::new(message, signer_keypair)
                 .map_err(|e| Error::Signing {
                     reason: format!("signer error: {e}"),
                 })?
-                .build()
+                .hash_algo(HashAlgorithm::SHA512)
                 .map_err(|e| Error::Signing {
                     reason: format!("signer error: {e}"),
-                })?;
-            let mut message = LiteralWriter::new(message)
+                })?
+                .detached()
                 .build()
                 .map_err(|e| Error::Signing {
-                    reason: format!("literal writer error: {e}"),
+                    reason: format!("signer error: {e}"),
                 })?;
 
             message.write_all(data).map_err(|e| Error::Signing {
@@ -709,7 +1847,12 @@ impl CryptoEngine for SequoiaEngine {
         Ok(output)
     }
 
-    fn verify(&self, signed_data: &[u8], signer_key: &[u8]) -> Result<VerifyResult> {
+    fn verify_detached(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        signer_key: &[u8],
+    ) -> Result<VerifyResult> {
         let signer_cert = Cert::from_bytes(signer_key).map_err(|e| Error::VerificationFailed {
             reason: format!("invalid signer key: {e}"),
         })?;
@@ -722,26 +1865,27 @@ impl CryptoEngine for SequoiaEngine {
             result: None,
         };
 
-        let mut verifier = VerifierBuilder::from_bytes(signed_data)
+        let mut verifier = DetachedVerifierBuilder::from_bytes(signature)
             .map_err(|e| Error::VerificationFailed {
-                reason: format!("invalid signed data: {e}"),
+                reason: format!("invalid signature: {e}"),
             })?
             .with_policy(&self.policy, None, helper)
             .map_err(|e| Error::VerificationFailed {
                 reason: format!("verification setup failed: {e}"),
             })?;
 
-        // Consume the verified content
-        let mut content = Vec::new();
-        std::io::copy(&mut verifier, &mut content).map_err(|e| Error::VerificationFailed {
-            reason: format!("read error: {e}"),
-        })?;
+        verifier
+            .verify_bytes(data)
+            .map_err(|e| Error::VerificationFailed {
+                reason: format!("verification failed: {e}"),
+            })?;
 
         let helper = verifier.into_helper();
 
         Ok(helper.result.unwrap_or(VerifyResult {
             valid: false,
             signer_fingerprint: Some(signer_fp),
+            ..Default::default()
         }))
     }
 
@@ -790,15 +1934,36 @@ impl CryptoEngine for SequoiaEngine {
 
         // Check for secret key material
         let has_secret_key = cert.is_tsk();
+        let primary_key_offline = has_secret_key && (!pk.has_secret() || is_secret_stub(pk));
 
         let is_revoked = cert
             .with_policy(&self.policy, None)
             .ok()
             .map(|valid_cert| {
-                valid_cert.primary_key().revocation_status()
-                    != sequoia_openpgp::types::RevocationStatus::NotAsFarAsWeKnow
+                matches!(
+                    valid_cert.primary_key().revocation_status(),
+                    sequoia_openpgp::types::RevocationStatus::Revoked(_)
+                )
             })
             .unwrap_or(false);
+        let possibly_revoked = !is_revoked
+            && cert
+                .with_policy(&self.policy, None)
+                .ok()
+                .is_some_and(|valid_cert| !designated_revocations(&valid_cert).is_empty());
+
+        let designated_revokers = cert
+            .with_policy(&self.policy, None)
+            .ok()
+            .map(|valid_cert| {
+                let mut revokers: Vec<String> = valid_cert
+                    .revocation_keys()
+                    .map(|rk| rk.revoker().1.to_hex())
+                    .collect();
+                revokers.sort();
+                revokers
+            })
+            .unwrap_or_default();
 
         // Extract subkey information
         let subkeys = cert
@@ -835,6 +2000,9 @@ impl CryptoEngine for SequoiaEngine {
                         if ka.for_authentication() {
                             capabilities.push(KeyCapability::Authenticate);
                         }
+                        if ka.key_flags().is_some_and(|f| f.get(ADSK_KEY_FLAG)) {
+                            capabilities.push(KeyCapability::AdditionalDecryption);
+                        }
 
                         let is_revoked = is_revoked
                             || (ka.revocation_status()
@@ -847,6 +2015,11 @@ impl CryptoEngine for SequoiaEngine {
                             expires_at: sk_expires,
                             capabilities,
                             is_revoked,
+                            s2k: if is_secret_stub(key) {
+                                None
+                            } else {
+                                s2k_type(key)
+                            },
                         }
                     })
                     .collect()
@@ -861,7 +2034,15 @@ impl CryptoEngine for SequoiaEngine {
             expires_at,
             has_secret_key,
             is_revoked,
+            s2k: if primary_key_offline {
+                None
+            } else {
+                s2k_type(pk)
+            },
+            primary_key_offline,
             subkeys,
+            designated_revokers,
+            possibly_revoked,
         })
     }
 
@@ -924,6 +2105,14 @@ impl CryptoEngine for SequoiaEngine {
             reason: format!("Internal UTF-8 error: {e}"),
         })
     }
+
+    fn public_key(&self, key_data: &[u8]) -> Result<Vec<u8>> {
+        let cert = Cert::from_bytes(key_data).map_err(|e| Error::InvalidArmor {
+            reason: e.to_string(),
+        })?;
+
+        self.armored_public_key(&cert)
+    }
 }
 
 /// Helper struct for the Sequoia decryption streaming API.
@@ -931,6 +2120,10 @@ struct DecryptHelper<'a> {
     policy: &'a StandardPolicy<'static>,
     cert: Cert,
     passphrase: Option<&'a [u8]>,
+    /// Backend holding the secret keys, used instead of `cert`'s secrets.
+    backend: Option<&'a dyn SecretKeyBackend>,
+    /// The session key that decrypted the message, once found.
+    session_key: Option<MessageSessionKey>,
 }
 
 impl VerificationHelper for DecryptHelper<'_> {
@@ -973,6 +2166,27 @@ impl DecryptionHelper for DecryptHelper<'_> {
     ) -> sequoia_openpgp::Result<Option<Cert>> {
         let valid_cert = self.cert.with_policy(self.policy, None)?;
 
+        if let Some(backend) = self.backend {
+            for ka in valid_cert
+                .keys()
+                .supported()
+                .for_transport_encryption()
+                .for_storage_encryption()
+            {
+                let mut keypair = BackendKeyPair::new(ka.key(), backend)?;
+                if !backend.has_key(&keypair.key)? {
+                    continue;
+                }
+                if let Some(sk) = try_pkesks(pkesks, &mut keypair, sym_algo, decrypt) {
+                    self.session_key = Some(sk);
+                    return Ok(None);
+                }
+                if let Some(e) = keypair.error.take() {
+                    return Err(e.into());
+                }
+            }
+        }
+
         // Try unencrypted secret keys first
         for ka in valid_cert
             .keys()
@@ -982,20 +2196,16 @@ impl DecryptionHelper for DecryptHelper<'_> {
             .for_storage_encryption()
         {
             let mut keypair = ka.key().clone().into_keypair()?;
-            for pkesk in pkesks {
-                if pkesk
-                    .decrypt(&mut keypair, sym_algo)
-                    .map(|(algo, sk)| decrypt(algo, &sk))
-                    .unwrap_or(false)
-                {
-                    return Ok(None);
-                }
+            if let Some(sk) = try_pkesks(pkesks, &mut keypair, sym_algo, decrypt) {
+                self.session_key = Some(sk);
+                return Ok(None);
             }
         }
 
         // Try with passphrase-decrypted keys
         if let Some(passphrase) = self.passphrase {
             let password = sequoia_openpgp::crypto::Password::from(passphrase);
+            let mut locked = false;
 
             for ka in valid_cert
                 .keys()
@@ -1005,20 +2215,26 @@ impl DecryptionHelper for DecryptHelper<'_> {
                 .for_storage_encryption()
             {
                 let key = ka.key().clone();
-                if let Ok(decrypted) = key.decrypt_secret(&password) {
-                    if let Ok(mut keypair) = decrypted.into_keypair() {
-                        for pkesk in pkesks {
-                            if pkesk
-                                .decrypt(&mut keypair, sym_algo)
-                                .map(|(algo, sk)| decrypt(algo, &sk))
-                                .unwrap_or(false)
-                            {
+                match key.decrypt_secret(&password) {
+                    Ok(decrypted) => {
+                        if let Ok(mut keypair) = decrypted.into_keypair() {
+                            if let Some(sk) = try_pkesks(pkesks, &mut keypair, sym_algo, decrypt) {
+                                self.session_key = Some(sk);
                                 return Ok(None);
                             }
                         }
                     }
+                    Err(_) => {
+                        let handle = ka.key().key_handle();
+                        locked |= pkesks.iter().any(|pkesk| addressed_to(pkesk, &handle));
+                    }
                 }
             }
+
+            // The message is for a key the passphrase does not unlock
+            if locked {
+                return Err(Error::BadPassphrase.into());
+            }
         }
 
         Err(
@@ -1028,6 +2244,243 @@ impl DecryptionHelper for DecryptHelper<'_> {
     }
 }
 
+/// The primary user ID of a certificate, if it has a valid one.
+fn primary_user_id(cert: &Cert, policy: &StandardPolicy<'static>) -> Option<String> {
+    cert.with_policy(policy, None)
+        .ok()
+        .and_then(|vc| vc.primary_userid().ok())
+        .map(|uid| String::from_utf8_lossy(uid.userid().value()).into_owned())
+        .or_else(|| {
+            cert.userids()
+                .next()
+                .map(|uid| String::from_utf8_lossy(uid.userid().value()).into_owned())
+        })
+}
+
+/// Name a recipient in error messages: user ID and fingerprint.
+fn describe_recipient(cert: &Cert, policy: &StandardPolicy<'static>) -> String {
+    match primary_user_id(cert, policy) {
+        Some(uid) => format!("{uid} ({})", cert.fingerprint().to_hex()),
+        None => cert.fingerprint().to_hex(),
+    }
+}
+
+/// Adapts a [`SecretKeyBackend`] key to Sequoia's signer and decryptor.
+struct BackendKeyPair<'a> {
+    public: Key<PublicParts, UnspecifiedRole>,
+    key: BackendKey,
+    backend: &'a dyn SecretKeyBackend,
+    /// The backend's error from the last decryption, which Sequoia would
+    /// otherwise discard while trying the PKESKs.
+    error: Option<Error>,
+}
+
+impl<'a> BackendKeyPair<'a> {
+    fn new<R: KeyRole>(
+        key: &Key<PublicParts, R>,
+        backend: &'a dyn SecretKeyBackend,
+    ) -> Result<Self> {
+        Ok(Self {
+            public: key.clone().role_into_unspecified(),
+            key: BackendKey {
+                fingerprint: key.fingerprint().to_hex(),
+                keygrip: crate::backend::keygrip(key.mpis())?,
+            },
+            backend,
+            error: None,
+        })
+    }
+}
+
+impl sequoia_openpgp::crypto::Signer for BackendKeyPair<'_> {
+    fn public(&self) -> &Key<PublicParts, UnspecifiedRole> {
+        &self.public
+    }
+
+    fn sign(
+        &mut self,
+        hash_algo: HashAlgorithm,
+        digest: &[u8],
+    ) -> sequoia_openpgp::Result<mpi::Signature> {
+        Ok(
+            match self.backend.sign(&self.key, hash_algo.into(), digest)? {
+                BackendSignature::EdDsa { r, s } => mpi::Signature::EdDSA {
+                    r: mpi::MPI::new(&r),
+                    s: mpi::MPI::new(&s),
+                },
+                BackendSignature::Rsa { s } => mpi::Signature::RSA {
+                    s: mpi::MPI::new(&s),
+                },
+            },
+        )
+    }
+}
+
+impl sequoia_openpgp::crypto::Decryptor for BackendKeyPair<'_> {
+    fn public(&self) -> &Key<PublicParts, UnspecifiedRole> {
+        &self.public
+    }
+
+    fn decrypt(
+        &mut self,
+        ciphertext: &mpi::Ciphertext,
+        plaintext_len: Option<usize>,
+    ) -> sequoia_openpgp::Result<SessionKey> {
+        let request = match ciphertext {
+            mpi::Ciphertext::RSA { c } => BackendCiphertext::Rsa {
+                c: c.value().to_vec(),
+            },
+            mpi::Ciphertext::ECDH { e, key } => BackendCiphertext::Ecdh {
+                e: e.value().to_vec(),
+                wrapped_key: key.to_vec(),
+            },
+            _ => {
+                return Err(Error::Decryption {
+                    reason: "unsupported ciphertext for a secret key backend".into(),
+                }
+                .into());
+            }
+        };
+
+        let value = match self.backend.decrypt(&self.key, &request) {
+            Ok(value) => value,
+            Err(e) => {
+                let reason = e.to_string();
+                self.error = Some(e);
+                return Err(Error::Decryption { reason }.into());
+            }
+        };
+
+        match (value, self.public.mpis()) {
+            (DecryptedValue::SharedPoint(point), mpi::PublicKey::ECDH { curve, .. }) => {
+                // Agents return the point in native (0x40-prefixed) encoding,
+                // but accept a bare X25519 value too
+                let point = if point.len() == 32 {
+                    mpi::ProtectedMPI::from([&[0x40][..], &point[..]].concat())
+                } else {
+                    mpi::ProtectedMPI::from(&point[..])
+                };
+                let (shared, _) = point.decode_point(curve)?;
+                sequoia_openpgp::crypto::ecdh::decrypt_unwrap(
+                    &self.public,
+                    &shared.into(),
+                    ciphertext,
+                    plaintext_len,
+                )
+            }
+            (DecryptedValue::Padded(value), mpi::PublicKey::RSA { .. }) => {
+                Ok(unpad_session_key(&value)?)
+            }
+            (DecryptedValue::Unpadded(value), mpi::PublicKey::RSA { .. }) => {
+                Ok(SessionKey::from(&value[..]))
+            }
+            _ => Err(Error::Decryption {
+                reason: "backend result does not match the key algorithm".into(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Recover an error a secret key backend raised inside Sequoia's streaming
+/// code (e.g. a wrong passphrase reported by an agent), or describe `e`
+/// with `fallback`.
+fn backend_error(
+    e: sequoia_openpgp::anyhow::Error,
+    fallback: impl FnOnce(sequoia_openpgp::anyhow::Error) -> Error,
+) -> Error {
+    e.downcast::<Error>().unwrap_or_else(fallback)
+}
+
+/// Remove the PKCS#1 v1.5 encoding (`0 2 <non-zero random> 0 <key>`) from an
+/// RSA-decrypted session key. The leading zero may already be gone.
+fn unpad_session_key(value: &[u8]) -> Result<SessionKey> {
+    let invalid = || Error::Decryption {
+        reason: "invalid session key encoding from backend".into(),
+    };
+    let value = value.strip_prefix(&[0]).unwrap_or(value);
+    let value = value.strip_prefix(&[2]).ok_or_else(invalid)?;
+    let start = value.iter().position(|&b| b == 0).ok_or_else(invalid)?;
+    Ok(SessionKey::from(&value[start + 1..]))
+}
+
+/// Try a key pair against the PKESKs addressed to it, returning the
+/// session key that decrypted the message.
+///
+/// PKESKs with a wildcard (anonymous) recipient are tried with every key,
+/// since they may be addressed to any of them.
+fn try_pkesks(
+    pkesks: &[sequoia_openpgp::packet::PKESK],
+    keypair: &mut dyn sequoia_openpgp::crypto::Decryptor,
+    sym_algo: Option<SymmetricAlgorithm>,
+    decrypt: &mut dyn FnMut(Option<SymmetricAlgorithm>, &SessionKey) -> bool,
+) -> Option<MessageSessionKey> {
+    let handle = keypair.public().key_handle();
+    pkesks.iter().find_map(|pkesk| {
+        if !addressed_to(pkesk, &handle) {
+            return None;
+        }
+        let (algo, sk) = pkesk.decrypt(keypair, sym_algo)?;
+        decrypt(algo, &sk).then(|| MessageSessionKey {
+            algorithm: algo.map(u8::from),
+            key: sk.to_vec(),
+        })
+    })
+}
+
+/// Whether a PKESK may be addressed to the key with this handle.
+fn addressed_to(pkesk: &sequoia_openpgp::packet::PKESK, handle: &KeyHandle) -> bool {
+    match pkesk.recipient() {
+        None => true,
+        Some(KeyHandle::KeyID(id)) if id.is_wildcard() => true,
+        Some(recipient) => recipient.aliases(handle),
+    }
+}
+
+/// Helper struct for decrypting with a revealed session key instead of a
+/// secret key.
+struct SessionKeyHelper<'a> {
+    session_key: &'a MessageSessionKey,
+}
+
+impl VerificationHelper for SessionKeyHelper<'_> {
+    fn get_certs(
+        &mut self,
+        _ids: &[sequoia_openpgp::KeyHandle],
+    ) -> sequoia_openpgp::Result<Vec<Cert>> {
+        Ok(Vec::new())
+    }
+
+    fn check(&mut self, _structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        // As in `DecryptHelper`, signatures are not verified here.
+        Ok(())
+    }
+}
+
+impl DecryptionHelper for SessionKeyHelper<'_> {
+    fn decrypt(
+        &mut self,
+        _pkesks: &[sequoia_openpgp::packet::PKESK],
+        _skesks: &[sequoia_openpgp::packet::SKESK],
+        sym_algo: Option<SymmetricAlgorithm>,
+        decrypt: &mut dyn FnMut(Option<SymmetricAlgorithm>, &SessionKey) -> bool,
+    ) -> sequoia_openpgp::Result<Option<Cert>> {
+        let algo = self
+            .session_key
+            .algorithm
+            .map(SymmetricAlgorithm::from)
+            .or(sym_algo);
+        if decrypt(algo, &SessionKey::from(self.session_key.key.as_slice())) {
+            Ok(None)
+        } else {
+            Err(sequoia_openpgp::Error::MissingSessionKey(
+                "the session key does not decrypt this message".into(),
+            )
+            .into())
+        }
+    }
+}
+
 /// Helper struct for the Sequoia signature verification streaming API.
 struct VerifyHelper<'a> {
     #[allow(dead_code)]
@@ -1044,95 +2497,665 @@ impl VerificationHelper for VerifyHelper<'_> {
         Ok(vec![self.cert.clone()])
     }
 
-    fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
-        for layer in structure {
-            if let MessageLayer::SignatureGroup { results } = layer {
-                if let Some(GoodChecksum { ka, .. }) = results.iter().flatten().next() {
-                    self.result = Some(VerifyResult {
-                        valid: true,
-                        signer_fingerprint: Some(ka.cert().fingerprint().to_hex()),
-                    });
-                    return Ok(());
-                }
-                // No good signature found
-                self.result = Some(VerifyResult {
-                    valid: false,
-                    signer_fingerprint: Some(self.cert.fingerprint().to_hex()),
-                });
-            }
+    fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        for layer in structure {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                if let Some(GoodChecksum { sig, ka }) = results.iter().flatten().next() {
+                    self.result = Some(VerifyResult {
+                        valid: true,
+                        signer_fingerprint: Some(ka.cert().fingerprint().to_hex()),
+                        // Binary notations (e.g. Sequoia's salt) are not meant for people
+                        notations: sig
+                            .notation_data()
+                            .filter(|n| n.flags().human_readable())
+                            .map(|n| SignatureNotation {
+                                name: n.name().to_string(),
+                                value: String::from_utf8_lossy(n.value()).into_owned(),
+                            })
+                            .collect(),
+                        expires_at: sig
+                            .signature_expiration_time()
+                            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                        signer_user_id: sig
+                            .signers_user_id()
+                            .map(|uid| String::from_utf8_lossy(uid).into_owned()),
+                        intended_recipients: sig
+                            .intended_recipients()
+                            .map(|fp| fp.to_hex())
+                            .collect(),
+                    });
+                    return Ok(());
+                }
+                // No good signature found
+                self.result = Some(VerifyResult {
+                    valid: false,
+                    signer_fingerprint: Some(self.cert.fingerprint().to_hex()),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Argon2Params, KeyGenOptions, UserId};
+
+    #[test]
+    fn test_generate_ed25519_key_pair() {
+        let engine = SequoiaEngine::new();
+        let options = KeyGenOptions::new(UserId::new("Test User", "test@example.com"));
+        let result = engine.generate_key_pair(options);
+        assert!(result.is_ok());
+
+        let key_pair = result.unwrap();
+        assert!(!key_pair.public_key.is_empty());
+        assert!(!key_pair.fingerprint.0.is_empty());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let engine = SequoiaEngine::new();
+
+        let options = KeyGenOptions::new(UserId::new("Recipient", "recipient@example.com"));
+        let key_pair = engine.generate_key_pair(options).unwrap();
+
+        let plaintext = b"Hello, this is a secret message!";
+        let ciphertext = engine
+            .encrypt(plaintext, &[key_pair.public_key.clone()])
+            .unwrap();
+
+        assert!(!ciphertext.is_empty());
+        assert!(String::from_utf8_lossy(&ciphertext).contains("BEGIN PGP MESSAGE"));
+
+        let decrypted = engine
+            .decrypt(&ciphertext, key_pair.secret_key.expose_secret(), None)
+            .unwrap();
+
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt_multiple_recipients() {
+        let engine = SequoiaEngine::new();
+
+        let kp1 = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let kp2 = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let plaintext = b"Message for both Alice and Bob";
+        let ciphertext = engine
+            .encrypt(plaintext, &[kp1.public_key.clone(), kp2.public_key.clone()])
+            .unwrap();
+
+        // Both recipients should be able to decrypt
+        let dec1 = engine
+            .decrypt(&ciphertext, kp1.secret_key.expose_secret(), None)
+            .unwrap();
+        assert_eq!(dec1, plaintext);
+
+        let dec2 = engine
+            .decrypt(&ciphertext, kp2.secret_key.expose_secret(), None)
+            .unwrap();
+        assert_eq!(dec2, plaintext);
+    }
+
+    #[test]
+    fn test_hidden_recipients() {
+        use sequoia_openpgp::{Packet, PacketPile};
+
+        let engine = SequoiaEngine::new();
+        engine.set_hidden_recipients(true);
+
+        let kp1 = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let kp2 = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let plaintext = b"Nobody knows who this is for";
+        let ciphertext = engine
+            .encrypt(plaintext, &[kp1.public_key.clone(), kp2.public_key.clone()])
+            .unwrap();
+
+        // No PKESK names its recipient
+        let pile = PacketPile::from_bytes(&ciphertext).unwrap();
+        let pkesks: Vec<_> = pile
+            .children()
+            .filter_map(|p| match p {
+                Packet::PKESK(pkesk) => Some(pkesk),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pkesks.len(), 2);
+        assert!(pkesks.iter().all(|pkesk| match pkesk.recipient() {
+            None => true,
+            Some(KeyHandle::KeyID(id)) => id.is_wildcard(),
+            Some(_) => false,
+        }));
+
+        // Each recipient still finds the PKESK meant for them
+        for kp in [&kp1, &kp2] {
+            let decrypted = engine
+                .decrypt(&ciphertext, kp.secret_key.expose_secret(), None)
+                .unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_reports_wrong_passphrase() {
+        let engine = SequoiaEngine::new();
+        let user = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Alice", "alice@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"pw".to_vec()))),
+            )
+            .unwrap();
+        let stranger = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let ciphertext = engine.encrypt(b"hi", &[user.public_key.clone()]).unwrap();
+        let err = engine
+            .decrypt(&ciphertext, user.secret_key.expose_secret(), Some(b"wrong"))
+            .unwrap_err();
+        assert!(matches!(err, Error::BadPassphrase));
+        let decrypted = engine
+            .decrypt(&ciphertext, user.secret_key.expose_secret(), Some(b"pw"))
+            .unwrap();
+        assert_eq!(decrypted, b"hi");
+
+        // A key the message is not for is not a passphrase problem
+        let other = engine
+            .decrypt(
+                &ciphertext,
+                stranger.secret_key.expose_secret(),
+                Some(b"pw"),
+            )
+            .unwrap_err();
+        assert!(matches!(other, Error::Decryption { .. }));
+    }
+
+    #[test]
+    fn test_add_adsk_receives_copy_of_messages() {
+        let engine = SequoiaEngine::new();
+        let company = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Escrow",
+                "escrow@example.com",
+            )))
+            .unwrap();
+        let user = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Alice", "alice@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"pw".to_vec()))),
+            )
+            .unwrap();
+
+        let err = engine
+            .add_adsk(
+                user.secret_key.expose_secret(),
+                Some(b"wrong"),
+                &company.public_key,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::BadPassphrase));
+
+        let updated = engine
+            .add_adsk(
+                user.secret_key.expose_secret(),
+                Some(b"pw"),
+                &company.public_key,
+            )
+            .unwrap();
+
+        let company_info = engine.inspect_key(&company.public_key).unwrap();
+        let company_subkey = company_info
+            .subkeys
+            .iter()
+            .find(|sk| sk.capabilities.contains(&KeyCapability::Encrypt))
+            .unwrap();
+        let info = engine.inspect_key(&updated).unwrap();
+        assert_eq!(info.adsks(), vec![company_subkey.fingerprint.as_str()]);
+
+        // Senders encrypting to Alice's updated key also reach the ADSK
+        let public = engine.public_key(&updated).unwrap();
+        assert!(!engine.inspect_key(&public).unwrap().has_secret_key);
+        let plaintext = b"Quarterly numbers";
+        let ciphertext = engine.encrypt(plaintext, &[public]).unwrap();
+        for secret in [&updated, company.secret_key.expose_secret()] {
+            let decrypted = engine.decrypt(&ciphertext, secret, Some(b"pw")).unwrap();
+            assert_eq!(decrypted, plaintext);
         }
-        Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::{KeyGenOptions, UserId};
 
     #[test]
-    fn test_generate_ed25519_key_pair() {
+    fn test_generate_with_adsk() {
         let engine = SequoiaEngine::new();
-        let options = KeyGenOptions::new(UserId::new("Test User", "test@example.com"));
-        let result = engine.generate_key_pair(options);
-        assert!(result.is_ok());
+        let company = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Escrow",
+                "escrow@example.com",
+            )))
+            .unwrap();
+        let user = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Bob", "bob@example.com"))
+                    .with_adsk(company.public_key.clone()),
+            )
+            .unwrap();
 
-        let key_pair = result.unwrap();
-        assert!(!key_pair.public_key.is_empty());
-        assert!(!key_pair.fingerprint.0.is_empty());
+        let info = engine.inspect_key(&user.public_key).unwrap();
+        assert_eq!(info.adsks().len(), 1);
+
+        let ciphertext = engine.encrypt(b"hi", &[user.public_key.clone()]).unwrap();
+        let decrypted = engine
+            .decrypt(&ciphertext, company.secret_key.expose_secret(), None)
+            .unwrap();
+        assert_eq!(decrypted, b"hi");
+
+        // A key cannot escrow to itself
+        let err = engine
+            .add_adsk(user.secret_key.expose_secret(), None, &user.public_key)
+            .unwrap_err();
+        assert!(matches!(err, Error::KeyUpdate { .. }));
     }
 
     #[test]
-    fn test_encrypt_decrypt_round_trip() {
+    fn test_generate_from_mnemonic_is_deterministic() {
+        use std::time::{Duration, UNIX_EPOCH};
+
         let engine = SequoiaEngine::new();
+        let phrase = crate::mnemonic::generate_mnemonic();
+        let created = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let options = |passphrase: &[u8]| {
+            KeyGenOptions::new(UserId::new("Alice", "alice@example.com"))
+                .with_mnemonic(secrecy::SecretBox::new(Box::new(phrase.to_string())))
+                .with_creation_time(created)
+                .with_passphrase(secrecy::SecretBox::new(Box::new(passphrase.to_vec())))
+        };
 
-        let options = KeyGenOptions::new(UserId::new("Recipient", "recipient@example.com"));
-        let key_pair = engine.generate_key_pair(options).unwrap();
+        let first = engine.generate_key_pair(options(b"pw")).unwrap();
+        let restored = engine.generate_key_pair(options(b"other")).unwrap();
+        assert_eq!(first.fingerprint, restored.fingerprint);
 
-        let plaintext = b"Hello, this is a secret message!";
-        let ciphertext = engine
-            .encrypt(plaintext, &[key_pair.public_key.clone()])
+        let info = engine.inspect_key(&restored.public_key).unwrap();
+        assert_eq!(info.created_at, "2023-11-14T22:13:20+00:00");
+        assert!(info.subkeys.len() >= 2);
+        // The two-year expiration counts from the restore, not the creation
+        let expires = chrono::DateTime::parse_from_rfc3339(info.expires_at.as_deref().unwrap());
+        assert!(expires.unwrap() > chrono::Utc::now() + chrono::Duration::days(700));
+
+        // A key made from the phrase works with the restored copy
+        let ciphertext = engine.encrypt(b"hi", &[first.public_key.clone()]).unwrap();
+        let decrypted = engine
+            .decrypt(
+                &ciphertext,
+                restored.secret_key.expose_secret(),
+                Some(b"other"),
+            )
+            .unwrap();
+        assert_eq!(decrypted, b"hi");
+        let signed = engine
+            .sign(
+                b"hello",
+                restored.secret_key.expose_secret(),
+                Some(b"other"),
+            )
             .unwrap();
+        assert!(engine.verify(&signed, &first.public_key).unwrap().valid);
 
-        assert!(!ciphertext.is_empty());
-        assert!(String::from_utf8_lossy(&ciphertext).contains("BEGIN PGP MESSAGE"));
+        // The creation time is part of the fingerprint
+        let later = engine
+            .generate_key_pair(options(b"pw").with_creation_time(created + Duration::from_secs(1)))
+            .unwrap();
+        assert_ne!(first.fingerprint, later.fingerprint);
 
-        let decrypted = engine
-            .decrypt(&ciphertext, key_pair.secret_key.expose_secret(), None)
+        // RSA keys cannot be derived from a phrase
+        let result =
+            engine.generate_key_pair(options(b"pw").with_algorithm(KeyAlgorithm::Rsa(3072)));
+        assert!(matches!(result, Err(Error::KeyGeneration { .. })));
+    }
+
+    #[test]
+    fn test_designated_revoker_at_generation() {
+        let engine = SequoiaEngine::new();
+        let security = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Security",
+                "security@example.com",
+            )))
+            .unwrap();
+        let staff = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Staff", "staff@example.com"))
+                    .with_revoker(security.public_key.clone()),
+            )
             .unwrap();
 
-        assert_eq!(decrypted, plaintext);
+        let info = engine.inspect_key(&staff.public_key).unwrap();
+        assert_eq!(
+            info.designated_revokers,
+            vec![security.fingerprint.0.clone()]
+        );
+        assert!(!info.is_revoked);
+
+        // Anyone else cannot revoke the key
+        let outsider = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Eve", "eve@example.com")))
+            .unwrap();
+        let err = engine
+            .revoke_as_designated_revoker(
+                &staff.public_key,
+                outsider.secret_key.expose_secret(),
+                None,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::KeyUpdate { .. }));
+
+        let revoked = engine
+            .revoke_as_designated_revoker(
+                &staff.public_key,
+                security.secret_key.expose_secret(),
+                None,
+            )
+            .unwrap();
+        // The revocation only counts once checked against the revoker's key
+        let info = engine.inspect_key(&revoked).unwrap();
+        assert!(!info.is_revoked);
+        assert!(info.possibly_revoked);
+        assert_eq!(
+            engine.check_recipients(&[revoked.clone()])[0].status,
+            RecipientStatus::Usable
+        );
+        assert!(
+            engine
+                .verify_designated_revocation(&revoked, &[security.public_key.clone()])
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .verify_designated_revocation(&revoked, &[outsider.public_key.clone()])
+                .unwrap()
+        );
+        assert!(!engine.verify_designated_revocation(&revoked, &[]).unwrap());
+        assert!(
+            !engine
+                .verify_designated_revocation(&staff.public_key, &[security.public_key.clone()])
+                .unwrap()
+        );
+
+        // The revocation is a valid signature by the revoker's primary key
+        let revoked_cert = Cert::from_bytes(&revoked).unwrap();
+        let security_cert = Cert::from_bytes(&security.public_key).unwrap();
+        let rev = revoked_cert
+            .primary_key()
+            .other_revocations()
+            .next()
+            .unwrap();
+        rev.verify_primary_key_revocation(
+            security_cert.primary_key().key(),
+            revoked_cert.primary_key().key(),
+        )
+        .unwrap();
     }
 
     #[test]
-    fn test_encrypt_multiple_recipients() {
+    fn test_forged_designated_revocation() {
+        use sequoia_openpgp::packet::signature::SignatureBuilder;
+        use sequoia_openpgp::types::SignatureType;
+
         let engine = SequoiaEngine::new();
+        let security = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Security",
+                "security@example.com",
+            )))
+            .unwrap();
+        let staff = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Staff", "staff@example.com"))
+                    .with_revoker(security.public_key.clone()),
+            )
+            .unwrap();
+        let outsider = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Eve", "eve@example.com")))
+            .unwrap();
 
-        let kp1 = engine
+        // Eve signs a revocation claiming to come from the security team
+        let security_cert = Cert::from_bytes(&security.public_key).unwrap();
+        let staff_cert = Cert::from_bytes(&staff.public_key).unwrap();
+        let outsider_cert = Cert::from_bytes(outsider.secret_key.expose_secret()).unwrap();
+        let mut signer = primary_keypair(&outsider_cert, None).unwrap();
+        let forged = SignatureBuilder::new(SignatureType::KeyRevocation)
+            .set_issuer_fingerprint(security_cert.fingerprint())
+            .unwrap()
+            .set_issuer(security_cert.keyid())
+            .unwrap()
+            .sign_direct_key(&mut signer, staff_cert.primary_key().key())
+            .unwrap();
+        let (forged_cert, _) = staff_cert.insert_packets(forged).unwrap();
+        let forged = engine.armored_public_key(&forged_cert).unwrap();
+
+        let info = engine.inspect_key(&forged).unwrap();
+        assert!(info.possibly_revoked);
+        assert!(!info.is_revoked);
+        assert!(
+            engine.check_recipients(&[forged.clone()])[0]
+                .status
+                .is_usable()
+        );
+        engine
+            .encrypt(b"still reachable", &[forged.clone()])
+            .unwrap();
+        assert!(
+            !engine
+                .verify_designated_revocation(&forged, &[security.public_key.clone()])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_add_designated_revoker_later() {
+        let engine = SequoiaEngine::new();
+        let security = engine
             .generate_key_pair(KeyGenOptions::new(UserId::new(
-                "Alice",
-                "alice@example.com",
+                "Security",
+                "security@example.com",
             )))
             .unwrap();
-        let kp2 = engine
-            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+        let staff = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Staff", "staff@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"pw".to_vec()))),
+            )
+            .unwrap();
+        let before = engine.inspect_key(&staff.public_key).unwrap();
+        assert!(before.designated_revokers.is_empty());
+
+        let updated = engine
+            .add_designated_revoker(
+                staff.secret_key.expose_secret(),
+                Some(b"pw"),
+                &security.public_key,
+            )
+            .unwrap();
+        let after = engine.inspect_key(&updated).unwrap();
+        assert_eq!(
+            after.designated_revokers,
+            vec![security.fingerprint.0.clone()]
+        );
+        assert_eq!(after.expires_at, before.expires_at);
+
+        // Adding the same revoker twice is refused
+        let err = engine
+            .add_designated_revoker(&updated, Some(b"pw"), &security.public_key)
+            .unwrap_err();
+        assert!(matches!(err, Error::KeyUpdate { .. }));
+
+        let public = engine.public_key(&updated).unwrap();
+        let revoked = engine
+            .revoke_as_designated_revoker(&public, security.secret_key.expose_secret(), None)
             .unwrap();
+        assert!(
+            engine
+                .verify_designated_revocation(&revoked, &[security.public_key.clone()])
+                .unwrap()
+        );
+    }
 
-        let plaintext = b"Message for both Alice and Bob";
+    #[test]
+    fn test_export_secret_subkeys_keeps_primary_offline() {
+        let engine = SequoiaEngine::new();
+        let key_pair = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Alice", "alice@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"pw".to_vec()))),
+            )
+            .unwrap();
+        assert!(
+            !engine
+                .inspect_key(key_pair.secret_key.expose_secret())
+                .unwrap()
+                .primary_key_offline
+        );
+
+        let subkeys_only = engine
+            .export_secret_subkeys(key_pair.secret_key.expose_secret())
+            .unwrap();
+        let info = engine.inspect_key(&subkeys_only).unwrap();
+        assert!(info.has_secret_key);
+        assert!(info.primary_key_offline);
+        assert_eq!(info.s2k, None);
+        assert!(info.subkeys.iter().all(|sk| sk.s2k.is_some()));
+        let cert = Cert::from_bytes(&subkeys_only).unwrap();
+        assert!(is_secret_stub(cert.primary_key().key()));
+
+        // Day-to-day operations still work with the subkeys
         let ciphertext = engine
-            .encrypt(plaintext, &[kp1.public_key.clone(), kp2.public_key.clone()])
+            .encrypt(b"subkeys only", &[key_pair.public_key.clone()])
+            .unwrap();
+        let decrypted = engine
+            .decrypt(&ciphertext, &subkeys_only, Some(b"pw"))
             .unwrap();
+        assert_eq!(decrypted, b"subkeys only");
+        let signed = engine
+            .sign(b"signed with a subkey", &subkeys_only, Some(b"pw"))
+            .unwrap();
+        assert!(engine.verify(&signed, &key_pair.public_key).unwrap().valid);
 
-        // Both recipients should be able to decrypt
-        let dec1 = engine
-            .decrypt(&ciphertext, kp1.secret_key.expose_secret(), None)
+        // Changing the passphrase keeps the stub; certifying needs the primary
+        let repassed = engine
+            .change_passphrase(&subkeys_only, Some(b"pw"), Some(b"new"), S2kMode::Iterated)
             .unwrap();
-        assert_eq!(dec1, plaintext);
+        assert!(engine.inspect_key(&repassed).unwrap().primary_key_offline);
+        let security = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Security",
+                "security@example.com",
+            )))
+            .unwrap();
+        let err = engine
+            .add_designated_revoker(&repassed, Some(b"new"), &security.public_key)
+            .unwrap_err();
+        assert!(err.to_string().contains("offline"));
+    }
 
-        let dec2 = engine
-            .decrypt(&ciphertext, kp2.secret_key.expose_secret(), None)
+    /// Armored public key of a certificate built directly with Sequoia.
+    fn armored_public(cert: &Cert) -> Vec<u8> {
+        use sequoia_openpgp::serialize::SerializeInto;
+        SerializeInto::to_vec(&cert.armored()).unwrap()
+    }
+
+    #[test]
+    fn test_check_recipients_reports_each_problem() {
+        use sequoia_openpgp::cert::CertBuilder;
+        use std::time::{Duration, SystemTime};
+
+        let engine = SequoiaEngine::new();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let good = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Good", "good@example.com")))
             .unwrap();
-        assert_eq!(dec2, plaintext);
+
+        let revoked = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Gone", "gone@example.com")))
+            .unwrap();
+        let revoked_cert = Cert::from_bytes(&revoked.public_key)
+            .unwrap()
+            .insert_packets(
+                sequoia_openpgp::PacketPile::from_bytes(&revoked.revocation_cert)
+                    .unwrap()
+                    .into_children(),
+            )
+            .unwrap()
+            .0;
+
+        let (expired_cert, _) = CertBuilder::new()
+            .add_userid("Old <old@example.com>")
+            .add_transport_encryption_subkey()
+            .set_creation_time(SystemTime::now() - 10 * day)
+            .set_validity_period(day)
+            .generate()
+            .unwrap();
+
+        let (sign_only_cert, _) = CertBuilder::new()
+            .add_userid("Signer <signer@example.com>")
+            .add_signing_subkey()
+            .generate()
+            .unwrap();
+
+        let checks = engine.check_recipients(&[
+            good.public_key.clone(),
+            armored_public(&revoked_cert),
+            armored_public(&expired_cert),
+            armored_public(&sign_only_cert),
+            b"not a key".to_vec(),
+        ]);
+
+        assert_eq!(checks[0].status, RecipientStatus::Usable);
+        assert_eq!(
+            checks[0].fingerprint.as_deref(),
+            Some(good.fingerprint.0.as_str())
+        );
+        assert_eq!(
+            checks[0].user_id.as_deref(),
+            Some("Good <good@example.com>")
+        );
+        assert_eq!(checks[1].status, RecipientStatus::Revoked);
+        assert!(matches!(checks[2].status, RecipientStatus::Expired { .. }));
+        assert!(checks[2].status.to_string().starts_with("expired on "));
+        assert_eq!(checks[3].status, RecipientStatus::NoEncryptionSubkey);
+        assert!(matches!(checks[4].status, RecipientStatus::Invalid { .. }));
+        assert!(checks[4].fingerprint.is_none());
+
+        // Encryption names the problematic recipient instead of a generic error
+        let err = engine
+            .encrypt(
+                b"hello",
+                &[good.public_key.clone(), armored_public(&expired_cert)],
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Old <old@example.com>"),
+            "unexpected error: {err}"
+        );
+        assert!(err.contains("expired on"), "unexpected error: {err}");
     }
 
     #[test]
@@ -1167,6 +3190,49 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_decrypt_with_revealed_session_key() {
+        let engine = SequoiaEngine::new();
+        let kp = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Recipient",
+                "recipient@example.com",
+            )))
+            .unwrap();
+        let other = engine
+            .encrypt(b"another message", std::slice::from_ref(&kp.public_key))
+            .unwrap();
+        let ciphertext = engine
+            .encrypt(b"audited message", std::slice::from_ref(&kp.public_key))
+            .unwrap();
+
+        let (plaintext, session_key) = engine
+            .decrypt_revealing_session_key(&ciphertext, kp.secret_key.expose_secret(), None)
+            .unwrap();
+        assert_eq!(plaintext, b"audited message");
+
+        // The text form round-trips and opens the message without the secret key
+        let parsed: MessageSessionKey = session_key.to_string().parse().unwrap();
+        assert_eq!(parsed, session_key);
+        let decrypted = engine
+            .decrypt_with_session_key(&ciphertext, &parsed)
+            .unwrap();
+        assert_eq!(decrypted, b"audited message");
+
+        // ...but not any other message
+        assert!(engine.decrypt_with_session_key(&other, &parsed).is_err());
+    }
+
+    #[test]
+    fn test_session_key_parse_errors() {
+        assert!("9:XYZ".parse::<MessageSessionKey>().is_err());
+        assert!("AES:0011".parse::<MessageSessionKey>().is_err());
+        assert!("9:".parse::<MessageSessionKey>().is_err());
+        let sk: MessageSessionKey = "00ff".parse().unwrap();
+        assert_eq!(sk.algorithm, None);
+        assert_eq!(sk.to_string(), "00FF");
+    }
+
     #[test]
     fn test_sign_and_verify() {
         let engine = SequoiaEngine::new();
@@ -1191,6 +3257,227 @@ mod tests {
         assert!(result.signer_fingerprint.is_some());
     }
 
+    #[test]
+    fn test_sign_with_options() {
+        let engine = SequoiaEngine::new();
+        let alice = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let bob = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Bob", "bob@example.com")))
+            .unwrap();
+
+        let options = SignOptions::new()
+            .with_notation("ticket@example.com", "OPS-1234")
+            .with_expiration(Some(std::time::Duration::from_secs(86400)))
+            .with_signer_user_id("alice@example.com")
+            .with_intended_recipient(bob.public_key.clone());
+        let signed = engine
+            .sign_with_options(b"deploy", alice.secret_key.expose_secret(), None, &options)
+            .unwrap();
+
+        let result = engine.verify(&signed, &alice.public_key).unwrap();
+        assert!(result.valid);
+        assert_eq!(
+            result.notations,
+            vec![SignatureNotation {
+                name: "ticket@example.com".into(),
+                value: "OPS-1234".into(),
+            }]
+        );
+        assert!(result.expires_at.is_some());
+        assert_eq!(
+            result.signer_user_id.as_deref(),
+            Some("Alice <alice@example.com>")
+        );
+        assert_eq!(result.intended_recipients, vec![bob.fingerprint.0.clone()]);
+
+        // A plain signature carries none of them
+        let plain = engine
+            .sign(b"deploy", alice.secret_key.expose_secret(), None)
+            .unwrap();
+        let result = engine.verify(&plain, &alice.public_key).unwrap();
+        assert!(result.notations.is_empty());
+        assert!(result.expires_at.is_none());
+        assert!(result.signer_user_id.is_none());
+        assert!(result.intended_recipients.is_empty());
+
+        // The signer's User ID must belong to the key
+        let err = engine
+            .sign_with_options(
+                b"deploy",
+                alice.secret_key.expose_secret(),
+                None,
+                &SignOptions::new().with_signer_user_id("bob@example.com"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::Signing { .. }));
+
+        // Notation names need a domain
+        let err = engine
+            .sign_with_options(
+                b"deploy",
+                alice.secret_key.expose_secret(),
+                None,
+                &SignOptions::new().with_notation("ticket", "OPS-1234"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::Signing { .. }));
+    }
+
+    #[test]
+    fn test_expired_signature_is_invalid() {
+        use sequoia_openpgp::serialize::stream::{LiteralWriter, Message, Signer};
+        use std::time::{Duration, SystemTime};
+
+        let engine = SequoiaEngine::new();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+        let kp = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Signer", "signer@example.com"))
+                    .with_creation_time(an_hour_ago),
+            )
+            .unwrap();
+
+        // A signature made a minute ago that was valid for one second
+        let options = SignOptions::new().with_expiration(Some(Duration::from_secs(1)));
+        let (template, _) = signature_template(kp.secret_key.expose_secret(), &options).unwrap();
+        let keypair = engine
+            .signing_keypair(kp.secret_key.expose_secret(), None)
+            .unwrap();
+        let mut signed = Vec::new();
+        let message = Signer::with_template(Message::new(&mut signed), keypair, template)
+            .unwrap()
+            .creation_time(SystemTime::now() - Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let mut message = LiteralWriter::new(message).build().unwrap();
+        message.write_all(b"short-lived").unwrap();
+        message.finalize().unwrap();
+
+        let result = engine.verify(&signed, &kp.public_key).unwrap();
+        assert!(!result.valid);
+    }
+
+    #[test]
+    fn test_sign_and_verify_detached() {
+        let engine = SequoiaEngine::new();
+        let key = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Signer",
+                "signer@example.com",
+            )))
+            .unwrap();
+        let data = b"Content-Type: text/plain\r\n\r\nHello\r\n";
+
+        let signature = engine
+            .sign_detached(data, key.secret_key.expose_secret(), None)
+            .unwrap();
+        assert!(signature.starts_with(b"-----BEGIN PGP SIGNATURE-----"));
+
+        let result = engine
+            .verify_detached(data, &signature, &key.public_key)
+            .unwrap();
+        assert!(result.valid);
+        assert_eq!(
+            result.signer_fingerprint.as_deref(),
+            Some(key.fingerprint.0.as_str())
+        );
+
+        let tampered = engine.verify_detached(b"Hello", &signature, &key.public_key);
+        assert!(!tampered.is_ok_and(|r| r.valid));
+    }
+
+    #[test]
+    fn test_generate_with_argon2_s2k() {
+        let engine = SequoiaEngine::new();
+        let params = Argon2Params {
+            passes: 1,
+            parallelism: 1,
+            memory_exponent: 10,
+        };
+        let kp = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Argon", "argon@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"hunter2".to_vec())))
+                    .with_s2k(S2kMode::Argon2(params)),
+            )
+            .unwrap();
+
+        let info = engine.inspect_key(kp.secret_key.expose_secret()).unwrap();
+        assert_eq!(info.s2k, Some(S2kType::Argon2));
+        assert!(info.subkeys.iter().all(|s| s.s2k == Some(S2kType::Argon2)));
+
+        // Public keys carry no secret material
+        let public_info = engine.inspect_key(&kp.public_key).unwrap();
+        assert_eq!(public_info.s2k, None);
+
+        let signed = engine
+            .sign(b"hello", kp.secret_key.expose_secret(), Some(b"hunter2"))
+            .unwrap();
+        assert!(engine.verify(&signed, &kp.public_key).unwrap().valid);
+    }
+
+    #[test]
+    fn test_change_passphrase_upgrades_s2k() {
+        let engine = SequoiaEngine::new();
+        let kp = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Old", "old@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"old".to_vec()))),
+            )
+            .unwrap();
+        let info = engine.inspect_key(kp.secret_key.expose_secret()).unwrap();
+        assert_eq!(info.s2k, Some(S2kType::Iterated));
+
+        let wrong = engine.change_passphrase(
+            kp.secret_key.expose_secret(),
+            Some(b"wrong"),
+            Some(b"new"),
+            S2kMode::Iterated,
+        );
+        assert!(matches!(wrong, Err(Error::BadPassphrase)));
+
+        let upgraded = engine
+            .change_passphrase(
+                kp.secret_key.expose_secret(),
+                Some(b"old"),
+                Some(b"new"),
+                S2kMode::Argon2(Argon2Params {
+                    passes: 1,
+                    parallelism: 1,
+                    memory_exponent: 10,
+                }),
+            )
+            .unwrap();
+        let info = engine.inspect_key(&upgraded).unwrap();
+        assert_eq!(info.s2k, Some(S2kType::Argon2));
+
+        let ciphertext = engine
+            .encrypt(b"secret", std::slice::from_ref(&kp.public_key))
+            .unwrap();
+        assert!(
+            engine
+                .decrypt(&ciphertext, &upgraded, Some(b"old"))
+                .is_err()
+        );
+        assert_eq!(
+            engine
+                .decrypt(&ciphertext, &upgraded, Some(b"new"))
+                .unwrap(),
+            b"secret"
+        );
+
+        let unprotected = engine
+            .change_passphrase(&upgraded, Some(b"new"), None, S2kMode::Iterated)
+            .unwrap();
+        let info = engine.inspect_key(&unprotected).unwrap();
+        assert_eq!(info.s2k, Some(S2kType::Unprotected));
+    }
+
     #[test]
     fn test_verify_tampered_fails() {
         let engine = SequoiaEngine::new();
@@ -1376,4 +3663,72 @@ mod tests {
             .expect("decryption should succeed with same passphrase");
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
+
+    #[test]
+    fn test_sign_and_decrypt_with_backend() {
+        use crate::backend::InMemoryBackend;
+
+        let engine = SequoiaEngine::new();
+        let key_pair = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Agent", "agent@example.com"))
+                    .with_passphrase(secrecy::SecretBox::new(Box::new(b"pw".to_vec()))),
+            )
+            .unwrap();
+        let backend =
+            InMemoryBackend::from_secret_key(key_pair.secret_key.expose_secret(), Some(b"pw"))
+                .unwrap();
+
+        // Only the public key is handed to the engine
+        let options = SignOptions::new().with_notation("ticket@example.com", "OPS-1");
+        let signed = engine
+            .sign_with_backend(b"via agent", &key_pair.public_key, &backend, &options)
+            .unwrap();
+        let result = engine.verify(&signed, &key_pair.public_key).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.notations.len(), 1);
+
+        let ciphertext = engine
+            .encrypt(b"for the agent", &[key_pair.public_key.clone()])
+            .unwrap();
+        let plaintext = engine
+            .decrypt_with_backend(&ciphertext, &key_pair.public_key, &backend)
+            .unwrap();
+        assert_eq!(plaintext, b"for the agent");
+
+        // A backend without the key cannot sign or decrypt for it
+        let other = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Other",
+                "other@example.com",
+            )))
+            .unwrap();
+        let backend =
+            InMemoryBackend::from_secret_key(other.secret_key.expose_secret(), None).unwrap();
+        let stranger = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Stranger", "s@example.com")))
+            .unwrap();
+        let err = engine
+            .sign_with_backend(b"x", &stranger.public_key, &backend, &SignOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::NoSecretKey));
+        let ciphertext = engine
+            .encrypt(b"x", &[stranger.public_key.clone()])
+            .unwrap();
+        assert!(
+            engine
+                .decrypt_with_backend(&ciphertext, &stranger.public_key, &backend)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unpad_session_key() {
+        let padded = [0, 2, 0x11, 0x22, 0x33, 0, 9, 1, 2, 3];
+        assert_eq!(&*unpad_session_key(&padded).unwrap(), &[9, 1, 2, 3]);
+        // The leading zero may have been dropped as an MPI
+        assert_eq!(&*unpad_session_key(&padded[1..]).unwrap(), &[9, 1, 2, 3]);
+        assert!(unpad_session_key(&[0, 1, 0x11, 0, 9]).is_err());
+        assert!(unpad_session_key(&[2, 0x11, 0x22]).is_err());
+    }
 }
@@ -0,0 +1,484 @@
+//! Shamir secret sharing of secret keys.
+//!
+//! A secret key (TSK) is split into `N` shares so that any `M` of them
+//! rebuild it, while fewer than `M` reveal nothing about the key. Sharing is
+//! done byte by byte over GF(2^8), the same field as AES.
+//!
+//! A SHA-256 digest of the key is shared along with it, so a recovered key
+//! is checked before it is returned. Shares also carry a random set ID,
+//! which keeps shares from different splits of the same key from being
+//! mixed up.
+
+use std::fmt::Write as _;
+
+use sequoia_openpgp::Cert;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::serialize::SerializeInto;
+use sequoia_openpgp::types::HashAlgorithm;
+use zeroize::Zeroizing;
+
+use crate::encoding::{base64_decode, base64_encode, hex_decode, hex_encode};
+use crate::error::{Error, Result};
+use crate::paperkey::crc24;
+
+/// Version of the binary share layout.
+const FORMAT_VERSION: u8 = 0;
+
+/// Length of the random set ID.
+const SET_ID_LEN: usize = 8;
+
+/// Length of the SHA-256 digest appended to the shared secret.
+const DIGEST_LEN: usize = 32;
+
+/// Number of base64 characters per line in the text form.
+const LINE_WIDTH: usize = 64;
+
+const TEXT_BEGIN: &str = "-----BEGIN KEYCHAINPGP KEY SHARE-----";
+const TEXT_END: &str = "-----END KEYCHAINPGP KEY SHARE-----";
+
+/// One share of a split secret key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    set_id: [u8; SET_ID_LEN],
+    threshold: u8,
+    index: u8,
+    /// Fingerprint of the primary key (hex).
+    fingerprint: String,
+    /// Evaluation of the sharing polynomials at `index`.
+    data: Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("set_id", &hex_encode(&self.set_id))
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Share {
+    /// Fingerprint of the primary key this share belongs to.
+    #[must_use]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Number of shares needed to recover the key.
+    #[must_use]
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Share number (1-based).
+    #[must_use]
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Serialize the share, e.g. for encoding as a series of QR codes.
+    ///
+    /// Layout: format version, set ID, threshold, index, fingerprint length,
+    /// fingerprint, share data.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fp = hex_decode(&self.fingerprint).unwrap_or_default();
+        let mut out = Vec::with_capacity(4 + SET_ID_LEN + fp.len() + self.data.len());
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&self.set_id);
+        out.push(self.threshold);
+        out.push(self.index);
+        out.push(fp.len() as u8);
+        out.extend_from_slice(&fp);
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    /// Parse a serialized share.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let truncated = || invalid("share data is truncated");
+        let (&format, rest) = data.split_first().ok_or_else(truncated)?;
+        if format != FORMAT_VERSION {
+            return Err(invalid(format!("unsupported share format {format}")));
+        }
+        let (set_id, rest) = rest.split_at_checked(SET_ID_LEN).ok_or_else(truncated)?;
+        let [threshold, index, fp_len, rest @ ..] = rest else {
+            return Err(truncated());
+        };
+        let (fp, secret) = rest
+            .split_at_checked(usize::from(*fp_len))
+            .ok_or_else(truncated)?;
+        if *threshold < 2 || *index == 0 || secret.len() <= DIGEST_LEN {
+            return Err(invalid("share header is corrupt"));
+        }
+
+        Ok(Self {
+            set_id: set_id.try_into().map_err(|_| truncated())?,
+            threshold: *threshold,
+            index: *index,
+            fingerprint: hex_encode(fp),
+            data: Zeroizing::new(secret.to_vec()),
+        })
+    }
+
+    /// Render the share as an armor-like text block.
+    ///
+    /// The base64 body is followed by a `=` line holding its CRC-24, like
+    /// OpenPGP ASCII armor, so damaged copies are caught per share.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let bytes = Zeroizing::new(self.to_bytes());
+        let encoded = Zeroizing::new(base64_encode(&bytes));
+
+        let mut out = String::new();
+        out.push_str(TEXT_BEGIN);
+        out.push('\n');
+        let _ = writeln!(
+            out,
+            "Comment: Share {} - any {} shares recover key {}",
+            self.index, self.threshold, self.fingerprint
+        );
+        out.push('\n');
+        for line in encoded.as_bytes().chunks(LINE_WIDTH) {
+            out.push_str(&String::from_utf8_lossy(line));
+            out.push('\n');
+        }
+        let crc = crc24(&bytes).to_be_bytes();
+        let _ = writeln!(out, "={}", base64_encode(&crc[1..]));
+        out.push_str(TEXT_END);
+        out.push('\n');
+        out
+    }
+
+    /// Parse the text form produced by [`Share::to_text`].
+    ///
+    /// Surrounding text is ignored, so a share can be pasted together with
+    /// the rest of an email or document.
+    pub fn parse_text(text: &str) -> Result<Self> {
+        let body = text
+            .split_once(TEXT_BEGIN)
+            .and_then(|(_, rest)| rest.split_once(TEXT_END))
+            .map(|(body, _)| body)
+            .ok_or_else(|| invalid("no key share block found"))?;
+
+        let mut encoded = String::new();
+        let mut crc = None;
+        for line in body.lines().map(str::trim) {
+            if line.is_empty() || line.contains(": ") {
+                continue;
+            }
+            match line.strip_prefix('=') {
+                Some(c) => crc = Some(c),
+                None => encoded.push_str(line),
+            }
+        }
+
+        let bytes = Zeroizing::new(
+            base64_decode(encoded.as_bytes()).map_err(|e| invalid(format!("bad encoding: {e}")))?,
+        );
+        if let Some(crc) = crc {
+            let expected = base64_decode(crc.as_bytes())
+                .ok()
+                .filter(|c| c.len() == 3)
+                .ok_or_else(|| invalid("bad checksum line"))?;
+            if crc24(&bytes).to_be_bytes()[1..] != expected[..] {
+                return Err(invalid("checksum mismatch, the share is damaged"));
+            }
+        }
+
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Split a secret key into `count` shares, any `threshold` of which recover it.
+///
+/// The key is shared as-is: if it is passphrase-protected, the recovered
+/// key still needs the passphrase.
+pub fn split_secret_key(secret_key: &[u8], threshold: u8, count: u8) -> Result<Vec<Share>> {
+    if threshold < 2 {
+        return Err(invalid("at least 2 shares must be required for recovery"));
+    }
+    if count < threshold {
+        return Err(invalid(format!(
+            "cannot require {threshold} shares when only {count} are created"
+        )));
+    }
+
+    let cert = Cert::from_bytes(secret_key).map_err(|e| Error::InvalidArmor {
+        reason: e.to_string(),
+    })?;
+    if !cert.is_tsk() {
+        return Err(Error::NoSecretKey);
+    }
+
+    let mut secret = Zeroizing::new(
+        SerializeInto::to_vec(&cert.as_tsk())
+            .map_err(|e| Error::Internal(format!("serialize error: {e}")))?,
+    );
+    let digest = sha256(&secret)?;
+    secret.extend_from_slice(&digest);
+
+    let mut set_id = [0u8; SET_ID_LEN];
+    crate::crypto_random(&mut set_id);
+
+    // One random polynomial per secret byte; coefficient 0 is the byte itself.
+    let degree = usize::from(threshold - 1);
+    let mut coefficients = Zeroizing::new(vec![0u8; secret.len() * degree]);
+    crate::crypto_random(&mut coefficients);
+
+    let fingerprint = cert.fingerprint().to_hex();
+    let shares = (1..=count)
+        .map(|x| {
+            let data = secret
+                .iter()
+                .zip(coefficients.chunks(degree))
+                .map(|(&s, coeffs)| {
+                    // Horner's rule, highest coefficient first
+                    coeffs
+                        .iter()
+                        .rev()
+                        .chain(std::iter::once(&s))
+                        .fold(0u8, |acc, &c| gf_mul(acc, x) ^ c)
+                })
+                .collect();
+            Share {
+                set_id,
+                threshold,
+                index: x,
+                fingerprint: fingerprint.clone(),
+                data: Zeroizing::new(data),
+            }
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Recover a secret key from at least `threshold` shares.
+///
+/// The shares must come from the same split. The recovered key is checked
+/// against the shared digest and the fingerprint recorded in the shares
+/// before it is returned ASCII-armored.
+pub fn recover_secret_key(shares: &[Share]) -> Result<Vec<u8>> {
+    let first = shares.first().ok_or_else(|| invalid("no shares given"))?;
+    if let Some(other) = shares.iter().find(|s| {
+        s.set_id != first.set_id
+            || s.threshold != first.threshold
+            || s.fingerprint != first.fingerprint
+            || s.data.len() != first.data.len()
+    }) {
+        return Err(invalid(format!(
+            "share {} does not belong to the same split as share {}",
+            other.index, first.index
+        )));
+    }
+
+    let mut distinct: Vec<&Share> = Vec::with_capacity(shares.len());
+    for share in shares {
+        if !distinct.iter().any(|s| s.index == share.index) {
+            distinct.push(share);
+        }
+    }
+    let needed = usize::from(first.threshold);
+    if distinct.len() < needed {
+        return Err(invalid(format!(
+            "{} distinct share(s) given, but {needed} are needed",
+            distinct.len()
+        )));
+    }
+    let used = &distinct[..needed];
+
+    // Lagrange basis at x = 0: l_i = prod_{j != i} x_j / (x_j - x_i).
+    // Subtraction is XOR in GF(2^8).
+    let basis: Vec<u8> = used
+        .iter()
+        .map(|si| {
+            used.iter()
+                .filter(|sj| sj.index != si.index)
+                .fold(1u8, |acc, sj| {
+                    gf_mul(acc, gf_mul(sj.index, gf_inv(sj.index ^ si.index)))
+                })
+        })
+        .collect();
+
+    let mut secret = Zeroizing::new(vec![0u8; first.data.len()]);
+    for (share, &l) in used.iter().zip(&basis) {
+        for (out, &y) in secret.iter_mut().zip(share.data.iter()) {
+            *out ^= gf_mul(y, l);
+        }
+    }
+
+    let (tsk, digest) = secret.split_at(secret.len() - DIGEST_LEN);
+    if sha256(tsk)? != digest {
+        return Err(invalid(
+            "recovered key failed the integrity check; a share is corrupt or from another split",
+        ));
+    }
+
+    let cert = Cert::from_bytes(tsk).map_err(|e| Error::InvalidArmor {
+        reason: e.to_string(),
+    })?;
+    if !cert
+        .fingerprint()
+        .to_hex()
+        .eq_ignore_ascii_case(&first.fingerprint)
+    {
+        return Err(invalid(
+            "recovered key does not match the share fingerprint",
+        ));
+    }
+    SerializeInto::to_vec(&cert.as_tsk().armored())
+        .map_err(|e| Error::Internal(format!("serialize error: {e}")))
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidShare {
+        reason: reason.into(),
+    }
+}
+
+fn sha256(data: &[u8]) -> Result<Vec<u8>> {
+    let mut ctx = HashAlgorithm::SHA256
+        .context()
+        .map_err(|e| Error::Internal(format!("hash error: {e}")))?
+        .for_digest();
+    ctx.update(data);
+    ctx.into_digest()
+        .map_err(|e| Error::Internal(format!("hash error: {e}")))
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1.
+///
+/// Branch-free so the timing does not depend on secret bytes.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1B & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8), computed as a^254.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{KeyGenOptions, UserId};
+    use crate::{CryptoEngine, SequoiaEngine};
+    use secrecy::ExposeSecret;
+
+    fn generate() -> crate::types::GeneratedKeyPair {
+        SequoiaEngine::new()
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Recovery",
+                "recovery@example.com",
+            )))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_gf_arithmetic() {
+        // Known AES field values
+        assert_eq!(gf_mul(0x57, 0x83), 0xC1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xFE);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {a:#04x}");
+        }
+    }
+
+    #[test]
+    fn test_any_threshold_subset_recovers_key() {
+        let engine = SequoiaEngine::new();
+        let kp = generate();
+        let shares = split_secret_key(kp.secret_key.expose_secret(), 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert!(shares.iter().all(|s| s.fingerprint() == kp.fingerprint.0));
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            let recovered = recover_secret_key(&picked).unwrap();
+            let info = engine.inspect_key(&recovered).unwrap();
+            assert!(info.has_secret_key);
+            assert_eq!(info.fingerprint, kp.fingerprint);
+        }
+    }
+
+    #[test]
+    fn test_too_few_shares_rejected() {
+        let kp = generate();
+        let shares = split_secret_key(kp.secret_key.expose_secret(), 3, 5).unwrap();
+        let err = recover_secret_key(&[shares[0].clone(), shares[1].clone(), shares[1].clone()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("3 are needed"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_shares_from_different_splits_rejected() {
+        let kp = generate();
+        let a = split_secret_key(kp.secret_key.expose_secret(), 2, 3).unwrap();
+        let b = split_secret_key(kp.secret_key.expose_secret(), 2, 3).unwrap();
+        assert!(matches!(
+            recover_secret_key(&[a[0].clone(), b[1].clone()]),
+            Err(Error::InvalidShare { .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_share_fails_integrity_check() {
+        let kp = generate();
+        let mut shares = split_secret_key(kp.secret_key.expose_secret(), 2, 2).unwrap();
+        shares[1].data[10] ^= 0x01;
+        let err = recover_secret_key(&shares).unwrap_err().to_string();
+        assert!(err.contains("integrity"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_share_text_round_trip() {
+        let kp = generate();
+        let shares = split_secret_key(kp.secret_key.expose_secret(), 2, 3).unwrap();
+
+        let text = format!("Hi Bob, here is your share:\n\n{}", shares[2].to_text());
+        let parsed = Share::parse_text(&text).unwrap();
+        assert_eq!(parsed, shares[2]);
+        assert_eq!(Share::from_bytes(&shares[0].to_bytes()).unwrap(), shares[0]);
+
+        // A damaged character is caught by the checksum
+        let mut lines: Vec<String> = shares[2].to_text().lines().map(String::from).collect();
+        let flipped = if lines[3].starts_with('A') { "B" } else { "A" };
+        lines[3].replace_range(0..1, flipped);
+        let err = Share::parse_text(&lines.join("\n"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("checksum"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_invalid_split_parameters() {
+        let kp = generate();
+        let sk = kp.secret_key.expose_secret();
+        assert!(split_secret_key(sk, 1, 3).is_err());
+        assert!(split_secret_key(sk, 4, 3).is_err());
+        assert!(matches!(
+            split_secret_key(&kp.public_key, 2, 3),
+            Err(Error::NoSecretKey)
+        ));
+    }
+}
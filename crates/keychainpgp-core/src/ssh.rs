@@ -0,0 +1,164 @@
+//! OpenSSH public key export for OpenPGP authentication keys.
+//!
+//! Converts the authentication-capable key of a certificate into the
+//! single-line `authorized_keys` format (`ssh-ed25519 AAAA... comment`),
+//! so one OpenPGP identity can be used for git, SSH and email.
+
+use sequoia_openpgp::Cert;
+use sequoia_openpgp::crypto::mpi;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::policy::StandardPolicy;
+use sequoia_openpgp::types::Curve;
+
+use crate::encoding::base64_encode;
+use crate::error::{Error, Result};
+
+/// Export the authentication key of a certificate as an OpenSSH public key line.
+///
+/// `cert` is an ASCII-armored or binary public (or secret) key. The newest
+/// valid, non-revoked, authentication-capable key is used. The line ends
+/// with an `openpgp:0x<short key ID>` comment, as emitted by
+/// `gpg --export-ssh-key`.
+///
+/// Supports Ed25519 (`ssh-ed25519`) and RSA (`ssh-rsa`) keys.
+pub fn export_ssh_public_key(cert: &[u8]) -> Result<String> {
+    let cert = Cert::from_bytes(cert).map_err(|e| Error::InvalidArmor {
+        reason: e.to_string(),
+    })?;
+
+    let policy = StandardPolicy::new();
+    let valid_cert = cert
+        .with_policy(&policy, None)
+        .map_err(|e| Error::Internal(format!("key policy check failed: {e}")))?;
+
+    let ka = valid_cert
+        .keys()
+        .alive()
+        .revoked(false)
+        .for_authentication()
+        .max_by_key(|ka| ka.key().creation_time())
+        .ok_or_else(|| Error::Internal("key has no usable authentication subkey".into()))?;
+
+    let mut blob = Vec::new();
+    let key_type = match ka.key().mpis() {
+        mpi::PublicKey::EdDSA { curve, q } if *curve == Curve::Ed25519 => {
+            let (point, _) = q
+                .decode_point(curve)
+                .map_err(|e| Error::Internal(format!("invalid Ed25519 point: {e}")))?;
+            put_string(&mut blob, b"ssh-ed25519");
+            put_string(&mut blob, point);
+            "ssh-ed25519"
+        }
+        mpi::PublicKey::Ed25519 { a } => {
+            put_string(&mut blob, b"ssh-ed25519");
+            put_string(&mut blob, a);
+            "ssh-ed25519"
+        }
+        mpi::PublicKey::RSA { e, n } => {
+            put_string(&mut blob, b"ssh-rsa");
+            put_mpint(&mut blob, e.value());
+            put_mpint(&mut blob, n.value());
+            "ssh-rsa"
+        }
+        other => {
+            return Err(Error::Internal(format!(
+                "unsupported algorithm for SSH export: {}",
+                other
+                    .algo()
+                    .map_or_else(|| "unknown".to_string(), |a| a.to_string())
+            )));
+        }
+    };
+
+    let key_id = ka.key().keyid().to_hex();
+    let short_id = &key_id[key_id.len().saturating_sub(8)..];
+    Ok(format!(
+        "{key_type} {} openpgp:0x{short_id}",
+        base64_encode(&blob)
+    ))
+}
+
+/// Append an SSH wire-format `string` (u32 length prefix + bytes).
+fn put_string(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Append an SSH wire-format `mpint` (two's complement, minimal length).
+fn put_mpint(out: &mut Vec<u8>, value: &[u8]) {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+    let value = &value[start..];
+    if value.first().is_some_and(|&b| b & 0x80 != 0) {
+        out.extend_from_slice(&((value.len() + 1) as u32).to_be_bytes());
+        out.push(0);
+        out.extend_from_slice(value);
+    } else {
+        put_string(out, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::base64_decode;
+    use crate::types::{KeyCapability, KeyGenOptions, UserId};
+    use crate::{CryptoEngine, SequoiaEngine};
+
+    #[test]
+    fn test_export_ed25519_auth_subkey() {
+        let engine = SequoiaEngine::new();
+        let kp = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Dev", "dev@example.com")).with_auth_subkey(true),
+            )
+            .unwrap();
+
+        let info = engine.inspect_key(&kp.public_key).unwrap();
+        assert!(
+            info.subkeys
+                .iter()
+                .any(|s| s.capabilities.contains(&KeyCapability::Authenticate))
+        );
+
+        let line = export_ssh_public_key(&kp.public_key).unwrap();
+        let fields: Vec<&str> = line.split(' ').collect();
+        assert_eq!(fields[0], "ssh-ed25519");
+        assert!(fields[2].starts_with("openpgp:0x"));
+
+        let blob = base64_decode(fields[1].as_bytes()).unwrap();
+        // string "ssh-ed25519" (4 + 11) followed by string key (4 + 32)
+        assert_eq!(blob.len(), 4 + 11 + 4 + 32);
+        assert_eq!(&blob[4..15], b"ssh-ed25519");
+    }
+
+    #[test]
+    fn test_export_rsa_auth_subkey() {
+        // RSA-2048 key with an authentication subkey made by GnuPG; the
+        // expected line is the output of `gpg --export-ssh-key`
+        let cert = include_bytes!("../testdata/rsa-auth.pub.asc");
+        let expected = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQDmu2DPtDAPwQzBwYWzfOyxHT97C3hdN6b6tJG0vN3SSwB90JYpX2q+FZM9XMgQPzcmfOUi2A+ni0T9hPI1JVH0/YyoITdILULa7EkSZOgCHhLYUbPsvLYxPoe+Zf1zqVBBrlo3ddjPsCU/5vGzwGZ00eetOB3e9Th22cyzMPm06D161/lbqu9ceIFwgd2GwExKZhZhDKQLMgVNPw1E3hAZWJEAr9H1zaLy0ack5T6hJRwiLAFg7eOBmnxOw5I/bAA5tXShGMopilYtlYV6iU1Z5GMKDIMG9O2xf9lA58VuEVPs63YX2NnQMY6kh2II3Kq++s8sSWYoOba+wGCJ7PYl openpgp:0x28B43D2C";
+        assert_eq!(export_ssh_public_key(cert).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_put_mpint_encoding() {
+        let mut out = Vec::new();
+        put_mpint(&mut out, &[0x01, 0x00, 0x01]);
+        assert_eq!(out, [0, 0, 0, 3, 0x01, 0x00, 0x01]);
+
+        // High bit set: a leading zero keeps the value positive
+        let mut out = Vec::new();
+        put_mpint(&mut out, &[0x00, 0x80, 0xFF]);
+        assert_eq!(out, [0, 0, 0, 3, 0x00, 0x80, 0xFF]);
+    }
+
+    #[test]
+    fn test_export_without_auth_subkey_fails() {
+        let engine = SequoiaEngine::new();
+        let kp = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Dev", "dev@example.com")))
+            .unwrap();
+
+        assert!(export_ssh_public_key(&kp.public_key).is_err());
+    }
+}
@@ -154,6 +154,9 @@ pub enum KeyCapability {
     Certify,
     /// Can authenticate.
     Authenticate,
+    /// Additional decryption subkey (ADSK): receives a copy of every message
+    /// encrypted to the certificate, but is never used on its own.
+    AdditionalDecryption,
 }
 
 impl fmt::Display for KeyCapability {
@@ -163,10 +166,75 @@ impl fmt::Display for KeyCapability {
             Self::Encrypt => write!(f, "Encrypt"),
             Self::Certify => write!(f, "Certify"),
             Self::Authenticate => write!(f, "Authenticate"),
+            Self::AdditionalDecryption => write!(f, "ADSK"),
         }
     }
 }
 
+/// String-to-key (S2K) scheme protecting a secret key's material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S2kType {
+    /// Secret key material is stored without passphrase protection.
+    Unprotected,
+    /// Argon2 memory-hard key derivation (RFC 9580).
+    Argon2,
+    /// Iterated and salted S2K (RFC 4880 default).
+    Iterated,
+    /// Salted S2K without iteration (deprecated).
+    Salted,
+    /// Simple S2K without salt (deprecated).
+    Simple,
+    /// A private or unknown S2K, e.g. a GnuPG stub.
+    Other,
+}
+
+impl fmt::Display for S2kType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unprotected => write!(f, "Unprotected"),
+            Self::Argon2 => write!(f, "Argon2"),
+            Self::Iterated => write!(f, "Iterated+Salted"),
+            Self::Salted => write!(f, "Salted"),
+            Self::Simple => write!(f, "Simple"),
+            Self::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// Cost parameters for the Argon2 S2K (RFC 9580 §3.7.1.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Number of passes (`t`).
+    pub passes: u8,
+    /// Degree of parallelism (`p`).
+    pub parallelism: u8,
+    /// Memory size as a power of two in KiB (`m`), e.g. 16 for 64 MiB.
+    pub memory_exponent: u8,
+}
+
+impl Default for Argon2Params {
+    /// The RFC 9580 recommended low-memory profile: t=3, p=4, 64 MiB.
+    fn default() -> Self {
+        Self {
+            passes: 3,
+            parallelism: 4,
+            memory_exponent: 16,
+        }
+    }
+}
+
+/// How a passphrase is turned into the key protecting secret key material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum S2kMode {
+    /// Iterated and salted S2K with CFB encryption, readable by all
+    /// OpenPGP implementations.
+    #[default]
+    Iterated,
+    /// Argon2 S2K with AEAD (OCB) encryption. Stronger against offline
+    /// guessing, but not understood by older implementations.
+    Argon2(Argon2Params),
+}
+
 /// Information about a subkey.
 #[derive(Debug, Clone)]
 pub struct SubkeyInfo {
@@ -182,6 +250,8 @@ pub struct SubkeyInfo {
     pub capabilities: Vec<KeyCapability>,
     /// Whether this subkey has been revoked.
     pub is_revoked: bool,
+    /// Protection of this subkey's secret material, if present.
+    pub s2k: Option<S2kType>,
 }
 
 /// Metadata extracted from a parsed OpenPGP certificate.
@@ -201,8 +271,20 @@ pub struct CertInfo {
     pub has_secret_key: bool,
     /// Whether the primary key has been revoked.
     pub is_revoked: bool,
+    /// Protection of the primary key's secret material, if present.
+    pub s2k: Option<S2kType>,
+    /// Whether only the subkeys' secret material is present and the primary
+    /// key is kept offline (e.g. a GnuPG-style stub).
+    pub primary_key_offline: bool,
     /// Subkeys.
     pub subkeys: Vec<SubkeyInfo>,
+    /// Fingerprints of the keys allowed to revoke this certificate.
+    pub designated_revokers: Vec<String>,
+    /// Whether a revocation claiming to come from a designated revoker is
+    /// attached. It can only be checked against the revoker's key (see
+    /// [`CryptoEngine::verify_designated_revocation`](crate::CryptoEngine::verify_designated_revocation)),
+    /// so until then the key is not treated as revoked.
+    pub possibly_revoked: bool,
 }
 
 impl CertInfo {
@@ -223,15 +305,215 @@ impl CertInfo {
     pub fn email(&self) -> Option<&str> {
         self.user_ids.first().and_then(|u| u.email.as_deref())
     }
+
+    /// Return the fingerprints of the additional decryption subkeys (ADSKs)
+    /// bound to this certificate.
+    #[must_use]
+    pub fn adsks(&self) -> Vec<&str> {
+        self.subkeys
+            .iter()
+            .filter(|sk| {
+                !sk.is_revoked
+                    && sk
+                        .capabilities
+                        .contains(&KeyCapability::AdditionalDecryption)
+            })
+            .map(|sk| sk.fingerprint.as_str())
+            .collect()
+    }
 }
 
 /// The result of a signature verification.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct VerifyResult {
     /// Whether the signature is valid.
     pub valid: bool,
     /// Fingerprint of the signing key, if identified.
     pub signer_fingerprint: Option<String>,
+    /// Human-readable notations carried by the signature.
+    pub notations: Vec<SignatureNotation>,
+    /// When the signature expires (RFC 3339), if it does.
+    pub expires_at: Option<String>,
+    /// User ID the signer named in the signature, if any.
+    pub signer_user_id: Option<String>,
+    /// Fingerprints of the intended recipients listed in the signature.
+    pub intended_recipients: Vec<String>,
+}
+
+/// A `name=value` notation in a signature, e.g. a ticket ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureNotation {
+    /// Notation name in `name@domain` form.
+    pub name: String,
+    /// Human-readable value.
+    pub value: String,
+}
+
+/// Options for creating a signature.
+#[derive(Debug, Clone, Default)]
+pub struct SignOptions {
+    /// Notations to embed in the signature.
+    pub notations: Vec<SignatureNotation>,
+
+    /// How long the signature stays valid. `None` means it does not expire.
+    pub expiration: Option<std::time::Duration>,
+
+    /// User ID of the signing key to name as signer, for keys with several
+    /// identities. Must be one of the key's User IDs (or its email).
+    pub signer_user_id: Option<String>,
+
+    /// ASCII-armored public keys of the intended recipients. Listing them
+    /// stops a recipient from forwarding the signed message as if it had
+    /// been addressed to someone else.
+    pub intended_recipients: Vec<Vec<u8>>,
+}
+
+impl SignOptions {
+    /// Create options for a plain signature.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a notation, e.g. `ticket@example.com` = `OPS-1234`.
+    #[must_use]
+    pub fn with_notation(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.notations.push(SignatureNotation {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Set how long the signature stays valid.
+    #[must_use]
+    pub fn with_expiration(mut self, expiration: Option<std::time::Duration>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    /// Name one of the signing key's User IDs as signer.
+    #[must_use]
+    pub fn with_signer_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.signer_user_id = Some(user_id.into());
+        self
+    }
+
+    /// List the key in `public_key` as an intended recipient.
+    #[must_use]
+    pub fn with_intended_recipient(mut self, public_key: Vec<u8>) -> Self {
+        self.intended_recipients.push(public_key);
+        self
+    }
+}
+
+/// Whether a recipient's key can be encrypted to, and if not, why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecipientStatus {
+    /// The key has a valid encryption subkey.
+    Usable,
+    /// The key, or every encryption subkey, expired at this time (RFC 3339).
+    Expired { expired_at: String },
+    /// The key has been revoked.
+    Revoked,
+    /// The key has no valid encryption-capable subkey.
+    NoEncryptionSubkey,
+    /// The key or its encryption subkeys are rejected by the crypto policy
+    /// (e.g. a weak algorithm or SHA-1 binding signature).
+    PolicyRejected { reason: String },
+    /// The key could not be parsed.
+    Invalid { reason: String },
+}
+
+impl RecipientStatus {
+    /// Whether messages can be encrypted to this key.
+    #[must_use]
+    pub fn is_usable(&self) -> bool {
+        matches!(self, Self::Usable)
+    }
+}
+
+impl fmt::Display for RecipientStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usable => write!(f, "usable"),
+            Self::Expired { expired_at } => {
+                let date = expired_at.get(..10).unwrap_or(expired_at);
+                write!(f, "expired on {date}")
+            }
+            Self::Revoked => write!(f, "revoked"),
+            Self::NoEncryptionSubkey => write!(f, "no encryption subkey"),
+            Self::PolicyRejected { reason } => write!(f, "rejected by policy: {reason}"),
+            Self::Invalid { reason } => write!(f, "invalid key: {reason}"),
+        }
+    }
+}
+
+/// The pre-flight check result for one recipient key.
+#[derive(Debug, Clone)]
+pub struct RecipientCheck {
+    /// Fingerprint of the primary key, if the key could be parsed.
+    pub fingerprint: Option<String>,
+    /// Primary user ID, for showing which contact is affected.
+    pub user_id: Option<String>,
+    /// Whether the key can be encrypted to.
+    pub status: RecipientStatus,
+}
+
+/// The session key of a single encrypted message.
+///
+/// Revealing it lets that one message be decrypted without the recipient's
+/// secret key, e.g. to prove its content to an auditor. The text form is
+/// GnuPG's `--show-session-key` format, `<algorithm id>:<hex key>`.
+#[derive(Clone, PartialEq, Eq, ZeroizeOnDrop)]
+pub struct MessageSessionKey {
+    /// OpenPGP symmetric algorithm ID, when the message format records it
+    /// next to the key (absent for v6 messages).
+    #[zeroize(skip)]
+    pub algorithm: Option<u8>,
+    /// The raw session key.
+    pub key: Vec<u8>,
+}
+
+impl fmt::Debug for MessageSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageSessionKey")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for MessageSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(algo) = self.algorithm {
+            write!(f, "{algo}:")?;
+        }
+        f.write_str(&crate::encoding::hex_encode(&self.key))
+    }
+}
+
+impl std::str::FromStr for MessageSessionKey {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let invalid = |reason: String| crate::Error::Decryption {
+            reason: format!("invalid session key: {reason}"),
+        };
+        let (algorithm, hex) = match s.trim().split_once(':') {
+            Some((algo, hex)) => {
+                let algo = algo
+                    .parse::<u8>()
+                    .map_err(|_| invalid(format!("bad algorithm ID '{algo}'")))?;
+                (Some(algo), hex)
+            }
+            None => (None, s.trim()),
+        };
+        let key = crate::encoding::hex_decode(hex).map_err(invalid)?;
+        if key.is_empty() {
+            return Err(invalid("key is empty".into()));
+        }
+        Ok(Self { algorithm, key })
+    }
 }
 
 /// Options for key generation.
@@ -247,6 +529,26 @@ pub struct KeyGenOptions {
 
     /// Expiration duration from now. `None` means no expiration.
     pub expiration: Option<std::time::Duration>,
+
+    /// Whether to add an authentication-capable subkey (e.g. for SSH).
+    pub auth_subkey: bool,
+
+    /// S2K scheme used to protect the secret keys with the passphrase.
+    pub s2k: S2kMode,
+
+    /// ASCII-armored public keys to bind as additional decryption subkeys.
+    pub adsks: Vec<Vec<u8>>,
+
+    /// ASCII-armored public keys allowed to revoke the generated key.
+    pub revokers: Vec<Vec<u8>>,
+
+    /// Recovery phrase to derive the keys from instead of random secrets.
+    /// Only supported for Ed25519.
+    pub mnemonic: Option<SecretBox<String>>,
+
+    /// Creation time of the keys (whole seconds). `None` means now. The
+    /// expiration still counts from now.
+    pub creation_time: Option<std::time::SystemTime>,
 }
 
 impl KeyGenOptions {
@@ -258,6 +560,12 @@ impl KeyGenOptions {
             passphrase: None,
             algorithm: KeyAlgorithm::Ed25519,
             expiration: Some(std::time::Duration::from_secs(2 * 365 * 24 * 60 * 60)),
+            auth_subkey: false,
+            s2k: S2kMode::Iterated,
+            adsks: Vec::new(),
+            revokers: Vec::new(),
+            mnemonic: None,
+            creation_time: None,
         }
     }
 
@@ -281,4 +589,50 @@ impl KeyGenOptions {
         self.expiration = expiration;
         self
     }
+
+    /// Add an authentication subkey alongside the signing and encryption subkeys.
+    #[must_use]
+    pub fn with_auth_subkey(mut self, enabled: bool) -> Self {
+        self.auth_subkey = enabled;
+        self
+    }
+
+    /// Set the S2K scheme used with the passphrase.
+    #[must_use]
+    pub fn with_s2k(mut self, s2k: S2kMode) -> Self {
+        self.s2k = s2k;
+        self
+    }
+
+    /// Bind the encryption subkey of `public_key` as an additional
+    /// decryption subkey, e.g. a company escrow key.
+    #[must_use]
+    pub fn with_adsk(mut self, public_key: Vec<u8>) -> Self {
+        self.adsks.push(public_key);
+        self
+    }
+
+    /// Name the key in `public_key` as designated revoker, so it can revoke
+    /// the generated key if it is lost together with its passphrase.
+    #[must_use]
+    pub fn with_revoker(mut self, public_key: Vec<u8>) -> Self {
+        self.revokers.push(public_key);
+        self
+    }
+
+    /// Derive the keys from a recovery phrase. The same phrase and creation
+    /// time always produce the same certificate fingerprint.
+    #[must_use]
+    pub fn with_mnemonic(mut self, phrase: SecretBox<String>) -> Self {
+        self.mnemonic = Some(phrase);
+        self
+    }
+
+    /// Set the creation time of the keys, e.g. to restore a key from its
+    /// recovery phrase.
+    #[must_use]
+    pub fn with_creation_time(mut self, time: std::time::SystemTime) -> Self {
+        self.creation_time = Some(time);
+        self
+    }
 }
@@ -0,0 +1,783 @@
+//! Private-key operations through an external agent such as `gpg-agent`.
+//!
+//! [`AgentBackend`] implements [`SecretKeyBackend`] by speaking the subset of
+//! the Assuan protocol that `gpg-agent` uses for private keys (`HAVEKEY`,
+//! `PKSIGN`, `PKDECRYPT`) over its Unix socket. The agent is only handed
+//! digests and encrypted session keys; the secret keys never leave it.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use keychainpgp_core::backend::{
+    BackendCiphertext, BackendKey, BackendSignature, DecryptedValue, SecretKeyBackend,
+};
+use secrecy::{ExposeSecret, SecretBox};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// Longest line the Assuan protocol allows, including the newline.
+const MAX_LINE: usize = 1000;
+
+/// libgpg-error code for a wrong passphrase.
+const GPG_ERR_BAD_PASSPHRASE: u32 = 11;
+/// libgpg-error code for a key the agent does not hold.
+const GPG_ERR_NO_SECKEY: u32 = 17;
+
+/// A [`SecretKeyBackend`] that forwards private-key operations to an agent
+/// listening on a Unix socket.
+pub struct AgentBackend {
+    socket: PathBuf,
+    passphrase: Option<SecretBox<Vec<u8>>>,
+}
+
+impl AgentBackend {
+    /// Use the agent listening on `socket`.
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+            passphrase: None,
+        }
+    }
+
+    /// Use the user's `gpg-agent`, starting it if needed.
+    ///
+    /// The socket is located with `gpgconf --list-dirs agent-socket`, which
+    /// honours `GNUPGHOME`.
+    pub fn gpg_agent() -> Result<Self> {
+        // Best effort: if the agent cannot be started, connecting reports it
+        let _ = Command::new("gpgconf")
+            .args(["--launch", "gpg-agent"])
+            .status();
+
+        let output = Command::new("gpgconf")
+            .args(["--list-dirs", "agent-socket"])
+            .output()
+            .map_err(|e| Error::Agent {
+                reason: format!("cannot run gpgconf: {e}"),
+            })?;
+        if !output.status.success() {
+            return Err(Error::Agent {
+                reason: "gpgconf could not locate the gpg-agent socket".into(),
+            });
+        }
+
+        // gpgconf percent-escapes special characters in paths
+        let mut path = Vec::new();
+        unescape_into(
+            String::from_utf8_lossy(&output.stdout).trim().as_bytes(),
+            &mut path,
+        );
+        Ok(Self::new(String::from_utf8_lossy(&path).into_owned()))
+    }
+
+    /// Answer the agent's passphrase inquiries with `passphrase` (loopback
+    /// pinentry) instead of letting the agent ask the user itself.
+    #[must_use]
+    pub fn with_passphrase(mut self, passphrase: SecretBox<Vec<u8>>) -> Self {
+        self.passphrase = Some(passphrase);
+        self
+    }
+
+    /// The agent socket this backend connects to.
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Open a session. Each operation uses its own, since the agent keeps the
+    /// selected key and hash per connection.
+    fn connect(&self) -> Result<Connection> {
+        let mut conn = Connection::open(&self.socket)?;
+        if self.passphrase.is_some() {
+            conn.transact("OPTION pinentry-mode=loopback", &mut no_inquiry)?;
+        }
+        Ok(conn)
+    }
+
+    /// Answer an inquiry the agent makes during an operation.
+    fn inquire(&self, keyword: &str) -> Option<Zeroizing<Vec<u8>>> {
+        match (keyword, &self.passphrase) {
+            ("PASSPHRASE", Some(passphrase)) => {
+                Some(Zeroizing::new(passphrase.expose_secret().clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Describe the key in the agent's pinentry prompt.
+    fn set_key_description(conn: &mut Connection, key: &BackendKey) -> Result<()> {
+        let description = format!(
+            "KeychainPGP needs the passphrase of key\n{}",
+            key.fingerprint
+        );
+        let mut escaped = Vec::new();
+        escape_into(description.as_bytes(), &mut escaped);
+        let escaped = String::from_utf8_lossy(&escaped).replace(' ', "+");
+        conn.transact(&format!("SETKEYDESC {escaped}"), &mut no_inquiry)?;
+        Ok(())
+    }
+
+    fn sign_with_agent(
+        &self,
+        key: &BackendKey,
+        hash_algo: u8,
+        digest: &[u8],
+    ) -> Result<BackendSignature> {
+        let mut conn = self.connect()?;
+        Self::set_key_description(&mut conn, key)?;
+        conn.transact(&format!("SIGKEY {}", key.keygrip), &mut no_inquiry)?;
+        let digest: String = digest.iter().map(|b| format!("{b:02X}")).collect();
+        conn.transact(
+            &format!("SETHASH {} {digest}", gcrypt_hash_algo(hash_algo)?),
+            &mut no_inquiry,
+        )?;
+        let response = conn.transact("PKSIGN", &mut |keyword| self.inquire(keyword))?;
+
+        let sexp = Sexp::parse(&response.data)?;
+        let malformed = || Error::Agent {
+            reason: "malformed signature from agent".into(),
+        };
+        if let Some(params) = sexp.find(b"eddsa") {
+            Ok(BackendSignature::EdDsa {
+                r: Sexp::value(params, b"r").ok_or_else(malformed)?.to_vec(),
+                s: Sexp::value(params, b"s").ok_or_else(malformed)?.to_vec(),
+            })
+        } else if let Some(params) = sexp.find(b"rsa") {
+            Ok(BackendSignature::Rsa {
+                s: Sexp::value(params, b"s").ok_or_else(malformed)?.to_vec(),
+            })
+        } else {
+            Err(malformed())
+        }
+    }
+
+    fn decrypt_with_agent(
+        &self,
+        key: &BackendKey,
+        ciphertext: &BackendCiphertext,
+    ) -> Result<DecryptedValue> {
+        let mut conn = self.connect()?;
+        Self::set_key_description(&mut conn, key)?;
+        conn.transact(&format!("SETKEY {}", key.keygrip), &mut no_inquiry)?;
+
+        let request = match ciphertext {
+            BackendCiphertext::Rsa { c } => Sexp::list(vec![
+                Sexp::atom(b"enc-val"),
+                Sexp::list(vec![
+                    Sexp::atom(b"rsa"),
+                    Sexp::list(vec![Sexp::atom(b"a"), Sexp::atom(c)]),
+                ]),
+            ]),
+            BackendCiphertext::Ecdh { e, wrapped_key } => Sexp::list(vec![
+                Sexp::atom(b"enc-val"),
+                Sexp::list(vec![
+                    Sexp::atom(b"ecdh"),
+                    Sexp::list(vec![Sexp::atom(b"s"), Sexp::atom(wrapped_key)]),
+                    Sexp::list(vec![Sexp::atom(b"e"), Sexp::atom(e)]),
+                ]),
+            ]),
+        };
+        let mut encoded = Vec::new();
+        request.encode(&mut encoded);
+
+        let response = conn.transact("PKDECRYPT", &mut |keyword| match keyword {
+            "CIPHERTEXT" => Some(Zeroizing::new(encoded.clone())),
+            _ => self.inquire(keyword),
+        })?;
+
+        let sexp = Sexp::parse(&response.data)?;
+        let value = sexp
+            .find(b"value")
+            .and_then(|items| match items.first() {
+                Some(Sexp::Atom(value)) => Some(Zeroizing::new(value.to_vec())),
+                _ => None,
+            })
+            .ok_or_else(|| Error::Agent {
+                reason: "malformed decryption result from agent".into(),
+            })?;
+
+        Ok(match ciphertext {
+            BackendCiphertext::Ecdh { .. } => DecryptedValue::SharedPoint(value),
+            // Agents that do not report padding return the PKCS#1 encoding
+            BackendCiphertext::Rsa { .. } if response.has_status("PADDING 0") => {
+                DecryptedValue::Unpadded(value)
+            }
+            BackendCiphertext::Rsa { .. } => DecryptedValue::Padded(value),
+        })
+    }
+}
+
+impl SecretKeyBackend for AgentBackend {
+    fn has_key(&self, key: &BackendKey) -> keychainpgp_core::Result<bool> {
+        let has_key = self.connect().and_then(|mut conn| {
+            conn.transact(&format!("HAVEKEY {}", key.keygrip), &mut no_inquiry)
+        });
+        match has_key {
+            Ok(_) => Ok(true),
+            Err(Error::Core(keychainpgp_core::Error::NoSecretKey)) => Ok(false),
+            Err(e) => Err(keychainpgp_core::Error::Internal(e.to_string())),
+        }
+    }
+
+    fn sign(
+        &self,
+        key: &BackendKey,
+        hash_algo: u8,
+        digest: &[u8],
+    ) -> keychainpgp_core::Result<BackendSignature> {
+        self.sign_with_agent(key, hash_algo, digest)
+            .map_err(|e| match e {
+                Error::Core(e) => e,
+                e => keychainpgp_core::Error::Signing {
+                    reason: e.to_string(),
+                },
+            })
+    }
+
+    fn decrypt(
+        &self,
+        key: &BackendKey,
+        ciphertext: &BackendCiphertext,
+    ) -> keychainpgp_core::Result<DecryptedValue> {
+        self.decrypt_with_agent(key, ciphertext)
+            .map_err(|e| match e {
+                Error::Core(e) => e,
+                e => keychainpgp_core::Error::Decryption {
+                    reason: e.to_string(),
+                },
+            })
+    }
+}
+
+/// Map an OpenPGP hash algorithm ID to libgcrypt's, which `SETHASH` takes.
+fn gcrypt_hash_algo(openpgp: u8) -> Result<u32> {
+    match openpgp {
+        // MD5, SHA-1, RIPEMD-160, SHA-2: libgcrypt uses the OpenPGP IDs
+        1..=3 | 8..=11 => Ok(u32::from(openpgp)),
+        12 => Ok(313), // SHA3-256
+        14 => Ok(315), // SHA3-512
+        other => Err(Error::Agent {
+            reason: format!("hash algorithm {other} is not supported by the agent"),
+        }),
+    }
+}
+
+fn no_inquiry(_keyword: &str) -> Option<Zeroizing<Vec<u8>>> {
+    None
+}
+
+/// What the agent returned for a command.
+#[derive(Default)]
+struct Response {
+    /// Concatenated, unescaped `D` lines.
+    data: Zeroizing<Vec<u8>>,
+    /// `S` status lines, without the prefix.
+    status: Vec<String>,
+}
+
+impl Response {
+    fn has_status(&self, status: &str) -> bool {
+        self.status.iter().any(|s| s == status)
+    }
+}
+
+/// One Assuan session with the agent.
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    fn open(socket: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(socket).map_err(|e| Error::Agent {
+            reason: format!("cannot connect to {}: {e}", socket.display()),
+        })?;
+        let writer = stream.try_clone()?;
+        let mut conn = Self {
+            reader: BufReader::new(stream),
+            writer,
+        };
+        // The agent greets with OK
+        conn.read_response(&mut no_inquiry)?;
+        Ok(conn)
+    }
+
+    /// Send a command and read its response, answering inquiries with
+    /// `inquire` (unanswered inquiries get an empty reply).
+    fn transact(
+        &mut self,
+        command: &str,
+        inquire: &mut dyn FnMut(&str) -> Option<Zeroizing<Vec<u8>>>,
+    ) -> Result<Response> {
+        writeln!(self.writer, "{command}")?;
+        self.read_response(inquire)
+    }
+
+    fn read_response(
+        &mut self,
+        inquire: &mut dyn FnMut(&str) -> Option<Zeroizing<Vec<u8>>>,
+    ) -> Result<Response> {
+        let mut response = Response::default();
+        loop {
+            let mut line = Zeroizing::new(Vec::new());
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Err(Error::Agent {
+                    reason: "the agent closed the connection".into(),
+                });
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            if line.as_slice() == b"OK" || line.starts_with(b"OK ") {
+                return Ok(response);
+            } else if let Some(data) = line.strip_prefix(b"D ") {
+                unescape_into(data, &mut response.data);
+            } else if let Some(status) = line.strip_prefix(b"S ") {
+                response
+                    .status
+                    .push(String::from_utf8_lossy(status).into_owned());
+            } else if let Some(error) = line.strip_prefix(b"ERR ") {
+                return Err(agent_error(&String::from_utf8_lossy(error)));
+            } else if let Some(params) = line.strip_prefix(b"INQUIRE ") {
+                let params = String::from_utf8_lossy(params);
+                let keyword = params.split(' ').next().unwrap_or_default();
+                if let Some(data) = inquire(keyword) {
+                    write_data(&mut self.writer, &data)?;
+                }
+                self.writer.write_all(b"END\n")?;
+            }
+            // Comments (#) and anything else are ignored
+        }
+    }
+}
+
+/// Turn an `ERR <code> <description>` reply into an error.
+fn agent_error(error: &str) -> Error {
+    let (code, description) = error.split_once(' ').unwrap_or((error, ""));
+    // The code carries the error source in its high bits
+    match code.parse::<u32>().map(|code| code & 0xFFFF) {
+        Ok(GPG_ERR_BAD_PASSPHRASE) => Error::Core(keychainpgp_core::Error::BadPassphrase),
+        Ok(GPG_ERR_NO_SECKEY) => Error::Core(keychainpgp_core::Error::NoSecretKey),
+        _ => Error::Agent {
+            reason: if description.is_empty() {
+                format!("error {code}")
+            } else {
+                description.to_string()
+            },
+        },
+    }
+}
+
+/// Send `data` as `D` lines, split to respect the line length limit.
+fn write_data(writer: &mut impl Write, data: &[u8]) -> std::io::Result<()> {
+    let mut line = Zeroizing::new(Vec::with_capacity(MAX_LINE));
+    for chunk in data.chunks(300) {
+        line.clear();
+        line.extend_from_slice(b"D ");
+        escape_into(chunk, &mut line);
+        line.push(b'\n');
+        writer.write_all(&line)?;
+    }
+    Ok(())
+}
+
+/// Percent-escape the bytes Assuan does not allow verbatim in a line.
+fn escape_into(data: &[u8], out: &mut Vec<u8>) {
+    for &byte in data {
+        match byte {
+            b'%' | b'\r' | b'\n' => out.extend_from_slice(format!("%{byte:02X}").as_bytes()),
+            _ => out.push(byte),
+        }
+    }
+}
+
+fn unescape_into(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let escaped = (data[i] == b'%')
+            .then(|| data.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(data[i]);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// A canonical S-expression, the format the agent exchanges key material in.
+enum Sexp {
+    Atom(Zeroizing<Vec<u8>>),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn atom(value: &[u8]) -> Self {
+        Self::Atom(Zeroizing::new(value.to_vec()))
+    }
+
+    fn list(items: Vec<Sexp>) -> Self {
+        Self::List(items)
+    }
+
+    /// Parse one expression; trailing bytes (agents may add a NUL) are ignored.
+    fn parse(input: &[u8]) -> Result<Self> {
+        Self::parse_prefix(input, 0).map(|(sexp, _)| sexp)
+    }
+
+    fn parse_prefix(input: &[u8], depth: usize) -> Result<(Self, &[u8])> {
+        let malformed = || Error::Agent {
+            reason: "malformed S-expression from agent".into(),
+        };
+        if depth > 16 {
+            return Err(malformed());
+        }
+
+        match input.first() {
+            Some(b'(') => {
+                let mut rest = &input[1..];
+                let mut items = Vec::new();
+                loop {
+                    if let Some(after) = rest.strip_prefix(b")") {
+                        return Ok((Self::List(items), after));
+                    }
+                    let (item, after) = Self::parse_prefix(rest, depth + 1)?;
+                    items.push(item);
+                    rest = after;
+                }
+            }
+            Some(b'0'..=b'9') => {
+                let colon = input
+                    .iter()
+                    .position(|&b| b == b':')
+                    .ok_or_else(malformed)?;
+                let len: usize = std::str::from_utf8(&input[..colon])
+                    .ok()
+                    .and_then(|len| len.parse().ok())
+                    .ok_or_else(malformed)?;
+                let end = (colon + 1).checked_add(len).ok_or_else(malformed)?;
+                let value = input.get(colon + 1..end).ok_or_else(malformed)?;
+                Ok((Self::atom(value), &input[end..]))
+            }
+            _ => Err(malformed()),
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Atom(value) => {
+                out.extend_from_slice(format!("{}:", value.len()).as_bytes());
+                out.extend_from_slice(value);
+            }
+            Self::List(items) => {
+                out.push(b'(');
+                for item in items {
+                    item.encode(out);
+                }
+                out.push(b')');
+            }
+        }
+    }
+
+    /// The items after the name of the first list (depth first) named `name`.
+    fn find(&self, name: &[u8]) -> Option<&[Sexp]> {
+        let Self::List(items) = self else {
+            return None;
+        };
+        match items.split_first() {
+            Some((Self::Atom(first), rest)) if first.as_slice() == name => Some(rest),
+            _ => items.iter().find_map(|item| item.find(name)),
+        }
+    }
+
+    /// The value of the `(name value)` pair among `items`.
+    fn value<'a>(items: &'a [Sexp], name: &[u8]) -> Option<&'a [u8]> {
+        items.iter().find_map(|item| match item {
+            Self::List(pair) => match pair.as_slice() {
+                [Self::Atom(key), Self::Atom(value)] if key.as_slice() == name => {
+                    Some(value.as_slice())
+                }
+                _ => None,
+            },
+            Self::Atom(_) => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::sync::Arc;
+
+    use keychainpgp_core::backend::InMemoryBackend;
+    use keychainpgp_core::types::{KeyGenOptions, SignOptions, UserId};
+    use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+
+    use super::*;
+
+    /// `ERR` codes as gpg-agent sends them (error source 4 in the high bits).
+    const ERR_BAD_PASSPHRASE: u32 = (4 << 24) | GPG_ERR_BAD_PASSPHRASE;
+    const ERR_NO_SECKEY: u32 = (4 << 24) | GPG_ERR_NO_SECKEY;
+
+    /// A stand-in for gpg-agent: answers the commands [`AgentBackend`] sends
+    /// with the keys of an [`InMemoryBackend`], asking for `passphrase`
+    /// before each private-key operation.
+    fn spawn_stand_in_agent(socket: &Path, keys: InMemoryBackend, passphrase: &'static [u8]) {
+        let listener = UnixListener::bind(socket).unwrap();
+        let keys = Arc::new(keys);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let keys = Arc::clone(&keys);
+                std::thread::spawn(move || serve(stream.unwrap(), &keys, passphrase));
+            }
+        });
+    }
+
+    fn serve(stream: UnixStream, keys: &InMemoryBackend, passphrase: &[u8]) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        writeln!(writer, "OK stand-in agent ready").unwrap();
+
+        let mut key = BackendKey {
+            fingerprint: String::new(),
+            keygrip: String::new(),
+        };
+        let mut hash = (0u8, Vec::new());
+
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+
+            let reply: std::result::Result<Option<Vec<u8>>, u32> = match command {
+                "OPTION" | "SETKEYDESC" => Ok(None),
+                "HAVEKEY" => {
+                    let key = BackendKey {
+                        fingerprint: String::new(),
+                        keygrip: arg.to_string(),
+                    };
+                    match keys.has_key(&key).unwrap() {
+                        true => Ok(None),
+                        false => Err(ERR_NO_SECKEY),
+                    }
+                }
+                "SIGKEY" | "SETKEY" => {
+                    key.keygrip = arg.to_string();
+                    Ok(None)
+                }
+                "SETHASH" => {
+                    let (algo, digest) = arg.split_once(' ').unwrap();
+                    let digest = (0..digest.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&digest[i..i + 2], 16).unwrap())
+                        .collect();
+                    hash = (algo.parse().unwrap(), digest);
+                    Ok(None)
+                }
+                "PKSIGN" => check_passphrase(&mut reader, &mut writer, passphrase).map(|()| {
+                    let params = match keys.sign(&key, hash.0, &hash.1).unwrap() {
+                        BackendSignature::EdDsa { r, s } => Sexp::list(vec![
+                            Sexp::atom(b"eddsa"),
+                            Sexp::list(vec![Sexp::atom(b"r"), Sexp::atom(&r)]),
+                            Sexp::list(vec![Sexp::atom(b"s"), Sexp::atom(&s)]),
+                        ]),
+                        BackendSignature::Rsa { s } => Sexp::list(vec![
+                            Sexp::atom(b"rsa"),
+                            Sexp::list(vec![Sexp::atom(b"s"), Sexp::atom(&s)]),
+                        ]),
+                    };
+                    let mut out = Vec::new();
+                    Sexp::list(vec![Sexp::atom(b"sig-val"), params]).encode(&mut out);
+                    Some(out)
+                }),
+                "PKDECRYPT" => {
+                    writeln!(writer, "INQUIRE CIPHERTEXT").unwrap();
+                    let request = Sexp::parse(&read_inquiry_reply(&mut reader)).unwrap();
+                    let params = request.find(b"ecdh").unwrap();
+                    let ciphertext = BackendCiphertext::Ecdh {
+                        e: Sexp::value(params, b"e").unwrap().to_vec(),
+                        wrapped_key: Sexp::value(params, b"s").unwrap().to_vec(),
+                    };
+                    check_passphrase(&mut reader, &mut writer, passphrase).map(|()| {
+                        let DecryptedValue::SharedPoint(point) =
+                            keys.decrypt(&key, &ciphertext).unwrap()
+                        else {
+                            unreachable!("stand-in keys are X25519")
+                        };
+                        let mut out = Vec::new();
+                        Sexp::list(vec![Sexp::atom(b"value"), Sexp::atom(&point)]).encode(&mut out);
+                        // gpg-agent terminates the result with a NUL
+                        out.push(0);
+                        Some(out)
+                    })
+                }
+                _ => Err(275),
+            };
+
+            match reply {
+                Ok(data) => {
+                    if let Some(data) = data {
+                        write_data(&mut writer, &data).unwrap();
+                    }
+                    writeln!(writer, "OK").unwrap();
+                }
+                Err(code) => writeln!(writer, "ERR {code} stand-in error").unwrap(),
+            }
+        }
+    }
+
+    fn check_passphrase(
+        reader: &mut BufReader<UnixStream>,
+        writer: &mut UnixStream,
+        passphrase: &[u8],
+    ) -> std::result::Result<(), u32> {
+        writeln!(writer, "INQUIRE PASSPHRASE").unwrap();
+        match read_inquiry_reply(reader) == passphrase {
+            true => Ok(()),
+            false => Err(ERR_BAD_PASSPHRASE),
+        }
+    }
+
+    fn read_inquiry_reply(reader: &mut BufReader<UnixStream>) -> Vec<u8> {
+        let mut data = Vec::new();
+        loop {
+            let mut line = Vec::new();
+            reader.read_until(b'\n', &mut line).unwrap();
+            match line.strip_prefix(b"D ") {
+                Some(chunk) => unescape_into(&chunk[..chunk.len() - 1], &mut data),
+                None => return data,
+            }
+        }
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let data = b"50% off\r\nthen+more".to_vec();
+        let mut escaped = Vec::new();
+        escape_into(&data, &mut escaped);
+        assert_eq!(escaped, b"50%25 off%0D%0Athen+more");
+
+        let mut unescaped = Vec::new();
+        unescape_into(&escaped, &mut unescaped);
+        assert_eq!(unescaped, data);
+
+        // A stray percent sign is kept as is
+        let mut out = Vec::new();
+        unescape_into(b"100%", &mut out);
+        assert_eq!(out, b"100%");
+    }
+
+    #[test]
+    fn test_sexp_round_trip() {
+        let input = b"(7:sig-val(5:eddsa(1:r3:\x01\x02\x03)(1:s2:\x00))))\x00";
+        let sexp = Sexp::parse(input).unwrap();
+        let params = sexp.find(b"eddsa").unwrap();
+        assert_eq!(Sexp::value(params, b"r"), Some(&b"\x01\x02\x03"[..]));
+        assert_eq!(Sexp::value(params, b"s"), Some(&b"\x00)"[..]));
+        assert!(sexp.find(b"rsa").is_none());
+
+        let mut encoded = Vec::new();
+        sexp.encode(&mut encoded);
+        assert_eq!(encoded, input[..input.len() - 1]);
+
+        assert!(Sexp::parse(b"(3:abc").is_err());
+        assert!(Sexp::parse(b"(9:abc)").is_err());
+        assert!(Sexp::parse(b"18446744073709551615:abc").is_err());
+    }
+
+    #[test]
+    fn test_agent_error_codes() {
+        assert!(matches!(
+            agent_error(&format!("{ERR_BAD_PASSPHRASE} Bad passphrase <GPG Agent>")),
+            Error::Core(keychainpgp_core::Error::BadPassphrase)
+        ));
+        assert!(matches!(
+            agent_error(&format!("{ERR_NO_SECKEY} No secret key <GPG Agent>")),
+            Error::Core(keychainpgp_core::Error::NoSecretKey)
+        ));
+        assert!(matches!(
+            agent_error("67108963 Operation cancelled <Pinentry>"),
+            Error::Agent { reason } if reason == "Operation cancelled <Pinentry>"
+        ));
+    }
+
+    #[test]
+    fn test_sign_and_decrypt_through_agent() {
+        let engine = SequoiaEngine::new();
+        let key_pair = engine
+            .generate_key_pair(
+                KeyGenOptions::new(UserId::new("Agent", "agent@example.com"))
+                    .with_passphrase(SecretBox::new(Box::new(b"agent-pw".to_vec()))),
+            )
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("S.gpg-agent");
+        let keys = InMemoryBackend::from_secret_key(
+            key_pair.secret_key.expose_secret(),
+            Some(b"agent-pw"),
+        )
+        .unwrap();
+        spawn_stand_in_agent(&socket, keys, b"agent-pw");
+
+        // From here on only the public key is used on our side
+        let agent = AgentBackend::new(&socket)
+            .with_passphrase(SecretBox::new(Box::new(b"agent-pw".to_vec())));
+
+        let signed = engine
+            .sign_with_backend(
+                b"signed by the agent",
+                &key_pair.public_key,
+                &agent,
+                &SignOptions::new(),
+            )
+            .unwrap();
+        let result = engine.verify(&signed, &key_pair.public_key).unwrap();
+        assert!(result.valid);
+        assert_eq!(
+            result.signer_fingerprint.as_deref(),
+            Some(key_pair.fingerprint.0.as_str())
+        );
+
+        let ciphertext = engine
+            .encrypt(b"for the agent", &[key_pair.public_key.clone()])
+            .unwrap();
+        let plaintext = engine
+            .decrypt_with_backend(&ciphertext, &key_pair.public_key, &agent)
+            .unwrap();
+        assert_eq!(plaintext, b"for the agent");
+
+        // The agent rejects a wrong passphrase
+        let wrong =
+            AgentBackend::new(&socket).with_passphrase(SecretBox::new(Box::new(b"nope".to_vec())));
+        let err = engine
+            .sign_with_backend(b"x", &key_pair.public_key, &wrong, &SignOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, keychainpgp_core::Error::BadPassphrase));
+        let err = engine
+            .decrypt_with_backend(&ciphertext, &key_pair.public_key, &wrong)
+            .unwrap_err();
+        assert!(matches!(err, keychainpgp_core::Error::BadPassphrase));
+
+        // Keys the agent does not hold are not offered to it
+        let stranger = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new("Stranger", "s@example.com")))
+            .unwrap();
+        let err = engine
+            .sign_with_backend(b"x", &stranger.public_key, &agent, &SignOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, keychainpgp_core::Error::NoSecretKey));
+    }
+}
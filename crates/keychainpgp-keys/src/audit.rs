@@ -0,0 +1,254 @@
+//! Append-only audit log of changes to the keyring.
+//!
+//! Each entry records when a key was imported and where from, when its
+//! trust changed, when it was revoked, merged with another copy, had its
+//! secret key exported, or was moved to the trash, restored or purged.
+//! Entries are never edited; the log can only be wiped as a whole, which
+//! OPSEC mode does so that the device keeps no history of whose keys passed
+//! through it.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Where an imported key came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    /// A key file on disk.
+    File,
+    /// Text pasted from the clipboard.
+    Clipboard,
+    /// A scanned QR code.
+    Qr,
+    /// A Web Key Directory lookup.
+    Wkd,
+    /// A keyserver.
+    Keyserver,
+    /// A sync bundle from another device.
+    Sync,
+    /// A backup, recovery phrase or set of recovery shares.
+    Backup,
+    /// A certificate store shared with other OpenPGP tools.
+    Shared,
+}
+
+impl ImportSource {
+    const ALL: [Self; 8] = [
+        Self::File,
+        Self::Clipboard,
+        Self::Qr,
+        Self::Wkd,
+        Self::Keyserver,
+        Self::Sync,
+        Self::Backup,
+        Self::Shared,
+    ];
+
+    /// Name stored in the database.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Clipboard => "clipboard",
+            Self::Qr => "qr",
+            Self::Wkd => "wkd",
+            Self::Keyserver => "keyserver",
+            Self::Sync => "sync",
+            Self::Backup => "backup",
+            Self::Shared => "shared",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == name)
+    }
+}
+
+impl fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File => write!(f, "file"),
+            Self::Clipboard => write!(f, "clipboard"),
+            Self::Qr => write!(f, "QR code"),
+            Self::Wkd => write!(f, "WKD"),
+            Self::Keyserver => write!(f, "keyserver"),
+            Self::Sync => write!(f, "sync bundle"),
+            Self::Backup => write!(f, "backup"),
+            Self::Shared => write!(f, "shared certificate store"),
+        }
+    }
+}
+
+/// What happened to a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// The key was added to the keyring.
+    Imported { source: ImportSource },
+    /// The trust level changed (0 unknown, 1 unverified, 2 verified).
+    TrustChanged { from: i32, to: i32 },
+    /// The key was marked as revoked.
+    Revoked,
+    /// Secret key material was merged into a public key already present.
+    Merged { source: ImportSource },
+    /// The secret key left the keyring, e.g. as a file, paper key, recovery
+    /// shares or sync bundle.
+    SecretKeyExported { destination: String },
+    /// The key was moved to the trash.
+    Deleted,
+    /// The key was restored from the trash.
+    Restored,
+    /// The key was permanently removed from the trash.
+    Purged,
+}
+
+impl AuditAction {
+    /// Short name stored in the database and usable as a query filter.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Imported { .. } => "imported",
+            Self::TrustChanged { .. } => "trust_changed",
+            Self::Revoked => "revoked",
+            Self::Merged { .. } => "merged",
+            Self::SecretKeyExported { .. } => "secret_key_exported",
+            Self::Deleted => "deleted",
+            Self::Restored => "restored",
+            Self::Purged => "purged",
+        }
+    }
+
+    /// Details stored next to the kind.
+    pub(crate) fn detail(&self) -> Option<String> {
+        match self {
+            Self::Imported { source } | Self::Merged { source } => {
+                Some(source.as_str().to_string())
+            }
+            Self::TrustChanged { from, to } => Some(format!("{from}->{to}")),
+            Self::SecretKeyExported { destination } => Some(destination.clone()),
+            Self::Revoked | Self::Deleted | Self::Restored | Self::Purged => None,
+        }
+    }
+
+    /// Rebuild an action from its stored kind and details. Returns `None`
+    /// for entries written by a newer version.
+    pub(crate) fn from_parts(kind: &str, detail: Option<&str>) -> Option<Self> {
+        Some(match kind {
+            "imported" => Self::Imported {
+                source: ImportSource::parse(detail?)?,
+            },
+            "trust_changed" => {
+                let (from, to) = detail?.split_once("->")?;
+                Self::TrustChanged {
+                    from: from.parse().ok()?,
+                    to: to.parse().ok()?,
+                }
+            }
+            "revoked" => Self::Revoked,
+            "merged" => Self::Merged {
+                source: ImportSource::parse(detail?)?,
+            },
+            "secret_key_exported" => Self::SecretKeyExported {
+                destination: detail?.to_string(),
+            },
+            "deleted" => Self::Deleted,
+            "restored" => Self::Restored,
+            "purged" => Self::Purged,
+            _ => return None,
+        })
+    }
+}
+
+fn trust_name(level: i32) -> &'static str {
+    match level {
+        2 => "verified",
+        1 => "unverified",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Imported { source } => write!(f, "imported from {source}"),
+            Self::TrustChanged { from, to } => write!(
+                f,
+                "trust changed from {} to {}",
+                trust_name(*from),
+                trust_name(*to)
+            ),
+            Self::Revoked => write!(f, "revoked"),
+            Self::Merged { source } => write!(f, "secret key merged from {source}"),
+            Self::SecretKeyExported { destination } => {
+                write!(f, "secret key exported ({destination})")
+            }
+            Self::Deleted => write!(f, "moved to the trash"),
+            Self::Restored => write!(f, "restored from the trash"),
+            Self::Purged => write!(f, "permanently deleted"),
+        }
+    }
+}
+
+/// One entry of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    /// Position in the log; later entries have larger ids.
+    pub id: i64,
+    /// When it happened (RFC 3339, UTC).
+    pub timestamp: String,
+    /// Primary key fingerprint of the key concerned.
+    pub fingerprint: String,
+    /// What happened.
+    pub action: AuditAction,
+}
+
+/// Which audit log entries to return. The default returns everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Only entries about this key.
+    pub fingerprint: Option<String>,
+    /// Only entries of this kind (see [`AuditAction::kind`]).
+    pub kind: Option<String>,
+    /// Only entries at or after this time (RFC 3339).
+    pub since: Option<String>,
+    /// At most this many entries, newest first.
+    pub limit: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions_round_trip() {
+        let actions = [
+            AuditAction::Imported {
+                source: ImportSource::Wkd,
+            },
+            AuditAction::TrustChanged { from: 1, to: 2 },
+            AuditAction::Revoked,
+            AuditAction::Merged {
+                source: ImportSource::Sync,
+            },
+            AuditAction::SecretKeyExported {
+                destination: "paper key".into(),
+            },
+            AuditAction::Deleted,
+            AuditAction::Restored,
+            AuditAction::Purged,
+        ];
+        for action in actions {
+            let detail = action.detail();
+            assert_eq!(
+                AuditAction::from_parts(action.kind(), detail.as_deref()),
+                Some(action)
+            );
+        }
+        assert_eq!(
+            AuditAction::from_parts("imported", Some("carrier pigeon")),
+            None
+        );
+        assert_eq!(AuditAction::from_parts("renamed", None), None);
+    }
+}
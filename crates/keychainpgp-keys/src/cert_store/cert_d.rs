@@ -0,0 +1,242 @@
+//! An `openpgp-cert-d` certificate directory.
+//!
+//! The shared certificate directory format lets OpenPGP tools on the same
+//! machine (Sequoia's `sq`, for one) see the same certificates. Each
+//! certificate is stored in binary form at `ab/cdef…`, where `ab` are the
+//! first two digits of its lowercase hex fingerprint and `cdef…` the rest.
+//! Writers hold an exclusive lock on the `writelock` file, merge their copy
+//! into the one already stored, and replace the file atomically, so readers
+//! never see a partial certificate.
+//!
+//! The directory holds certificates only: trust levels, notes, tags and
+//! usage stay in the keyring database.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use keychainpgp_core::{CryptoEngine, SequoiaEngine, cert};
+
+use super::{CertStore, sort_records};
+use crate::error::{Error, Result};
+use crate::search;
+use crate::storage::KeyRecord;
+
+/// Environment variable overriding the default directory location.
+pub const CERT_D_ENV: &str = "PGP_CERT_D";
+
+/// Name of the lock file writers hold.
+const WRITE_LOCK: &str = "writelock";
+
+/// Certificates in an `openpgp-cert-d` directory.
+#[derive(Debug)]
+pub struct CertDStore {
+    base: PathBuf,
+}
+
+impl CertDStore {
+    /// Open the directory at `base`, creating it if needed.
+    pub fn open(base: &Path) -> Result<Self> {
+        std::fs::create_dir_all(base)?;
+        Ok(Self {
+            base: base.to_path_buf(),
+        })
+    }
+
+    /// The directory other tools use by default: `$PGP_CERT_D` if set,
+    /// otherwise `pgp.cert.d` in the platform data directory (e.g.
+    /// `~/.local/share/pgp.cert.d` on Linux).
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CERT_D_ENV).filter(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        directories::BaseDirs::new().map(|dirs| dirs.data_dir().join("pgp.cert.d"))
+    }
+
+    /// The directory this store reads and writes.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.base
+    }
+
+    /// Where the certificate with this fingerprint is stored.
+    fn cert_path(&self, fingerprint: &str) -> Result<PathBuf> {
+        let valid = matches!(fingerprint.len(), 40 | 64)
+            && fingerprint.chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(Error::InvalidKey {
+                reason: format!("not a v4 or v6 fingerprint: {fingerprint}"),
+            });
+        }
+        let fingerprint = fingerprint.to_ascii_lowercase();
+        let (dir, file) = fingerprint.split_at(2);
+        Ok(self.base.join(dir).join(file))
+    }
+
+    /// Run `f` while holding the directory's write lock.
+    fn with_write_lock<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.base.join(WRITE_LOCK))?;
+        let mut lock = fd_lock::RwLock::new(file);
+        let _guard = lock.write()?;
+        f()
+    }
+
+    fn read(&self, fingerprint: &str) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(self.cert_path(fingerprint)?) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace a certificate file atomically: write a temporary file next
+    /// to it, then rename it into place.
+    fn write(&self, fingerprint: &str, data: &[u8]) -> Result<()> {
+        let path = self.cert_path(fingerprint)?;
+        let dir = path.parent().unwrap_or(&self.base);
+        std::fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!(".{fingerprint}.tmp"));
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Build a record from a stored certificate. Only what the certificate
+    /// itself says is filled in.
+    fn record(data: &[u8]) -> Result<KeyRecord> {
+        let engine = SequoiaEngine::new();
+        let info = engine.inspect_key(data)?;
+        Ok(KeyRecord {
+            fingerprint: info.fingerprint.0.clone(),
+            name: info.name().map(String::from),
+            email: info.email().map(String::from),
+            algorithm: info.algorithm.to_string(),
+            created_at: info.created_at,
+            expires_at: info.expires_at,
+            trust_level: 0,
+            is_own_key: false,
+            is_revoked: info.is_revoked,
+            pgp_data: engine.public_key(data)?,
+            metadata: Default::default(),
+        })
+    }
+
+    /// Fingerprints of every certificate file in the directory. Special
+    /// entries such as `trust-root` and other tools' files are skipped.
+    fn fingerprints(&self) -> Result<Vec<String>> {
+        let mut fingerprints = Vec::new();
+        for dir in std::fs::read_dir(&self.base)? {
+            let dir = dir?;
+            let prefix = dir.file_name().to_string_lossy().into_owned();
+            if prefix.len() != 2 || !dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(dir.path())? {
+                let rest = file?.file_name().to_string_lossy().into_owned();
+                let fingerprint = format!("{prefix}{rest}").to_ascii_uppercase();
+                if self.cert_path(&fingerprint).is_ok() {
+                    fingerprints.push(fingerprint);
+                }
+            }
+        }
+        Ok(fingerprints)
+    }
+}
+
+impl CertStore for CertDStore {
+    fn insert(&self, record: &KeyRecord) -> Result<()> {
+        let data = cert::to_binary(&record.pgp_data)?;
+        self.with_write_lock(|| {
+            if self.read(&record.fingerprint)?.is_some() {
+                return Err(Error::DuplicateKey {
+                    fingerprint: record.fingerprint.clone(),
+                });
+            }
+            self.write(&record.fingerprint, &data)
+        })
+    }
+
+    fn get(&self, fingerprint: &str) -> Result<Option<KeyRecord>> {
+        self.read(fingerprint)?
+            .map(|data| Self::record(&data))
+            .transpose()
+    }
+
+    fn list(&self) -> Result<Vec<KeyRecord>> {
+        let mut records = Vec::new();
+        for fingerprint in self.fingerprints()? {
+            match self.get(&fingerprint) {
+                Ok(Some(record)) => records.push(record),
+                Ok(None) => {}
+                // Another tool may have written something we cannot parse
+                Err(e) => tracing::debug!("skipping certificate {fingerprint}: {e}"),
+            }
+        }
+        sort_records(&mut records);
+        Ok(records)
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<KeyRecord>> {
+        let mut records = self.list()?;
+        records.retain(|record| search::record_matches(record, query));
+        Ok(records)
+    }
+
+    fn update(&self, record: &KeyRecord) -> Result<bool> {
+        self.with_write_lock(|| {
+            let Some(existing) = self.read(&record.fingerprint)? else {
+                return Ok(false);
+            };
+            let merged = cert::merge(&existing, &record.pgp_data)?;
+            if merged != existing {
+                self.write(&record.fingerprint, &merged)?;
+            }
+            Ok(true)
+        })
+    }
+
+    fn delete(&self, fingerprint: &str) -> Result<bool> {
+        let path = self.cert_path(fingerprint)?;
+        self.with_write_lock(|| match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cert_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = CertDStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            store
+                .cert_path("6C427F2A23D20CDC16A526EC06FA8279696FBBB8")
+                .unwrap(),
+            tmp.path()
+                .join("6c")
+                .join("427f2a23d20cdc16a526ec06fa8279696fbbb8")
+        );
+        assert!(store.cert_path("696FBBB8").is_err());
+        assert!(store.cert_path("../../etc/passwd").is_err());
+
+        // Entries that are not certificates are not listed
+        std::fs::write(tmp.path().join("trust-root"), b"").unwrap();
+        std::fs::create_dir(tmp.path().join("_sequoia")).unwrap();
+        std::fs::create_dir(tmp.path().join("6c")).unwrap();
+        std::fs::write(tmp.path().join("6c").join("notes.txt"), b"").unwrap();
+        assert!(store.fingerprints().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! A certificate store that lives in memory only.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use super::{CertStore, sort_records};
+use crate::error::{Error, Result};
+use crate::search;
+use crate::storage::KeyRecord;
+
+/// Certificates held in memory and gone when the store is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryCertStore {
+    records: Mutex<HashMap<String, KeyRecord>>,
+}
+
+impl MemoryCertStore {
+    /// Create an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> MutexGuard<'_, HashMap<String, KeyRecord>> {
+        // A panic while holding the lock cannot leave a map half-updated
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CertStore for MemoryCertStore {
+    fn insert(&self, record: &KeyRecord) -> Result<()> {
+        let mut records = self.records();
+        if records.contains_key(&record.fingerprint) {
+            return Err(Error::DuplicateKey {
+                fingerprint: record.fingerprint.clone(),
+            });
+        }
+        records.insert(record.fingerprint.clone(), record.clone());
+        Ok(())
+    }
+
+    fn get(&self, fingerprint: &str) -> Result<Option<KeyRecord>> {
+        Ok(self.records().get(fingerprint).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<KeyRecord>> {
+        let mut records: Vec<KeyRecord> = self.records().values().cloned().collect();
+        sort_records(&mut records);
+        Ok(records)
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<KeyRecord>> {
+        let mut records = self.list()?;
+        records.retain(|record| search::record_matches(record, query));
+        Ok(records)
+    }
+
+    fn update(&self, record: &KeyRecord) -> Result<bool> {
+        let mut records = self.records();
+        match records.get_mut(&record.fingerprint) {
+            Some(stored) => {
+                *stored = record.clone();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn delete(&self, fingerprint: &str) -> Result<bool> {
+        Ok(self.records().remove(fingerprint).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::make_record;
+    use keychainpgp_core::types::{KeyGenOptions, UserId};
+    use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+
+    #[test]
+    fn test_update_replaces_metadata() {
+        let store = MemoryCertStore::new();
+        let mut record = make_record("AAAA", "Alice", "alice@example.com");
+        store.insert(&record).unwrap();
+
+        record.trust_level = 2;
+        record.metadata.notes = Some("met at FOSDEM".to_string());
+        record.metadata.tags = vec!["vendor".to_string()];
+        assert!(store.update(&record).unwrap());
+        let stored = store.get("AAAA").unwrap().unwrap();
+        assert_eq!(stored.trust_level, 2);
+        assert_eq!(stored.metadata, record.metadata);
+
+        // Searches see the new notes and tags
+        assert_eq!(store.search("fosdem").unwrap().len(), 1);
+        assert_eq!(store.search("tag:vendor").unwrap().len(), 1);
+        assert!(store.search("tag:eu").unwrap().is_empty());
+
+        assert!(
+            !store
+                .update(&make_record("BBBB", "Bob", "bob@example.com"))
+                .unwrap()
+        );
+        assert!(store.get("BBBB").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_records_are_copies() {
+        let store = MemoryCertStore::new();
+        store
+            .insert(&make_record("AAAA", "Alice", "alice@example.com"))
+            .unwrap();
+        let mut fetched = store.get("AAAA").unwrap().unwrap();
+        fetched.metadata.petname = Some("Al".to_string());
+        assert_eq!(store.get("AAAA").unwrap().unwrap().metadata.petname, None);
+    }
+
+    #[test]
+    fn test_certificate_lookups() {
+        let engine = SequoiaEngine::new();
+        let key_pair = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(
+                "Alice",
+                "alice@example.com",
+            )))
+            .unwrap();
+        let info = engine.inspect_key(&key_pair.public_key).unwrap();
+        let fingerprint = key_pair.fingerprint.0.clone();
+        let mut record = make_record(&fingerprint, "Alice", "alice@example.com");
+        record.pgp_data = key_pair.public_key.clone();
+        let store = MemoryCertStore::new();
+        store.insert(&record).unwrap();
+
+        assert_eq!(
+            store.user_ids(&fingerprint).unwrap(),
+            [(
+                Some("Alice".to_string()),
+                Some("alice@example.com".to_string())
+            )]
+        );
+        assert!(store.user_ids("BBBB").unwrap().is_empty());
+
+        let key_id = crate::storage::key_id(&fingerprint).to_lowercase();
+        let subkey = info.subkeys[0].fingerprint.clone();
+        for handle in [
+            fingerprint.as_str(),
+            &key_id,
+            &subkey,
+            &format!("0x{key_id}"),
+        ] {
+            let found = store.find_by_key_handle(handle).unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].fingerprint, fingerprint);
+        }
+        assert!(store.find_by_key_handle("%").unwrap().is_empty());
+        assert!(
+            store
+                .find_by_key_handle(&"0".repeat(16))
+                .unwrap()
+                .is_empty()
+        );
+
+        let page = store.search_page("alice", 0, 10).unwrap();
+        assert_eq!((page.keys.len(), page.total), (1, 1));
+        let page = store.search_page("alice", 1, 10).unwrap();
+        assert_eq!((page.keys.len(), page.total), (0, 1));
+    }
+}
@@ -0,0 +1,194 @@
+//! Pluggable stores for public certificates.
+//!
+//! [`CertStore`] is the subset of [`KeyStorage`](crate::storage::KeyStorage)
+//! that other stores can provide: adding, reading, searching, updating and
+//! removing certificates. Three stores implement it:
+//!
+//! - [`KeyStorage`](crate::storage::KeyStorage), the SQLite database that
+//!   also holds the user's trust, notes, tags, groups and audit log.
+//! - [`MemoryCertStore`], which keeps certificates in memory only, so that
+//!   nothing reaches the disk (OPSEC mode, tests).
+//! - [`CertDStore`], an `openpgp-cert-d` directory shared with other
+//!   OpenPGP tools on the same machine.
+//!
+//! A [`Keyring`](crate::Keyring) keeps its public keys in one store, chosen
+//! when it is opened (see [`Keyring::open_with_store`](crate::Keyring::open_with_store)),
+//! and can mirror them into a shared one; see
+//! [`Keyring::set_shared_store`](crate::Keyring::set_shared_store).
+
+pub mod cert_d;
+pub mod memory;
+
+use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+
+use crate::error::Result;
+use crate::search::SearchPage;
+use crate::storage::{self, KeyRecord};
+
+pub use cert_d::CertDStore;
+pub use memory::MemoryCertStore;
+
+/// A store of public certificates, keyed by primary key fingerprint
+/// (uppercase hex).
+pub trait CertStore: Send {
+    /// Add a certificate. Fails with
+    /// [`Error::DuplicateKey`](crate::Error::DuplicateKey) if the store
+    /// already holds one with the same fingerprint.
+    fn insert(&self, record: &KeyRecord) -> Result<()>;
+
+    /// Get a certificate by fingerprint.
+    fn get(&self, fingerprint: &str) -> Result<Option<KeyRecord>>;
+
+    /// Every certificate in the store, own keys first, then by name.
+    fn list(&self) -> Result<Vec<KeyRecord>>;
+
+    /// Certificates matching a query by name, email, petname, notes or
+    /// fingerprint, with `tag:NAME` filters.
+    fn search(&self, query: &str) -> Result<Vec<KeyRecord>>;
+
+    /// Replace a stored certificate with a newer copy. Stores that only
+    /// hold certificates merge the two copies, keeping signatures added by
+    /// other tools. Returns `false` if the store does not hold it.
+    fn update(&self, record: &KeyRecord) -> Result<bool>;
+
+    /// Remove a certificate. Returns `false` if the store does not hold it.
+    fn delete(&self, fingerprint: &str) -> Result<bool>;
+
+    /// Search like [`CertStore::search`] and return the `limit` results
+    /// after the first `offset`, with the total number of matches.
+    fn search_page(&self, query: &str, offset: usize, limit: usize) -> Result<SearchPage> {
+        let matches = self.search(query)?;
+        let total = matches.len();
+        Ok(SearchPage {
+            keys: matches.into_iter().skip(offset).take(limit).collect(),
+            total,
+        })
+    }
+
+    /// Find the certificates that a primary or subkey fingerprint or key ID
+    /// belongs to. Several certificates can carry the same subkey.
+    fn find_by_key_handle(&self, handle: &str) -> Result<Vec<KeyRecord>> {
+        let handle = storage::normalize_handle(handle);
+        if handle.is_empty() || !handle.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(Vec::new());
+        }
+        let names = |fingerprint: &str| {
+            fingerprint == handle || (handle.len() == 16 && storage::key_id(fingerprint) == handle)
+        };
+        let engine = SequoiaEngine::new();
+        let mut owners = Vec::new();
+        for record in self.list()? {
+            let owns = names(&record.fingerprint)
+                || engine.inspect_key(&record.pgp_data).is_ok_and(|info| {
+                    info.subkeys
+                        .iter()
+                        .any(|subkey| names(&subkey.fingerprint.to_uppercase()))
+                });
+            if owns {
+                owners.push(record);
+            }
+        }
+        owners.sort_by(|a, b| a.fingerprint.cmp(&b.fingerprint));
+        Ok(owners)
+    }
+
+    /// All User IDs of a certificate as `(name, email)` pairs, primary
+    /// first.
+    fn user_ids(&self, fingerprint: &str) -> Result<Vec<(Option<String>, Option<String>)>> {
+        let Some(record) = self.get(fingerprint)? else {
+            return Ok(Vec::new());
+        };
+        let user_ids = SequoiaEngine::new()
+            .inspect_key(&record.pgp_data)
+            .map(|info| {
+                info.user_ids
+                    .into_iter()
+                    .map(|uid| (uid.name, uid.email))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(user_ids)
+    }
+}
+
+/// Sort records the way [`CertStore::list`] returns them.
+pub(crate) fn sort_records(records: &mut [KeyRecord]) {
+    records.sort_by(|a, b| {
+        b.is_own_key
+            .cmp(&a.is_own_key)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.fingerprint.cmp(&b.fingerprint))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::KeyStorage;
+    use keychainpgp_core::types::{KeyGenOptions, UserId};
+    use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+
+    fn make_record(engine: &SequoiaEngine, name: &str, email: &str) -> KeyRecord {
+        let key_pair = engine
+            .generate_key_pair(KeyGenOptions::new(UserId::new(name, email)))
+            .unwrap();
+        let mut record = crate::storage::tests::make_record(&key_pair.fingerprint.0, name, email);
+        record.pgp_data = key_pair.public_key.clone();
+        record
+    }
+
+    fn exercise(store: &dyn CertStore) {
+        let engine = SequoiaEngine::new();
+        let alice = make_record(&engine, "Alice", "alice@example.com");
+        let bob = make_record(&engine, "Bob", "bob@example.com");
+
+        store.insert(&alice).unwrap();
+        store.insert(&bob).unwrap();
+        assert!(matches!(
+            store.insert(&alice),
+            Err(crate::Error::DuplicateKey { .. })
+        ));
+
+        let fetched = store.get(&alice.fingerprint).unwrap().unwrap();
+        assert_eq!(fetched.email.as_deref(), Some("alice@example.com"));
+        assert!(store.get(&"0".repeat(40)).unwrap().is_none());
+
+        let names: Vec<_> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .filter_map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["Alice", "Bob"]);
+
+        let found = store.search("bob").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].fingerprint, bob.fingerprint);
+        let suffix = &alice.fingerprint[32..];
+        assert_eq!(store.search(suffix).unwrap().len(), 1);
+
+        assert!(store.update(&alice).unwrap());
+        let carol = make_record(&engine, "Carol", "carol@example.com");
+        assert!(!store.update(&carol).unwrap());
+
+        assert!(store.delete(&bob.fingerprint).unwrap());
+        assert!(!store.delete(&bob.fingerprint).unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        exercise(&KeyStorage::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&MemoryCertStore::new());
+    }
+
+    #[test]
+    fn test_cert_d_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        exercise(&CertDStore::open(tmp.path()).unwrap());
+    }
+}
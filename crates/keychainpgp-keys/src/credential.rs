@@ -5,30 +5,103 @@
 //! - macOS: Keychain Services via the `keyring` crate
 //! - Linux: Secret Service (GNOME Keyring / KDE Wallet) via the `keyring` crate
 //!
-//! Fallback backend (used when OS store is unavailable):
-//! - Encrypted files in `{data_dir}/secrets/` directory
+//! Fallback backend (always written, used when OS store is unavailable):
+//! - Files in `{data_dir}/secrets/`, encrypted with AES-256-GCM under a
+//!   random vault key. `secrets/vault.json` records how the vault key itself
+//!   is kept (see [`Protection`]): wrapped with a key derived by Argon2id from
+//!   a master password, in the OS credential store, or, when neither is
+//!   available (e.g. portable mode without a master password), unwrapped in
+//!   the vault file, where only file permissions protect it.
+//!
+//! Files written by older versions as plain base64 are re-encrypted as soon
+//! as the vault key is available.
 
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 
-use secrecy::SecretBox;
-use zeroize::Zeroize;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use secrecy::{ExposeSecret, SecretBox};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::error::{Error, Result};
 
 const SERVICE_NAME: &str = "keychainpgp";
 
+/// Name of the file describing the vault key, inside the secrets directory.
+const VAULT_FILE: &str = "vault.json";
+
+/// Prefix of an encrypted secret key file, followed by nonce and ciphertext.
+const FILE_MAGIC: &[u8] = b"KPGPSEC1";
+
+const NONCE_LEN: usize = 12;
+
+/// Associated data binding the wrapped vault key to its purpose.
+const VAULT_KEY_AAD: &[u8] = b"keychainpgp vault key";
+
+/// Argon2id cost of the master password: the RFC 9580 recommended
+/// low-memory profile (t=3, p=4, 64 MiB).
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 4;
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+
+type VaultKey = Zeroizing<[u8; 32]>;
+
+/// How the vault key encrypting the secret key files is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Protection {
+    /// Wrapped with a key derived from the master password; secret keys can
+    /// only be read after [`CredentialStore::unlock`].
+    MasterPassword,
+    /// Kept in the OS credential store.
+    OsKeyring,
+    /// Stored unwrapped next to the encrypted files. Only the file
+    /// permissions protect it; set a master password to fix this.
+    Unprotected,
+}
+
+/// Contents of `secrets/vault.json`.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    /// Random ID naming the OS credential store entry of the vault key.
+    id: String,
+    protection: Protection,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    /// Base64 vault key: nonce and ciphertext when wrapped with the master
+    /// password, the raw key when unprotected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+}
+
+/// Argon2id parameters the master password was stretched with.
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
 /// Abstraction over secret key storage.
 ///
 /// Tries the OS credential store first, falls back to file-based storage.
 /// In portable mode, the OS credential store is skipped entirely to leave
-/// no traces on the host system.
+/// no traces on the host system. Once a master password is set, secret keys
+/// are no longer copied to the OS credential store, so they can only be read
+/// with the password.
 pub struct CredentialStore {
     secrets_dir: PathBuf,
     /// When true, skip OS credential store (DPAPI/Keychain/Secret Service).
     portable: bool,
+    /// The vault key, once loaded or unlocked.
+    vault_key: Mutex<Option<VaultKey>>,
 }
 
 impl CredentialStore {
@@ -39,6 +112,7 @@ impl CredentialStore {
         Ok(Self {
             secrets_dir,
             portable: false,
+            vault_key: Mutex::new(None),
         })
     }
 
@@ -47,6 +121,179 @@ impl CredentialStore {
         self.portable = portable;
     }
 
+    /// How the secret key files are protected, or `None` if no secret key
+    /// has been stored yet.
+    pub fn protection(&self) -> Result<Option<Protection>> {
+        Ok(self.read_vault()?.map(|vault| vault.protection))
+    }
+
+    /// Unlock secret keys protected by a master password.
+    ///
+    /// Does nothing if there is no master password. Fails with
+    /// [`Error::BadMasterPassword`] if the password is wrong.
+    pub fn unlock(&self, master_password: &[u8]) -> Result<()> {
+        let Some(vault) = self.read_vault()? else {
+            return Ok(());
+        };
+        if vault.protection != Protection::MasterPassword {
+            return Ok(());
+        }
+        let (kdf, wrapped) = vault
+            .kdf
+            .as_ref()
+            .zip(vault.key.as_deref())
+            .ok_or_else(|| Error::CredentialStore {
+                reason: "vault file lacks the wrapped key".into(),
+            })?;
+        let kek = derive_key(master_password, kdf)?;
+        let wrapped = base64_decode(wrapped.as_bytes()).map_err(|e| Error::CredentialStore {
+            reason: format!("invalid wrapped vault key: {e}"),
+        })?;
+        let key = open(&kek, VAULT_KEY_AAD, &wrapped)
+            .and_then(|raw| to_vault_key(&raw))
+            .ok_or(Error::BadMasterPassword)?;
+        self.loaded(key).map(drop)
+    }
+
+    /// Forget the unlocked vault key. With a master password set, secret keys
+    /// can then only be read after [`CredentialStore::unlock`]; without one,
+    /// the key is simply loaded again on next use.
+    pub fn lock(&self) {
+        *self.lock_vault_key() = None;
+    }
+
+    /// Whether secret keys are behind a master password that has not been
+    /// entered since the store was opened or last locked.
+    pub fn is_locked(&self) -> bool {
+        self.lock_vault_key().is_none()
+            && matches!(self.protection(), Ok(Some(Protection::MasterPassword)))
+    }
+
+    /// Protect the secret key files with a master password, or change it.
+    ///
+    /// If a master password is already set, the store must be unlocked.
+    /// Setting the first one moves the files, including those in the trash,
+    /// to a fresh vault key: the old one was kept where the password does
+    /// not protect it. Secret key copies in the OS credential store are
+    /// removed, since they would bypass the password.
+    pub fn set_master_password(&self, master_password: &[u8]) -> Result<()> {
+        if master_password.is_empty() {
+            return Err(Error::CredentialStore {
+                reason: "master password must not be empty".into(),
+            });
+        }
+        let old_key = self.vault_key()?;
+        let Some(mut vault) = self.read_vault()? else {
+            return Err(Error::CredentialStore {
+                reason: "vault file is missing".into(),
+            });
+        };
+        let previous = vault.protection;
+        let key = if previous == Protection::MasterPassword {
+            old_key.clone()
+        } else {
+            let mut key = Zeroizing::new([0u8; 32]);
+            keychainpgp_core::crypto_random(key.as_mut_slice());
+            key
+        };
+
+        let mut salt = [0u8; 16];
+        keychainpgp_core::crypto_random(&mut salt);
+        let kdf = KdfParams {
+            salt: base64_encode(&salt),
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+        };
+        let kek = derive_key(master_password, &kdf)?;
+        let wrapped = seal(&kek, VAULT_KEY_AAD, key.as_slice())?;
+
+        // Stage every file under the new key before the vault switches to it
+        let staged = if previous == Protection::MasterPassword {
+            Vec::new()
+        } else {
+            self.stage_rekeyed_files(&old_key, &key)?
+        };
+
+        vault.protection = Protection::MasterPassword;
+        vault.kdf = Some(kdf);
+        vault.key = Some(base64_encode(&wrapped));
+        self.replace_vault(&vault)?;
+        for (path, staged) in staged {
+            shred_file(&path)
+                .and_then(|()| std::fs::rename(&staged, &path))
+                .map_err(|e| Error::CredentialStore {
+                    reason: format!("failed to re-encrypt secret key file: {e}"),
+                })?;
+        }
+        *self.lock_vault_key() = Some(key);
+
+        if !self.portable {
+            if previous == Protection::OsKeyring {
+                if let Ok(entry) = keyring::Entry::new(SERVICE_NAME, &vault_entry(&vault.id)) {
+                    let _ = entry.delete_credential();
+                }
+            }
+            for fingerprint in self.stored_fingerprints()? {
+                if let Ok(entry) = keyring::Entry::new(SERVICE_NAME, &fingerprint) {
+                    let _ = entry.delete_credential();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a copy of every secret key file, in the trash too, encrypted
+    /// under `new_key` next to the original. Returns the originals with
+    /// their staged copies; on error, no staged copy is left behind.
+    fn stage_rekeyed_files(
+        &self,
+        old_key: &VaultKey,
+        new_key: &VaultKey,
+    ) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let mut staged = Vec::new();
+        let result = self.key_files().and_then(|paths| {
+            for path in paths {
+                let Some(fingerprint) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                let data = Zeroizing::new(std::fs::read(&path)?);
+                let secret_key = match data.strip_prefix(FILE_MAGIC) {
+                    Some(sealed) => open(old_key, fingerprint.as_bytes(), sealed).ok_or_else(
+                        || Error::CredentialStore {
+                            reason: format!(
+                                "secret key file for {fingerprint} is damaged or belongs to another vault"
+                            ),
+                        },
+                    )?,
+                    None => base64_decode(&data).map(Zeroizing::new).map_err(|e| {
+                        Error::CredentialStore {
+                            reason: format!("failed to decode secret key: {e}"),
+                        }
+                    })?,
+                };
+                let mut staged_path = path.as_os_str().to_owned();
+                staged_path.push(".new");
+                let staged_path = PathBuf::from(staged_path);
+                let sealed = self.sealed_file(fingerprint, &secret_key, new_key)?;
+                write_private_file(&staged_path, &sealed).map_err(|e| {
+                    Error::CredentialStore {
+                        reason: format!("failed to re-encrypt secret key file: {e}"),
+                    }
+                })?;
+                staged.push((path, staged_path));
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            for (_, staged_path) in &staged {
+                let _ = shred_file(staged_path);
+            }
+            return Err(e);
+        }
+        Ok(staged)
+    }
+
     /// Store a private key. Always stores to file (with restrictive permissions);
     /// also tries OS credential store as a preferred retrieval source.
     pub fn store_secret_key(&self, fingerprint: &str, secret_key: &[u8]) -> Result<()> {
@@ -55,8 +302,9 @@ impl CredentialStore {
         // Always store to file as reliable fallback (with restrictive permissions)
         self.store_to_file(fingerprint, secret_key)?;
 
-        // Also try OS credential store for faster retrieval (skip in portable mode)
-        if !self.portable {
+        // Also try OS credential store for faster retrieval (skip in portable
+        // mode and behind a master password)
+        if self.uses_os_store() {
             if let Ok(entry) = keyring::Entry::new(SERVICE_NAME, fingerprint) {
                 let encoded = base64_encode(secret_key);
                 let _ = entry.set_secret(encoded.as_bytes());
@@ -71,8 +319,9 @@ impl CredentialStore {
     pub fn get_secret_key(&self, fingerprint: &str) -> Result<SecretBox<Vec<u8>>> {
         Self::validate_fingerprint(fingerprint)?;
 
-        // Try OS credential store first (skip in portable mode)
-        if !self.portable {
+        // Try OS credential store first (skip in portable mode and behind a
+        // master password)
+        if self.uses_os_store() {
             if let Ok(entry) = keyring::Entry::new(SERVICE_NAME, fingerprint) {
                 if let Ok(mut encoded) = entry.get_secret() {
                     if let Ok(decoded) = base64_decode(&encoded) {
@@ -98,61 +347,86 @@ impl CredentialStore {
             }
         }
 
-        // Overwrite file with zeros before deleting to minimize residual data on disk.
-        // NOTE: On SSDs with wear-leveling, overwritten data may persist in other blocks.
+        shred_file(&self.secret_key_path(fingerprint))?;
+        // Also overwrite any revocation cert
+        let _ = shred_file(&self.secrets_dir.join(format!("{fingerprint}.rev")));
+
+        Ok(())
+    }
+
+    /// Move a private key to the trash, where it stays encrypted under the
+    /// vault key until restored or purged. The copy in the OS credential
+    /// store is removed.
+    pub fn trash_secret_key(&self, fingerprint: &str) -> Result<()> {
+        Self::validate_fingerprint(fingerprint)?;
+
+        // A key only in the OS store, or in a legacy base64 file, is written
+        // encrypted first
         let path = self.secret_key_path(fingerprint);
-        if path.exists() {
-            if let Ok(metadata) = std::fs::metadata(&path) {
-                let _ = std::fs::write(&path, vec![0u8; metadata.len() as usize]);
-            }
-            std::fs::remove_file(&path)?;
+        let encrypted = std::fs::read(&path)
+            .map(Zeroizing::new)
+            .is_ok_and(|data| data.starts_with(FILE_MAGIC));
+        if !encrypted && self.has_secret_key(fingerprint) {
+            let secret = self.get_secret_key(fingerprint)?;
+            self.store_to_file(fingerprint, secret.expose_secret())?;
         }
 
-        // Also overwrite any revocation cert
-        let rev_path = self.secrets_dir.join(format!("{fingerprint}.rev"));
-        if rev_path.exists() {
-            if let Ok(metadata) = std::fs::metadata(&rev_path) {
-                let _ = std::fs::write(&rev_path, vec![0u8; metadata.len() as usize]);
+        let trash_dir = self.trash_dir();
+        std::fs::create_dir_all(&trash_dir)?;
+        for ext in ["key", "rev"] {
+            let from = self.secrets_dir.join(format!("{fingerprint}.{ext}"));
+            if from.exists() {
+                let to = trash_dir.join(format!("{fingerprint}.{ext}"));
+                // Left over from an earlier deletion of the same key
+                shred_file(&to)?;
+                std::fs::rename(&from, &to)?;
             }
-            let _ = std::fs::remove_file(&rev_path);
         }
 
+        if !self.portable {
+            if let Ok(entry) = keyring::Entry::new(SERVICE_NAME, fingerprint) {
+                let _ = entry.delete_credential();
+            }
+        }
         Ok(())
     }
 
-    /// Store a revocation certificate for the given key.
-    pub fn store_revocation_cert(&self, fingerprint: &str, rev_cert: &[u8]) -> Result<()> {
+    /// Move a private key back out of the trash.
+    pub fn restore_secret_key(&self, fingerprint: &str) -> Result<()> {
         Self::validate_fingerprint(fingerprint)?;
-        let path = self.secrets_dir.join(format!("{fingerprint}.rev"));
-
-        #[cfg(unix)]
-        {
-            use std::io::Write;
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .mode(0o600)
-                .open(&path)
-                .map_err(|e| Error::CredentialStore {
-                    reason: format!("failed to write revocation cert: {e}"),
-                })?;
-            file.write_all(rev_cert)
-                .map_err(|e| Error::CredentialStore {
-                    reason: format!("failed to write revocation cert: {e}"),
-                })?;
+        for ext in ["key", "rev"] {
+            let from = self.trash_dir().join(format!("{fingerprint}.{ext}"));
+            if from.exists() {
+                std::fs::rename(&from, self.secrets_dir.join(format!("{fingerprint}.{ext}")))?;
+            }
         }
+        Ok(())
+    }
 
-        #[cfg(not(unix))]
-        {
-            std::fs::write(&path, rev_cert).map_err(|e| Error::CredentialStore {
-                reason: format!("failed to write revocation cert: {e}"),
-            })?;
+    /// Overwrite and delete a private key in the trash.
+    pub fn purge_secret_key(&self, fingerprint: &str) -> Result<()> {
+        Self::validate_fingerprint(fingerprint)?;
+        for ext in ["key", "rev"] {
+            shred_file(&self.trash_dir().join(format!("{fingerprint}.{ext}")))?;
         }
-
         Ok(())
     }
 
+    /// Check if a private key is in the trash.
+    pub fn has_trashed_secret_key(&self, fingerprint: &str) -> bool {
+        Self::validate_fingerprint(fingerprint).is_ok()
+            && self.trash_dir().join(format!("{fingerprint}.key")).exists()
+    }
+
+    /// Store a revocation certificate for the given key.
+    pub fn store_revocation_cert(&self, fingerprint: &str, rev_cert: &[u8]) -> Result<()> {
+        Self::validate_fingerprint(fingerprint)?;
+        let path = self.secrets_dir.join(format!("{fingerprint}.rev"));
+        write_private_file(&path, rev_cert).map_err(|e| Error::CredentialStore {
+            reason: format!("failed to write revocation cert: {e}"),
+        })
+    }
+
     /// Retrieve a revocation certificate for the given key.
     pub fn get_revocation_cert(&self, fingerprint: &str) -> Result<Option<Vec<u8>>> {
         Self::validate_fingerprint(fingerprint)?;
@@ -199,60 +473,376 @@ impl CredentialStore {
         self.secrets_dir.join(format!("{fingerprint}.key"))
     }
 
-    fn store_to_file(&self, fingerprint: &str, secret_key: &[u8]) -> Result<()> {
-        let path = self.secret_key_path(fingerprint);
-        let tmp_path = path.with_extension("key.tmp");
-        let encoded = base64_encode(secret_key);
-
-        // Write to a temp file first, then atomically rename to prevent
-        // data loss on crash/power failure during write.
-        // On Unix, create with restrictive permissions (owner-only read/write)
-        #[cfg(unix)]
-        {
-            use std::io::Write;
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .mode(0o600)
-                .open(&tmp_path)
-                .map_err(|e| Error::CredentialStore {
-                    reason: format!("failed to write secret key file: {e}"),
-                })?;
-            file.write_all(encoded.as_bytes())
-                .map_err(|e| Error::CredentialStore {
-                    reason: format!("failed to write secret key file: {e}"),
-                })?;
+    fn trash_dir(&self) -> PathBuf {
+        self.secrets_dir.join("trash")
+    }
+
+    /// Whether secret keys are also kept in the OS credential store.
+    fn uses_os_store(&self) -> bool {
+        !self.portable
+            && !matches!(
+                self.read_vault(),
+                Ok(Some(VaultFile {
+                    protection: Protection::MasterPassword,
+                    ..
+                }))
+            )
+    }
+
+    /// Paths of all secret key files, in the trash too.
+    fn key_files(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for dir in [self.secrets_dir.clone(), self.trash_dir()] {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "key") {
+                    paths.push(path);
+                }
+            }
         }
+        Ok(paths)
+    }
 
-        #[cfg(not(unix))]
-        {
-            std::fs::write(&tmp_path, encoded.as_bytes()).map_err(|e| Error::CredentialStore {
-                reason: format!("failed to write secret key file: {e}"),
-            })?;
+    /// Fingerprints of all secret key files.
+    fn stored_fingerprints(&self) -> Result<Vec<String>> {
+        let mut fingerprints = Vec::new();
+        for entry in std::fs::read_dir(&self.secrets_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "key") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    fingerprints.push(stem.to_string());
+                }
+            }
+        }
+        Ok(fingerprints)
+    }
+
+    fn lock_vault_key(&self) -> MutexGuard<'_, Option<VaultKey>> {
+        self.vault_key
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn read_vault(&self) -> Result<Option<VaultFile>> {
+        let path = self.secrets_dir.join(VAULT_FILE);
+        if !path.exists() {
+            return Ok(None);
         }
+        let data = std::fs::read(&path).map_err(|e| Error::CredentialStore {
+            reason: format!("failed to read vault file: {e}"),
+        })?;
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| Error::CredentialStore {
+                reason: format!("invalid vault file: {e}"),
+            })
+    }
+
+    fn write_vault(&self, vault: &VaultFile) -> Result<()> {
+        let json = serde_json::to_vec_pretty(vault).map_err(|e| Error::CredentialStore {
+            reason: format!("failed to encode vault file: {e}"),
+        })?;
+        write_private_file(&self.secrets_dir.join(VAULT_FILE), &json).map_err(|e| {
+            Error::CredentialStore {
+                reason: format!("failed to write vault file: {e}"),
+            }
+        })
+    }
 
-        // Atomic rename: if this fails, the original file is untouched
-        std::fs::rename(&tmp_path, &path).map_err(|e| Error::CredentialStore {
-            reason: format!("failed to finalize secret key file: {e}"),
+    /// Replace the vault file, overwriting the old one so the key it held
+    /// does not linger on disk.
+    fn replace_vault(&self, vault: &VaultFile) -> Result<()> {
+        let path = self.secrets_dir.join(VAULT_FILE);
+        let staged = self.secrets_dir.join(format!("{VAULT_FILE}.new"));
+        let json = serde_json::to_vec_pretty(vault).map_err(|e| Error::CredentialStore {
+            reason: format!("failed to encode vault file: {e}"),
         })?;
+        write_private_file(&staged, &json)
+            .and_then(|()| shred_file(&path))
+            .and_then(|()| std::fs::rename(&staged, &path))
+            .map_err(|e| Error::CredentialStore {
+                reason: format!("failed to write vault file: {e}"),
+            })
+    }
+
+    /// Return the vault key, loading it (or creating the vault) on first use.
+    fn vault_key(&self) -> Result<VaultKey> {
+        if let Some(key) = self.lock_vault_key().as_ref() {
+            return Ok(key.clone());
+        }
+        let key = match self.read_vault()? {
+            Some(vault) => Self::load_vault_key(&vault)?,
+            None => self.create_vault()?,
+        };
+        self.loaded(key)
+    }
+
+    /// Remember an unlocked vault key and re-encrypt any old base64 files.
+    fn loaded(&self, key: VaultKey) -> Result<VaultKey> {
+        *self.lock_vault_key() = Some(key.clone());
+        self.migrate_legacy_files(&key)?;
+        Ok(key)
+    }
+
+    fn load_vault_key(vault: &VaultFile) -> Result<VaultKey> {
+        let encoded = match vault.protection {
+            Protection::MasterPassword => return Err(Error::Locked),
+            Protection::OsKeyring => keyring::Entry::new(SERVICE_NAME, &vault_entry(&vault.id))
+                .and_then(|entry| entry.get_secret())
+                .map(Zeroizing::new)
+                .map_err(|e| Error::CredentialStore {
+                    reason: format!(
+                        "could not read the vault key from the OS credential store: {e}"
+                    ),
+                })?,
+            Protection::Unprotected => Zeroizing::new(
+                vault
+                    .key
+                    .clone()
+                    .ok_or_else(|| Error::CredentialStore {
+                        reason: "vault file lacks the key".into(),
+                    })?
+                    .into_bytes(),
+            ),
+        };
+        base64_decode(&encoded)
+            .ok()
+            .map(Zeroizing::new)
+            .and_then(|raw| to_vault_key(&raw))
+            .ok_or_else(|| Error::CredentialStore {
+                reason: "invalid vault key".into(),
+            })
+    }
+
+    /// Create a new vault key, kept in the OS credential store if it works.
+    fn create_vault(&self) -> Result<VaultKey> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        keychainpgp_core::crypto_random(key.as_mut_slice());
+        let mut id = [0u8; 16];
+        keychainpgp_core::crypto_random(&mut id);
+        let id = id.iter().map(|b| format!("{b:02x}")).collect::<String>();
+
+        let encoded = Zeroizing::new(base64_encode(key.as_slice()));
+        let in_os_store = !self.portable && store_in_os(&vault_entry(&id), &encoded);
+        let vault = VaultFile {
+            version: 1,
+            id,
+            protection: if in_os_store {
+                Protection::OsKeyring
+            } else {
+                Protection::Unprotected
+            },
+            kdf: None,
+            key: (!in_os_store).then(|| encoded.to_string()),
+        };
+        self.write_vault(&vault)?;
+        Ok(key)
+    }
 
+    /// Re-encrypt secret key files written as plain base64 by older versions.
+    fn migrate_legacy_files(&self, key: &VaultKey) -> Result<()> {
+        for fingerprint in self.stored_fingerprints()? {
+            let path = self.secret_key_path(&fingerprint);
+            let data = Zeroizing::new(std::fs::read(&path)?);
+            if data.starts_with(FILE_MAGIC) {
+                continue;
+            }
+            if let Ok(decoded) = base64_decode(&data) {
+                let decoded = Zeroizing::new(decoded);
+                // Stage the encrypted copy, then overwrite the plaintext
+                // before the copy takes its place
+                let mut staged = path.as_os_str().to_owned();
+                staged.push(".new");
+                let staged = PathBuf::from(staged);
+                let sealed = self.sealed_file(&fingerprint, &decoded, key)?;
+                write_private_file(&staged, &sealed)
+                    .and_then(|()| shred_file(&path))
+                    .and_then(|()| std::fs::rename(&staged, &path))
+                    .map_err(|e| Error::CredentialStore {
+                        reason: format!("failed to encrypt legacy secret key file: {e}"),
+                    })?;
+                tracing::debug!("encrypted legacy secret key file for {fingerprint}");
+            }
+        }
         Ok(())
     }
 
+    fn store_to_file(&self, fingerprint: &str, secret_key: &[u8]) -> Result<()> {
+        let key = self.vault_key()?;
+        self.write_encrypted(fingerprint, secret_key, &key)
+    }
+
+    fn write_encrypted(&self, fingerprint: &str, secret_key: &[u8], key: &VaultKey) -> Result<()> {
+        let data = self.sealed_file(fingerprint, secret_key, key)?;
+        write_private_file(&self.secret_key_path(fingerprint), &data).map_err(|e| {
+            Error::CredentialStore {
+                reason: format!("failed to write secret key file: {e}"),
+            }
+        })
+    }
+
+    /// The contents of an encrypted secret key file.
+    fn sealed_file(&self, fingerprint: &str, secret_key: &[u8], key: &VaultKey) -> Result<Vec<u8>> {
+        let mut data = FILE_MAGIC.to_vec();
+        data.extend(seal(key, fingerprint.as_bytes(), secret_key)?);
+        Ok(data)
+    }
+
     fn load_from_file(&self, fingerprint: &str) -> Result<SecretBox<Vec<u8>>> {
         let path = self.secret_key_path(fingerprint);
-        let mut encoded = std::fs::read(&path).map_err(|e| Error::CredentialStore {
+        let mut data = std::fs::read(&path).map_err(|e| Error::CredentialStore {
             reason: format!("failed to read secret key file: {e}"),
         })?;
 
-        let decoded = base64_decode(&encoded).map_err(|e| Error::CredentialStore {
-            reason: format!("failed to decode secret key: {e}"),
+        let Some(sealed) = data.strip_prefix(FILE_MAGIC) else {
+            // A base64 file from an older version: read it, then have it
+            // encrypted if the vault key is at hand.
+            let decoded = base64_decode(&data).map_err(|e| Error::CredentialStore {
+                reason: format!("failed to decode secret key: {e}"),
+            })?;
+            data.zeroize();
+            let _ = self.vault_key();
+            return Ok(SecretBox::new(Box::new(decoded)));
+        };
+
+        let key = self.vault_key()?;
+        let plaintext =
+            open(&key, fingerprint.as_bytes(), sealed).ok_or_else(|| Error::CredentialStore {
+                reason: format!(
+                    "secret key file for {fingerprint} is damaged or belongs to another vault"
+                ),
+            })?;
+        Ok(SecretBox::new(Box::new(plaintext.to_vec())))
+    }
+}
+
+/// Name of the OS credential store entry holding a vault key.
+fn vault_entry(id: &str) -> String {
+    format!("vault-{id}")
+}
+
+/// Store a secret in the OS credential store and check it can be read back
+/// through a fresh entry, i.e. that a real, persistent store is available.
+fn store_in_os(name: &str, secret: &str) -> bool {
+    let Ok(entry) = keyring::Entry::new(SERVICE_NAME, name) else {
+        return false;
+    };
+    if entry.set_secret(secret.as_bytes()).is_err() {
+        return false;
+    }
+    let readable = keyring::Entry::new(SERVICE_NAME, name)
+        .and_then(|entry| entry.get_secret())
+        .is_ok_and(|stored| Zeroizing::new(stored).as_slice() == secret.as_bytes());
+    if !readable {
+        let _ = entry.delete_credential();
+    }
+    readable
+}
+
+fn to_vault_key(raw: &[u8]) -> Option<VaultKey> {
+    let key: [u8; 32] = raw.try_into().ok()?;
+    Some(Zeroizing::new(key))
+}
+
+/// Derive the key wrapping the vault key from the master password.
+fn derive_key(password: &[u8], kdf: &KdfParams) -> Result<VaultKey> {
+    let invalid = |e: String| Error::CredentialStore {
+        reason: format!("invalid key derivation parameters: {e}"),
+    };
+    let salt = base64_decode(kdf.salt.as_bytes()).map_err(invalid)?;
+    let params = argon2::Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| invalid(e.to_string()))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(password, &salt, key.as_mut_slice())
+        .map_err(|e| invalid(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypt with AES-256-GCM under a random nonce; returns nonce and ciphertext.
+fn seal(key: &VaultKey, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    keychainpgp_core::crypto_random(&mut nonce);
+    let ciphertext = Aes256Gcm::new(key.as_slice().into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::CredentialStore {
+            reason: "encryption failed".into(),
         })?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt the output of [`seal`]. `None` if the key or data is wrong.
+fn open(key: &VaultKey, aad: &[u8], sealed: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Aes256Gcm::new(key.as_slice().into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+        .map(Zeroizing::new)
+}
+
+/// Write a file readable only by the owner, via a temp file and an atomic
+/// rename so a crash or power failure never leaves a half-written file.
+fn write_private_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
 
-        encoded.zeroize();
-        Ok(SecretBox::new(Box::new(decoded)))
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)?;
+        file.write_all(data)?;
     }
+
+    #[cfg(not(unix))]
+    std::fs::write(&tmp_path, data)?;
+
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Overwrite a file with zeros, flush it to disk and delete it, so the
+/// secret it held does not linger in freed blocks. On SSDs with
+/// wear-leveling, the old data may still persist elsewhere.
+fn shred_file(path: &Path) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let len = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.write_all(&vec![0u8; len as usize])?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)
 }
 
 fn base64_encode(data: &[u8]) -> String {
@@ -350,6 +940,36 @@ mod tests {
         assert!(!store.secret_key_path("ABCD1234").exists());
     }
 
+    #[test]
+    fn test_trash_restore_and_purge() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = portable_store(tmp.path());
+        store.store_secret_key("ABCD1234", b"secret").unwrap();
+        store.store_revocation_cert("ABCD1234", b"rev").unwrap();
+
+        store.trash_secret_key("ABCD1234").unwrap();
+        assert!(!store.has_secret_key("ABCD1234"));
+        assert!(store.has_trashed_secret_key("ABCD1234"));
+        assert_eq!(store.get_revocation_cert("ABCD1234").unwrap(), None);
+        // Still encrypted in the trash
+        let trashed = std::fs::read(store.trash_dir().join("ABCD1234.key")).unwrap();
+        assert!(trashed.starts_with(FILE_MAGIC));
+
+        store.restore_secret_key("ABCD1234").unwrap();
+        use secrecy::ExposeSecret;
+        let secret = store.get_secret_key("ABCD1234").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"secret");
+        assert_eq!(
+            store.get_revocation_cert("ABCD1234").unwrap().as_deref(),
+            Some(&b"rev"[..])
+        );
+
+        store.trash_secret_key("ABCD1234").unwrap();
+        store.purge_secret_key("ABCD1234").unwrap();
+        assert!(!store.has_trashed_secret_key("ABCD1234"));
+        assert!(!store.trash_dir().join("ABCD1234.rev").exists());
+    }
+
     #[test]
     fn test_base64_roundtrip() {
         let data = b"Hello, World! This is test data with special chars: \x00\xFF\x80";
@@ -357,4 +977,143 @@ mod tests {
         let decoded = base64_decode(encoded.as_bytes()).unwrap();
         assert_eq!(decoded, data);
     }
+
+    fn portable_store(dir: &Path) -> CredentialStore {
+        let mut store = CredentialStore::new(dir).unwrap();
+        store.set_portable(true);
+        store
+    }
+
+    #[test]
+    fn test_secret_key_file_is_encrypted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = portable_store(tmp.path());
+
+        store
+            .store_secret_key("ABCD1234", b"top secret key")
+            .unwrap();
+        let data = std::fs::read(store.secret_key_path("ABCD1234")).unwrap();
+        assert!(data.starts_with(FILE_MAGIC));
+        assert!(!data.windows(10).any(|w| w == b"top secret"));
+        assert_eq!(store.protection().unwrap(), Some(Protection::Unprotected));
+
+        // A file moved to another fingerprint does not decrypt.
+        std::fs::copy(
+            store.secret_key_path("ABCD1234"),
+            store.secret_key_path("EEEE0000"),
+        )
+        .unwrap();
+        assert!(matches!(
+            store.get_secret_key("EEEE0000"),
+            Err(Error::CredentialStore { .. })
+        ));
+    }
+
+    #[test]
+    fn test_legacy_file_migration() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = portable_store(tmp.path());
+        let path = store.secret_key_path("ABCD1234");
+        std::fs::write(&path, base64_encode(b"old secret")).unwrap();
+
+        use secrecy::ExposeSecret;
+        let secret = store.get_secret_key("ABCD1234").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"old secret");
+        assert!(std::fs::read(&path).unwrap().starts_with(FILE_MAGIC));
+        let staged = tmp.path().join("secrets").join("ABCD1234.key.new");
+        assert!(!staged.exists());
+
+        let reopened = portable_store(tmp.path());
+        let secret = reopened.get_secret_key("ABCD1234").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"old secret");
+    }
+
+    #[test]
+    fn test_master_password() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = portable_store(tmp.path());
+        store.store_secret_key("ABCD1234", b"secret").unwrap();
+        store.set_master_password(b"correct horse").unwrap();
+        assert_eq!(
+            store.protection().unwrap(),
+            Some(Protection::MasterPassword)
+        );
+
+        let vault = std::fs::read_to_string(tmp.path().join("secrets").join(VAULT_FILE)).unwrap();
+        let raw_key = base64_encode(store.vault_key().unwrap().as_slice());
+        assert!(!vault.contains(&raw_key));
+
+        let reopened = portable_store(tmp.path());
+        assert!(matches!(
+            reopened.get_secret_key("ABCD1234"),
+            Err(Error::Locked)
+        ));
+        assert!(matches!(
+            reopened.unlock(b"wrong"),
+            Err(Error::BadMasterPassword)
+        ));
+        reopened.unlock(b"correct horse").unwrap();
+
+        use secrecy::ExposeSecret;
+        let secret = reopened.get_secret_key("ABCD1234").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"secret");
+
+        reopened.lock();
+        assert!(reopened.is_locked());
+        assert!(matches!(
+            reopened.store_secret_key("EEEE0000", b"other"),
+            Err(Error::Locked)
+        ));
+        reopened.unlock(b"correct horse").unwrap();
+        assert!(!reopened.is_locked());
+
+        // Changing the password keeps the stored keys readable.
+        reopened.set_master_password(b"battery staple").unwrap();
+        let again = portable_store(tmp.path());
+        again.unlock(b"battery staple").unwrap();
+        assert!(again.get_secret_key("ABCD1234").is_ok());
+    }
+
+    #[test]
+    fn test_master_password_moves_to_fresh_vault_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = portable_store(tmp.path());
+        store.store_secret_key("ABCD1234", b"secret").unwrap();
+        store.store_secret_key("EEEE0000", b"trashed").unwrap();
+        store.trash_secret_key("EEEE0000").unwrap();
+        let old_key = store.vault_key().unwrap();
+
+        store.set_master_password(b"correct horse").unwrap();
+        assert_ne!(*store.vault_key().unwrap(), *old_key);
+
+        // The unprotected old key no longer opens any file
+        let secrets = tmp.path().join("secrets");
+        for path in [
+            secrets.join("ABCD1234.key"),
+            secrets.join("trash").join("EEEE0000.key"),
+        ] {
+            let data = std::fs::read(&path).unwrap();
+            let fingerprint = path.file_stem().unwrap().to_str().unwrap();
+            let sealed = data.strip_prefix(FILE_MAGIC).unwrap();
+            assert!(open(&old_key, fingerprint.as_bytes(), sealed).is_none());
+        }
+        let leftovers = std::fs::read_dir(&secrets)
+            .unwrap()
+            .chain(std::fs::read_dir(secrets.join("trash")).unwrap())
+            .filter(|entry| {
+                let path = entry.as_ref().unwrap().path();
+                path.extension().is_some_and(|ext| ext == "new")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let reopened = portable_store(tmp.path());
+        reopened.unlock(b"correct horse").unwrap();
+        use secrecy::ExposeSecret;
+        let secret = reopened.get_secret_key("ABCD1234").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"secret");
+        reopened.restore_secret_key("EEEE0000").unwrap();
+        let secret = reopened.get_secret_key("EEEE0000").unwrap();
+        assert_eq!(secret.expose_secret().as_slice(), b"trashed");
+    }
 }
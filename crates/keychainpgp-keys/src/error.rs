@@ -5,10 +5,31 @@ pub enum Error {
     #[error("database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    /// The database was created by a newer version of KeychainPGP.
+    #[error(
+        "database schema version {found} was created by a newer version of KeychainPGP \
+         (this version supports up to {supported})"
+    )]
+    SchemaTooNew { found: u32, supported: u32 },
+
     /// OS credential store error.
     #[error("credential store error: {reason}")]
     CredentialStore { reason: String },
 
+    /// The master password protecting the secret keys is wrong.
+    #[error("incorrect master password")]
+    BadMasterPassword,
+
+    /// Secret keys are protected by a master password that has not been
+    /// entered yet.
+    #[error("keyring is locked: enter the master password to unlock it")]
+    Locked,
+
+    /// Keys are kept in memory only, e.g. in OPSEC mode, and the operation
+    /// would bring a key back from the disk.
+    #[error("not available while keys are kept in memory only")]
+    MemoryOnly,
+
     /// Key not found in the keyring.
     #[error("key not found: {fingerprint}")]
     KeyNotFound { fingerprint: String },
@@ -17,10 +38,31 @@ pub enum Error {
     #[error("key already exists: {fingerprint}")]
     DuplicateKey { fingerprint: String },
 
+    /// No recipient group with this name.
+    #[error("recipient group not found: {name}")]
+    GroupNotFound { name: String },
+
+    /// A recipient group with this name already exists.
+    #[error("recipient group already exists: {name}")]
+    DuplicateGroup { name: String },
+
+    /// Group names are used as `@name` on the command line, so they are
+    /// limited to letters, digits, `-`, `_` and `.`.
+    #[error("invalid group name '{name}': use letters, digits, '-', '_' or '.'")]
+    InvalidGroupName { name: String },
+
+    /// Tags are single words without `,` or `:`.
+    #[error("invalid tag '{tag}': use a single word without ',' or ':'")]
+    InvalidTag { tag: String },
+
     /// Invalid key data.
     #[error("invalid key data: {reason}")]
     InvalidKey { reason: String },
 
+    /// Talking to an external key agent failed.
+    #[error("agent error: {reason}")]
+    Agent { reason: String },
+
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
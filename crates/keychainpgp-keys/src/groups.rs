@@ -0,0 +1,224 @@
+//! Named recipient groups ("distribution lists").
+//!
+//! A group is a name and a set of member key fingerprints, so a team can be
+//! encrypted to as `@ops` instead of picking every key by hand. Expanding a
+//! group leaves out members that cannot be encrypted to (no longer in the
+//! keyring, revoked or expired) and reports each one as a warning, so one
+//! stale key does not block a message to the rest of the team.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::storage::KeyRecord;
+
+/// A named set of recipients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientGroup {
+    /// Group name, unique regardless of case.
+    pub name: String,
+    /// Primary key fingerprints of the members, sorted.
+    pub members: Vec<String>,
+}
+
+/// The members of a group that can be encrypted to, and why any others
+/// were left out.
+#[derive(Debug, Clone, Default)]
+pub struct GroupExpansion {
+    /// Usable member keys, in fingerprint order.
+    pub members: Vec<KeyRecord>,
+    /// One warning per member that was left out.
+    pub warnings: Vec<GroupWarning>,
+}
+
+/// Why a group member was left out of an expansion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupWarning {
+    /// The member's key is no longer in the keyring.
+    Missing { fingerprint: String },
+    /// The member's key is revoked.
+    Revoked {
+        fingerprint: String,
+        user_id: String,
+    },
+    /// The member's key has expired.
+    Expired {
+        fingerprint: String,
+        user_id: String,
+        expired_at: String,
+    },
+}
+
+impl GroupWarning {
+    /// Fingerprint of the member this warning is about.
+    #[must_use]
+    pub fn fingerprint(&self) -> &str {
+        match self {
+            Self::Missing { fingerprint }
+            | Self::Revoked { fingerprint, .. }
+            | Self::Expired { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+impl fmt::Display for GroupWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { fingerprint } => {
+                write!(f, "{fingerprint} is no longer in the keyring")
+            }
+            Self::Revoked {
+                fingerprint,
+                user_id,
+            } => write!(f, "{user_id} ({fingerprint}) is revoked"),
+            Self::Expired {
+                fingerprint,
+                user_id,
+                expired_at,
+            } => write!(f, "{user_id} ({fingerprint}) expired on {expired_at}"),
+        }
+    }
+}
+
+/// The group a recipient argument refers to, if it is written `@name`.
+#[must_use]
+pub fn group_reference(recipient: &str) -> Option<&str> {
+    recipient.strip_prefix('@').filter(|name| !name.is_empty())
+}
+
+/// Check that a group name can be written as `@name`.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidGroupName {
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Split group members into usable keys and warnings, as of `now`.
+pub(crate) fn expand(
+    members: Vec<(String, Option<KeyRecord>)>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> GroupExpansion {
+    let mut expansion = GroupExpansion::default();
+    for (fingerprint, record) in members {
+        let Some(record) = record else {
+            expansion
+                .warnings
+                .push(GroupWarning::Missing { fingerprint });
+            continue;
+        };
+        let user_id = match (&record.name, &record.email) {
+            (Some(name), Some(email)) => format!("{name} <{email}>"),
+            (Some(name), None) => name.clone(),
+            (None, Some(email)) => format!("<{email}>"),
+            (None, None) => "(no user ID)".to_string(),
+        };
+        let expired_at = record.expires_at.as_deref().filter(|expires_at| {
+            chrono::DateTime::parse_from_rfc3339(expires_at).is_ok_and(|t| t < now)
+        });
+        if record.is_revoked {
+            expansion.warnings.push(GroupWarning::Revoked {
+                fingerprint,
+                user_id,
+            });
+        } else if let Some(expired_at) = expired_at {
+            expansion.warnings.push(GroupWarning::Expired {
+                fingerprint,
+                user_id,
+                expired_at: expired_at.to_string(),
+            });
+        } else {
+            expansion.members.push(record);
+        }
+    }
+    expansion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fingerprint: &str, expires_at: Option<&str>, is_revoked: bool) -> KeyRecord {
+        KeyRecord {
+            fingerprint: fingerprint.to_string(),
+            name: Some("Member".to_string()),
+            email: Some("member@example.com".to_string()),
+            algorithm: "Ed25519".to_string(),
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+            expires_at: expires_at.map(str::to_string),
+            trust_level: 0,
+            is_own_key: false,
+            is_revoked,
+            pgp_data: Vec::new(),
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_group_names() {
+        assert_eq!(group_reference("@ops"), Some("ops"));
+        assert_eq!(group_reference("ops@example.com"), None);
+        assert_eq!(group_reference("@"), None);
+
+        assert!(validate_name("ops").is_ok());
+        assert!(validate_name("sec-team_2.eu").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("two words").is_err());
+        assert!(validate_name("@ops").is_err());
+    }
+
+    #[test]
+    fn test_expand_skips_unusable_members() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-06-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        let expansion = expand(
+            vec![
+                ("AAAA".into(), Some(record("AAAA", None, false))),
+                ("BBBB".into(), None),
+                ("CCCC".into(), Some(record("CCCC", None, true))),
+                (
+                    "DDDD".into(),
+                    Some(record("DDDD", Some("2026-01-31T00:00:00+00:00"), false)),
+                ),
+                (
+                    "EEEE".into(),
+                    Some(record("EEEE", Some("2027-01-31T00:00:00+00:00"), false)),
+                ),
+            ],
+            now,
+        );
+
+        let usable: Vec<_> = expansion
+            .members
+            .iter()
+            .map(|r| r.fingerprint.as_str())
+            .collect();
+        assert_eq!(usable, ["AAAA", "EEEE"]);
+        assert_eq!(
+            expansion.warnings,
+            [
+                GroupWarning::Missing {
+                    fingerprint: "BBBB".into()
+                },
+                GroupWarning::Revoked {
+                    fingerprint: "CCCC".into(),
+                    user_id: "Member <member@example.com>".into()
+                },
+                GroupWarning::Expired {
+                    fingerprint: "DDDD".into(),
+                    user_id: "Member <member@example.com>".into(),
+                    expired_at: "2026-01-31T00:00:00+00:00".into()
+                },
+            ]
+        );
+    }
+}
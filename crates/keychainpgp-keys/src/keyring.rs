@@ -1,23 +1,65 @@
-//! High-level keyring interface combining SQLite storage and OS credentials.
+//! High-level keyring interface combining a certificate store, SQLite
+//! storage and OS credentials.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
+use keychainpgp_core::cert;
 use keychainpgp_core::types::TrustLevel;
+use keychainpgp_core::{CryptoEngine, SequoiaEngine};
 use secrecy::SecretBox;
 
-use crate::credential::CredentialStore;
+use crate::audit::{AuditAction, AuditEntry, AuditQuery, ImportSource};
+use crate::cert_store::{CertDStore, CertStore, MemoryCertStore};
+use crate::credential::{CredentialStore, Protection};
 use crate::error::{Error, Result};
-use crate::storage::{KeyRecord, KeyStorage};
+use crate::groups::{self, GroupExpansion, RecipientGroup};
+use crate::search::{self, SearchPage};
+use crate::storage::{self, KeyRecord, KeyStorage};
+use crate::trash::TrashedKey;
 
-/// The main keyring interface. Manages both public keys (SQLite) and
-/// private keys (OS credential store with file-based fallback).
+/// The main keyring interface. Manages both public keys (a [`CertStore`],
+/// by default the SQLite database) and private keys (OS credential store
+/// with file-based fallback).
 pub struct Keyring {
+    /// Public keys with the user's trust, notes, tags and usage of them.
+    certs: Box<dyn CertStore>,
+    /// The store set aside while public keys are kept in memory only.
+    stashed: Option<Box<dyn CertStore>>,
+    /// The keyring database: recipient groups, trash, audit log and
+    /// settings.
     storage: KeyStorage,
     credentials: CredentialStore,
     data_dir: PathBuf,
+    audit_enabled: bool,
+    /// Store that public keys are mirrored into, e.g. an `openpgp-cert-d`
+    /// directory shared with other OpenPGP tools.
+    shared: Option<Box<dyn CertStore>>,
+    sharing_enabled: bool,
 }
 
+/// What [`Keyring::sync_shared_store`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SharedStoreSync {
+    /// Keys found only in the shared store, now in the keyring.
+    pub imported: usize,
+    /// Keys found only in the keyring, now in the shared store.
+    pub exported: usize,
+    /// Keys in both whose keyring copy gained signatures, User IDs or
+    /// subkeys from the shared copy.
+    pub updated: usize,
+}
+
+/// Keyring setting holding the path of the shared `openpgp-cert-d` directory.
+const CERT_D_SETTING: &str = "cert_d_path";
+
+/// Keyring setting set when purged keys are also removed from the shared store.
+const UNSHARE_PURGED_SETTING: &str = "unshare_purged";
+
+/// Name of the keyring database in the data directory.
+const DB_FILE: &str = "keyring.db";
+
 impl Keyring {
     /// Open the keyring using the default platform data directory.
     pub fn open_default() -> Result<Self> {
@@ -26,32 +68,35 @@ impl Keyring {
                 reason: "could not determine application data directory".into(),
             }
         })?;
-
-        let data_dir = dirs.data_dir().to_path_buf();
-        std::fs::create_dir_all(&data_dir)?;
-
-        let db_path = data_dir.join("keyring.db");
-        let storage = KeyStorage::open(&db_path)?;
-        let credentials = CredentialStore::new(&data_dir)?;
-
-        Ok(Self {
-            storage,
-            credentials,
-            data_dir,
-        })
+        Self::open_at(dirs.data_dir())
     }
 
-    /// Open the keyring at a specific directory (for testing).
+    /// Open the keyring at a specific directory, keeping public keys in its
+    /// database.
     pub fn open_at(data_dir: &Path) -> Result<Self> {
         std::fs::create_dir_all(data_dir)?;
-        let db_path = data_dir.join("keyring.db");
-        let storage = KeyStorage::open(&db_path)?;
+        let certs = KeyStorage::open(&data_dir.join(DB_FILE))?;
+        Self::open_with_store(data_dir, Box::new(certs))
+    }
+
+    /// Open the keyring at a specific directory, keeping public keys in
+    /// `certs` instead of the keyring database.
+    pub fn open_with_store(data_dir: &Path, certs: Box<dyn CertStore>) -> Result<Self> {
+        std::fs::create_dir_all(data_dir)?;
+        let storage = KeyStorage::open(&data_dir.join(DB_FILE))?;
         let credentials = CredentialStore::new(data_dir)?;
-        Ok(Self {
+        let mut keyring = Self {
+            certs,
+            stashed: None,
             storage,
             credentials,
             data_dir: data_dir.to_path_buf(),
-        })
+            audit_enabled: true,
+            shared: None,
+            sharing_enabled: true,
+        };
+        keyring.after_open();
+        Ok(keyring)
     }
 
     /// Get the data directory path.
@@ -62,42 +107,210 @@ impl Keyring {
 
     /// Store a generated key pair (public key in DB, private key in credential store).
     pub fn store_generated_key(&self, record: KeyRecord, secret_key: &[u8]) -> Result<()> {
+        if self.keys_in_memory() {
+            return Err(Error::MemoryOnly);
+        }
         // Store private key
         self.credentials
             .store_secret_key(&record.fingerprint, secret_key)?;
 
-        // Store public key in SQLite
-        self.storage.insert(&record)?;
+        // Store public key
+        self.certs.insert(&record)?;
+        self.share(&record);
 
         Ok(())
     }
 
+    /// Store the public half of a generated key whose secret key is kept
+    /// elsewhere, such as in memory in OPSEC mode.
+    pub fn store_generated_public_key(&self, record: KeyRecord) -> Result<()> {
+        self.certs.insert(&record)
+    }
+
     /// Import a public key into the keyring.
-    pub fn import_public_key(&self, record: KeyRecord) -> Result<()> {
-        self.storage.insert(&record)
+    pub fn import_public_key(&self, mut record: KeyRecord, source: ImportSource) -> Result<()> {
+        record.is_revoked |= self.revoked_by_designated_revoker(&record.pgp_data)?;
+        self.certs.insert(&record)?;
+        self.share(&record);
+        self.audit(&record.fingerprint, AuditAction::Imported { source })
+    }
+
+    /// Import a key together with its secret key. If the keyring already
+    /// holds the public key, it is replaced by `record` and the secret key
+    /// merged in, keeping the user's notes, tags and usage of the key.
+    /// Fails with [`Error::MemoryOnly`] while keys are kept in memory only,
+    /// since the secret key would be written to disk.
+    pub fn import_secret_key(
+        &self,
+        record: KeyRecord,
+        secret_key: &[u8],
+        source: ImportSource,
+    ) -> Result<()> {
+        if self.keys_in_memory() {
+            return Err(Error::MemoryOnly);
+        }
+        self.credentials
+            .store_secret_key(&record.fingerprint, secret_key)?;
+        self.import_own_public_key(record, source)
+    }
+
+    /// Import the public half of an own key whose secret key is kept
+    /// elsewhere, such as in memory in OPSEC mode. An existing public key
+    /// is replaced as by [`Keyring::import_secret_key`].
+    pub fn import_own_public_key(&self, mut record: KeyRecord, source: ImportSource) -> Result<()> {
+        record.is_revoked |= self.revoked_by_designated_revoker(&record.pgp_data)?;
+        let action = match self.certs.get(&record.fingerprint)? {
+            Some(existing) => {
+                record.metadata = existing.metadata;
+                self.certs.update(&record)?;
+                AuditAction::Merged { source }
+            }
+            None => {
+                self.certs.insert(&record)?;
+                AuditAction::Imported { source }
+            }
+        };
+        self.share(&record);
+        self.audit(&record.fingerprint, action)
     }
 
     /// Get a key record by fingerprint.
     pub fn get_key(&self, fingerprint: &str) -> Result<Option<KeyRecord>> {
-        self.storage.get(fingerprint)
+        self.certs.get(fingerprint)
     }
 
     /// List all keys in the keyring.
     pub fn list_keys(&self) -> Result<Vec<KeyRecord>> {
-        self.storage.list_all()
+        self.certs.list()
     }
 
-    /// Search keys by name, email, or fingerprint fragment.
+    /// Search keys by name, email, comment, notes, or fingerprint fragment,
+    /// matching every User ID and subkey of each key. Best matches come first.
     pub fn search_keys(&self, query: &str) -> Result<Vec<KeyRecord>> {
-        self.storage.search(query)
+        self.certs.search(query)
+    }
+
+    /// Search keys with typo-tolerant matching, returning one page of ranked
+    /// results. See [`KeyStorage::search_page`] for the ranking in the
+    /// keyring database.
+    pub fn search_keys_page(&self, query: &str, offset: usize, limit: usize) -> Result<SearchPage> {
+        self.certs.search_page(query, offset, limit)
+    }
+
+    /// Find the keys owning a primary or subkey fingerprint or key ID, such
+    /// as the issuer of a signature or the recipient of a message. More than
+    /// one key can carry the same subkey.
+    pub fn find_by_key_handle(&self, handle: &str) -> Result<Vec<KeyRecord>> {
+        self.certs.find_by_key_handle(handle)
     }
 
-    /// Delete a key from the keyring (both public and private if present).
+    /// All User IDs of a key as `(name, email)` pairs, primary first.
+    pub fn user_ids(&self, fingerprint: &str) -> Result<Vec<(Option<String>, Option<String>)>> {
+        self.certs.user_ids(fingerprint)
+    }
+
+    /// Delete a key from the keyring by moving it, and its private key if
+    /// present, to the trash. It can be restored with
+    /// [`Keyring::restore_key`] until it is purged. The shared store keeps
+    /// its copy.
+    ///
+    /// While keys are kept in memory only, the key is just dropped from
+    /// memory and nothing is written to the trash.
     pub fn delete_key(&self, fingerprint: &str) -> Result<bool> {
-        // Try to delete private key (ignore errors if not present)
-        let _ = self.credentials.delete_secret_key(fingerprint);
+        let Some(record) = self.certs.get(fingerprint)? else {
+            return Ok(false);
+        };
+        if self.keys_in_memory() {
+            return self.certs.delete(fingerprint);
+        }
+        self.credentials.trash_secret_key(fingerprint)?;
+        self.storage.trash(&record)?;
+        self.certs.delete(fingerprint)?;
+        self.audit(fingerprint, AuditAction::Deleted)?;
+        Ok(true)
+    }
+
+    /// Every key in the trash, most recently deleted first.
+    pub fn list_trash(&self) -> Result<Vec<TrashedKey>> {
+        self.storage.list_trash()
+    }
 
-        self.storage.delete(fingerprint)
+    /// Get a key in the trash by fingerprint.
+    pub fn get_trashed_key(&self, fingerprint: &str) -> Result<Option<TrashedKey>> {
+        self.storage.get_trashed(fingerprint)
+    }
+
+    /// Move a key, and its private key if it had one, out of the trash.
+    /// Fails with [`Error::DuplicateKey`] if the key was imported again
+    /// since it was deleted, and with [`Error::MemoryOnly`] while keys are
+    /// kept in memory only.
+    pub fn restore_key(&self, fingerprint: &str) -> Result<bool> {
+        if self.keys_in_memory() {
+            return Err(Error::MemoryOnly);
+        }
+        let Some(trashed) = self.storage.get_trashed(fingerprint)? else {
+            return Ok(false);
+        };
+        self.certs.insert(&trashed.record)?;
+        self.storage.remove_trashed(fingerprint)?;
+        self.credentials.restore_secret_key(fingerprint)?;
+        self.share(&trashed.record);
+        self.audit(fingerprint, AuditAction::Restored)?;
+        Ok(true)
+    }
+
+    /// Permanently delete a key from the trash. Its private key file is
+    /// overwritten before it is removed. It is also removed from the shared
+    /// store if [`Keyring::set_unshare_purged`] was turned on. Returns
+    /// `false`, touching nothing, if the key is not in the trash.
+    pub fn purge_key(&self, fingerprint: &str) -> Result<bool> {
+        if self.storage.get_trashed(fingerprint)?.is_none() {
+            return Ok(false);
+        }
+        self.credentials.purge_secret_key(fingerprint)?;
+        let purged = self.storage.purge(fingerprint)?;
+        if purged {
+            if self.unshare_purged()? {
+                self.unshare(fingerprint);
+            }
+            self.audit(fingerprint, AuditAction::Purged)?;
+        }
+        Ok(purged)
+    }
+
+    /// Purge every key in the trash. Returns the number of keys purged.
+    pub fn empty_trash(&self) -> Result<usize> {
+        let mut purged = 0;
+        for key in self.storage.list_trash()? {
+            if self.purge_key(&key.record.fingerprint)? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Purge the keys that have been in the trash for longer than the
+    /// retention period. Returns the number of keys purged.
+    pub fn purge_expired_trash(&self) -> Result<usize> {
+        let mut purged = 0;
+        for fingerprint in self.storage.expired_trash(chrono::Utc::now())? {
+            if self.purge_key(&fingerprint)? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Days a deleted key stays in the trash before it is purged; 0 keeps
+    /// deleted keys until purged by hand.
+    pub fn trash_retention_days(&self) -> Result<u32> {
+        self.storage.trash_retention_days()
+    }
+
+    /// Change how many days deleted keys stay in the trash. Keys already
+    /// past the new period are purged the next time the keyring is opened.
+    pub fn set_trash_retention_days(&self, days: u32) -> Result<()> {
+        self.storage.set_trash_retention_days(days)
     }
 
     /// Retrieve the secret key for the given fingerprint.
@@ -105,6 +318,46 @@ impl Keyring {
         self.credentials.get_secret_key(fingerprint)
     }
 
+    /// Replace the stored secret key, e.g. after a passphrase change.
+    pub fn update_secret_key(&self, fingerprint: &str, secret_key: &[u8]) -> Result<()> {
+        if self.certs.get(fingerprint)?.is_none() {
+            return Err(Error::KeyNotFound {
+                fingerprint: fingerprint.to_string(),
+            });
+        }
+        self.credentials.store_secret_key(fingerprint, secret_key)
+    }
+
+    /// How the stored secret keys are protected at rest, or `None` before the
+    /// first secret key is stored.
+    pub fn secret_key_protection(&self) -> Result<Option<Protection>> {
+        self.credentials.protection()
+    }
+
+    /// Protect the stored secret keys with a master password, or change it.
+    /// If one is already set, the keyring must be unlocked first.
+    pub fn set_master_password(&self, master_password: &[u8]) -> Result<()> {
+        self.credentials.set_master_password(master_password)
+    }
+
+    /// Unlock secret keys protected by a master password. Fails with
+    /// [`Error::BadMasterPassword`] if the password is wrong.
+    pub fn unlock(&self, master_password: &[u8]) -> Result<()> {
+        self.credentials.unlock(master_password)
+    }
+
+    /// Lock the keyring: every secret key access fails with [`Error::Locked`]
+    /// until [`Keyring::unlock`]. Only has an effect once a master password
+    /// is set.
+    pub fn lock(&self) {
+        self.credentials.lock();
+    }
+
+    /// Whether secret keys are locked behind the master password.
+    pub fn is_locked(&self) -> bool {
+        self.credentials.is_locked()
+    }
+
     /// Check if a secret key exists for the given fingerprint.
     pub fn has_secret_key(&self, fingerprint: &str) -> bool {
         self.credentials.has_secret_key(fingerprint)
@@ -112,17 +365,269 @@ impl Keyring {
 
     /// Update the trust level for a key.
     pub fn set_trust(&self, fingerprint: &str, trust: TrustLevel) -> Result<bool> {
-        self.storage.set_trust(fingerprint, trust)
+        let to = storage::trust_value(trust);
+        let Some(before) = self.modify(fingerprint, |record| record.trust_level = to)? else {
+            return Ok(false);
+        };
+        if before.trust_level != to {
+            self.audit(
+                fingerprint,
+                AuditAction::TrustChanged {
+                    from: before.trust_level,
+                    to,
+                },
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Set or clear the petname of a key.
+    pub fn set_key_petname(&self, fingerprint: &str, petname: Option<&str>) -> Result<bool> {
+        let petname = petname.map(str::trim).filter(|p| !p.is_empty());
+        let before = self.modify(fingerprint, |record| {
+            record.metadata.petname = petname.map(str::to_string);
+        })?;
+        Ok(before.is_some())
+    }
+
+    /// Set or clear the user's notes on a key. Notes are searchable.
+    pub fn set_key_notes(&self, fingerprint: &str, notes: Option<&str>) -> Result<bool> {
+        let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+        let before = self.modify(fingerprint, |record| {
+            record.metadata.notes = notes.map(str::to_string);
+        })?;
+        Ok(before.is_some())
+    }
+
+    /// Replace the tags of a key. Tags are normalized to lowercase.
+    pub fn set_key_tags(&self, fingerprint: &str, tags: &[String]) -> Result<bool> {
+        let tags = normalize_tags(tags)?;
+        let before = self.modify(fingerprint, |record| record.metadata.tags = tags)?;
+        Ok(before.is_some())
+    }
+
+    /// Set the petname, notes and tags of a key at once, as
+    /// [`Keyring::set_key_petname`], [`Keyring::set_key_notes`] and
+    /// [`Keyring::set_key_tags`] do. Nothing changes if a tag is invalid.
+    pub fn set_key_metadata(
+        &self,
+        fingerprint: &str,
+        petname: Option<&str>,
+        notes: Option<&str>,
+        tags: &[String],
+    ) -> Result<bool> {
+        let tags = normalize_tags(tags)?;
+        let petname = petname.map(str::trim).filter(|p| !p.is_empty());
+        let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+        let before = self.modify(fingerprint, |record| {
+            record.metadata.petname = petname.map(str::to_string);
+            record.metadata.notes = notes.map(str::to_string);
+            record.metadata.tags = tags;
+        })?;
+        Ok(before.is_some())
+    }
+
+    /// Every tag in use, with the number of keys carrying it.
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let mut tags = BTreeMap::<String, usize>::new();
+        for record in self.certs.list()? {
+            for tag in record.metadata.tags {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+        Ok(tags.into_iter().collect())
+    }
+
+    /// Record that a key was just encrypted to or verified, updating its
+    /// use count and last use time.
+    pub fn record_key_use(&self, fingerprint: &str) -> Result<bool> {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let before = self.modify(fingerprint, |record| {
+            record.metadata.last_used_at = Some(now);
+            record.metadata.use_count += 1;
+        })?;
+        Ok(before.is_some())
+    }
+
+    /// Apply `change` to the stored copy of a key. Returns the key as it was
+    /// before, or `None` if the keyring does not hold it.
+    fn modify(
+        &self,
+        fingerprint: &str,
+        change: impl FnOnce(&mut KeyRecord),
+    ) -> Result<Option<KeyRecord>> {
+        let Some(before) = self.certs.get(fingerprint)? else {
+            return Ok(None);
+        };
+        let mut record = before.clone();
+        change(&mut record);
+        if !self.certs.update(&record)? {
+            return Ok(None);
+        }
+        Ok(Some(before))
+    }
+
+    /// Create a recipient group from primary key fingerprints. Every member
+    /// must be in the keyring.
+    pub fn create_group(&self, name: &str, members: &[String]) -> Result<()> {
+        self.check_members(members)?;
+        self.storage.create_group(name, members)
+    }
+
+    /// Get a recipient group by name (case-insensitive).
+    pub fn get_group(&self, name: &str) -> Result<Option<RecipientGroup>> {
+        self.storage.get_group(name)
+    }
+
+    /// List all recipient groups, by name.
+    pub fn list_groups(&self) -> Result<Vec<RecipientGroup>> {
+        self.storage.list_groups()
+    }
+
+    /// Replace the members of a recipient group.
+    pub fn set_group_members(&self, name: &str, members: &[String]) -> Result<()> {
+        self.check_members(members)?;
+        if self.storage.set_group_members(name, members)? {
+            Ok(())
+        } else {
+            Err(Error::GroupNotFound {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    /// Add members to a recipient group. Existing members are kept.
+    pub fn add_group_members(&self, name: &str, members: &[String]) -> Result<()> {
+        let mut group = self.require_group(name)?;
+        group.members.extend_from_slice(members);
+        self.set_group_members(name, &group.members)
+    }
+
+    /// Remove members from a recipient group. Fingerprints that are not
+    /// members are ignored.
+    pub fn remove_group_members(&self, name: &str, members: &[String]) -> Result<()> {
+        let mut group = self.require_group(name)?;
+        group.members.retain(|m| !members.contains(m));
+        self.storage.set_group_members(name, &group.members)?;
+        Ok(())
+    }
+
+    /// Rename a recipient group.
+    pub fn rename_group(&self, name: &str, new_name: &str) -> Result<()> {
+        if self.storage.rename_group(name, new_name)? {
+            Ok(())
+        } else {
+            Err(Error::GroupNotFound {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    /// Delete a recipient group. Member keys are not affected.
+    pub fn delete_group(&self, name: &str) -> Result<bool> {
+        self.storage.delete_group(name)
+    }
+
+    /// Expand a recipient group into the member keys that can be encrypted
+    /// to, with a warning for each member left out because its key is
+    /// missing, revoked or expired.
+    pub fn expand_group(&self, name: &str) -> Result<GroupExpansion> {
+        let group = self.require_group(name)?;
+        let members = group
+            .members
+            .into_iter()
+            .map(|fingerprint| {
+                let record = self.certs.get(&fingerprint)?;
+                Ok((fingerprint, record))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(groups::expand(members, chrono::Utc::now()))
+    }
+
+    /// Merge recipient groups from another device: new groups are created
+    /// and existing ones gain the incoming members. Members whose key is not
+    /// in the keyring are skipped, and so are groups with an invalid or
+    /// colliding name. Returns the number of groups created or changed.
+    pub fn import_groups(&self, incoming: &[RecipientGroup]) -> Result<usize> {
+        let mut changed = 0;
+        for group in incoming {
+            if let Err(e) = groups::validate_name(&group.name) {
+                tracing::warn!("skipping imported group: {e}");
+                continue;
+            }
+            let mut members = Vec::new();
+            for fingerprint in &group.members {
+                if self.certs.get(fingerprint)?.is_some() {
+                    members.push(fingerprint.clone());
+                }
+            }
+            match self.storage.get_group(&group.name)? {
+                Some(existing) => {
+                    let mut merged = existing.members.clone();
+                    for fingerprint in members {
+                        if !merged.contains(&fingerprint) {
+                            merged.push(fingerprint);
+                        }
+                    }
+                    if merged.len() != existing.members.len() {
+                        self.storage.set_group_members(&existing.name, &merged)?;
+                        changed += 1;
+                    }
+                }
+                None => match self.storage.create_group(&group.name, &members) {
+                    Ok(()) => changed += 1,
+                    Err(e @ (Error::DuplicateGroup { .. } | Error::InvalidGroupName { .. })) => {
+                        tracing::warn!("skipping imported group: {e}");
+                    }
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        Ok(changed)
+    }
+
+    fn require_group(&self, name: &str) -> Result<RecipientGroup> {
+        self.storage
+            .get_group(name)?
+            .ok_or_else(|| Error::GroupNotFound {
+                name: name.to_string(),
+            })
     }
 
-    /// Mark a key as revoked in the database.
+    fn check_members(&self, members: &[String]) -> Result<()> {
+        for fingerprint in members {
+            if self.certs.get(fingerprint)?.is_none() {
+                return Err(Error::KeyNotFound {
+                    fingerprint: fingerprint.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark a key as revoked.
     pub fn set_revoked(&self, fingerprint: &str, revoked: bool) -> Result<bool> {
-        self.storage.set_revoked(fingerprint, revoked)
+        let Some(before) = self.modify(fingerprint, |record| record.is_revoked = revoked)? else {
+            return Ok(false);
+        };
+        if revoked && !before.is_revoked {
+            self.audit(fingerprint, AuditAction::Revoked)?;
+        }
+        Ok(true)
     }
 
     /// Update the PGP data for a key.
     pub fn update_pgp_data(&self, fingerprint: &str, pgp_data: &[u8]) -> Result<bool> {
-        self.storage.update_pgp_data(fingerprint, pgp_data)
+        let updated = self
+            .modify(fingerprint, |record| record.pgp_data = pgp_data.to_vec())?
+            .is_some();
+        if updated {
+            if self.revoked_by_designated_revoker(pgp_data)? {
+                self.set_revoked(fingerprint, true)?;
+            }
+            self.share_key(fingerprint);
+        }
+        Ok(updated)
     }
 
     /// Store a revocation certificate for the given key.
@@ -136,8 +641,273 @@ impl Keyring {
         self.credentials.get_revocation_cert(fingerprint)
     }
 
+    /// Record that the secret key of `fingerprint` was exported, e.g. to a
+    /// file, paper key or sync bundle. Callers log this themselves since
+    /// exports happen outside the keyring.
+    pub fn record_secret_export(&self, fingerprint: &str, destination: &str) -> Result<()> {
+        self.audit(
+            fingerprint,
+            AuditAction::SecretKeyExported {
+                destination: destination.to_string(),
+            },
+        )
+    }
+
+    /// Audit log entries matching `query`, newest first.
+    pub fn audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        self.storage.audit_log(query)
+    }
+
+    /// Securely wipe the audit log. Returns the number of entries removed.
+    pub fn clear_audit_log(&self) -> Result<usize> {
+        self.storage.clear_audit_log()
+    }
+
+    /// Turn audit logging on or off. OPSEC mode turns it off so that no
+    /// record of its session reaches the disk.
+    pub fn set_audit_enabled(&mut self, enabled: bool) {
+        self.audit_enabled = enabled;
+    }
+
+    /// Purge expired keys from the trash and attach the configured shared
+    /// store. A failure of either is logged rather than making the keyring
+    /// unusable.
+    fn after_open(&mut self) {
+        if let Err(e) = self.purge_expired_trash() {
+            tracing::warn!("failed to purge expired keys from the trash: {e}");
+        }
+        match self.cert_d_path() {
+            Ok(Some(path)) => match CertDStore::open(&path) {
+                Ok(store) => self.shared = Some(Box::new(store)),
+                Err(e) => tracing::warn!("failed to open {}: {e}", path.display()),
+            },
+            Ok(None) => {}
+            Err(e) => tracing::warn!("failed to read the shared store setting: {e}"),
+        }
+    }
+
+    /// Keep public keys in memory only, starting from a copy of the current
+    /// ones, so that keys imported, changed or deleted from now on leave no
+    /// trace on disk; or, with `false`, go back to the store the keyring was
+    /// opened with, dropping those changes. OPSEC mode uses this.
+    pub fn keep_keys_in_memory(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.keys_in_memory() {
+            return Ok(());
+        }
+        if enabled {
+            let memory = MemoryCertStore::new();
+            for record in self.certs.list()? {
+                memory.insert(&record)?;
+            }
+            self.stashed = Some(std::mem::replace(&mut self.certs, Box::new(memory)));
+        } else if let Some(certs) = self.stashed.take() {
+            self.certs = certs;
+        }
+        Ok(())
+    }
+
+    /// Whether public keys are kept in memory only.
+    #[must_use]
+    pub fn keys_in_memory(&self) -> bool {
+        self.stashed.is_some()
+    }
+
+    /// Mirror public keys into `store` from now on, or stop with `None`.
+    /// Existing keys are copied over by [`Keyring::sync_shared_store`].
+    pub fn set_shared_store(&mut self, store: Option<Box<dyn CertStore>>) {
+        self.shared = store;
+    }
+
+    /// The store public keys are mirrored into, if any.
+    pub fn shared_store(&self) -> Option<&dyn CertStore> {
+        self.shared.as_deref()
+    }
+
+    /// Pause or resume mirroring into the shared store. OPSEC mode pauses
+    /// it so that keys handled in its session stay out of other tools' view.
+    /// Keys kept in memory only are never shared.
+    pub fn set_sharing_enabled(&mut self, enabled: bool) {
+        self.sharing_enabled = enabled;
+    }
+
+    /// Share public keys through the `openpgp-cert-d` directory at `path`,
+    /// or at the default location other tools use, now and whenever the
+    /// keyring is opened. Returns the directory used.
+    pub fn use_cert_d(&mut self, path: Option<&Path>) -> Result<PathBuf> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => CertDStore::default_path().ok_or_else(|| Error::CredentialStore {
+                reason: "could not determine the shared certificate directory".into(),
+            })?,
+        };
+        let store = CertDStore::open(&path)?;
+        self.storage
+            .set_setting(CERT_D_SETTING, &path.to_string_lossy())?;
+        self.shared = Some(Box::new(store));
+        Ok(path)
+    }
+
+    /// Stop sharing public keys through an `openpgp-cert-d` directory. The
+    /// certificates already in it are left for the other tools.
+    pub fn stop_cert_d(&mut self) -> Result<()> {
+        self.storage.remove_setting(CERT_D_SETTING)?;
+        self.shared = None;
+        Ok(())
+    }
+
+    /// Whether purging a key from the trash also removes it from the shared
+    /// store. Off by default: other tools may still rely on the key.
+    pub fn unshare_purged(&self) -> Result<bool> {
+        Ok(self.storage.setting(UNSHARE_PURGED_SETTING)?.is_some())
+    }
+
+    /// Choose whether purging a key from the trash also removes it from the
+    /// shared store.
+    pub fn set_unshare_purged(&self, enabled: bool) -> Result<()> {
+        if enabled {
+            self.storage.set_setting(UNSHARE_PURGED_SETTING, "1")
+        } else {
+            self.storage.remove_setting(UNSHARE_PURGED_SETTING)
+        }
+    }
+
+    /// The `openpgp-cert-d` directory public keys are shared through, if any.
+    pub fn cert_d_path(&self) -> Result<Option<PathBuf>> {
+        Ok(self.storage.setting(CERT_D_SETTING)?.map(PathBuf::from))
+    }
+
+    /// Bring the keyring and the shared store in line: keys only in the
+    /// shared store are imported, keys only in the keyring are exported,
+    /// and keys in both are merged.
+    pub fn sync_shared_store(&self) -> Result<SharedStoreSync> {
+        let mut sync = SharedStoreSync::default();
+        let Some(shared) = self.sharing() else {
+            return Ok(sync);
+        };
+
+        for record in shared.list()? {
+            if self.certs.get(&record.fingerprint)?.is_none() {
+                self.import_public_key(record, ImportSource::Shared)?;
+                sync.imported += 1;
+            }
+        }
+        for local in self.certs.list()? {
+            if !shared.update(&local)? {
+                shared.insert(&local)?;
+                sync.exported += 1;
+                continue;
+            }
+            let Some(merged) = shared.get(&local.fingerprint)? else {
+                continue;
+            };
+            if cert::to_binary(&merged.pgp_data)? != cert::to_binary(&local.pgp_data)? {
+                // The merged certificate may bring a new primary User ID,
+                // expiration or revocation
+                let info = SequoiaEngine::new().inspect_key(&merged.pgp_data)?;
+                self.modify(&local.fingerprint, |record| {
+                    record.name = info.name().map(String::from);
+                    record.email = info.email().map(String::from);
+                    record.algorithm = info.algorithm.to_string();
+                    record.created_at = info.created_at.clone();
+                    record.expires_at = info.expires_at.clone();
+                    record.pgp_data = merged.pgp_data.clone();
+                })?;
+                if info.is_revoked || self.revoked_by_designated_revoker(&merged.pgp_data)? {
+                    self.set_revoked(&local.fingerprint, true)?;
+                }
+                sync.updated += 1;
+            }
+        }
+        Ok(sync)
+    }
+
+    /// Whether `pgp_data` carries a revocation by one of its designated
+    /// revokers that checks out against the revoker's key in the keyring.
+    /// Without the revoker's key the revocation cannot be checked, and the
+    /// key is only reported as possibly revoked by
+    /// [`CryptoEngine::inspect_key`].
+    fn revoked_by_designated_revoker(&self, pgp_data: &[u8]) -> Result<bool> {
+        let engine = SequoiaEngine::new();
+        let Ok(info) = engine.inspect_key(pgp_data) else {
+            return Ok(false);
+        };
+        if !info.possibly_revoked {
+            return Ok(false);
+        }
+        let mut revoker_keys = Vec::new();
+        for fingerprint in &info.designated_revokers {
+            if let Some(revoker) = self.certs.get(fingerprint)? {
+                revoker_keys.push(revoker.pgp_data);
+            }
+        }
+        Ok(engine.verify_designated_revocation(pgp_data, &revoker_keys)?)
+    }
+
+    /// Copy a public key into the shared store. The keyring stays the
+    /// reference, so a failure is logged rather than undoing the change.
+    fn share(&self, record: &KeyRecord) {
+        let Some(shared) = self.sharing() else {
+            return;
+        };
+        let result = match shared.update(record) {
+            Ok(true) => Ok(()),
+            Ok(false) => shared.insert(record),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("failed to share {}: {e}", record.fingerprint);
+        }
+    }
+
+    /// Share the current copy of a key, after a change to it.
+    fn share_key(&self, fingerprint: &str) {
+        if self.sharing().is_some() {
+            match self.certs.get(fingerprint) {
+                Ok(Some(record)) => self.share(&record),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("failed to share {fingerprint}: {e}"),
+            }
+        }
+    }
+
+    /// Remove a purged key from the shared store.
+    fn unshare(&self, fingerprint: &str) {
+        let Some(shared) = self.sharing() else {
+            return;
+        };
+        if let Err(e) = shared.delete(fingerprint) {
+            tracing::warn!("failed to remove {fingerprint} from the shared store: {e}");
+        }
+    }
+
+    /// The shared store, unless sharing is paused or keys are kept in
+    /// memory only.
+    fn sharing(&self) -> Option<&dyn CertStore> {
+        self.shared
+            .as_deref()
+            .filter(|_| self.sharing_enabled && !self.keys_in_memory())
+    }
+
+    fn audit(&self, fingerprint: &str, action: AuditAction) -> Result<()> {
+        if self.audit_enabled {
+            self.storage.append_audit(fingerprint, &action)?;
+        }
+        Ok(())
+    }
+
     /// Enable portable mode on the credential store (skips OS keyring).
     pub fn set_portable(&mut self, portable: bool) {
         self.credentials.set_portable(portable);
     }
 }
+
+/// Normalize tags to lowercase, sorted and without duplicates.
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    let mut tags = tags
+        .iter()
+        .map(|tag| search::normalize_tag(tag))
+        .collect::<Result<Vec<_>>>()?;
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
@@ -3,21 +3,43 @@
 //! Keyring management for KeychainPGP.
 //!
 //! This crate handles:
-//! - Persistent storage of public keys in a SQLite database.
+//! - Persistent storage of public keys in a SQLite database, behind a
+//!   [`CertStore`] trait also implemented in memory and by a shared
+//!   `openpgp-cert-d` directory that the keyring can mirror its keys into.
 //! - Secure storage of private keys via OS credential stores
-//!   (Windows DPAPI, macOS Keychain, Linux Secret Service).
+//!   (Windows DPAPI, macOS Keychain, Linux Secret Service), with an
+//!   encrypted file fallback that can be locked with a master password.
 //! - Key import and export in ASCII-armored format.
-//! - Key search by name, email, or fingerprint.
+//! - An append-only audit log of imports, trust changes, revocations,
+//!   merges, secret key exports and deletions.
+//! - A trash holding deleted keys for a retention period, from which they
+//!   can be restored until they are securely purged.
+//! - Named recipient groups that expand to their members' keys.
+//! - Ranked full-text key search by name, email, comment, notes, or
+//!   fingerprint, with prefix and fuzzy matching.
+//! - Private-key operations through gpg-agent (Unix).
 
+#[cfg(unix)]
+pub mod agent;
+pub mod audit;
+pub mod cert_store;
 pub mod credential;
 pub mod error;
 pub mod export;
+pub mod groups;
 pub mod import;
 pub mod keyring;
+pub mod migrations;
 pub mod network;
+pub mod search;
 pub mod storage;
 pub mod sync;
+pub mod trash;
 
+pub use audit::{AuditAction, AuditEntry, AuditQuery, ImportSource};
+pub use cert_store::{CertDStore, CertStore, MemoryCertStore};
 pub use error::{Error, Result};
-pub use keyring::Keyring;
-pub use storage::KeyRecord;
+pub use keyring::{Keyring, SharedStoreSync};
+pub use search::SearchPage;
+pub use storage::{KeyMetadata, KeyRecord};
+pub use trash::TrashedKey;
@@ -0,0 +1,479 @@
+//! Versioned schema migrations for the key database.
+//!
+//! The schema version is kept in SQLite's `user_version` header field.
+//! Migrations are applied in order, each in its own transaction together
+//! with the version bump, so an interrupted upgrade leaves the database at
+//! the last version that completed. A database whose version is newer than
+//! [`SCHEMA_VERSION`] was written by a newer release and is refused rather
+//! than modified.
+
+use keychainpgp_core::{CryptoEngine, SequoiaEngine};
+use rusqlite::{Connection, Transaction, params};
+
+use crate::error::{Error, Result};
+
+/// A single schema upgrade from `version - 1` to `version`.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Transaction<'_>) -> rusqlite::Result<()>,
+}
+
+/// All migrations, in order. Append new ones at the end; never edit or
+/// reorder a migration that has shipped in a release.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline keys table",
+        up: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "user_ids and subkeys tables",
+        up: user_ids_and_subkeys,
+    },
+    Migration {
+        version: 3,
+        description: "full-text search index, notes and last use",
+        up: search_index,
+    },
+    Migration {
+        version: 4,
+        description: "recipient groups",
+        up: recipient_groups,
+    },
+    Migration {
+        version: 5,
+        description: "petnames, tags and usage counts",
+        up: key_metadata,
+    },
+    Migration {
+        version: 6,
+        description: "audit log",
+        up: audit_log,
+    },
+    Migration {
+        version: 7,
+        description: "trash and keyring settings",
+        up: trash,
+    },
+];
+
+/// Schema version this build creates and understands.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Bring the database schema up to [`SCHEMA_VERSION`].
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    apply(conn, MIGRATIONS)
+}
+
+/// Read the schema version stored in the database.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+fn apply(conn: &mut Connection, migrations: &[Migration]) -> Result<()> {
+    let latest = migrations.last().map_or(0, |m| m.version);
+    let current = schema_version(conn)?;
+    if current > latest {
+        return Err(Error::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        tracing::debug!(
+            "migrated key database to schema {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+    Ok(())
+}
+
+/// Schema 1: the `keys` table as shipped before versioning existed.
+///
+/// Unversioned databases come in two shapes: the initial release without
+/// `is_revoked`, and later ones with it. Both are brought to the same
+/// baseline here.
+fn baseline(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS keys (
+            fingerprint TEXT PRIMARY KEY NOT NULL,
+            name        TEXT,
+            email       TEXT,
+            algorithm   TEXT NOT NULL,
+            created_at  TEXT NOT NULL,
+            expires_at  TEXT,
+            trust_level INTEGER NOT NULL DEFAULT 0,
+            is_own_key  INTEGER NOT NULL DEFAULT 0,
+            is_revoked  INTEGER NOT NULL DEFAULT 0,
+            pgp_data    BLOB NOT NULL
+        );",
+    )?;
+
+    if !has_column(tx, "keys", "is_revoked")? {
+        tx.execute(
+            "ALTER TABLE keys ADD COLUMN is_revoked INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_keys_email ON keys(email);
+         CREATE INDEX IF NOT EXISTS idx_keys_name  ON keys(name);",
+    )
+}
+
+/// Schema 2: every User ID and subkey of each key, for lookups by any
+/// email address, subkey fingerprint or key ID. Existing keys are indexed
+/// from their stored certificates.
+fn user_ids_and_subkeys(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE user_ids (
+            fingerprint TEXT NOT NULL,
+            position    INTEGER NOT NULL,
+            name        TEXT,
+            email       TEXT,
+            PRIMARY KEY (fingerprint, position)
+        );
+        CREATE INDEX idx_user_ids_email ON user_ids(email COLLATE NOCASE);
+
+        CREATE TABLE subkeys (
+            primary_fingerprint TEXT NOT NULL,
+            fingerprint         TEXT NOT NULL,
+            key_id              TEXT NOT NULL,
+            algorithm           TEXT NOT NULL,
+            created_at          TEXT NOT NULL,
+            expires_at          TEXT,
+            capabilities        TEXT NOT NULL,
+            is_revoked          INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (primary_fingerprint, fingerprint)
+        );
+        CREATE INDEX idx_subkeys_fingerprint ON subkeys(fingerprint);
+        CREATE INDEX idx_subkeys_key_id ON subkeys(key_id);",
+    )?;
+
+    let keys = tx
+        .prepare("SELECT fingerprint, pgp_data FROM keys")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (fingerprint, pgp_data) in keys {
+        index_cert_v2(tx, &fingerprint, &pgp_data)?;
+    }
+    Ok(())
+}
+
+/// Fill the schema 2 `user_ids` and `subkeys` rows of a key. A copy of
+/// `storage::index_cert` as it was when schema 2 shipped, so later changes
+/// to the live indexing do not change what this migration does.
+fn index_cert_v2(tx: &Transaction<'_>, fingerprint: &str, pgp_data: &[u8]) -> rusqlite::Result<()> {
+    let info = match SequoiaEngine::new().inspect_key(pgp_data) {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::debug!("not indexing User IDs and subkeys of {fingerprint}: {e}");
+            return Ok(());
+        }
+    };
+
+    for (position, uid) in info.user_ids.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO user_ids (fingerprint, position, name, email) VALUES (?1, ?2, ?3, ?4)",
+            params![fingerprint, position as i64, uid.name, uid.email],
+        )?;
+    }
+    for subkey in &info.subkeys {
+        let subkey_fingerprint = subkey.fingerprint.to_uppercase();
+        // The key ID is the first 16 digits of a v6 fingerprint, the last
+        // 16 of a v4 one
+        let key_id = if subkey_fingerprint.len() == 64 {
+            &subkey_fingerprint[..16]
+        } else {
+            &subkey_fingerprint[subkey_fingerprint.len().saturating_sub(16)..]
+        };
+        let capabilities = subkey
+            .capabilities
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        tx.execute(
+            "INSERT OR REPLACE INTO subkeys
+                (primary_fingerprint, fingerprint, key_id, algorithm, created_at, expires_at, capabilities, is_revoked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                fingerprint,
+                subkey_fingerprint,
+                key_id,
+                subkey.algorithm,
+                subkey.created_at,
+                subkey.expires_at,
+                capabilities,
+                subkey.is_revoked,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Schema 3: an FTS5 index over the names, emails and comments of every
+/// User ID and the user's notes, plus the columns search ranking needs.
+fn search_index(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE keys ADD COLUMN notes TEXT;
+        ALTER TABLE keys ADD COLUMN last_used_at TEXT;
+
+        CREATE VIRTUAL TABLE key_search USING fts5(
+            fingerprint UNINDEXED,
+            names,
+            emails,
+            comments,
+            notes,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );",
+    )?;
+
+    let fingerprints = tx
+        .prepare("SELECT fingerprint FROM keys")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for fingerprint in fingerprints {
+        index_search_v3(tx, &fingerprint)?;
+    }
+    Ok(())
+}
+
+/// Fill the schema 3 `key_search` row of a key. A copy of
+/// `storage::index_search` as it was when schema 3 shipped.
+fn index_search_v3(tx: &Transaction<'_>, fingerprint: &str) -> rusqlite::Result<()> {
+    fn push_unique(list: &mut Vec<String>, value: &str) {
+        if !value.is_empty() && !list.iter().any(|v| v == value) {
+            list.push(value.to_string());
+        }
+    }
+
+    let (name, email, notes) = tx.query_row(
+        "SELECT name, email, notes FROM keys WHERE fingerprint = ?1",
+        params![fingerprint],
+        |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        },
+    )?;
+
+    let mut user_ids = vec![(name, email)];
+    let mut stmt =
+        tx.prepare("SELECT name, email FROM user_ids WHERE fingerprint = ?1 ORDER BY position")?;
+    for uid in stmt.query_map(params![fingerprint], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        user_ids.push(uid?);
+    }
+
+    let mut names = Vec::new();
+    let mut emails = Vec::new();
+    let mut comments = Vec::new();
+    for (name, email) in &user_ids {
+        if let Some(name) = name {
+            // "Name (comment)": the comment is indexed on its own
+            let name = name.trim();
+            match (name.rfind('('), name.ends_with(')')) {
+                (Some(open), true) if !name[open + 1..name.len() - 1].trim().is_empty() => {
+                    push_unique(&mut names, name[..open].trim());
+                    push_unique(&mut comments, name[open + 1..name.len() - 1].trim());
+                }
+                _ => push_unique(&mut names, name),
+            }
+        }
+        if let Some(email) = email {
+            push_unique(&mut emails, email);
+        }
+    }
+
+    tx.execute(
+        "INSERT INTO key_search (fingerprint, names, emails, comments, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            fingerprint,
+            names.join("\n"),
+            emails.join("\n"),
+            comments.join("\n"),
+            notes.unwrap_or_default(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Schema 4: named recipient groups. Members are kept by fingerprint, even
+/// while their key is not in the keyring.
+fn recipient_groups(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE recipient_groups (
+            id         INTEGER PRIMARY KEY,
+            name       TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE recipient_group_members (
+            group_id    INTEGER NOT NULL,
+            fingerprint TEXT NOT NULL,
+            PRIMARY KEY (group_id, fingerprint)
+        );",
+    )
+}
+
+/// Schema 5: the user's petname and tags for each key, and how often the
+/// key was used (`notes` and `last_used_at` came with schema 3).
+fn key_metadata(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE keys ADD COLUMN petname TEXT;
+        ALTER TABLE keys ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TABLE key_tags (
+            fingerprint TEXT NOT NULL,
+            tag         TEXT NOT NULL,
+            PRIMARY KEY (fingerprint, tag)
+        );
+        CREATE INDEX idx_key_tags_tag ON key_tags(tag);",
+    )
+}
+
+/// Schema 6: the append-only audit log. Rows are never updated; the only
+/// deletion is wiping the whole log.
+fn audit_log(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE audit_log (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp   TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            action      TEXT NOT NULL,
+            detail      TEXT
+        );
+        CREATE INDEX idx_audit_log_fingerprint ON audit_log(fingerprint);
+
+        CREATE TRIGGER audit_log_append_only BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'the audit log is append-only');
+        END;",
+    )
+}
+
+/// Schema 7: deleted keys kept in the trash with their metadata until they
+/// are purged, and a table of keyring-wide settings such as the retention
+/// period.
+fn trash(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE trashed_keys (
+            fingerprint  TEXT PRIMARY KEY,
+            name         TEXT,
+            email        TEXT,
+            algorithm    TEXT NOT NULL,
+            created_at   TEXT NOT NULL,
+            expires_at   TEXT,
+            trust_level  INTEGER NOT NULL DEFAULT 0,
+            is_own_key   INTEGER NOT NULL DEFAULT 0,
+            is_revoked   INTEGER NOT NULL DEFAULT 0,
+            pgp_data     BLOB NOT NULL,
+            petname      TEXT,
+            notes        TEXT,
+            tags         TEXT,
+            last_used_at TEXT,
+            use_count    INTEGER NOT NULL DEFAULT 0,
+            deleted_at   TEXT NOT NULL
+        );
+
+        CREATE TABLE keyring_settings (
+            name  TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+fn has_column(tx: &Transaction<'_>, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_notes(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        tx.execute_batch("CREATE TABLE notes (body TEXT NOT NULL);")
+    }
+
+    fn broken(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        tx.execute_batch(
+            "CREATE TABLE half_done (id INTEGER);
+             INSERT INTO no_such_table VALUES (1);",
+        )
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+
+        // Running again is a no-op
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "notes",
+                up: create_notes,
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                up: broken,
+            },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert!(apply(&mut conn, &migrations).is_err());
+
+        // The first migration stuck, the failed one left nothing behind
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        let half_done: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(half_done, 0);
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        match migrate(&mut conn) {
+            Err(Error::SchemaTooNew { found, supported }) => {
+                assert_eq!(found, SCHEMA_VERSION + 1);
+                assert_eq!(supported, SCHEMA_VERSION);
+            }
+            other => panic!("expected SchemaTooNew, got {other:?}"),
+        }
+    }
+}
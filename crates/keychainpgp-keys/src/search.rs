@@ -0,0 +1,265 @@
+//! Ranked full-text search over the keyring.
+//!
+//! Every key has one row in the `key_search` FTS5 table holding the names,
+//! emails and comments of all its User IDs plus the user's notes on it.
+//! Each word of a query matches as a prefix, so "jon" finds "Jonathan" and
+//! "jon.doe@example.com". With fuzzy matching enabled, keys the full-text
+//! index misses are compared word by word with a small edit distance, so a
+//! typo like "jonh" still finds "John".
+//!
+//! Words of the form `tag:name` are filters rather than search terms: only
+//! keys carrying every such tag match, so `tag:vendor acme` finds vendor
+//! keys matching "acme" and `tag:vendor` alone lists all vendor keys.
+
+use crate::error::{Error, Result};
+use crate::storage::{self, KeyRecord};
+
+/// One page of ranked search results.
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    /// The keys on this page, best match first.
+    pub keys: Vec<KeyRecord>,
+    /// Number of matching keys across all pages.
+    pub total: usize,
+}
+
+/// Normalize a tag as typed by a user: trimmed and lowercased. Tags are
+/// single words without `,` or `:`, so they can follow `tag:` in a query.
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',' || c == ':') {
+        return Err(Error::InvalidTag { tag });
+    }
+    Ok(tag)
+}
+
+/// Separate `tag:` filters from the search text of a query.
+pub(crate) fn parse_filters(query: &str) -> (String, Vec<String>) {
+    let mut text = Vec::new();
+    let mut tags = Vec::new();
+    for word in query.split_whitespace() {
+        match word.strip_prefix("tag:").map(normalize_tag) {
+            Some(Ok(tag)) => {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            _ => text.push(word),
+        }
+    }
+    (text.join(" "), tags)
+}
+
+/// Split text into lowercase words the way the FTS5 `unicode61` tokenizer
+/// does: runs of letters and digits, everything else is a separator.
+pub(crate) fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Build an FTS5 `MATCH` expression requiring every word of the query as a
+/// prefix. Returns `None` if the query has no words.
+pub(crate) fn fts_query(query: &str) -> Option<String> {
+    let terms = words(query)
+        .into_iter()
+        .map(|w| format!("\"{w}\"*"))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Whether a key matches a query, for stores without a full-text index:
+/// every `tag:` filter is one of its tags, and every word of the rest is a
+/// prefix of a word of its name, email, petname or notes, or the rest is
+/// part of its fingerprint.
+pub(crate) fn record_matches(record: &KeyRecord, query: &str) -> bool {
+    let (text, tags) = parse_filters(query);
+    if !tags.iter().all(|tag| record.metadata.tags.contains(tag)) {
+        return false;
+    }
+    let query_words = words(&text);
+    if query_words.is_empty() {
+        return !tags.is_empty();
+    }
+    let handle = storage::normalize_handle(&text);
+    if !handle.is_empty() && record.fingerprint.contains(&handle) {
+        return true;
+    }
+    let fields = [
+        &record.name,
+        &record.email,
+        &record.metadata.petname,
+        &record.metadata.notes,
+    ];
+    let record_words: Vec<String> = fields
+        .into_iter()
+        .flatten()
+        .flat_map(|field| words(field))
+        .collect();
+    query_words
+        .iter()
+        .all(|q| record_words.iter().any(|w| w.starts_with(q.as_str())))
+}
+
+/// Split a User ID name of the form `Name (comment)` into its parts.
+pub(crate) fn split_comment(name: &str) -> (&str, Option<&str>) {
+    let name = name.trim();
+    if let (Some(open), true) = (name.rfind('('), name.ends_with(')')) {
+        let comment = name[open + 1..name.len() - 1].trim();
+        let name = name[..open].trim();
+        if !comment.is_empty() {
+            return (name, Some(comment));
+        }
+    }
+    (name, None)
+}
+
+/// Whether every query word is close to some word of the text: a prefix of
+/// it, or within a few typos of it or of its prefix of the same length.
+pub(crate) fn fuzzy_matches(query_words: &[String], text_words: &[String]) -> bool {
+    !query_words.is_empty()
+        && query_words.iter().all(|q| {
+            let typos = max_typos(q.chars().count());
+            text_words.iter().any(|w| {
+                w.starts_with(q.as_str())
+                    || (typos > 0
+                        && (edit_distance(q, w) <= typos
+                            || edit_distance(q, &prefix(w, q.chars().count())) <= typos))
+            })
+        })
+}
+
+/// Typos tolerated in a query word: none for very short words, where any
+/// edit leads to a different name.
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn prefix(word: &str, chars: usize) -> String {
+    word.chars().take(chars).collect()
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and transpositions of adjacent characters each count as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr[j] = curr[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_uses_prefixes() {
+        assert_eq!(
+            fts_query("Jon  O'Brien").as_deref(),
+            Some("\"jon\"* \"o\"* \"brien\"*")
+        );
+        assert_eq!(
+            fts_query("carol@work").as_deref(),
+            Some("\"carol\"* \"work\"*")
+        );
+        assert_eq!(fts_query(" \"*- "), None);
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        assert_eq!(
+            parse_filters("  tag:Vendor acme tag:eu tag:vendor "),
+            (
+                "acme".to_string(),
+                vec!["vendor".to_string(), "eu".to_string()]
+            )
+        );
+        assert_eq!(parse_filters("tag:"), ("tag:".to_string(), vec![]));
+        assert!(normalize_tag("two words").is_err());
+        assert!(normalize_tag("a,b").is_err());
+    }
+
+    #[test]
+    fn test_record_matches() {
+        let record = KeyRecord {
+            fingerprint: "6C427F2A23D20CDC16A526EC06FA8279696FBBB8".to_string(),
+            name: Some("Carol Example".to_string()),
+            email: Some("carol@example.com".to_string()),
+            algorithm: "Ed25519".to_string(),
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+            expires_at: None,
+            trust_level: 0,
+            is_own_key: false,
+            is_revoked: false,
+            pgp_data: Vec::new(),
+            metadata: crate::KeyMetadata {
+                tags: vec!["vendor".to_string()],
+                ..Default::default()
+            },
+        };
+        assert!(record_matches(&record, "car exa"));
+        assert!(record_matches(&record, "carol@example"));
+        assert!(record_matches(&record, "0x696f bbb8"));
+        assert!(record_matches(&record, "tag:vendor"));
+        assert!(record_matches(&record, "tag:vendor carol"));
+        assert!(!record_matches(&record, "tag:eu carol"));
+        assert!(!record_matches(&record, "arol"));
+        assert!(!record_matches(&record, ""));
+    }
+
+    #[test]
+    fn test_split_comment() {
+        assert_eq!(
+            split_comment("Carol Example (work laptop)"),
+            ("Carol Example", Some("work laptop"))
+        );
+        assert_eq!(split_comment("Carol Example"), ("Carol Example", None));
+        assert_eq!(split_comment("Carol ()"), ("Carol ()", None));
+    }
+
+    #[test]
+    fn test_fuzzy_matching() {
+        let text = words("Jonathan Smith jsmith@example.com");
+        let matches = |q: &str| fuzzy_matches(&words(q), &text);
+
+        assert!(matches("jonh"));
+        assert!(matches("jonatan"));
+        assert!(matches("smtih"));
+        assert!(matches("jon smi"));
+        assert!(!matches("jan"));
+        assert!(!matches("jonh brown"));
+        assert!(!matches(""));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("john", "john"), 0);
+        assert_eq!(edit_distance("jonh", "john"), 1);
+        assert_eq!(edit_distance("jon", "john"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
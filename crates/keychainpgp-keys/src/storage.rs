@@ -1,9 +1,18 @@
-use rusqlite::{Connection, OptionalExtension, params};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use keychainpgp_core::types::TrustLevel;
+use keychainpgp_core::{CryptoEngine, SequoiaEngine};
 
-use crate::error::Result;
+use crate::audit::{AuditAction, AuditEntry, AuditQuery};
+use crate::cert_store::CertStore;
+use crate::error::{Error, Result};
+use crate::groups::{self, RecipientGroup};
+use crate::migrations;
+use crate::search::{self, SearchPage};
+use crate::trash::{self, TrashedKey};
 
 /// A record representing a public key stored in the database.
 #[derive(Debug, Clone)]
@@ -28,6 +37,77 @@ pub struct KeyRecord {
     pub is_revoked: bool,
     /// Raw ASCII-armored public key data.
     pub pgp_data: Vec<u8>,
+    /// The user's own annotations and usage of the key.
+    pub metadata: KeyMetadata,
+}
+
+/// What the user knows about a key beyond its certificate: annotations
+/// they edit, and how often and how recently they used the key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyMetadata {
+    /// Nickname shown instead of, or next to, the User ID.
+    pub petname: Option<String>,
+    /// Free-form notes, e.g. "verified in person at FOSDEM".
+    pub notes: Option<String>,
+    /// Lowercase tags, sorted, e.g. `vendor`.
+    pub tags: Vec<String>,
+    /// When the key was last encrypted to or verified (ISO 8601).
+    pub last_used_at: Option<String>,
+    /// How many times the key was encrypted to or verified.
+    pub use_count: u32,
+}
+
+/// Columns selected for a [`KeyRecord`], in the order [`record_from_row`] reads them.
+const RECORD_COLUMNS: &str = "keys.fingerprint, keys.name, keys.email, keys.algorithm, \
+    keys.created_at, keys.expires_at, keys.trust_level, keys.is_own_key, keys.is_revoked, \
+    keys.pgp_data, keys.petname, keys.notes, \
+    (SELECT group_concat(tag, ',') FROM \
+        (SELECT tag FROM key_tags WHERE key_tags.fingerprint = keys.fingerprint ORDER BY tag)), \
+    keys.last_used_at, keys.use_count";
+
+fn record_from_row(row: &Row<'_>) -> rusqlite::Result<KeyRecord> {
+    Ok(KeyRecord {
+        fingerprint: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        algorithm: row.get(3)?,
+        created_at: row.get(4)?,
+        expires_at: row.get(5)?,
+        trust_level: row.get(6)?,
+        is_own_key: row.get(7)?,
+        is_revoked: row.get::<_, i32>(8)? != 0,
+        pgp_data: row.get(9)?,
+        metadata: KeyMetadata {
+            petname: row.get(10)?,
+            notes: row.get(11)?,
+            tags: row
+                .get::<_, Option<String>>(12)?
+                .map(|tags| tags.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            last_used_at: row.get(13)?,
+            use_count: row.get(14)?,
+        },
+    })
+}
+
+/// The 16-hex-digit key ID of a v4 (40 digits) or v6 (64 digits) fingerprint.
+pub fn key_id(fingerprint: &str) -> &str {
+    if fingerprint.len() == 64 {
+        &fingerprint[..16]
+    } else {
+        &fingerprint[fingerprint.len().saturating_sub(16)..]
+    }
+}
+
+/// Normalize a fingerprint or key ID typed by a user: drop a `0x` prefix
+/// and spaces, and uppercase the hex digits.
+pub(crate) fn normalize_handle(handle: &str) -> String {
+    let handle = handle.trim();
+    let handle = handle
+        .strip_prefix("0x")
+        .or_else(|| handle.strip_prefix("0X"))
+        .unwrap_or(handle);
+    handle.replace(' ', "").to_uppercase()
 }
 
 /// SQLite-backed storage for public keys.
@@ -37,82 +117,40 @@ pub struct KeyStorage {
 
 impl KeyStorage {
     /// Open or create the key storage database at the given path.
+    ///
+    /// The schema is migrated to the current version. A database written by
+    /// a newer release fails with [`Error::SchemaTooNew`](crate::Error::SchemaTooNew).
     pub fn open(path: &Path) -> Result<Self> {
-        let conn = Connection::open(path)?;
-        let storage = Self { conn };
-        storage.initialize()?;
-        Ok(storage)
+        let mut conn = Connection::open(path)?;
+        migrations::migrate(&mut conn)?;
+        Ok(Self { conn })
     }
 
     /// Open an in-memory database (for testing).
     pub fn open_in_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
-        let storage = Self { conn };
-        storage.initialize()?;
-        Ok(storage)
-    }
-
-    fn initialize(&self) -> Result<()> {
-        self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS keys (
-                fingerprint TEXT PRIMARY KEY NOT NULL,
-                name        TEXT,
-                email       TEXT,
-                algorithm   TEXT NOT NULL,
-                created_at  TEXT NOT NULL,
-                expires_at  TEXT,
-                trust_level INTEGER NOT NULL DEFAULT 0,
-                is_own_key  INTEGER NOT NULL DEFAULT 0,
-                is_revoked  INTEGER NOT NULL DEFAULT 0,
-                pgp_data    BLOB NOT NULL
-            );
-
-            -- Migration: add is_revoked if it does not exist
-            PRAGMA table_info(keys);",
-        )?;
-
-        // Check if is_revoked exists, if not add it
-        let mut stmt = self.conn.prepare("PRAGMA table_info(keys)")?;
-        let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
-        let mut has_revoked = false;
-        for col in columns {
-            if col? == "is_revoked" {
-                has_revoked = true;
-                break;
-            }
-        }
-        if !has_revoked {
-            self.conn.execute(
-                "ALTER TABLE keys ADD COLUMN is_revoked INTEGER NOT NULL DEFAULT 0",
-                [],
-            )?;
-        }
+        let mut conn = Connection::open_in_memory()?;
+        migrations::migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
 
-        self.conn.execute_batch(
-            "CREATE INDEX IF NOT EXISTS idx_keys_email ON keys(email);
-             CREATE INDEX IF NOT EXISTS idx_keys_name  ON keys(name);",
-        )?;
-        Ok(())
+    /// The schema version of the open database.
+    pub fn schema_version(&self) -> Result<u32> {
+        migrations::schema_version(&self.conn)
     }
 
-    /// Insert a key record. Returns an error if the fingerprint already exists.
+    /// Insert a key record. Fails with [`Error::DuplicateKey`] if the
+    /// fingerprint already exists.
+    ///
+    /// Every User ID and subkey of the certificate is indexed alongside it.
     pub fn insert(&self, record: &KeyRecord) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO keys (fingerprint, name, email, algorithm, created_at, expires_at, trust_level, is_own_key, is_revoked, pgp_data)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                record.fingerprint,
-                record.name,
-                record.email,
-                record.algorithm,
-                record.created_at,
-                record.expires_at,
-                record.trust_level,
-                record.is_own_key,
-                record.is_revoked,
-                record.pgp_data,
-            ],
-        )?;
+        if self.get(&record.fingerprint)?.is_some() {
+            return Err(Error::DuplicateKey {
+                fingerprint: record.fingerprint.clone(),
+            });
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        insert_record(&tx, record)?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -121,89 +159,240 @@ impl KeyStorage {
         let record = self
             .conn
             .query_row(
-                "SELECT fingerprint, name, email, algorithm, created_at, expires_at, trust_level, is_own_key, is_revoked, pgp_data
-                 FROM keys WHERE fingerprint = ?1",
+                &format!("SELECT {RECORD_COLUMNS} FROM keys WHERE fingerprint = ?1"),
                 params![fingerprint],
-                |row| {
-                    Ok(KeyRecord {
-                        fingerprint: row.get(0)?,
-                        name: row.get(1)?,
-                        email: row.get(2)?,
-                        algorithm: row.get(3)?,
-                        created_at: row.get(4)?,
-                        expires_at: row.get(5)?,
-                        trust_level: row.get(6)?,
-                        is_own_key: row.get(7)?,
-                        is_revoked: row.get::<_, i32>(8)? != 0,
-                        pgp_data: row.get(9)?,
-                    })
-                },
+                record_from_row,
             )
             .optional()?;
         Ok(record)
     }
 
+    /// Find the keys that a fingerprint or key ID belongs to, whether it
+    /// names the primary key or one of its subkeys. Several keys can carry
+    /// the same subkey, so callers must handle more than one owner.
+    ///
+    /// This is the lookup for issuers of signatures and recipients of
+    /// encrypted messages, which usually name a subkey.
+    pub fn find_by_key_handle(&self, handle: &str) -> Result<Vec<KeyRecord>> {
+        let handle = normalize_handle(handle);
+        if handle.is_empty() || !handle.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(Vec::new());
+        }
+        // A key ID is the last 16 digits of a v4 fingerprint but the first
+        // 16 of a v6 one, as in `key_id`
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS} FROM keys
+             WHERE keys.fingerprint = ?1
+                OR (length(?1) = 16 AND (
+                    (length(keys.fingerprint) = 40 AND substr(keys.fingerprint, 25) = ?1)
+                    OR (length(keys.fingerprint) = 64 AND substr(keys.fingerprint, 1, 16) = ?1)
+                ))
+                OR keys.fingerprint IN (
+                    SELECT primary_fingerprint FROM subkeys
+                    WHERE fingerprint = ?1 OR key_id = ?1
+                )
+             ORDER BY keys.fingerprint"
+        ))?;
+        let records = stmt
+            .query_map(params![handle], record_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// All User IDs of a key as `(name, email)` pairs, primary first.
+    pub fn user_ids(&self, fingerprint: &str) -> Result<Vec<(Option<String>, Option<String>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, email FROM user_ids WHERE fingerprint = ?1 ORDER BY position")?;
+        let user_ids = stmt
+            .query_map(params![fingerprint], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(user_ids)
+    }
+
     /// List all key records.
     pub fn list_all(&self) -> Result<Vec<KeyRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT fingerprint, name, email, algorithm, created_at, expires_at, trust_level, is_own_key, is_revoked, pgp_data
-             FROM keys ORDER BY is_own_key DESC, name ASC",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS} FROM keys ORDER BY is_own_key DESC, name ASC"
+        ))?;
         let records = stmt
-            .query_map([], |row| {
-                Ok(KeyRecord {
-                    fingerprint: row.get(0)?,
-                    name: row.get(1)?,
-                    email: row.get(2)?,
-                    algorithm: row.get(3)?,
-                    created_at: row.get(4)?,
-                    expires_at: row.get(5)?,
-                    trust_level: row.get(6)?,
-                    is_own_key: row.get(7)?,
-                    is_revoked: row.get::<_, i32>(8)? != 0,
-                    pgp_data: row.get(9)?,
-                })
-            })?
+            .query_map([], record_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(records)
     }
 
-    /// Search keys by name or email (case-insensitive partial match).
+    /// Search keys by name, email, comment, notes or fingerprint, across
+    /// every User ID and every subkey fingerprint or key ID.
+    ///
+    /// Words match as prefixes of indexed words; fingerprints and key IDs
+    /// match anywhere. Results are ranked as described in
+    /// [`KeyStorage::search_page`]. No fuzzy matching is done, so a query
+    /// that names one key is not silently taken to mean a similar one.
     pub fn search(&self, query: &str) -> Result<Vec<KeyRecord>> {
+        self.ranked_matches(query, false)?
+            .iter()
+            .filter_map(|fingerprint| self.get(fingerprint).transpose())
+            .collect()
+    }
+
+    /// Search like [`KeyStorage::search`], also fuzzily matching words with
+    /// typos, and return the `limit` results after the first `offset`.
+    ///
+    /// Results are ranked by, in order: an exact match of the query with one
+    /// of the key's email addresses; full-text over fuzzy matches; own keys,
+    /// then verified keys; most recently used; and full-text relevance.
+    pub fn search_page(&self, query: &str, offset: usize, limit: usize) -> Result<SearchPage> {
+        let matches = self.ranked_matches(query, true)?;
+        let keys = matches
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|fingerprint| self.get(fingerprint).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(SearchPage {
+            keys,
+            total: matches.len(),
+        })
+    }
+
+    /// Fingerprints of the keys matching a query, best first.
+    fn ranked_matches(&self, query: &str, fuzzy: bool) -> Result<Vec<String>> {
+        let (query, tags) = search::parse_filters(query);
+        let query = query.as_str();
+        let tagged = if tags.is_empty() {
+            None
+        } else {
+            Some(self.keys_with_tags(&tags)?)
+        };
+
+        // Full-text and fingerprint matches, with their BM25 relevance
+        // (lower is better; 0 for fingerprint-only matches)
+        let mut relevance: HashMap<String, f64> = HashMap::new();
+        if let Some(fts_query) = search::fts_query(query) {
+            let mut stmt = self.conn.prepare(
+                "SELECT fingerprint, bm25(key_search, 0.0, 10.0, 10.0, 2.0, 1.0)
+                 FROM key_search WHERE key_search MATCH ?1",
+            )?;
+            for row in stmt.query_map(params![fts_query], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (fingerprint, rank) = row?;
+                relevance.insert(fingerprint, rank);
+            }
+        }
         let pattern = format!("%{query}%");
+        let hex_pattern = format!("%{}%", normalize_handle(query));
         let mut stmt = self.conn.prepare(
-            "SELECT fingerprint, name, email, algorithm, created_at, expires_at, trust_level, is_own_key, is_revoked, pgp_data
-             FROM keys
+            "SELECT fingerprint FROM keys
              WHERE name LIKE ?1 COLLATE NOCASE
                 OR email LIKE ?1 COLLATE NOCASE
-                OR fingerprint LIKE ?1 COLLATE NOCASE
-             ORDER BY is_own_key DESC, name ASC",
+                OR petname LIKE ?1 COLLATE NOCASE
+                OR fingerprint LIKE ?2
+                OR fingerprint IN (
+                    SELECT fingerprint FROM user_ids
+                    WHERE name LIKE ?1 COLLATE NOCASE OR email LIKE ?1 COLLATE NOCASE
+                )
+                OR fingerprint IN (
+                    SELECT primary_fingerprint FROM subkeys WHERE fingerprint LIKE ?2
+                )",
         )?;
-        let records = stmt
-            .query_map(params![pattern], |row| {
-                Ok(KeyRecord {
-                    fingerprint: row.get(0)?,
-                    name: row.get(1)?,
-                    email: row.get(2)?,
-                    algorithm: row.get(3)?,
-                    created_at: row.get(4)?,
-                    expires_at: row.get(5)?,
-                    trust_level: row.get(6)?,
-                    is_own_key: row.get(7)?,
-                    is_revoked: row.get::<_, i32>(8)? != 0,
-                    pgp_data: row.get(9)?,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-        Ok(records)
+        for fingerprint in stmt.query_map(params![pattern, hex_pattern], |row| row.get(0))? {
+            relevance.entry(fingerprint?).or_insert(0.0);
+        }
+
+        let mut fuzzy_matches = HashSet::new();
+        let query_words = search::words(query);
+        if fuzzy && !query_words.is_empty() {
+            let mut stmt = self.conn.prepare(
+                "SELECT fingerprint, names || ' ' || emails || ' ' || comments || ' ' || notes
+                 FROM key_search",
+            )?;
+            for row in stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })? {
+                let (fingerprint, text) = row?;
+                if !relevance.contains_key(&fingerprint)
+                    && search::fuzzy_matches(&query_words, &search::words(&text))
+                {
+                    fuzzy_matches.insert(fingerprint);
+                }
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT fingerprint FROM keys WHERE email = ?1 COLLATE NOCASE
+             UNION SELECT fingerprint FROM user_ids WHERE email = ?1 COLLATE NOCASE",
+        )?;
+        let exact_email = stmt
+            .query_map(params![query], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<String>, _>>()?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT fingerprint, is_own_key, trust_level, last_used_at, name FROM keys")?;
+        let mut candidates = Vec::new();
+        for row in stmt.query_map([], |row| {
+            Ok(Candidate {
+                fingerprint: row.get(0)?,
+                is_own_key: row.get(1)?,
+                is_verified: row.get::<_, i32>(2)? == 2,
+                last_used_at: row.get(3)?,
+                name: row.get(4)?,
+                exact_email: false,
+                fuzzy: false,
+                relevance: 0.0,
+            })
+        })? {
+            let mut candidate = row?;
+            if tagged
+                .as_ref()
+                .is_some_and(|tagged| !tagged.contains(&candidate.fingerprint))
+            {
+                continue;
+            }
+            if let Some(rank) = relevance.get(&candidate.fingerprint) {
+                candidate.relevance = *rank;
+            } else if fuzzy_matches.contains(&candidate.fingerprint) {
+                candidate.fuzzy = true;
+            } else {
+                continue;
+            }
+            candidate.exact_email = exact_email.contains(&candidate.fingerprint);
+            candidates.push(candidate);
+        }
+        candidates.sort_by(Candidate::rank);
+        Ok(candidates.into_iter().map(|c| c.fingerprint).collect())
+    }
+
+    /// Fingerprints of the keys carrying every one of `tags`.
+    fn keys_with_tags(&self, tags: &[String]) -> Result<HashSet<String>> {
+        let placeholders = vec!["?"; tags.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT fingerprint FROM key_tags WHERE tag IN ({placeholders})
+             GROUP BY fingerprint HAVING COUNT(DISTINCT tag) = {}",
+            tags.len()
+        ))?;
+        let fingerprints = stmt
+            .query_map(rusqlite::params_from_iter(tags), |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<_>, _>>()?;
+        Ok(fingerprints)
+    }
+
+    /// Replace a stored key record, including its notes, tags and usage.
+    /// Returns `false` if the fingerprint is not in the database.
+    pub fn update(&self, record: &KeyRecord) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        if delete_record(&tx, &record.fingerprint)? == 0 {
+            return Ok(false);
+        }
+        insert_record(&tx, record)?;
+        tx.commit()?;
+        Ok(true)
     }
 
     /// Delete a key by fingerprint.
     pub fn delete(&self, fingerprint: &str) -> Result<bool> {
-        let rows = self.conn.execute(
-            "DELETE FROM keys WHERE fingerprint = ?1",
-            params![fingerprint],
-        )?;
+        let tx = self.conn.unchecked_transaction()?;
+        let rows = delete_record(&tx, fingerprint)?;
+        tx.commit()?;
         Ok(rows > 0)
     }
 
@@ -216,35 +405,720 @@ impl KeyStorage {
         Ok(rows > 0)
     }
 
-    /// Update the PGP data for a key.
+    /// Update the PGP data for a key, re-indexing its User IDs and subkeys.
     pub fn update_pgp_data(&self, fingerprint: &str, pgp_data: &[u8]) -> Result<bool> {
-        let rows = self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        let rows = tx.execute(
             "UPDATE keys SET pgp_data = ?1 WHERE fingerprint = ?2",
             params![pgp_data, fingerprint],
         )?;
+        if rows > 0 {
+            index_cert(&tx, fingerprint, pgp_data)?;
+            index_search(&tx, fingerprint)?;
+        }
+        tx.commit()?;
         Ok(rows > 0)
     }
 
     /// Update the trust level for a key.
     pub fn set_trust(&self, fingerprint: &str, trust_level: TrustLevel) -> Result<bool> {
-        let level: i32 = match trust_level {
-            TrustLevel::Unknown => 0,
-            TrustLevel::Unverified => 1,
-            TrustLevel::Verified => 2,
-        };
         let rows = self.conn.execute(
             "UPDATE keys SET trust_level = ?1 WHERE fingerprint = ?2",
-            params![level, fingerprint],
+            params![trust_value(trust_level), fingerprint],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Set or clear the petname of a key.
+    pub fn set_petname(&self, fingerprint: &str, petname: Option<&str>) -> Result<bool> {
+        let petname = petname.map(str::trim).filter(|p| !p.is_empty());
+        let rows = self.conn.execute(
+            "UPDATE keys SET petname = ?1 WHERE fingerprint = ?2",
+            params![petname, fingerprint],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Set or clear the user's notes on a key. Notes are searchable.
+    pub fn set_notes(&self, fingerprint: &str, notes: Option<&str>) -> Result<bool> {
+        let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+        let tx = self.conn.unchecked_transaction()?;
+        let rows = tx.execute(
+            "UPDATE keys SET notes = ?1 WHERE fingerprint = ?2",
+            params![notes, fingerprint],
+        )?;
+        if rows > 0 {
+            index_search(&tx, fingerprint)?;
+        }
+        tx.commit()?;
+        Ok(rows > 0)
+    }
+
+    /// Replace the tags of a key. Tags are normalized to lowercase.
+    pub fn set_tags(&self, fingerprint: &str, tags: &[String]) -> Result<bool> {
+        let tags = tags
+            .iter()
+            .map(|tag| search::normalize_tag(tag))
+            .collect::<Result<Vec<_>>>()?;
+        if self.get(fingerprint)?.is_none() {
+            return Ok(false);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM key_tags WHERE fingerprint = ?1",
+            params![fingerprint],
+        )?;
+        insert_tags(&tx, fingerprint, &tags)?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Every tag in use, with the number of keys carrying it.
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag, COUNT(*) FROM key_tags GROUP BY tag ORDER BY tag")?;
+        let tags = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    /// Record that a key was just used: bump its use count and last use
+    /// time, so it ranks higher in searches.
+    pub fn record_use(&self, fingerprint: &str) -> Result<bool> {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let rows = self.conn.execute(
+            "UPDATE keys SET last_used_at = ?1, use_count = use_count + 1 WHERE fingerprint = ?2",
+            params![now, fingerprint],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Create a recipient group. Fails if a group with the same name, in any
+    /// case, already exists.
+    pub fn create_group(&self, name: &str, members: &[String]) -> Result<()> {
+        groups::validate_name(name)?;
+        if self.group_id(name)?.is_some() {
+            return Err(Error::DuplicateGroup {
+                name: name.to_string(),
+            });
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO recipient_groups (name, created_at) VALUES (?1, ?2)",
+            params![name, chrono::Utc::now().to_rfc3339()],
+        )?;
+        insert_group_members(&tx, tx.last_insert_rowid(), members)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a recipient group by name (case-insensitive).
+    pub fn get_group(&self, name: &str) -> Result<Option<RecipientGroup>> {
+        let group = self
+            .conn
+            .query_row(
+                "SELECT id, name FROM recipient_groups WHERE name = ?1",
+                params![name],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        match group {
+            Some((id, name)) => Ok(Some(RecipientGroup {
+                name,
+                members: self.group_members(id)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// List all recipient groups, by name.
+    pub fn list_groups(&self) -> Result<Vec<RecipientGroup>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name FROM recipient_groups ORDER BY name")?;
+        let groups = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        groups
+            .into_iter()
+            .map(|(id, name)| {
+                Ok(RecipientGroup {
+                    name,
+                    members: self.group_members(id)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Replace the members of a recipient group.
+    pub fn set_group_members(&self, name: &str, members: &[String]) -> Result<bool> {
+        let Some(id) = self.group_id(name)? else {
+            return Ok(false);
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM recipient_group_members WHERE group_id = ?1",
+            params![id],
+        )?;
+        insert_group_members(&tx, id, members)?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Rename a recipient group.
+    pub fn rename_group(&self, name: &str, new_name: &str) -> Result<bool> {
+        groups::validate_name(new_name)?;
+        let Some(id) = self.group_id(name)? else {
+            return Ok(false);
+        };
+        if self.group_id(new_name)?.is_some_and(|other| other != id) {
+            return Err(Error::DuplicateGroup {
+                name: new_name.to_string(),
+            });
+        }
+        self.conn.execute(
+            "UPDATE recipient_groups SET name = ?1 WHERE id = ?2",
+            params![new_name, id],
+        )?;
+        Ok(true)
+    }
+
+    /// Delete a recipient group. Member keys are not affected.
+    pub fn delete_group(&self, name: &str) -> Result<bool> {
+        let Some(id) = self.group_id(name)? else {
+            return Ok(false);
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM recipient_group_members WHERE group_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM recipient_groups WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn group_id(&self, name: &str) -> Result<Option<i64>> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT id FROM recipient_groups WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    fn group_members(&self, id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT fingerprint FROM recipient_group_members WHERE group_id = ?1
+             ORDER BY fingerprint",
+        )?;
+        let members = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(members)
+    }
+
+    /// Append an entry to the audit log.
+    pub fn append_audit(&self, fingerprint: &str, action: &AuditAction) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.conn.execute(
+            "INSERT INTO audit_log (timestamp, fingerprint, action, detail)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![now, fingerprint, action.kind(), action.detail()],
+        )?;
+        Ok(())
+    }
+
+    /// Audit log entries matching `query`, newest first. Entries written by
+    /// a newer version that this one does not understand are left out.
+    pub fn audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let limit = query
+            .limit
+            .map_or(-1, |limit| i64::try_from(limit).unwrap_or(i64::MAX));
+        let mut stmt = self.conn.prepare(
+            "SELECT id, timestamp, fingerprint, action, detail FROM audit_log
+             WHERE (?1 IS NULL OR fingerprint = ?1)
+               AND (?2 IS NULL OR action = ?2)
+               AND (?3 IS NULL OR timestamp >= ?3)
+             ORDER BY id DESC
+             LIMIT ?4",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![query.fingerprint, query.kind, query.since, limit],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                },
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, timestamp, fingerprint, kind, detail)| {
+                Some(AuditEntry {
+                    id,
+                    timestamp,
+                    fingerprint,
+                    action: AuditAction::from_parts(&kind, detail.as_deref())?,
+                })
+            })
+            .collect())
+    }
+
+    /// Delete every audit log entry, overwriting the freed space in the
+    /// database file. Returns the number of entries removed.
+    pub fn clear_audit_log(&self) -> Result<usize> {
+        self.conn.pragma_update(None, "secure_delete", true)?;
+        Ok(self.conn.execute("DELETE FROM audit_log", [])?)
+    }
+
+    /// Put a copy of a key in the trash, keeping its notes, tags and
+    /// usage. A key already in the trash under the same fingerprint is
+    /// replaced. Removing the key from wherever it was kept is up to the
+    /// caller.
+    pub fn trash(&self, record: &KeyRecord) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let tags = (!record.metadata.tags.is_empty()).then(|| record.metadata.tags.join(","));
+        self.conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO trashed_keys ({TRASH_COLUMNS}, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"
+            ),
+            params![
+                record.fingerprint,
+                record.name,
+                record.email,
+                record.algorithm,
+                record.created_at,
+                record.expires_at,
+                record.trust_level,
+                record.is_own_key,
+                record.is_revoked,
+                record.pgp_data,
+                record.metadata.petname,
+                record.metadata.notes,
+                tags,
+                record.metadata.last_used_at,
+                record.metadata.use_count,
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every key in the trash, most recently deleted first.
+    pub fn list_trash(&self) -> Result<Vec<TrashedKey>> {
+        let retention_days = self.trash_retention_days()?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TRASH_COLUMNS}, deleted_at FROM trashed_keys
+             ORDER BY deleted_at DESC, fingerprint"
+        ))?;
+        let keys = stmt
+            .query_map([], |row| trashed_from_row(row, retention_days))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(keys)
+    }
+
+    /// Get a key in the trash by fingerprint.
+    pub fn get_trashed(&self, fingerprint: &str) -> Result<Option<TrashedKey>> {
+        let retention_days = self.trash_retention_days()?;
+        let key = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {TRASH_COLUMNS}, deleted_at FROM trashed_keys WHERE fingerprint = ?1"
+                ),
+                params![fingerprint],
+                |row| trashed_from_row(row, retention_days),
+            )
+            .optional()?;
+        Ok(key)
+    }
+
+    /// Take a key out of the trash once it is back in the keyring.
+    pub fn remove_trashed(&self, fingerprint: &str) -> Result<bool> {
+        let rows = self.conn.execute(
+            "DELETE FROM trashed_keys WHERE fingerprint = ?1",
+            params![fingerprint],
         )?;
         Ok(rows > 0)
     }
+
+    /// Permanently delete a key from the trash, overwriting the freed space
+    /// in the database file.
+    pub fn purge(&self, fingerprint: &str) -> Result<bool> {
+        self.conn.pragma_update(None, "secure_delete", true)?;
+        let rows = self.conn.execute(
+            "DELETE FROM trashed_keys WHERE fingerprint = ?1",
+            params![fingerprint],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Fingerprints of the trashed keys due to be purged at `now`.
+    pub fn expired_trash(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<String>> {
+        let retention_days = self.trash_retention_days()?;
+        let mut stmt = self
+            .conn
+            .prepare("SELECT fingerprint, deleted_at FROM trashed_keys")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter(|(_, deleted_at)| trash::is_expired(deleted_at, retention_days, now))
+            .map(|(fingerprint, _)| fingerprint)
+            .collect())
+    }
+
+    /// Days a deleted key stays in the trash before it is purged; 0 keeps
+    /// deleted keys until purged by hand.
+    pub fn trash_retention_days(&self) -> Result<u32> {
+        Ok(self
+            .setting(TRASH_RETENTION_SETTING)?
+            .and_then(|days| days.parse().ok())
+            .unwrap_or(trash::DEFAULT_RETENTION_DAYS))
+    }
+
+    /// Change how many days deleted keys stay in the trash.
+    pub fn set_trash_retention_days(&self, days: u32) -> Result<()> {
+        self.set_setting(TRASH_RETENTION_SETTING, &days.to_string())
+    }
+
+    pub(crate) fn setting(&self, name: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM keyring_settings WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    pub(crate) fn set_setting(&self, name: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO keyring_settings (name, value) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+            params![name, value],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn remove_setting(&self, name: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM keyring_settings WHERE name = ?1",
+            params![name],
+        )?;
+        Ok(())
+    }
+}
+
+const TRASH_RETENTION_SETTING: &str = "trash_retention_days";
+
+/// Columns of `trashed_keys` holding a [`KeyRecord`], in the order
+/// [`record_from_row`] reads them; tags are stored comma-separated.
+const TRASH_COLUMNS: &str = "fingerprint, name, email, algorithm, created_at, expires_at, \
+    trust_level, is_own_key, is_revoked, pgp_data, petname, notes, tags, last_used_at, use_count";
+
+fn trashed_from_row(row: &Row<'_>, retention_days: u32) -> rusqlite::Result<TrashedKey> {
+    let deleted_at: String = row.get(15)?;
+    Ok(TrashedKey {
+        record: record_from_row(row)?,
+        purge_at: trash::purge_at(&deleted_at, retention_days).map(|t| t.to_rfc3339()),
+        deleted_at,
+    })
+}
+
+impl CertStore for KeyStorage {
+    fn insert(&self, record: &KeyRecord) -> Result<()> {
+        KeyStorage::insert(self, record)
+    }
+
+    fn get(&self, fingerprint: &str) -> Result<Option<KeyRecord>> {
+        KeyStorage::get(self, fingerprint)
+    }
+
+    fn list(&self) -> Result<Vec<KeyRecord>> {
+        self.list_all()
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<KeyRecord>> {
+        KeyStorage::search(self, query)
+    }
+
+    fn update(&self, record: &KeyRecord) -> Result<bool> {
+        KeyStorage::update(self, record)
+    }
+
+    fn delete(&self, fingerprint: &str) -> Result<bool> {
+        KeyStorage::delete(self, fingerprint)
+    }
+
+    fn search_page(&self, query: &str, offset: usize, limit: usize) -> Result<SearchPage> {
+        KeyStorage::search_page(self, query, offset, limit)
+    }
+
+    fn find_by_key_handle(&self, handle: &str) -> Result<Vec<KeyRecord>> {
+        KeyStorage::find_by_key_handle(self, handle)
+    }
+
+    fn user_ids(&self, fingerprint: &str) -> Result<Vec<(Option<String>, Option<String>)>> {
+        KeyStorage::user_ids(self, fingerprint)
+    }
+}
+
+/// The value stored in the `trust_level` column for a trust level.
+pub(crate) fn trust_value(trust_level: TrustLevel) -> i32 {
+    match trust_level {
+        TrustLevel::Unknown => 0,
+        TrustLevel::Unverified => 1,
+        TrustLevel::Verified => 2,
+    }
+}
+
+/// Insert a key record with its tags, User IDs, subkeys and search entry.
+fn insert_record(conn: &Connection, record: &KeyRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO keys (fingerprint, name, email, algorithm, created_at, expires_at, trust_level, is_own_key, is_revoked, pgp_data,
+                           petname, notes, last_used_at, use_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            record.fingerprint,
+            record.name,
+            record.email,
+            record.algorithm,
+            record.created_at,
+            record.expires_at,
+            record.trust_level,
+            record.is_own_key,
+            record.is_revoked,
+            record.pgp_data,
+            record.metadata.petname,
+            record.metadata.notes,
+            record.metadata.last_used_at,
+            record.metadata.use_count,
+        ],
+    )?;
+    insert_tags(conn, &record.fingerprint, &record.metadata.tags)?;
+    index_cert(conn, &record.fingerprint, &record.pgp_data)?;
+    index_search(conn, &record.fingerprint)?;
+    Ok(())
+}
+
+/// Delete a key and everything indexed from it. Returns the number of keys
+/// removed.
+fn delete_record(conn: &Connection, fingerprint: &str) -> Result<usize> {
+    let rows = conn.execute(
+        "DELETE FROM keys WHERE fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    conn.execute(
+        "DELETE FROM user_ids WHERE fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    conn.execute(
+        "DELETE FROM subkeys WHERE primary_fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    conn.execute(
+        "DELETE FROM key_tags WHERE fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    index_search(conn, fingerprint)?;
+    Ok(rows)
+}
+
+fn insert_tags(conn: &Connection, fingerprint: &str, tags: &[String]) -> Result<()> {
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO key_tags (fingerprint, tag) VALUES (?1, ?2)",
+            params![fingerprint, tag],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_group_members(conn: &Connection, group_id: i64, members: &[String]) -> Result<()> {
+    for fingerprint in members {
+        conn.execute(
+            "INSERT OR IGNORE INTO recipient_group_members (group_id, fingerprint) VALUES (?1, ?2)",
+            params![group_id, fingerprint],
+        )?;
+    }
+    Ok(())
+}
+
+/// A key matching a search, with what it is ranked by.
+struct Candidate {
+    fingerprint: String,
+    exact_email: bool,
+    fuzzy: bool,
+    is_own_key: bool,
+    is_verified: bool,
+    last_used_at: Option<String>,
+    relevance: f64,
+    name: Option<String>,
+}
+
+impl Candidate {
+    fn rank(a: &Self, b: &Self) -> Ordering {
+        b.exact_email
+            .cmp(&a.exact_email)
+            .then(a.fuzzy.cmp(&b.fuzzy))
+            .then(b.is_own_key.cmp(&a.is_own_key))
+            .then(b.is_verified.cmp(&a.is_verified))
+            // ISO 8601 timestamps sort chronologically; never used sorts last
+            .then(b.last_used_at.cmp(&a.last_used_at))
+            .then(a.relevance.total_cmp(&b.relevance))
+            .then(a.name.cmp(&b.name))
+    }
+}
+
+/// Replace the `user_ids` and `subkeys` rows of a key with those of its
+/// certificate.
+///
+/// Key data that cannot be parsed leaves the key without indexed User IDs
+/// or subkeys; it is still found by its primary name, email and fingerprint.
+fn index_cert(conn: &Connection, fingerprint: &str, pgp_data: &[u8]) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM user_ids WHERE fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    conn.execute(
+        "DELETE FROM subkeys WHERE primary_fingerprint = ?1",
+        params![fingerprint],
+    )?;
+
+    let info = match SequoiaEngine::new().inspect_key(pgp_data) {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::debug!("not indexing User IDs and subkeys of {fingerprint}: {e}");
+            return Ok(());
+        }
+    };
+
+    for (position, uid) in info.user_ids.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO user_ids (fingerprint, position, name, email) VALUES (?1, ?2, ?3, ?4)",
+            params![fingerprint, position as i64, uid.name, uid.email],
+        )?;
+    }
+    for subkey in &info.subkeys {
+        let subkey_fingerprint = subkey.fingerprint.to_uppercase();
+        let capabilities = subkey
+            .capabilities
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        conn.execute(
+            "INSERT OR REPLACE INTO subkeys
+                (primary_fingerprint, fingerprint, key_id, algorithm, created_at, expires_at, capabilities, is_revoked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                fingerprint,
+                subkey_fingerprint,
+                key_id(&subkey_fingerprint),
+                subkey.algorithm,
+                subkey.created_at,
+                subkey.expires_at,
+                capabilities,
+                subkey.is_revoked,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Rebuild the full-text search row of a key from its stored User IDs and
+/// notes, or drop it if the key no longer exists.
+fn index_search(conn: &Connection, fingerprint: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM key_search WHERE fingerprint = ?1",
+        params![fingerprint],
+    )?;
+    let Some((name, email, notes)) = conn
+        .query_row(
+            "SELECT name, email, notes FROM keys WHERE fingerprint = ?1",
+            params![fingerprint],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )
+        .optional()?
+    else {
+        return Ok(());
+    };
+
+    let mut user_ids = vec![(name, email)];
+    let mut stmt =
+        conn.prepare("SELECT name, email FROM user_ids WHERE fingerprint = ?1 ORDER BY position")?;
+    for uid in stmt.query_map(params![fingerprint], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        user_ids.push(uid?);
+    }
+
+    let mut names = Vec::new();
+    let mut emails = Vec::new();
+    let mut comments = Vec::new();
+    for (name, email) in &user_ids {
+        if let Some(name) = name {
+            let (name, comment) = search::split_comment(name);
+            push_unique(&mut names, name);
+            if let Some(comment) = comment {
+                push_unique(&mut comments, comment);
+            }
+        }
+        if let Some(email) = email {
+            push_unique(&mut emails, email);
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO key_search (fingerprint, names, emails, comments, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            fingerprint,
+            names.join("\n"),
+            emails.join("\n"),
+            comments.join("\n"),
+            notes.unwrap_or_default(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn push_unique<'a>(list: &mut Vec<&'a str>, value: &'a str) {
+    if !value.is_empty() && !list.contains(&value) {
+        list.push(value);
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
-    fn make_record(fingerprint: &str, name: &str, email: &str) -> KeyRecord {
+    /// Builds a record with placeholder key material for store tests.
+    pub(crate) fn make_record(fingerprint: &str, name: &str, email: &str) -> KeyRecord {
         KeyRecord {
             fingerprint: fingerprint.to_string(),
             name: Some(name.to_string()),
@@ -256,9 +1130,47 @@ mod tests {
             is_own_key: false,
             is_revoked: false,
             pgp_data: b"fake-pgp-data".to_vec(),
+            metadata: KeyMetadata::default(),
         }
     }
 
+    #[test]
+    fn test_find_by_key_id() {
+        let storage = KeyStorage::open_in_memory().unwrap();
+        let v4 = "0123456789ABCDEF0123456789ABCDEF01234567";
+        let v6 = format!("{}{}", "FEDCBA9876543210", "AB".repeat(24));
+        storage
+            .insert(&make_record(v4, "Four", "v4@example.com"))
+            .unwrap();
+        storage
+            .insert(&make_record(&v6, "Six", "v6@example.com"))
+            .unwrap();
+
+        let found = |handle: &str| {
+            storage
+                .find_by_key_handle(handle)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.fingerprint)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found(key_id(v4)), [v4]);
+        assert_eq!(found("0x89abcdef01234567"), [v4]);
+        assert_eq!(found(key_id(&v6)), [v6.clone()]);
+        // The tail of a v6 fingerprint is not its key ID
+        assert!(found(&v6[48..]).is_empty());
+        // LIKE wildcards are not patterns
+        assert!(found("%").is_empty());
+        assert!(found("________________").is_empty());
+
+        // A colliding key ID names every key it belongs to
+        let twin = "FFFFFFFFFFFFFFFFFFFFFFFF89ABCDEF01234567";
+        storage
+            .insert(&make_record(twin, "Twin", "twin@example.com"))
+            .unwrap();
+        assert_eq!(found(key_id(v4)), [v4, twin]);
+    }
+
     #[test]
     fn test_insert_and_get() {
         let storage = KeyStorage::open_in_memory().unwrap();
@@ -285,6 +1197,165 @@ mod tests {
         assert_eq!(results[0].fingerprint, "AAAA");
     }
 
+    #[test]
+    fn test_search_ranking_and_pages() {
+        let storage = KeyStorage::open_in_memory().unwrap();
+        let mut own = make_record("AAAA", "Jonas Own", "jonas@example.com");
+        own.is_own_key = true;
+        let mut verified = make_record("BBBB", "Jonathan Byers", "jbyers@example.com");
+        verified.trust_level = 2;
+        for record in [
+            own,
+            verified,
+            make_record("CCCC", "Jon Snow", "jon@winterfell.example"),
+            make_record("DDDD", "Jonah Hill", "jonah@example.com"),
+            make_record("EEEE", "Carol (work laptop)", "carol@example.com"),
+        ] {
+            storage.insert(&record).unwrap();
+        }
+        let fingerprints = |keys: &[KeyRecord]| {
+            keys.iter()
+                .map(|k| k.fingerprint.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        // Own, then verified, then recently used keys come first
+        storage.record_use("DDDD").unwrap();
+        let results = storage.search("jon").unwrap();
+        assert_eq!(fingerprints(&results), "AAAA,BBBB,DDDD,CCCC");
+
+        // An exact email match beats everything
+        let results = storage.search("JON@winterfell.example").unwrap();
+        assert_eq!(fingerprints(&results), "CCCC");
+        let page = storage
+            .search_page("jon@winterfell.example", 0, 10)
+            .unwrap();
+        assert_eq!(page.keys[0].fingerprint, "CCCC");
+
+        // Pages slice the ranked list
+        let page = storage.search_page("jon", 1, 2).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(fingerprints(&page.keys), "BBBB,DDDD");
+
+        // Typos are only forgiven by the paginated search
+        assert!(storage.search("snwo").unwrap().is_empty());
+        let page = storage.search_page("snwo", 0, 10).unwrap();
+        assert_eq!(fingerprints(&page.keys), "CCCC");
+
+        // Comments and notes are searchable
+        assert_eq!(fingerprints(&storage.search("laptop").unwrap()), "EEEE");
+        assert!(storage.search("vendor").unwrap().is_empty());
+        assert!(storage.set_notes("EEEE", Some("Vendor contact")).unwrap());
+        assert_eq!(
+            storage
+                .get("EEEE")
+                .unwrap()
+                .unwrap()
+                .metadata
+                .notes
+                .as_deref(),
+            Some("Vendor contact")
+        );
+        assert_eq!(fingerprints(&storage.search("vendor").unwrap()), "EEEE");
+
+        // Deleted keys leave the index
+        storage.delete("EEEE").unwrap();
+        assert!(storage.search("vendor").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_key_metadata_and_tag_filters() {
+        let storage = KeyStorage::open_in_memory().unwrap();
+        storage
+            .insert(&make_record("AAAA", "Alice Acme", "alice@acme.example"))
+            .unwrap();
+        storage
+            .insert(&make_record("BBBB", "Bob Builder", "bob@builder.example"))
+            .unwrap();
+        storage
+            .insert(&make_record("CCCC", "Carol Acme", "carol@acme.example"))
+            .unwrap();
+
+        assert!(storage.set_petname("AAAA", Some(" Ali ")).unwrap());
+        assert!(
+            storage
+                .set_tags("AAAA", &["Vendor".into(), "eu".into()])
+                .unwrap()
+        );
+        assert!(storage.set_tags("BBBB", &["vendor".into()]).unwrap());
+        assert!(storage.set_tags("CCCC", &["bad tag".into()]).is_err());
+        assert!(!storage.set_tags("ZZZZ", &["vendor".into()]).unwrap());
+        storage.record_use("AAAA").unwrap();
+        storage.record_use("AAAA").unwrap();
+
+        let alice = storage.get("AAAA").unwrap().unwrap().metadata;
+        assert_eq!(alice.petname.as_deref(), Some("Ali"));
+        assert_eq!(alice.tags, ["eu", "vendor"]);
+        assert_eq!(alice.use_count, 2);
+        assert!(alice.last_used_at.is_some());
+        assert_eq!(
+            storage.get("CCCC").unwrap().unwrap().metadata,
+            KeyMetadata::default()
+        );
+        assert_eq!(
+            storage.list_tags().unwrap(),
+            [("eu".to_string(), 1), ("vendor".to_string(), 2)]
+        );
+
+        let fingerprints = |query: &str| {
+            storage
+                .search(query)
+                .unwrap()
+                .into_iter()
+                .map(|k| k.fingerprint)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fingerprints("tag:vendor"), ["AAAA", "BBBB"]);
+        assert_eq!(fingerprints("tag:vendor acme"), ["AAAA"]);
+        assert_eq!(fingerprints("tag:vendor tag:EU"), ["AAAA"]);
+        assert!(fingerprints("tag:nope").is_empty());
+        assert_eq!(fingerprints("ali"), ["AAAA"]);
+
+        // Tags go with the key
+        storage.delete("AAAA").unwrap();
+        assert_eq!(storage.list_tags().unwrap(), [("vendor".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_audit_log_is_append_only() {
+        let storage = KeyStorage::open_in_memory().unwrap();
+        storage
+            .append_audit("AAAA1111", &AuditAction::Deleted)
+            .unwrap();
+
+        let tampered = storage
+            .conn
+            .execute("UPDATE audit_log SET action = 'revoked'", []);
+        assert!(tampered.is_err());
+
+        let entries = storage.audit_log(&AuditQuery::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::Deleted);
+        assert!(
+            storage
+                .audit_log(&AuditQuery {
+                    fingerprint: Some("BBBB2222".into()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(storage.clear_audit_log().unwrap(), 1);
+        assert!(
+            storage
+                .audit_log(&AuditQuery::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_delete() {
         let storage = KeyStorage::open_in_memory().unwrap();
@@ -294,4 +1365,44 @@ mod tests {
         assert!(storage.delete("AAAA").unwrap());
         assert!(storage.get("AAAA").unwrap().is_none());
     }
+
+    #[test]
+    fn test_trash_keeps_metadata_until_purged() {
+        let storage = KeyStorage::open_in_memory().unwrap();
+        storage
+            .insert(&make_record("AAAA", "Alice", "alice@example.com"))
+            .unwrap();
+        storage
+            .set_tags("AAAA", &["eu".to_string(), "vendor".to_string()])
+            .unwrap();
+        storage.set_petname("AAAA", Some("Al")).unwrap();
+
+        let record = storage.get("AAAA").unwrap().unwrap();
+        storage.trash(&record).unwrap();
+        storage.trash(&record).unwrap();
+        assert_eq!(storage.list_trash().unwrap().len(), 1);
+        let trashed = storage.get_trashed("AAAA").unwrap().unwrap();
+        assert_eq!(trashed.record.metadata.tags, ["eu", "vendor"]);
+
+        // Nothing expires within the retention period, everything after it
+        let deleted_at = chrono::DateTime::parse_from_rfc3339(&trashed.deleted_at)
+            .unwrap()
+            .to_utc();
+        assert!(storage.expired_trash(deleted_at).unwrap().is_empty());
+        let later = deleted_at + chrono::Duration::days(31);
+        assert_eq!(storage.expired_trash(later).unwrap(), ["AAAA"]);
+        storage.set_trash_retention_days(0).unwrap();
+        assert_eq!(storage.trash_retention_days().unwrap(), 0);
+        assert!(storage.expired_trash(later).unwrap().is_empty());
+        assert_eq!(storage.get_trashed("AAAA").unwrap().unwrap().purge_at, None);
+
+        assert_eq!(trashed.record.metadata.petname.as_deref(), Some("Al"));
+        assert!(storage.remove_trashed("AAAA").unwrap());
+        assert!(!storage.remove_trashed("AAAA").unwrap());
+
+        storage.trash(&record).unwrap();
+        assert!(storage.purge("AAAA").unwrap());
+        assert!(storage.list_trash().unwrap().is_empty());
+        assert!(!storage.purge("AAAA").unwrap());
+    }
 }
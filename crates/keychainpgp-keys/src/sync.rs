@@ -8,6 +8,8 @@ use std::io::{Read, Write};
 
 use serde::{Deserialize, Serialize};
 
+use crate::groups::RecipientGroup;
+
 /// Custom serde: serialize `Vec<u8>` as base64 string, deserialize from either
 /// base64 string (v2) or number array (v1) for backward compatibility.
 mod serde_b64 {
@@ -82,6 +84,9 @@ pub struct KeyBundle {
     pub version: u32,
     /// Each key entry.
     pub keys: Vec<KeyBundleEntry>,
+    /// Recipient groups. Absent from bundles made before groups existed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<RecipientGroup>,
 }
 
 /// A single key entry within a sync bundle.
@@ -618,6 +623,7 @@ mod tests {
                     trust_level: 1,
                 },
             ],
+            groups: Vec::new(),
         };
 
         let json = serde_json::to_string(&bundle).unwrap();
@@ -649,6 +655,23 @@ mod tests {
         let bundle: KeyBundle = serde_json::from_str(v1_json).unwrap();
         assert_eq!(bundle.keys[0].public_key, vec![153, 1, 2, 3]);
         assert!(bundle.keys[0].secret_key.is_none());
+        assert!(bundle.groups.is_empty());
+    }
+
+    #[test]
+    fn test_bundle_groups_round_trip() {
+        let bundle = KeyBundle {
+            version: 2,
+            keys: Vec::new(),
+            groups: vec![RecipientGroup {
+                name: "ops".into(),
+                members: vec!["AABBCCDD".into(), "EEFF0011".into()],
+            }],
+        };
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let restored: KeyBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.groups, bundle.groups);
     }
 
     #[test]
@@ -669,6 +692,7 @@ mod tests {
                     trust_level: 1,
                 },
             ],
+            groups: Vec::new(),
         };
 
         // Serialize -> compress -> decompress -> deserialize
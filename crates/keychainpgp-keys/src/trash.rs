@@ -0,0 +1,69 @@
+//! Recoverable deletion of keys.
+//!
+//! Deleting a key moves it to the trash instead of destroying it: its
+//! record, notes and tags move to the `trashed_keys` table and its secret
+//! key file, still encrypted under the vault key, to `secrets/trash/`. A
+//! trashed key can be restored as it was until it is purged, either by hand
+//! or once it has been in the trash for the retention period. Purging
+//! overwrites the secret key file before removing it.
+
+use crate::storage::KeyRecord;
+
+/// Days a key stays in the trash unless the user changes it.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// A key in the trash.
+#[derive(Debug, Clone)]
+pub struct TrashedKey {
+    /// The key as it was when deleted.
+    pub record: KeyRecord,
+    /// When the key was deleted (RFC 3339, UTC).
+    pub deleted_at: String,
+    /// When the key will be purged, or `None` if the trash is only emptied
+    /// by hand.
+    pub purge_at: Option<String>,
+}
+
+/// When a key deleted at `deleted_at` is due to be purged, keeping keys
+/// for `retention_days`; 0 keeps them until purged by hand.
+pub(crate) fn purge_at(
+    deleted_at: &str,
+    retention_days: u32,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    if retention_days == 0 {
+        return None;
+    }
+    let deleted_at = chrono::DateTime::parse_from_rfc3339(deleted_at).ok()?;
+    Some(deleted_at.to_utc() + chrono::Duration::days(i64::from(retention_days)))
+}
+
+/// Whether a key deleted at `deleted_at` is due to be purged at `now`.
+pub(crate) fn is_expired(
+    deleted_at: &str,
+    retention_days: u32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    purge_at(deleted_at, retention_days).is_some_and(|purge_at| purge_at <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_dates() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-06-30T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            purge_at("2026-06-01T12:00:00Z", 30).map(|t| t.to_rfc3339()),
+            Some("2026-07-01T12:00:00+00:00".to_string())
+        );
+        assert!(!is_expired("2026-06-01T12:00:00Z", 30, now));
+        assert!(is_expired("2026-05-30T00:00:00Z", 30, now));
+
+        // 0 keeps keys until purged by hand
+        assert_eq!(purge_at("2020-01-01T00:00:00Z", 0), None);
+        assert!(!is_expired("2020-01-01T00:00:00Z", 0, now));
+    }
+}
@@ -0,0 +1,27 @@
+-- Key database as created by releases with revocation support
+-- (unversioned, is_revoked added by ALTER TABLE on upgrade).
+CREATE TABLE keys (
+    fingerprint TEXT PRIMARY KEY NOT NULL,
+    name        TEXT,
+    email       TEXT,
+    algorithm   TEXT NOT NULL,
+    created_at  TEXT NOT NULL,
+    expires_at  TEXT,
+    trust_level INTEGER NOT NULL DEFAULT 0,
+    is_own_key  INTEGER NOT NULL DEFAULT 0,
+    pgp_data    BLOB NOT NULL,
+    is_revoked  INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX idx_keys_email ON keys(email);
+CREATE INDEX idx_keys_name  ON keys(name);
+
+INSERT INTO keys VALUES (
+    'A1B2C3D4E5F60718293A4B5C6D7E8F9012345678', 'Alice', 'alice@example.com',
+    'Ed25519', '2025-06-01T12:00:00Z', '2027-06-01T12:00:00Z', 3, 1,
+    CAST('fake-pgp-data-alice' AS BLOB), 0
+);
+INSERT INTO keys VALUES (
+    'B1B2C3D4E5F60718293A4B5C6D7E8F9012345678', 'Bob', 'bob@example.com',
+    'RSA-4096', '2024-01-15T08:30:00Z', NULL, 2, 0,
+    CAST('fake-pgp-data-bob' AS BLOB), 1
+);
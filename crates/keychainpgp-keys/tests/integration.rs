@@ -3,10 +3,13 @@
 //! Tests generate → store → encrypt → decrypt → sign → verify round trips
 //! using a temporary keyring directory.
 
-use keychainpgp_core::types::{KeyGenOptions, UserId};
-use keychainpgp_core::{CryptoEngine, SequoiaEngine};
-use keychainpgp_keys::Keyring;
+use keychainpgp_core::mock::{MockFailure, MockOperation};
+use keychainpgp_core::types::{KeyGenOptions, TrustLevel, UserId};
+use keychainpgp_core::{CryptoEngine, Error as CoreError, MockEngine, SequoiaEngine};
 use keychainpgp_keys::storage::KeyRecord;
+use keychainpgp_keys::{
+    AuditAction, AuditQuery, CertDStore, CertStore, Error, ImportSource, Keyring, MemoryCertStore,
+};
 use secrecy::ExposeSecret;
 
 fn setup() -> (SequoiaEngine, Keyring, tempfile::TempDir) {
@@ -17,7 +20,7 @@ fn setup() -> (SequoiaEngine, Keyring, tempfile::TempDir) {
 }
 
 fn generate_and_store(
-    engine: &SequoiaEngine,
+    engine: &dyn CryptoEngine,
     keyring: &Keyring,
     name: &str,
     email: &str,
@@ -37,6 +40,7 @@ fn generate_and_store(
         is_own_key: true,
         is_revoked: info.is_revoked,
         pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
     };
 
     keyring
@@ -189,6 +193,77 @@ fn test_search_keys() {
     assert!(results.is_empty());
 }
 
+#[test]
+fn test_search_all_user_ids_and_subkeys() {
+    let (engine, keyring, _tmp) = setup();
+    let carol = include_bytes!("fixtures/carol-two-uids.asc");
+    let info = engine.inspect_key(carol).unwrap();
+    let fingerprint = info.fingerprint.0.clone();
+
+    keyring
+        .import_public_key(
+            KeyRecord {
+                fingerprint: fingerprint.clone(),
+                name: info.name().map(str::to_string),
+                email: info.email().map(str::to_string),
+                algorithm: info.algorithm.to_string(),
+                created_at: info.created_at.clone(),
+                expires_at: info.expires_at.clone(),
+                trust_level: 1,
+                is_own_key: false,
+                is_revoked: false,
+                pgp_data: carol.to_vec(),
+                metadata: Default::default(),
+            },
+            ImportSource::File,
+        )
+        .unwrap();
+    generate_and_store(&engine, &keyring, "Bob Smith", "bob@example.com");
+
+    assert_eq!(keyring.user_ids(&fingerprint).unwrap().len(), 2);
+
+    // Both addresses find Carol, whichever one is primary
+    for query in ["carol@example.com", "carol@work.example.org", "Carol Work"] {
+        let results = keyring.search_keys(query).unwrap();
+        assert_eq!(results.len(), 1, "{query}");
+        assert_eq!(results[0].fingerprint, fingerprint, "{query}");
+    }
+
+    // So do the subkey fingerprint and key ID, in any notation
+    for query in [
+        "9F3473226FF23461DA9FFC1AD7030062BC0E196F",
+        "0xd7030062bc0e196f",
+        "D703 0062 BC0E 196F",
+    ] {
+        let results = keyring.search_keys(query).unwrap();
+        assert_eq!(results.len(), 1, "{query}");
+        assert_eq!(results[0].fingerprint, fingerprint, "{query}");
+    }
+
+    // The issuer of a signature made by the signing subkey leads to Carol
+    let signed = include_bytes!("fixtures/carol-signed.asc");
+    let issuers = keychainpgp_core::packets::signature_issuers(signed).unwrap();
+    assert_eq!(issuers[0], "76CA9BA82810B4ABDD572FA4B15C73F0CF89CD0F");
+    for issuer in &issuers {
+        let found = keyring.find_by_key_handle(issuer).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].fingerprint, fingerprint);
+    }
+    let found = keyring.find_by_key_handle(&fingerprint[24..]).unwrap();
+    assert_eq!(found[0].fingerprint, fingerprint);
+    assert!(
+        keyring
+            .find_by_key_handle("0000000000000000")
+            .unwrap()
+            .is_empty()
+    );
+
+    // Deleting the key drops its User IDs and subkeys too
+    assert!(keyring.delete_key(&fingerprint).unwrap());
+    assert!(keyring.user_ids(&fingerprint).unwrap().is_empty());
+    assert!(keyring.search_keys("carol@work").unwrap().is_empty());
+}
+
 #[test]
 fn test_delete_key() {
     let (engine, keyring, _tmp) = setup();
@@ -200,6 +275,509 @@ fn test_delete_key() {
     assert!(keyring.get_key(&fp).unwrap().is_none());
 }
 
+#[test]
+fn test_trash_restore_and_purge() {
+    let (engine, keyring, tmp) = setup();
+
+    let fp = generate_and_store(&engine, &keyring, "Trash Me", "trash@test.com");
+    keyring.set_key_notes(&fp, Some("laptop key")).unwrap();
+    let secret_file = tmp.path().join("secrets/trash").join(format!("{fp}.key"));
+
+    assert!(keyring.delete_key(&fp).unwrap());
+    assert!(keyring.get_key(&fp).unwrap().is_none());
+    assert!(!keyring.has_secret_key(&fp));
+    assert!(secret_file.exists());
+    let trash = keyring.list_trash().unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].record.fingerprint, fp);
+    assert!(trash[0].purge_at.is_some());
+
+    // Restoring brings back the key, its notes and its secret key
+    assert!(keyring.restore_key(&fp).unwrap());
+    let restored = keyring.get_key(&fp).unwrap().unwrap();
+    assert_eq!(restored.metadata.notes.as_deref(), Some("laptop key"));
+    assert!(keyring.get_secret_key(&fp).is_ok());
+    assert!(keyring.list_trash().unwrap().is_empty());
+    assert!(!keyring.restore_key(&fp).unwrap());
+    // A live key cannot be purged
+    assert!(!keyring.purge_key(&fp).unwrap());
+    assert!(keyring.get_secret_key(&fp).is_ok());
+
+    // Purging removes the key and its secret key for good
+    keyring.delete_key(&fp).unwrap();
+    assert!(keyring.purge_key(&fp).unwrap());
+    assert!(!secret_file.exists());
+    assert!(keyring.get_trashed_key(&fp).unwrap().is_none());
+    assert!(!keyring.restore_key(&fp).unwrap());
+
+    let actions: Vec<AuditAction> = keyring
+        .audit_log(&AuditQuery::default())
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.action)
+        .take(4)
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            AuditAction::Purged,
+            AuditAction::Deleted,
+            AuditAction::Restored,
+            AuditAction::Deleted,
+        ]
+    );
+
+    // A key imported again after deletion is not overwritten by a restore
+    let other = generate_and_store(&engine, &keyring, "Twice", "twice@test.com");
+    keyring.delete_key(&other).unwrap();
+    let record = keyring.get_trashed_key(&other).unwrap().unwrap().record;
+    keyring
+        .import_public_key(record, ImportSource::File)
+        .unwrap();
+    assert!(keyring.restore_key(&other).is_err());
+    assert_eq!(keyring.empty_trash().unwrap(), 1);
+    assert!(keyring.get_key(&other).unwrap().is_some());
+}
+
+#[test]
+fn test_cert_d_sharing() {
+    let (engine, mut keyring, tmp) = setup();
+    let cert_d = tmp.path().join("pgp.cert.d");
+
+    // Keys already in the keyring are exported on the first sync
+    let own = generate_and_store(&engine, &keyring, "Shared", "shared@test.com");
+    assert_eq!(keyring.use_cert_d(Some(&cert_d)).unwrap(), cert_d);
+    let sync = keyring.sync_shared_store().unwrap();
+    assert_eq!((sync.imported, sync.exported, sync.updated), (0, 1, 0));
+    let other_tool = CertDStore::open(&cert_d).unwrap();
+    let shared = other_tool.get(&own).unwrap().unwrap();
+    // Only the public key is shared
+    assert!(!engine.inspect_key(&shared.pgp_data).unwrap().has_secret_key);
+
+    // Keys written by another tool are imported
+    let key_pair = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new("Dave", "dave@test.com")))
+        .unwrap();
+    let dave = key_pair.fingerprint.0.clone();
+    let mut record = keyring.get_key(&own).unwrap().unwrap();
+    record.fingerprint = dave.clone();
+    record.pgp_data = key_pair.public_key.clone();
+    other_tool.insert(&record).unwrap();
+    let sync = keyring.sync_shared_store().unwrap();
+    assert_eq!((sync.imported, sync.exported, sync.updated), (1, 0, 0));
+    assert_eq!(
+        keyring.get_key(&dave).unwrap().unwrap().email.as_deref(),
+        Some("dave@test.com")
+    );
+
+    // A certificate updated by another tool refreshes what the keyring
+    // derived from it
+    let key_pair = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new("Frank", "frank@test.com")))
+        .unwrap();
+    let frank = key_pair.fingerprint.0.clone();
+    record.fingerprint = frank.clone();
+    record.name = Some("Stale".into());
+    record.pgp_data = key_pair.public_key.clone();
+    keyring
+        .import_public_key(record, ImportSource::File)
+        .unwrap();
+    let updated = engine
+        .add_designated_revoker(
+            key_pair.secret_key.expose_secret(),
+            None,
+            &keyring.get_key(&dave).unwrap().unwrap().pgp_data,
+        )
+        .unwrap();
+    let mut shared = other_tool.get(&frank).unwrap().unwrap();
+    shared.pgp_data = engine.public_key(&updated).unwrap();
+    assert!(other_tool.update(&shared).unwrap());
+    let sync = keyring.sync_shared_store().unwrap();
+    assert_eq!((sync.imported, sync.exported, sync.updated), (0, 0, 1));
+    let refreshed = keyring.get_key(&frank).unwrap().unwrap();
+    assert_eq!(refreshed.name.as_deref(), Some("Frank"));
+    assert_eq!(
+        engine
+            .inspect_key(&refreshed.pgp_data)
+            .unwrap()
+            .designated_revokers,
+        [dave.clone()]
+    );
+
+    // Later changes are mirrored, and the setting survives reopening
+    let eve = generate_and_store(&engine, &keyring, "Eve", "eve@test.com");
+    assert!(other_tool.get(&eve).unwrap().is_some());
+    drop(keyring);
+
+    let mut keyring = Keyring::open_at(tmp.path()).unwrap();
+    assert_eq!(keyring.cert_d_path().unwrap(), Some(cert_d.clone()));
+    assert!(keyring.shared_store().is_some());
+
+    // Deleting and purging leave other tools' copy alone unless asked to
+    keyring.delete_key(&eve).unwrap();
+    keyring.purge_key(&eve).unwrap();
+    assert!(other_tool.get(&eve).unwrap().is_some());
+    assert!(!keyring.unshare_purged().unwrap());
+    keyring.set_unshare_purged(true).unwrap();
+    keyring.delete_key(&dave).unwrap();
+    assert!(other_tool.get(&dave).unwrap().is_some());
+    keyring.purge_key(&dave).unwrap();
+    assert!(other_tool.get(&dave).unwrap().is_none());
+
+    // Nothing is mirrored while sharing is paused (OPSEC mode)
+    keyring.set_sharing_enabled(false);
+    let hidden = generate_and_store(&engine, &keyring, "Hidden", "hidden@test.com");
+    assert!(other_tool.get(&hidden).unwrap().is_none());
+    keyring.set_sharing_enabled(true);
+
+    keyring.stop_cert_d().unwrap();
+    assert!(keyring.shared_store().is_none());
+    keyring.delete_key(&own).unwrap();
+    assert!(other_tool.get(&own).unwrap().is_some());
+}
+
+#[test]
+fn test_memory_shared_store() {
+    let (engine, mut keyring, _tmp) = setup();
+    keyring.set_shared_store(Some(Box::new(MemoryCertStore::new())));
+
+    let fp = generate_and_store(&engine, &keyring, "Mirror", "mirror@test.com");
+    keyring.set_key_notes(&fp, Some("kept locally")).unwrap();
+    let shared = keyring.shared_store().unwrap().get(&fp).unwrap().unwrap();
+    assert_eq!(shared.email.as_deref(), Some("mirror@test.com"));
+    assert_eq!(
+        keyring
+            .shared_store()
+            .unwrap()
+            .search("mirror")
+            .unwrap()
+            .len(),
+        1
+    );
+
+    keyring.delete_key(&fp).unwrap();
+    assert!(keyring.shared_store().unwrap().get(&fp).unwrap().is_some());
+    keyring.restore_key(&fp).unwrap();
+    assert!(keyring.shared_store().unwrap().get(&fp).unwrap().is_some());
+}
+
+#[test]
+fn test_keys_in_memory() {
+    let (engine, mut keyring, tmp) = setup();
+    keyring.set_shared_store(Some(Box::new(MemoryCertStore::new())));
+    let kept = generate_and_store(&engine, &keyring, "Kept", "kept@test.com");
+
+    keyring.keep_keys_in_memory(true).unwrap();
+    assert!(keyring.keys_in_memory());
+    assert!(keyring.get_key(&kept).unwrap().is_some());
+
+    // Nothing done in memory reaches the database, the shared store or the
+    // secret key files: secret keys are refused and kept by the caller
+    let key_pair = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new(
+            "Session",
+            "session@test.com",
+        )))
+        .unwrap();
+    let session = key_pair.fingerprint.0.clone();
+    let info = engine.inspect_key(&key_pair.public_key).unwrap();
+    let record = KeyRecord {
+        fingerprint: session.clone(),
+        name: info.name().map(String::from),
+        email: info.email().map(String::from),
+        algorithm: info.algorithm.to_string(),
+        created_at: info.created_at,
+        expires_at: info.expires_at,
+        trust_level: 2,
+        is_own_key: true,
+        is_revoked: false,
+        pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
+    };
+    let secret_key = key_pair.secret_key.expose_secret();
+    assert!(matches!(
+        keyring.store_generated_key(record.clone(), secret_key),
+        Err(keychainpgp_keys::Error::MemoryOnly)
+    ));
+    assert!(matches!(
+        keyring.import_secret_key(record.clone(), secret_key, ImportSource::File),
+        Err(keychainpgp_keys::Error::MemoryOnly)
+    ));
+    keyring
+        .import_own_public_key(record, ImportSource::File)
+        .unwrap();
+    assert!(keyring.get_key(&session).unwrap().unwrap().is_own_key);
+    assert!(!keyring.has_secret_key(&session));
+    assert!(
+        keyring
+            .shared_store()
+            .unwrap()
+            .get(&session)
+            .unwrap()
+            .is_none()
+    );
+    keyring.set_key_tags(&kept, &["opsec".to_string()]).unwrap();
+    assert_eq!(keyring.list_tags().unwrap(), [("opsec".to_string(), 1)]);
+    assert!(keyring.delete_key(&kept).unwrap());
+    assert!(keyring.get_key(&kept).unwrap().is_none());
+    assert!(keyring.list_trash().unwrap().is_empty());
+    assert!(matches!(
+        keyring.restore_key(&kept),
+        Err(keychainpgp_keys::Error::MemoryOnly)
+    ));
+    let on_disk = Keyring::open_at(tmp.path()).unwrap();
+    assert!(on_disk.get_key(&session).unwrap().is_none());
+    assert!(on_disk.get_key(&kept).unwrap().is_some());
+    drop(on_disk);
+
+    keyring.keep_keys_in_memory(false).unwrap();
+    assert!(!keyring.keys_in_memory());
+    assert!(keyring.get_key(&session).unwrap().is_none());
+    let kept = keyring.get_key(&kept).unwrap().unwrap();
+    assert!(kept.metadata.tags.is_empty());
+}
+
+#[test]
+fn test_open_with_store() {
+    let tmp = tempfile::tempdir().unwrap();
+    let engine = SequoiaEngine::new();
+    let keyring = Keyring::open_with_store(tmp.path(), Box::new(MemoryCertStore::new())).unwrap();
+
+    let fp = generate_and_store(&engine, &keyring, "Alice", "alice@test.com");
+    keyring.set_trust(&fp, TrustLevel::Unverified).unwrap();
+    keyring
+        .set_key_notes(&fp, Some("  met at FOSDEM "))
+        .unwrap();
+    keyring
+        .set_key_tags(&fp, &["Vendor".to_string(), "eu".to_string()])
+        .unwrap();
+    keyring.record_key_use(&fp).unwrap();
+    let record = keyring.get_key(&fp).unwrap().unwrap();
+    assert_eq!(record.trust_level, 1);
+    assert_eq!(record.metadata.notes.as_deref(), Some("met at FOSDEM"));
+    assert_eq!(record.metadata.tags, ["eu", "vendor"]);
+    assert_eq!(record.metadata.use_count, 1);
+
+    // An invalid tag leaves all of the metadata alone
+    let err = keyring
+        .set_key_metadata(&fp, Some("Al"), None, &["two words".to_string()])
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidTag { .. }));
+    let unchanged = keyring.get_key(&fp).unwrap().unwrap();
+    assert_eq!(unchanged.metadata.petname, None);
+    assert_eq!(unchanged.metadata.notes.as_deref(), Some("met at FOSDEM"));
+    assert_eq!(keyring.search_keys("fosdem").unwrap().len(), 1);
+    assert_eq!(keyring.user_ids(&fp).unwrap().len(), 1);
+    let key_id = keychainpgp_keys::storage::key_id(&fp);
+    assert_eq!(
+        keyring.find_by_key_handle(key_id).unwrap()[0].fingerprint,
+        fp
+    );
+
+    // Deleted keys still go through the trash on disk
+    keyring.delete_key(&fp).unwrap();
+    assert!(keyring.get_key(&fp).unwrap().is_none());
+    assert_eq!(keyring.list_trash().unwrap().len(), 1);
+    keyring.restore_key(&fp).unwrap();
+    assert_eq!(
+        keyring.get_key(&fp).unwrap().unwrap().metadata.tags,
+        ["eu", "vendor"]
+    );
+    assert!(keyring.has_secret_key(&fp));
+
+    // The keyring database holds no public keys
+    let on_disk = Keyring::open_at(tmp.path()).unwrap();
+    assert!(on_disk.list_keys().unwrap().is_empty());
+}
+
+#[test]
+fn test_recipient_groups() {
+    let (engine, keyring, _tmp) = setup();
+    let alice = generate_and_store(&engine, &keyring, "Alice", "alice@ops.example");
+    let bob = generate_and_store(&engine, &keyring, "Bob", "bob@ops.example");
+    let carol = generate_and_store(&engine, &keyring, "Carol", "carol@ops.example");
+
+    keyring
+        .create_group("ops", &[alice.clone(), bob.clone()])
+        .unwrap();
+    assert!(matches!(
+        keyring.create_group("OPS", &[]),
+        Err(keychainpgp_keys::Error::DuplicateGroup { .. })
+    ));
+    assert!(matches!(
+        keyring.create_group("oncall", &["0000".to_string()]),
+        Err(keychainpgp_keys::Error::KeyNotFound { .. })
+    ));
+    assert!(matches!(
+        keyring.create_group("on call", &[]),
+        Err(keychainpgp_keys::Error::InvalidGroupName { .. })
+    ));
+
+    keyring.add_group_members("Ops", &[carol.clone()]).unwrap();
+    let expansion = keyring.expand_group("ops").unwrap();
+    assert_eq!(expansion.members.len(), 3);
+    assert!(expansion.warnings.is_empty());
+
+    // Revoked and deleted members are left out with a warning each
+    keyring.set_revoked(&bob, true).unwrap();
+    keyring.delete_key(&carol).unwrap();
+    let expansion = keyring.expand_group("ops").unwrap();
+    assert_eq!(expansion.members.len(), 1);
+    assert_eq!(expansion.members[0].fingerprint, alice);
+    assert_eq!(expansion.warnings.len(), 2);
+    assert!(expansion.warnings.iter().any(|w| w.fingerprint() == bob));
+    assert!(expansion.warnings.iter().any(|w| w.fingerprint() == carol));
+
+    keyring
+        .remove_group_members("ops", &[carol.clone()])
+        .unwrap();
+    keyring.rename_group("ops", "operations").unwrap();
+    assert!(keyring.get_group("ops").unwrap().is_none());
+    let mut members = vec![alice.clone(), bob.clone()];
+    members.sort();
+    assert_eq!(
+        keyring.get_group("operations").unwrap().unwrap().members,
+        members
+    );
+
+    // Groups from another device merge in, dropping members we don't have
+    // and groups whose name is not valid here
+    let incoming = vec![
+        keychainpgp_keys::groups::RecipientGroup {
+            name: "bad name!".into(),
+            members: vec![alice.clone()],
+        },
+        keychainpgp_keys::groups::RecipientGroup {
+            name: "operations".into(),
+            members: vec![alice.clone(), carol.clone()],
+        },
+        keychainpgp_keys::groups::RecipientGroup {
+            name: "legal".into(),
+            members: vec![bob.clone(), carol.clone()],
+        },
+    ];
+    assert_eq!(keyring.import_groups(&incoming).unwrap(), 1);
+    let groups = keyring.list_groups().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].name, "legal");
+    assert_eq!(groups[0].members, vec![bob.clone()]);
+    assert_eq!(groups[1].members, members);
+
+    assert!(keyring.delete_group("legal").unwrap());
+    assert!(!keyring.delete_group("legal").unwrap());
+    assert!(matches!(
+        keyring.expand_group("legal"),
+        Err(keychainpgp_keys::Error::GroupNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_audit_log() {
+    let (engine, mut keyring, _tmp) = setup();
+    let key_pair = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new("Dana", "dana@example.com")))
+        .unwrap();
+    let info = engine.inspect_key(&key_pair.public_key).unwrap();
+    let fp = key_pair.fingerprint.0.clone();
+    let record = KeyRecord {
+        fingerprint: fp.clone(),
+        name: info.name().map(String::from),
+        email: info.email().map(String::from),
+        algorithm: info.algorithm.to_string(),
+        created_at: info.created_at,
+        expires_at: info.expires_at,
+        trust_level: 1,
+        is_own_key: false,
+        is_revoked: false,
+        pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
+    };
+
+    keyring
+        .import_public_key(record.clone(), ImportSource::Wkd)
+        .unwrap();
+    keyring.set_key_notes(&fp, Some("met at FOSDEM")).unwrap();
+    keyring.set_trust(&fp, TrustLevel::Verified).unwrap();
+    // Setting the same level again is not a change
+    keyring.set_trust(&fp, TrustLevel::Verified).unwrap();
+    keyring
+        .import_secret_key(
+            KeyRecord {
+                is_own_key: true,
+                ..record
+            },
+            key_pair.secret_key.expose_secret(),
+            ImportSource::Sync,
+        )
+        .unwrap();
+    keyring.record_secret_export(&fp, "file").unwrap();
+    keyring.set_revoked(&fp, true).unwrap();
+
+    // The merge kept the user's notes
+    let merged = keyring.get_key(&fp).unwrap().unwrap();
+    assert!(merged.is_own_key);
+    assert_eq!(merged.metadata.notes.as_deref(), Some("met at FOSDEM"));
+
+    keyring.delete_key(&fp).unwrap();
+
+    let actions: Vec<AuditAction> = keyring
+        .audit_log(&AuditQuery::default())
+        .unwrap()
+        .into_iter()
+        .map(|entry| {
+            assert_eq!(entry.fingerprint, fp);
+            entry.action
+        })
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            AuditAction::Deleted,
+            AuditAction::Revoked,
+            AuditAction::SecretKeyExported {
+                destination: "file".into()
+            },
+            AuditAction::Merged {
+                source: ImportSource::Sync
+            },
+            AuditAction::TrustChanged { from: 1, to: 2 },
+            AuditAction::Imported {
+                source: ImportSource::Wkd
+            },
+        ]
+    );
+
+    let imports = keyring
+        .audit_log(&AuditQuery {
+            kind: Some("imported".into()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(imports.len(), 1);
+    let latest = keyring
+        .audit_log(&AuditQuery {
+            limit: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(latest.len(), 2);
+    assert_eq!(latest[0].action, AuditAction::Deleted);
+
+    // With auditing off (OPSEC mode) nothing is recorded, and the log can be wiped
+    keyring.set_audit_enabled(false);
+    let eve = generate_and_store(&engine, &keyring, "Eve", "eve@example.com");
+    keyring.set_trust(&eve, TrustLevel::Unknown).unwrap();
+    keyring.delete_key(&eve).unwrap();
+    assert_eq!(keyring.audit_log(&AuditQuery::default()).unwrap().len(), 6);
+    assert_eq!(keyring.clear_audit_log().unwrap(), 6);
+    assert!(
+        keyring
+            .audit_log(&AuditQuery::default())
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[test]
 fn test_import_public_key() {
     let (engine, keyring, _tmp) = setup();
@@ -221,9 +799,12 @@ fn test_import_public_key() {
         is_own_key: false,
         is_revoked: info.is_revoked,
         pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
     };
 
-    keyring.import_public_key(record).unwrap();
+    keyring
+        .import_public_key(record, ImportSource::File)
+        .unwrap();
 
     let keys = keyring.list_keys().unwrap();
     assert_eq!(keys.len(), 1);
@@ -234,6 +815,71 @@ fn test_import_public_key() {
     assert!(!keyring.has_secret_key(&key_pair.fingerprint.0));
 }
 
+#[test]
+fn test_designated_revocation_checked_against_revoker() {
+    let (engine, keyring, _tmp) = setup();
+    let security = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new(
+            "Security",
+            "security@test.com",
+        )))
+        .unwrap();
+    let staff = engine
+        .generate_key_pair(
+            KeyGenOptions::new(UserId::new("Staff", "staff@test.com"))
+                .with_revoker(security.public_key.clone()),
+        )
+        .unwrap();
+    let revoked = engine
+        .revoke_as_designated_revoker(&staff.public_key, security.secret_key.expose_secret(), None)
+        .unwrap();
+    let record_for = |pgp_data: &[u8]| {
+        let info = engine.inspect_key(pgp_data).unwrap();
+        KeyRecord {
+            fingerprint: info.fingerprint.0.clone(),
+            name: info.name().map(String::from),
+            email: info.email().map(String::from),
+            algorithm: info.algorithm.to_string(),
+            created_at: info.created_at,
+            expires_at: info.expires_at,
+            trust_level: 1,
+            is_own_key: false,
+            is_revoked: info.is_revoked,
+            pgp_data: pgp_data.to_vec(),
+            metadata: Default::default(),
+        }
+    };
+
+    // Without the revoker's key, the revocation cannot be checked
+    keyring
+        .import_public_key(record_for(&revoked), ImportSource::File)
+        .unwrap();
+    assert!(
+        !keyring
+            .get_key(&staff.fingerprint.0)
+            .unwrap()
+            .unwrap()
+            .is_revoked
+    );
+
+    // Once the revoker's key is known, an update is checked against it
+    keyring
+        .import_public_key(record_for(&security.public_key), ImportSource::File)
+        .unwrap();
+    assert!(
+        keyring
+            .update_pgp_data(&staff.fingerprint.0, &revoked)
+            .unwrap()
+    );
+    assert!(
+        keyring
+            .get_key(&staff.fingerprint.0)
+            .unwrap()
+            .unwrap()
+            .is_revoked
+    );
+}
+
 #[test]
 fn test_inspect_key_metadata() {
     let engine = SequoiaEngine::new();
@@ -352,3 +998,106 @@ fn test_full_pipeline_generate_store_encrypt_sign_verify_decrypt() {
         .unwrap();
     assert_eq!(decrypted, message);
 }
+
+#[test]
+fn test_keyring_with_subkeys_only() {
+    let (engine, keyring, _tmp) = setup();
+
+    let key_pair = engine
+        .generate_key_pair(KeyGenOptions::new(UserId::new("Laptop", "laptop@test.com")))
+        .unwrap();
+    let subkeys_only = engine
+        .export_secret_subkeys(key_pair.secret_key.expose_secret())
+        .unwrap();
+
+    // Import the subkey-only export as an own key
+    let info = engine.inspect_key(&subkeys_only).unwrap();
+    assert!(info.has_secret_key);
+    assert!(info.primary_key_offline);
+    let record = KeyRecord {
+        fingerprint: info.fingerprint.0.clone(),
+        name: info.name().map(String::from),
+        email: info.email().map(String::from),
+        algorithm: info.algorithm.to_string(),
+        created_at: info.created_at.clone(),
+        expires_at: info.expires_at.clone(),
+        trust_level: 2,
+        is_own_key: true,
+        is_revoked: info.is_revoked,
+        pgp_data: engine.public_key(&subkeys_only).unwrap(),
+        metadata: Default::default(),
+    };
+    keyring.store_generated_key(record, &subkeys_only).unwrap();
+
+    let record = keyring.get_key(&info.fingerprint.0).unwrap().unwrap();
+    let secret_key = keyring.get_secret_key(&info.fingerprint.0).unwrap();
+
+    let ciphertext = engine
+        .encrypt(b"day to day", &[record.pgp_data.clone()])
+        .unwrap();
+    let decrypted = engine
+        .decrypt(&ciphertext, secret_key.expose_secret(), None)
+        .unwrap();
+    assert_eq!(decrypted, b"day to day");
+
+    let signed = engine
+        .sign(b"signed on the laptop", secret_key.expose_secret(), None)
+        .unwrap();
+    assert!(engine.verify(&signed, &record.pgp_data).unwrap().valid);
+}
+
+#[test]
+fn test_mock_engine_with_keyring() {
+    let tmp = tempfile::tempdir().unwrap();
+    let keyring = Keyring::open_at(tmp.path()).unwrap();
+    let engine = MockEngine::new();
+
+    let fp = generate_and_store(&engine, &keyring, "Alice", "alice@test.com");
+    let record = keyring.get_key(&fp).unwrap().unwrap();
+    let ciphertext = engine.encrypt(b"hello", &[record.pgp_data]).unwrap();
+    let secret_key = keyring.get_secret_key(&fp).unwrap();
+
+    engine.fail_next(MockOperation::Decrypt, MockFailure::BadPassphrase);
+    assert!(matches!(
+        engine.decrypt(&ciphertext, secret_key.expose_secret(), None),
+        Err(CoreError::BadPassphrase)
+    ));
+    let decrypted = engine
+        .decrypt(&ciphertext, secret_key.expose_secret(), None)
+        .unwrap();
+    assert_eq!(decrypted, b"hello");
+    assert_eq!(engine.call_count(MockOperation::Decrypt), 2);
+}
+
+#[test]
+fn test_locked_keyring_gates_secret_keys() {
+    let tmp = tempfile::tempdir().unwrap();
+    let keyring = Keyring::open_at(tmp.path()).unwrap();
+    let engine = MockEngine::new();
+    let fp = generate_and_store(&engine, &keyring, "Alice", "alice@test.com");
+
+    // Without a master password there is nothing to lock.
+    keyring.lock();
+    assert!(!keyring.is_locked());
+    assert!(keyring.get_secret_key(&fp).is_ok());
+
+    keyring.set_master_password(b"master").unwrap();
+    keyring.lock();
+    assert!(keyring.is_locked());
+    assert!(matches!(
+        keyring.get_secret_key(&fp),
+        Err(keychainpgp_keys::Error::Locked)
+    ));
+    assert!(matches!(
+        keyring.unlock(b"wrong"),
+        Err(keychainpgp_keys::Error::BadMasterPassword)
+    ));
+    keyring.unlock(b"master").unwrap();
+    assert!(keyring.get_secret_key(&fp).is_ok());
+
+    // A fresh process starts locked.
+    drop(keyring);
+    let keyring = Keyring::open_at(tmp.path()).unwrap();
+    assert!(keyring.is_locked());
+    assert!(keyring.has_secret_key(&fp));
+}
@@ -0,0 +1,113 @@
+//! Schema migration tests against databases from every released schema.
+
+use std::path::Path;
+
+use keychainpgp_keys::Error;
+use keychainpgp_keys::migrations::SCHEMA_VERSION;
+use keychainpgp_keys::storage::KeyStorage;
+use rusqlite::Connection;
+
+const ALICE: &str = "A1B2C3D4E5F60718293A4B5C6D7E8F9012345678";
+const BOB: &str = "B1B2C3D4E5F60718293A4B5C6D7E8F9012345678";
+
+/// SQL dumps of the key database as written by each released schema, with
+/// whether Bob's key is marked revoked in it.
+const FIXTURES: &[(&str, &str, bool)] = &[
+    (
+        "schema-0-initial",
+        include_str!("fixtures/schema-0-initial.sql"),
+        false,
+    ),
+    (
+        "schema-0-revocation",
+        include_str!("fixtures/schema-0-revocation.sql"),
+        true,
+    ),
+];
+
+fn create_fixture(path: &Path, sql: &str) {
+    let conn = Connection::open(path).unwrap();
+    conn.execute_batch(sql).unwrap();
+}
+
+#[test]
+fn test_released_schemas_migrate() {
+    for (name, sql, bob_revoked) in FIXTURES {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keyring.db");
+        create_fixture(&path, sql);
+
+        let storage = KeyStorage::open(&path).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION, "{name}");
+
+        let alice = storage.get(ALICE).unwrap().expect(name);
+        assert_eq!(alice.name.as_deref(), Some("Alice"), "{name}");
+        assert_eq!(alice.trust_level, 3, "{name}");
+        assert!(alice.is_own_key, "{name}");
+        assert!(!alice.is_revoked, "{name}");
+        assert_eq!(alice.pgp_data, b"fake-pgp-data-alice", "{name}");
+
+        let bob = storage.get(BOB).unwrap().expect(name);
+        assert_eq!(bob.expires_at, None, "{name}");
+        assert_eq!(bob.is_revoked, *bob_revoked, "{name}");
+
+        // The migrated schema accepts writes and survives a reopen
+        assert!(storage.set_revoked(ALICE, true).unwrap(), "{name}");
+        drop(storage);
+        let storage = KeyStorage::open(&path).unwrap();
+        assert!(storage.get(ALICE).unwrap().unwrap().is_revoked, "{name}");
+        assert_eq!(storage.list_all().unwrap().len(), 2, "{name}");
+    }
+}
+
+#[test]
+fn test_newer_database_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keyring.db");
+    drop(KeyStorage::open(&path).unwrap());
+
+    let conn = Connection::open(&path).unwrap();
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+        .unwrap();
+    drop(conn);
+
+    let err = KeyStorage::open(&path)
+        .err()
+        .expect("newer schema must fail");
+    assert!(matches!(err, Error::SchemaTooNew { .. }), "{err}");
+    assert!(err.to_string().contains("newer version"));
+}
+
+#[test]
+fn test_existing_keys_are_indexed_on_upgrade() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keyring.db");
+    create_fixture(&path, include_str!("fixtures/schema-0-revocation.sql"));
+
+    let carol = include_bytes!("fixtures/carol-two-uids.asc");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute(
+        "INSERT INTO keys (fingerprint, name, email, algorithm, created_at, pgp_data)
+         VALUES ('6C427F2A23D20CDC16A526EC06FA8279696FBBB8', 'Carol Example',
+                 'carol@example.com', 'Ed25519', '2026-10-18T00:00:00Z', ?1)",
+        [&carol[..]],
+    )
+    .unwrap();
+    drop(conn);
+
+    let storage = KeyStorage::open(&path).unwrap();
+    let found = storage.search("carol@work.example.org").unwrap();
+    assert_eq!(found.len(), 1);
+    let by_subkey = storage.find_by_key_handle("B15C73F0CF89CD0F").unwrap();
+    assert_eq!(by_subkey.len(), 1);
+    assert_eq!(by_subkey[0].fingerprint, found[0].fingerprint);
+
+    // Keys whose data does not parse are kept, just not indexed
+    assert!(storage.get(ALICE).unwrap().is_some());
+    assert!(storage.user_ids(ALICE).unwrap().is_empty());
+
+    // Every key is in the full-text index, which fuzzy matching reads
+    let page = storage.search_page("alcie", 0, 10).unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.keys[0].fingerprint, ALICE);
+}
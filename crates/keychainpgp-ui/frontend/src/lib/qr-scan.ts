@@ -80,7 +80,7 @@ export async function importScannedContent(content: string): Promise<KeyInfo> {
   if (content.startsWith("KCPGP:")) {
     throw new Error(m.error_sync_qr_wrong_context());
   }
-  return await importKey(content);
+  return await importKey(content, "qr");
 }
 
 /** Parsed KCPGP QR part. */
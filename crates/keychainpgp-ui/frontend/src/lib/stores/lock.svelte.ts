@@ -0,0 +1,64 @@
+import {
+  getLockStatus,
+  lockKeyring,
+  recordActivity,
+  setMasterPassword,
+  unlockKeyring,
+} from "$lib/tauri";
+
+/** Minimum delay between two activity reports to the backend. */
+const ACTIVITY_THROTTLE_MS = 30_000;
+
+let hasMasterPassword: boolean = $state(false);
+let locked: boolean = $state(false);
+let lastReport = 0;
+
+export const lockStore = {
+  get hasMasterPassword() {
+    return hasMasterPassword;
+  },
+  get locked() {
+    return locked;
+  },
+
+  async refresh() {
+    try {
+      const status = await getLockStatus();
+      hasMasterPassword = status.has_master_password;
+      locked = status.locked;
+    } catch (e) {
+      console.error("Failed to read lock status:", e);
+    }
+  },
+
+  /** Called when the backend reports that the keyring was locked. */
+  markLocked() {
+    locked = true;
+  },
+
+  async lock() {
+    await lockKeyring();
+    await this.refresh();
+  },
+
+  /** Throws the backend error message if the password is wrong. */
+  async unlock(password: string) {
+    await unlockKeyring(password);
+    locked = false;
+    lastReport = Date.now();
+  },
+
+  async setMasterPassword(current: string | null, newPassword: string) {
+    await setMasterPassword(current, newPassword);
+    await this.refresh();
+  },
+
+  /** Postpone the idle auto-lock, at most once per throttle window. */
+  reportActivity() {
+    if (locked || !hasMasterPassword) return;
+    const now = Date.now();
+    if (now - lastReport < ACTIVITY_THROTTLE_MS) return;
+    lastReport = now;
+    recordActivity().catch(() => {});
+  },
+};
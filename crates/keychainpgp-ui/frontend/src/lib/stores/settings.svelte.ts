@@ -21,6 +21,9 @@ const defaults: Settings = {
   opsec_mode: false,
   opsec_window_title: "Notes",
   opsec_view_timeout_secs: 30,
+  opsec_hidden_recipients: true,
+  auto_lock_minutes: 15,
+  lock_on_suspend: true,
 };
 
 let settings: Settings = $state({ ...defaults });
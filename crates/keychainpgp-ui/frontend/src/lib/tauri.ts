@@ -15,6 +15,27 @@ export interface KeyInfo {
   trust_level: number;
   is_own_key: boolean;
   is_revoked: boolean;
+  primary_key_offline: boolean;
+  petname: string | null;
+  notes: string | null;
+  tags: string[];
+  last_used_at: string | null;
+  use_count: number;
+}
+
+export interface KeySearchPage {
+  keys: KeyInfo[];
+  total: number;
+}
+
+export interface GroupInfo {
+  name: string;
+  members: string[];
+}
+
+export interface GroupExpansion {
+  fingerprints: string[];
+  warnings: string[];
 }
 
 export interface DiscoveryResult extends KeyInfo {
@@ -26,6 +47,13 @@ export interface EncryptResult {
   message: string;
 }
 
+export interface RecipientStatusInfo {
+  fingerprint: string;
+  status: string; // "usable" | "expired" | "revoked" | "no_encryption_subkey" | "policy_rejected" | "invalid"
+  detail: string | null;
+  message: string;
+}
+
 export interface DecryptResult {
   success: boolean;
   plaintext: string;
@@ -44,6 +72,10 @@ export interface VerifyResultInfo {
   signer_fingerprint: string | null;
   trust_level: number;
   message: string;
+  signer_user_id: string | null;
+  expires_at: string | null;
+  notations: { name: string; value: string }[];
+  intended_recipients: string[];
 }
 
 export interface SubkeyInfoDto {
@@ -53,6 +85,7 @@ export interface SubkeyInfoDto {
   expires_at: string | null;
   capabilities: string[];
   is_revoked: boolean;
+  s2k: string | null;
 }
 
 export interface UserIdDto {
@@ -72,6 +105,11 @@ export interface KeyDetailedInfo {
   is_revoked: boolean;
   user_ids: UserIdDto[];
   subkeys: SubkeyInfoDto[];
+  adsks: string[];
+  designated_revokers: string[];
+  possibly_revoked: boolean;
+  primary_key_offline: boolean;
+  s2k: string | null;
 }
 
 export interface Settings {
@@ -94,6 +132,9 @@ export interface Settings {
   opsec_mode: boolean;
   opsec_window_title: string;
   opsec_view_timeout_secs: number;
+  opsec_hidden_recipients: boolean;
+  auto_lock_minutes: number;
+  lock_on_suspend: boolean;
 }
 
 // --- Crypto ---
@@ -111,6 +152,10 @@ export async function encryptText(
   return invoke("encrypt_text", { text, recipientFingerprints });
 }
 
+export async function checkRecipients(fingerprints: string[]): Promise<RecipientStatusInfo[]> {
+  return invoke("check_recipients", { fingerprints });
+}
+
 export async function decryptClipboard(passphrase?: string): Promise<DecryptResult> {
   return invoke("decrypt_clipboard", { passphrase: passphrase ?? null });
 }
@@ -141,11 +186,33 @@ export async function generateKeyPair(
   name: string,
   email: string,
   passphrase?: string,
+  mnemonic?: string,
 ): Promise<KeyInfo> {
   return invoke("generate_key_pair", {
     name,
     email,
     passphrase: passphrase ?? null,
+    mnemonic: mnemonic ?? null,
+  });
+}
+
+export async function generateRecoveryPhrase(): Promise<string> {
+  return invoke("generate_recovery_phrase");
+}
+
+export async function restoreKeyFromMnemonic(
+  name: string,
+  email: string,
+  mnemonic: string,
+  createdAt: number,
+  passphrase?: string,
+): Promise<KeyInfo> {
+  return invoke("restore_key_from_mnemonic", {
+    name,
+    email,
+    mnemonic,
+    createdAt,
+    passphrase: passphrase ?? null,
   });
 }
 
@@ -153,8 +220,10 @@ export async function listKeys(): Promise<KeyInfo[]> {
   return invoke("list_keys");
 }
 
-export async function importKey(keyData: string): Promise<KeyInfo> {
-  return invoke("import_key", { keyData });
+export type ImportSource = "file" | "clipboard" | "qr" | "wkd" | "keyserver" | "sync" | "backup" | "shared";
+
+export async function importKey(keyData: string, source?: ImportSource): Promise<KeyInfo> {
+  return invoke("import_key", { keyData, source });
 }
 
 export async function exportKey(fingerprint: string): Promise<string> {
@@ -165,6 +234,10 @@ export async function exportPrivateKey(fingerprint: string, path: string): Promi
   return invoke("export_private_key", { fingerprint, path });
 }
 
+export async function exportSecretSubkeys(fingerprint: string, path: string): Promise<void> {
+  return invoke("export_secret_subkeys", { fingerprint, path });
+}
+
 export async function publishRevocationCert(fingerprint: string): Promise<string> {
   return invoke("publish_revocation_cert", { fingerprint });
 }
@@ -173,8 +246,62 @@ export async function deleteKey(fingerprint: string): Promise<boolean> {
   return invoke("delete_key", { fingerprint });
 }
 
-export async function searchKeys(query: string): Promise<KeyInfo[]> {
-  return invoke("search_keys", { query });
+export interface TrashedKeyInfo {
+  key: KeyInfo;
+  deleted_at: string;
+  purge_at: string | null;
+}
+
+export async function listTrash(): Promise<TrashedKeyInfo[]> {
+  return invoke("list_trash");
+}
+
+export async function restoreKey(fingerprint: string): Promise<boolean> {
+  return invoke("restore_key", { fingerprint });
+}
+
+export async function purgeKey(fingerprint: string): Promise<boolean> {
+  return invoke("purge_key", { fingerprint });
+}
+
+export async function emptyTrash(): Promise<number> {
+  return invoke("empty_trash");
+}
+
+export async function getTrashRetention(): Promise<number> {
+  return invoke("get_trash_retention");
+}
+
+export async function setTrashRetention(days: number): Promise<void> {
+  return invoke("set_trash_retention", { days });
+}
+
+export async function searchKeys(
+  query: string,
+  offset = 0,
+  limit = 50,
+): Promise<KeySearchPage> {
+  return invoke("search_keys", { query, offset, limit });
+}
+
+export async function listGroups(): Promise<GroupInfo[]> {
+  return invoke("list_groups");
+}
+
+export async function saveGroup(name: string, members: string[]): Promise<void> {
+  return invoke("save_group", { name, members });
+}
+
+export async function renameGroup(name: string, newName: string): Promise<void> {
+  return invoke("rename_group", { name, newName });
+}
+
+export async function deleteGroup(name: string): Promise<boolean> {
+  return invoke("delete_group", { name });
+}
+
+export async function expandGroup(name: string): Promise<GroupExpansion> {
+  return invoke("expand_group", { name });
 }
 
 export async function inspectKey(fingerprint: string): Promise<KeyInfo> {
@@ -185,10 +312,77 @@ export async function setKeyTrust(fingerprint: string, trustLevel: number): Prom
   return invoke("set_key_trust", { fingerprint, trustLevel });
 }
 
+export async function updateKeyMetadata(
+  fingerprint: string,
+  petname: string | null,
+  notes: string | null,
+  tags: string[],
+): Promise<KeyInfo> {
+  return invoke("update_key_metadata", { fingerprint, petname, notes, tags });
+}
+
+export async function listKeyTags(): Promise<[string, number][]> {
+  return invoke("list_key_tags");
+}
+
+export interface AuditEntry {
+  id: number;
+  timestamp: string;
+  fingerprint: string;
+  action:
+    | { kind: "imported"; source: ImportSource }
+    | { kind: "trust_changed"; from: number; to: number }
+    | { kind: "revoked" }
+    | { kind: "merged"; source: ImportSource }
+    | { kind: "secret_key_exported"; destination: string }
+    | { kind: "deleted" }
+    | { kind: "restored" }
+    | { kind: "purged" };
+}
+
+export async function getAuditLog(
+  fingerprint?: string,
+  kind?: string,
+  since?: string,
+  limit?: number,
+): Promise<AuditEntry[]> {
+  return invoke("get_audit_log", { fingerprint, kind, since, limit });
+}
+
 export async function inspectKeyDetailed(fingerprint: string): Promise<KeyDetailedInfo> {
   return invoke("inspect_key_detailed", { fingerprint });
 }
 
+export async function changeKeyPassphrase(
+  fingerprint: string,
+  oldPassphrase: string | null,
+  newPassphrase: string | null,
+  useArgon2: boolean,
+): Promise<void> {
+  return invoke("change_key_passphrase", {
+    fingerprint,
+    oldPassphrase,
+    newPassphrase,
+    useArgon2,
+  });
+}
+
+export async function addKeyAdsk(
+  fingerprint: string,
+  adskFingerprint: string,
+  passphrase: string | null,
+): Promise<KeyDetailedInfo> {
+  return invoke("add_key_adsk", { fingerprint, adskFingerprint, passphrase });
+}
+
+export async function addKeyRevoker(
+  fingerprint: string,
+  revokerFingerprint: string,
+  passphrase: string | null,
+): Promise<KeyDetailedInfo> {
+  return invoke("add_key_revoker", { fingerprint, revokerFingerprint, passphrase });
+}
+
 export async function clearPassphraseCache(): Promise<void> {
   return invoke("clear_passphrase_cache");
 }
@@ -288,6 +482,36 @@ export async function getOpsecStatus(): Promise<boolean> {
   return invoke("get_opsec_status");
 }
 
+// --- Lock ---
+
+export interface LockStatus {
+  has_master_password: boolean;
+  locked: boolean;
+}
+
+export async function getLockStatus(): Promise<LockStatus> {
+  return invoke("get_lock_status");
+}
+
+export async function lockKeyring(): Promise<void> {
+  return invoke("lock_keyring");
+}
+
+export async function unlockKeyring(password: string): Promise<void> {
+  return invoke("unlock_keyring", { password });
+}
+
+export async function setMasterPassword(
+  current: string | null,
+  newPassword: string,
+): Promise<void> {
+  return invoke("set_master_password", { current, newPassword });
+}
+
+export async function recordActivity(): Promise<void> {
+  return invoke("record_activity");
+}
+
 // --- QR ---
 
 export async function generateQrSvg(data: string): Promise<string> {
@@ -0,0 +1,89 @@
+//! Background auto-lock: locks the keyring after an idle period or when the
+//! system resumes from suspend.
+
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, SystemTime};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::state::AppState;
+
+/// How often the idle and suspend checks run.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Wall-clock time that may pass beyond the monotonic clock between two
+/// checks before we assume the system was suspended.
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Event emitted to the frontend when the keyring gets locked.
+pub const LOCKED_EVENT: &str = "keyring-locked";
+
+/// Start the auto-lock watcher thread.
+pub fn spawn(app: AppHandle) {
+    std::thread::Builder::new()
+        .name("auto-lock".into())
+        .spawn(move || watch(app))
+        .expect("failed to spawn auto-lock thread");
+}
+
+fn watch(app: AppHandle) {
+    let mut last_tick = (Instant::now(), SystemTime::now());
+    loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        let now = (Instant::now(), SystemTime::now());
+        let suspended = was_suspended(last_tick, now);
+        last_tick = now;
+
+        let Some(state) = app.try_state::<AppState>() else {
+            continue;
+        };
+        let reason = if suspended && state.lock_on_suspend.load(Ordering::Relaxed) {
+            "system resumed from suspend"
+        } else if state.idle_timeout_elapsed() {
+            "idle timeout"
+        } else {
+            continue;
+        };
+
+        if state.lock_keyring() {
+            tracing::info!("keyring locked: {reason}");
+            let _ = app.emit(LOCKED_EVENT, ());
+        }
+    }
+}
+
+/// Whether the system slept between two ticks.
+///
+/// The monotonic clock stops while the system is suspended on Linux and
+/// macOS, but the wall clock keeps running, so a suspend shows up as the
+/// wall clock advancing much further than the monotonic one.
+fn was_suspended(before: (Instant, SystemTime), after: (Instant, SystemTime)) -> bool {
+    let monotonic = after.0.saturating_duration_since(before.0);
+    let wall = after.1.duration_since(before.1).unwrap_or_default();
+    wall.saturating_sub(monotonic) > SUSPEND_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suspend_detection() {
+        let start = (Instant::now(), SystemTime::now());
+        let awake = (start.0 + CHECK_INTERVAL, start.1 + CHECK_INTERVAL);
+        assert!(!was_suspended(start, awake));
+
+        let resumed = (
+            start.0 + CHECK_INTERVAL,
+            start.1 + CHECK_INTERVAL + Duration::from_secs(3600),
+        );
+        assert!(was_suspended(start, resumed));
+
+        // A wall clock set backwards is not a suspend
+        let rewound = (
+            start.0 + CHECK_INTERVAL,
+            start.1 - Duration::from_secs(3600),
+        );
+        assert!(!was_suspended(start, rewound));
+    }
+}
@@ -6,6 +6,7 @@ use serde::Serialize;
 use tauri::State;
 
 use keychainpgp_core::CryptoEngine;
+use keychainpgp_core::types::RecipientStatus;
 use secrecy::{ExposeSecret, SecretBox};
 
 use crate::state::AppState;
@@ -28,6 +29,27 @@ pub struct DecryptResult {
     pub message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct RecipientStatusInfo {
+    /// Fingerprint of the recipient key.
+    pub fingerprint: String,
+    /// Machine-readable status: "usable", "expired", "revoked",
+    /// "no_encryption_subkey", "policy_rejected" or "invalid".
+    pub status: String,
+    /// Expiration time (RFC 3339) or rejection reason, depending on the status.
+    pub detail: Option<String>,
+    /// Human-readable description of the status.
+    pub message: String,
+}
+
+/// Best-effort update of a key's usage data. Skipped in OPSEC mode, which
+/// must not leave a record of who the user talked to.
+fn record_use(state: &AppState, keyring: &keychainpgp_keys::Keyring, fingerprint: &str) {
+    if !state.opsec_mode.load(Ordering::SeqCst) {
+        let _ = keyring.record_key_use(fingerprint);
+    }
+}
+
 /// Shared encrypt logic: encrypt plaintext for given recipients, return armored ciphertext.
 fn encrypt_impl(
     state: &AppState,
@@ -45,6 +67,12 @@ fn encrypt_impl(
             .get_key(fp)
             .map_err(|e| format!("Failed to look up key: {e}"))?
             .ok_or_else(|| format!("Key not found: {fp}"))?;
+        if record.is_revoked {
+            return Err(format!(
+                "Cannot encrypt to {fp}: {}",
+                RecipientStatus::Revoked
+            ));
+        }
         recipient_keys.push(record.pgp_data);
     }
 
@@ -55,10 +83,71 @@ fn encrypt_impl(
         .encrypt(plaintext.as_bytes(), &recipient_keys)
         .map_err(|e| format!("Encryption failed: {e}"))?;
 
+    if let Ok(keyring) = state.keyring.lock() {
+        for fp in recipient_fingerprints {
+            record_use(state, &keyring, fp);
+        }
+    }
+
     String::from_utf8(ciphertext)
         .map_err(|_| "Internal error: encrypted output is not valid text".to_string())
 }
 
+/// Check which of the given keys can be encrypted to, and why not.
+#[tauri::command]
+pub fn check_recipients(
+    state: State<'_, AppState>,
+    fingerprints: Vec<String>,
+) -> Result<Vec<RecipientStatusInfo>, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    let mut recipient_keys = Vec::new();
+    let mut revoked = Vec::new();
+    for fp in &fingerprints {
+        let record = keyring
+            .get_key(fp)
+            .map_err(|e| format!("Failed to look up key: {e}"))?
+            .ok_or_else(|| format!("Key not found: {fp}"))?;
+        revoked.push(record.is_revoked);
+        recipient_keys.push(record.pgp_data);
+    }
+
+    drop(keyring);
+
+    let checks = state.engine.check_recipients(&recipient_keys);
+    Ok(fingerprints
+        .into_iter()
+        .zip(checks)
+        .zip(revoked)
+        .map(|((fingerprint, mut check), revoked)| {
+            // Revocations by a designated revoker are only trusted once the
+            // keyring checked them against the revoker's key
+            if revoked {
+                check.status = RecipientStatus::Revoked;
+            }
+            let (status, detail) = match &check.status {
+                RecipientStatus::Usable => ("usable", None),
+                RecipientStatus::Expired { expired_at } => ("expired", Some(expired_at.clone())),
+                RecipientStatus::Revoked => ("revoked", None),
+                RecipientStatus::NoEncryptionSubkey => ("no_encryption_subkey", None),
+                RecipientStatus::PolicyRejected { reason } => {
+                    ("policy_rejected", Some(reason.clone()))
+                }
+                RecipientStatus::Invalid { reason } => ("invalid", Some(reason.clone())),
+            };
+            RecipientStatusInfo {
+                fingerprint,
+                status: status.into(),
+                detail,
+                message: check.status.to_string(),
+            }
+        })
+        .collect())
+}
+
 /// Encrypt the current clipboard content for the given recipients.
 #[cfg(desktop)]
 #[tauri::command]
@@ -129,6 +218,8 @@ fn decrypt_impl(
         return Err("You don't have any private keys. Generate or import a key first.".into());
     }
 
+    // The lock covers the OPSEC keys in memory as well
+    super::lock::ensure_unlocked(&keyring)?;
     let is_opsec = state.opsec_mode.load(Ordering::SeqCst);
 
     for key_record in &own_keys {
@@ -231,6 +322,16 @@ pub struct VerifyResultInfo {
     pub signer_fingerprint: Option<String>,
     pub trust_level: i32,
     pub message: String,
+    pub signer_user_id: Option<String>,
+    pub expires_at: Option<String>,
+    pub notations: Vec<NotationInfo>,
+    pub intended_recipients: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotationInfo {
+    pub name: String,
+    pub value: String,
 }
 
 /// Shared sign logic: sign plaintext, return armored signed text.
@@ -254,6 +355,8 @@ fn sign_impl(
         return Err("You don't have any private keys. Generate or import a key first.".into());
     }
 
+    // The lock covers the OPSEC keys in memory as well
+    super::lock::ensure_unlocked(&keyring)?;
     let is_opsec = state.opsec_mode.load(Ordering::SeqCst);
 
     for key_record in &own_keys {
@@ -314,10 +417,21 @@ fn verify_impl(state: &AppState, signed_text: &str) -> Result<VerifyResultInfo,
         .keyring
         .lock()
         .map_err(|e| format!("Internal error: {e}"))?;
-    let all_keys = keyring
+    let mut all_keys = keyring
         .list_keys()
         .map_err(|e| format!("Failed to list keys: {e}"))?;
 
+    // Try the key that issued the signature (found by primary or subkey
+    // handle) first, then every other key
+    let issuer_keys: Vec<String> =
+        keychainpgp_core::packets::signature_issuers(signed_text.as_bytes())
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|issuer| keyring.find_by_key_handle(issuer).unwrap_or_default())
+            .map(|record| record.fingerprint)
+            .collect();
+    all_keys.sort_by_key(|k| !issuer_keys.contains(&k.fingerprint));
+
     if all_keys.is_empty() {
         return Ok(VerifyResultInfo {
             valid: false,
@@ -326,6 +440,10 @@ fn verify_impl(state: &AppState, signed_text: &str) -> Result<VerifyResultInfo,
             signer_fingerprint: None,
             trust_level: 0,
             message: "No keys in keyring to verify against.".into(),
+            signer_user_id: None,
+            expires_at: None,
+            notations: Vec::new(),
+            intended_recipients: Vec::new(),
         });
     }
 
@@ -335,6 +453,7 @@ fn verify_impl(state: &AppState, signed_text: &str) -> Result<VerifyResultInfo,
             .verify(signed_text.as_bytes(), &key_record.pgp_data)
         {
             Ok(result) if result.valid => {
+                record_use(state, &keyring, &key_record.fingerprint);
                 return Ok(VerifyResultInfo {
                     valid: true,
                     signer_name: key_record.name.clone(),
@@ -345,6 +464,17 @@ fn verify_impl(state: &AppState, signed_text: &str) -> Result<VerifyResultInfo,
                         "Valid signature from {}.",
                         key_record.name.as_deref().unwrap_or("unknown")
                     ),
+                    signer_user_id: result.signer_user_id,
+                    expires_at: result.expires_at,
+                    notations: result
+                        .notations
+                        .into_iter()
+                        .map(|n| NotationInfo {
+                            name: n.name,
+                            value: n.value,
+                        })
+                        .collect(),
+                    intended_recipients: result.intended_recipients,
                 });
             }
             _ => continue,
@@ -359,6 +489,10 @@ fn verify_impl(state: &AppState, signed_text: &str) -> Result<VerifyResultInfo,
         trust_level: 0,
         message: "Signature could not be verified. The signer's key may not be in your keyring."
             .into(),
+        signer_user_id: None,
+        expires_at: None,
+        notations: Vec::new(),
+        intended_recipients: Vec::new(),
     })
 }
 
@@ -474,6 +608,7 @@ mod tests {
             is_own_key: true,
             is_revoked: info.is_revoked,
             pgp_data: key_pair.public_key.clone(),
+            metadata: Default::default(),
         };
 
         let keyring = state.keyring.lock().unwrap();
@@ -0,0 +1,122 @@
+//! Tauri commands for recipient groups.
+
+use keychainpgp_keys::groups::RecipientGroup;
+use serde::Serialize;
+use tauri::State;
+
+use crate::state::AppState;
+
+/// A recipient group as shown in the recipient selector.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupInfo {
+    pub name: String,
+    /// Primary key fingerprints of the members.
+    pub members: Vec<String>,
+}
+
+impl From<RecipientGroup> for GroupInfo {
+    fn from(g: RecipientGroup) -> Self {
+        Self {
+            name: g.name,
+            members: g.members,
+        }
+    }
+}
+
+/// The members of a group that can be encrypted to.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupExpansionInfo {
+    /// Fingerprints of the usable members.
+    pub fingerprints: Vec<String>,
+    /// Why each other member was left out.
+    pub warnings: Vec<String>,
+}
+
+/// List all recipient groups.
+#[tauri::command]
+pub fn list_groups(state: State<'_, AppState>) -> Result<Vec<GroupInfo>, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let groups = keyring
+        .list_groups()
+        .map_err(|e| format!("Failed to list groups: {e}"))?;
+    Ok(groups.into_iter().map(GroupInfo::from).collect())
+}
+
+/// Create a recipient group, or replace the members of an existing one
+/// with the same name.
+#[tauri::command]
+pub fn save_group(
+    state: State<'_, AppState>,
+    name: String,
+    members: Vec<String>,
+) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let name = name.trim();
+    let exists = keyring
+        .get_group(name)
+        .map_err(|e| format!("Failed to save group: {e}"))?
+        .is_some();
+    let result = if exists {
+        keyring.set_group_members(name, &members)
+    } else {
+        keyring.create_group(name, &members)
+    };
+    result.map_err(|e| format!("Failed to save group: {e}"))
+}
+
+/// Rename a recipient group.
+#[tauri::command]
+pub fn rename_group(
+    state: State<'_, AppState>,
+    name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .rename_group(&name, new_name.trim())
+        .map_err(|e| format!("Failed to rename group: {e}"))
+}
+
+/// Delete a recipient group. Member keys are kept.
+#[tauri::command]
+pub fn delete_group(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .delete_group(&name)
+        .map_err(|e| format!("Failed to delete group: {e}"))
+}
+
+/// Expand a group into the fingerprints of its usable members.
+#[tauri::command]
+pub fn expand_group(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<GroupExpansionInfo, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let expansion = keyring
+        .expand_group(&name)
+        .map_err(|e| format!("Failed to expand group: {e}"))?;
+    Ok(GroupExpansionInfo {
+        fingerprints: expansion
+            .members
+            .into_iter()
+            .map(|k| k.fingerprint)
+            .collect(),
+        warnings: expansion.warnings.iter().map(ToString::to_string).collect(),
+    })
+}
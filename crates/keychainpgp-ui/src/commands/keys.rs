@@ -2,16 +2,20 @@
 
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, UNIX_EPOCH};
 
 use serde::Serialize;
 use tauri::{AppHandle, State};
 
-use keychainpgp_core::CryptoEngine;
-use keychainpgp_core::types::{KeyGenOptions, TrustLevel, UserId};
+use keychainpgp_core::types::{
+    Argon2Params, CertInfo, GeneratedKeyPair, KeyGenOptions, S2kMode, TrustLevel, UserId,
+};
+use keychainpgp_core::{CryptoEngine, mnemonic};
 use keychainpgp_keys::network::keyserver::{
     KeyserverMatch, keyserver_fetch, keyserver_search as ks_search, validate_keyserver_url,
 };
 use keychainpgp_keys::storage::KeyRecord;
+use keychainpgp_keys::{AuditEntry, AuditQuery, ImportSource, Keyring};
 use secrecy::{ExposeSecret, SecretBox};
 use tokio::sync::Semaphore;
 
@@ -80,6 +84,14 @@ pub struct KeyInfo {
     pub trust_level: i32,
     pub is_own_key: bool,
     pub is_revoked: bool,
+    /// Whether only the secret subkeys are here and the primary key is
+    /// offline. Filled in by listings; `false` elsewhere.
+    pub primary_key_offline: bool,
+    pub petname: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    pub last_used_at: Option<String>,
+    pub use_count: u32,
 }
 
 /// Key discovery result with source information.
@@ -109,6 +121,12 @@ impl From<KeyRecord> for KeyInfo {
             trust_level: r.trust_level,
             is_own_key: r.is_own_key,
             is_revoked: r.is_revoked,
+            primary_key_offline: false,
+            petname: r.metadata.petname,
+            notes: r.metadata.notes,
+            tags: r.metadata.tags,
+            last_used_at: r.metadata.last_used_at,
+            use_count: r.metadata.use_count,
         }
     }
 }
@@ -120,14 +138,9 @@ pub fn generate_key_pair(
     name: String,
     email: String,
     passphrase: Option<String>,
+    mnemonic: Option<String>,
 ) -> Result<KeyInfo, String> {
-    // Input validation
-    if name.is_empty() || name.len() > 256 {
-        return Err("Name must be between 1 and 256 characters".into());
-    }
-    if email.is_empty() || email.len() > 256 {
-        return Err("Email must be between 1 and 256 characters".into());
-    }
+    validate_user_id(&name, &email)?;
 
     let user_id = UserId::new(&name, &email);
     let mut options = KeyGenOptions::new(user_id);
@@ -136,11 +149,70 @@ pub fn generate_key_pair(
         options = options.with_passphrase(SecretBox::new(Box::new(pass.into_bytes())));
     }
 
+    if let Some(phrase) = mnemonic {
+        options = options.with_mnemonic(SecretBox::new(Box::new(phrase)));
+    }
+
     let key_pair = state
         .engine
         .generate_key_pair(options)
         .map_err(|e| format!("Key generation failed: {e}"))?;
 
+    store_own_key(&state, key_pair, name, email)
+}
+
+/// Create a new recovery phrase to generate a key from.
+#[tauri::command]
+pub fn generate_recovery_phrase() -> String {
+    mnemonic::generate_mnemonic().to_string()
+}
+
+/// Rebuild a key from its recovery phrase and creation time (Unix seconds).
+#[tauri::command]
+pub fn restore_key_from_mnemonic(
+    state: State<'_, AppState>,
+    name: String,
+    email: String,
+    mnemonic: String,
+    created_at: u64,
+    passphrase: Option<String>,
+) -> Result<KeyInfo, String> {
+    validate_user_id(&name, &email)?;
+
+    let phrase = mnemonic::normalize_mnemonic(&mnemonic).map_err(|e| e.to_string())?;
+    let mut options = KeyGenOptions::new(UserId::new(&name, &email))
+        .with_mnemonic(SecretBox::new(Box::new(phrase.to_string())))
+        .with_creation_time(UNIX_EPOCH + Duration::from_secs(created_at));
+
+    if let Some(pass) = passphrase {
+        options = options.with_passphrase(SecretBox::new(Box::new(pass.into_bytes())));
+    }
+
+    let key_pair = state
+        .engine
+        .generate_key_pair(options)
+        .map_err(|e| format!("Key restore failed: {e}"))?;
+
+    store_own_key(&state, key_pair, name, email)
+}
+
+fn validate_user_id(name: &str, email: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 256 {
+        return Err("Name must be between 1 and 256 characters".into());
+    }
+    if email.is_empty() || email.len() > 256 {
+        return Err("Email must be between 1 and 256 characters".into());
+    }
+    Ok(())
+}
+
+/// Store a newly generated key pair as one of the user's own keys.
+fn store_own_key(
+    state: &AppState,
+    key_pair: GeneratedKeyPair,
+    name: String,
+    email: String,
+) -> Result<KeyInfo, String> {
     let info = state
         .engine
         .inspect_key(&key_pair.public_key)
@@ -157,6 +229,7 @@ pub fn generate_key_pair(
         is_own_key: true,
         is_revoked: info.is_revoked,
         pgp_data: key_pair.public_key.clone(),
+        metadata: Default::default(),
     };
 
     let keyring = state
@@ -165,9 +238,10 @@ pub fn generate_key_pair(
         .map_err(|e| format!("Internal error: {e}"))?;
 
     if state.opsec_mode.load(Ordering::SeqCst) {
-        // OPSEC mode: store secret key in RAM only, public key in DB
+        // OPSEC mode: store secret key in RAM only, public key in the
+        // keyring's in-memory store
         keyring
-            .import_public_key(record.clone())
+            .store_generated_public_key(record.clone())
             .map_err(|e| format!("Failed to store key: {e}"))?;
         let mut opsec_keys = state
             .opsec_secret_keys
@@ -195,6 +269,68 @@ pub fn generate_key_pair(
     Ok(KeyInfo::from(record))
 }
 
+/// Import a key with its secret key. In OPSEC mode the secret key stays in
+/// RAM and the keyring, kept in memory as well, only gets the public key.
+pub fn import_own_key(
+    state: &AppState,
+    keyring: &Keyring,
+    record: KeyRecord,
+    secret_key: &[u8],
+    source: ImportSource,
+) -> keychainpgp_keys::Result<()> {
+    if !state.opsec_mode.load(Ordering::SeqCst) {
+        return keyring.import_secret_key(record, secret_key, source);
+    }
+    let fingerprint = record.fingerprint.clone();
+    keyring.import_own_public_key(record, source)?;
+    state
+        .opsec_secret_keys
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(fingerprint, zeroize::Zeroizing::new(secret_key.to_vec()));
+    Ok(())
+}
+
+/// Inspect the secret key of an own key, from the OPSEC store or the
+/// keyring. `None` for other keys or while the secret key is unavailable,
+/// e.g. behind a locked master password.
+fn secret_key_info(
+    state: &AppState,
+    keyring: &Keyring,
+    record: &KeyRecord,
+) -> Result<Option<CertInfo>, String> {
+    if !record.is_own_key {
+        return Ok(None);
+    }
+    let opsec_key = state
+        .opsec_secret_keys
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?
+        .get(&record.fingerprint)
+        .map(|z| (**z).clone());
+    Ok(opsec_key
+        .or_else(|| {
+            keyring
+                .get_secret_key(&record.fingerprint)
+                .ok()
+                .map(|sk| sk.expose_secret().clone())
+        })
+        .and_then(|sk| state.engine.inspect_key(&sk).ok()))
+}
+
+/// [`KeyInfo`] for a key listing, flagging own keys whose primary key is
+/// offline.
+fn listed_key_info(state: &AppState, keyring: &Keyring, record: KeyRecord) -> KeyInfo {
+    let primary_key_offline = secret_key_info(state, keyring, &record)
+        .ok()
+        .flatten()
+        .is_some_and(|info| info.primary_key_offline);
+    KeyInfo {
+        primary_key_offline,
+        ..KeyInfo::from(record)
+    }
+}
+
 /// List all keys in the keyring.
 #[tauri::command]
 pub fn list_keys(state: State<'_, AppState>) -> Result<Vec<KeyInfo>, String> {
@@ -205,12 +341,20 @@ pub fn list_keys(state: State<'_, AppState>) -> Result<Vec<KeyInfo>, String> {
     let keys = keyring
         .list_keys()
         .map_err(|e| format!("Failed to list keys: {e}"))?;
-    Ok(keys.into_iter().map(KeyInfo::from).collect())
+    Ok(keys
+        .into_iter()
+        .map(|record| listed_key_info(&state, &keyring, record))
+        .collect())
 }
 
 /// Import a key from ASCII-armored text.
 #[tauri::command]
-pub fn import_key(state: State<'_, AppState>, key_data: String) -> Result<KeyInfo, String> {
+pub fn import_key(
+    state: State<'_, AppState>,
+    key_data: String,
+    source: Option<ImportSource>,
+) -> Result<KeyInfo, String> {
+    let source = source.unwrap_or(ImportSource::File);
     let cert_info = state
         .engine
         .inspect_key(key_data.as_bytes())
@@ -218,6 +362,10 @@ pub fn import_key(state: State<'_, AppState>, key_data: String) -> Result<KeyInf
 
     let name = cert_info.name().map(String::from);
     let email = cert_info.email().map(String::from);
+    let public_key = state
+        .engine
+        .public_key(key_data.as_bytes())
+        .map_err(|e| format!("Invalid key data: {e}"))?;
 
     let record = KeyRecord {
         fingerprint: cert_info.fingerprint.0.clone(),
@@ -229,7 +377,8 @@ pub fn import_key(state: State<'_, AppState>, key_data: String) -> Result<KeyInf
         trust_level: if cert_info.has_secret_key { 2 } else { 1 },
         is_own_key: cert_info.has_secret_key,
         is_revoked: cert_info.is_revoked,
-        pgp_data: key_data.as_bytes().to_vec(),
+        pgp_data: public_key,
+        metadata: Default::default(),
     };
 
     let keyring = state
@@ -249,26 +398,18 @@ pub fn import_key(state: State<'_, AppState>, key_data: String) -> Result<KeyInf
         ));
     }
 
-    if cert_info.has_secret_key && state.opsec_mode.load(Ordering::SeqCst) {
-        // OPSEC mode: store secret key in RAM only, public key in DB
-        keyring
-            .import_public_key(record.clone())
-            .map_err(|e| format!("Failed to import key: {e}"))?;
-        let mut opsec_keys = state
-            .opsec_secret_keys
-            .lock()
-            .map_err(|e| format!("Internal error: {e}"))?;
-        opsec_keys.insert(
-            record.fingerprint.clone(),
-            zeroize::Zeroizing::new(key_data.as_bytes().to_vec()),
-        );
-    } else if cert_info.has_secret_key {
-        keyring
-            .store_generated_key(record.clone(), key_data.as_bytes())
-            .map_err(|e| format!("Failed to import key: {e}"))?;
+    if cert_info.has_secret_key {
+        import_own_key(
+            &state,
+            &keyring,
+            record.clone(),
+            key_data.as_bytes(),
+            source,
+        )
+        .map_err(|e| format!("Failed to import key: {e}"))?;
     } else {
         keyring
-            .import_public_key(record.clone())
+            .import_public_key(record.clone(), source)
             .map_err(|e| format!("Failed to import key: {e}"))?;
     }
 
@@ -290,29 +431,158 @@ pub fn export_key(state: State<'_, AppState>, fingerprint: String) -> Result<Str
     Ok(String::from_utf8_lossy(&record.pgp_data).into_owned())
 }
 
-/// Delete a key from the keyring.
+/// Move a key to the trash. In OPSEC mode the key is only dropped from the
+/// session's in-memory keys, along with its RAM-only secret key.
 #[tauri::command]
 pub fn delete_key(state: State<'_, AppState>, fingerprint: String) -> Result<bool, String> {
     let keyring = state
         .keyring
         .lock()
         .map_err(|e| format!("Internal error: {e}"))?;
-    keyring
+    let deleted = keyring
         .delete_key(&fingerprint)
-        .map_err(|e| format!("Failed to delete key: {e}"))
+        .map_err(|e| format!("Failed to delete key: {e}"))?;
+    if deleted && state.opsec_mode.load(Ordering::SeqCst) {
+        state
+            .opsec_secret_keys
+            .lock()
+            .map_err(|e| format!("Internal error: {e}"))?
+            .remove(&fingerprint);
+    }
+    Ok(deleted)
 }
 
-/// Search keys by name, email, or fingerprint.
+/// A deleted key waiting in the trash.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashedKeyInfo {
+    pub key: KeyInfo,
+    pub deleted_at: String,
+    /// When the key will be purged, if the trash is emptied automatically.
+    pub purge_at: Option<String>,
+}
+
+/// List the keys in the trash, most recently deleted first.
 #[tauri::command]
-pub fn search_keys(state: State<'_, AppState>, query: String) -> Result<Vec<KeyInfo>, String> {
+pub fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashedKeyInfo>, String> {
     let keyring = state
         .keyring
         .lock()
         .map_err(|e| format!("Internal error: {e}"))?;
-    let keys = keyring
-        .search_keys(&query)
+    let trash = keyring
+        .list_trash()
+        .map_err(|e| format!("Failed to list the trash: {e}"))?;
+    Ok(trash
+        .into_iter()
+        .map(|t| TrashedKeyInfo {
+            key: KeyInfo::from(t.record),
+            deleted_at: t.deleted_at,
+            purge_at: t.purge_at,
+        })
+        .collect())
+}
+
+/// Restore a deleted key from the trash.
+#[tauri::command]
+pub fn restore_key(state: State<'_, AppState>, fingerprint: String) -> Result<bool, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .restore_key(&fingerprint)
+        .map_err(|e| format!("Failed to restore key: {e}"))
+}
+
+/// Permanently delete a key from the trash.
+#[tauri::command]
+pub fn purge_key(state: State<'_, AppState>, fingerprint: String) -> Result<bool, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .purge_key(&fingerprint)
+        .map_err(|e| format!("Failed to purge key: {e}"))
+}
+
+/// Permanently delete every key in the trash.
+#[tauri::command]
+pub fn empty_trash(state: State<'_, AppState>) -> Result<usize, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .empty_trash()
+        .map_err(|e| format!("Failed to empty the trash: {e}"))
+}
+
+/// Days deleted keys stay in the trash (0: until purged by hand).
+#[tauri::command]
+pub fn get_trash_retention(state: State<'_, AppState>) -> Result<u32, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .trash_retention_days()
+        .map_err(|e| format!("Failed to read trash settings: {e}"))
+}
+
+/// Change how long deleted keys stay in the trash, purging keys already
+/// past the new period.
+#[tauri::command]
+pub fn set_trash_retention(state: State<'_, AppState>, days: u32) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .set_trash_retention_days(days)
+        .and_then(|()| keyring.purge_expired_trash())
+        .map(|_| ())
+        .map_err(|e| format!("Failed to change trash settings: {e}"))
+}
+
+/// Page size used when the frontend does not ask for one.
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// One page of ranked key search results.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeySearchPage {
+    pub keys: Vec<KeyInfo>,
+    /// Number of matching keys across all pages.
+    pub total: usize,
+}
+
+/// Search keys by name, petname, email, comment, notes, or fingerprint, best
+/// match first, one page at a time. `tag:NAME` words filter by tag.
+#[tauri::command]
+pub fn search_keys(
+    state: State<'_, AppState>,
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<KeySearchPage, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let page = keyring
+        .search_keys_page(
+            &query,
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
         .map_err(|e| format!("Search failed: {e}"))?;
-    Ok(keys.into_iter().map(KeyInfo::from).collect())
+    Ok(KeySearchPage {
+        keys: page
+            .keys
+            .into_iter()
+            .map(|record| listed_key_info(&state, &keyring, record))
+            .collect(),
+        total: page.total,
+    })
 }
 
 /// Set the trust level of a key.
@@ -337,6 +607,68 @@ pub fn set_key_trust(
         .map_err(|e| format!("Failed to set trust: {e}"))
 }
 
+/// Replace the user's petname, notes and tags on a key.
+#[tauri::command]
+pub fn update_key_metadata(
+    state: State<'_, AppState>,
+    fingerprint: String,
+    petname: Option<String>,
+    notes: Option<String>,
+    tags: Vec<String>,
+) -> Result<KeyInfo, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let updated = keyring
+        .set_key_metadata(&fingerprint, petname.as_deref(), notes.as_deref(), &tags)
+        .map_err(|e| format!("Failed to update key: {e}"))?;
+    if !updated {
+        return Err(format!("Key not found: {fingerprint}"));
+    }
+    let record = keyring
+        .get_key(&fingerprint)
+        .map_err(|e| format!("Failed to look up key: {e}"))?
+        .ok_or_else(|| format!("Key not found: {fingerprint}"))?;
+    Ok(KeyInfo::from(record))
+}
+
+/// List every tag in use, with the number of keys carrying it.
+#[tauri::command]
+pub fn list_key_tags(state: State<'_, AppState>) -> Result<Vec<(String, usize)>, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .list_tags()
+        .map_err(|e| format!("Failed to list tags: {e}"))
+}
+
+/// Read the audit log, newest first, optionally for one key, one kind of
+/// entry or since a given time.
+#[tauri::command]
+pub fn get_audit_log(
+    state: State<'_, AppState>,
+    fingerprint: Option<String>,
+    kind: Option<String>,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .audit_log(&AuditQuery {
+            fingerprint,
+            kind,
+            since,
+            limit,
+        })
+        .map_err(|e| format!("Failed to read audit log: {e}"))
+}
+
 /// Inspect a key and return detailed metadata.
 #[tauri::command]
 pub fn inspect_key(state: State<'_, AppState>, fingerprint: String) -> Result<KeyInfo, String> {
@@ -361,6 +693,8 @@ pub struct SubkeyInfoDto {
     pub expires_at: Option<String>,
     pub capabilities: Vec<String>,
     pub is_revoked: bool,
+    /// S2K protecting this subkey's secret material (own keys only).
+    pub s2k: Option<String>,
 }
 
 /// User ID information returned to the frontend.
@@ -383,6 +717,17 @@ pub struct KeyDetailedInfo {
     pub is_own_key: bool,
     pub user_ids: Vec<UserIdDto>,
     pub subkeys: Vec<SubkeyInfoDto>,
+    /// Fingerprints of the additional decryption subkeys (ADSKs).
+    pub adsks: Vec<String>,
+    /// Fingerprints of the keys allowed to revoke this key.
+    pub designated_revokers: Vec<String>,
+    /// Whether a designated revoker claims to have revoked this key, but the
+    /// claim could not be checked against the revoker's key.
+    pub possibly_revoked: bool,
+    /// Whether only the secret subkeys are here and the primary key is offline.
+    pub primary_key_offline: bool,
+    /// S2K protecting the primary secret key (own keys only).
+    pub s2k: Option<String>,
 }
 
 /// Inspect a key and return detailed metadata including subkeys and all User IDs.
@@ -405,6 +750,9 @@ pub fn inspect_key_detailed(
         .inspect_key(&record.pgp_data)
         .map_err(|e| format!("Failed to inspect key: {e}"))?;
 
+    // Secret key protection is only visible on the secret key itself
+    let secret_info = secret_key_info(&state, &keyring, &record)?;
+
     let user_ids = cert_info
         .user_ids
         .iter()
@@ -414,6 +762,8 @@ pub fn inspect_key_detailed(
         })
         .collect();
 
+    let adsks = cert_info.adsks().into_iter().map(String::from).collect();
+
     let subkeys = cert_info
         .subkeys
         .iter()
@@ -424,6 +774,15 @@ pub fn inspect_key_detailed(
             expires_at: sk.expires_at.clone(),
             capabilities: sk.capabilities.iter().map(|c| c.to_string()).collect(),
             is_revoked: sk.is_revoked,
+            s2k: secret_info
+                .as_ref()
+                .and_then(|info| {
+                    info.subkeys
+                        .iter()
+                        .find(|s| s.fingerprint == sk.fingerprint)
+                })
+                .and_then(|s| s.s2k)
+                .map(|t| t.to_string()),
         })
         .collect();
 
@@ -438,9 +797,180 @@ pub fn inspect_key_detailed(
         is_own_key: record.is_own_key,
         user_ids,
         subkeys,
+        adsks,
+        designated_revokers: cert_info.designated_revokers.clone(),
+        possibly_revoked: cert_info.possibly_revoked && !record.is_revoked,
+        primary_key_offline: secret_info
+            .as_ref()
+            .is_some_and(|info| info.primary_key_offline),
+        s2k: secret_info.and_then(|info| info.s2k).map(|t| t.to_string()),
     })
 }
 
+/// Change the passphrase of an own key, optionally upgrading it to Argon2.
+#[tauri::command]
+pub fn change_key_passphrase(
+    state: State<'_, AppState>,
+    fingerprint: String,
+    old_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+    use_argon2: bool,
+) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    let s2k = if use_argon2 {
+        S2kMode::Argon2(Argon2Params::default())
+    } else {
+        S2kMode::Iterated
+    };
+
+    let is_opsec = state.opsec_mode.load(Ordering::SeqCst);
+    let mut opsec_keys = state
+        .opsec_secret_keys
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    let secret_key = match opsec_keys.get(&fingerprint) {
+        Some(k) if is_opsec => (**k).clone(),
+        _ => keyring
+            .get_secret_key(&fingerprint)
+            .map_err(|e| format!("Failed to retrieve secret key: {e}"))?
+            .expose_secret()
+            .clone(),
+    };
+
+    let updated = state
+        .engine
+        .change_passphrase(
+            &secret_key,
+            old_passphrase.as_deref().map(str::as_bytes),
+            new_passphrase.as_deref().map(str::as_bytes),
+            s2k,
+        )
+        .map_err(|e| format!("Failed to change passphrase: {e}"))?;
+
+    if is_opsec && opsec_keys.contains_key(&fingerprint) {
+        opsec_keys.insert(fingerprint.clone(), zeroize::Zeroizing::new(updated));
+    } else {
+        keyring
+            .update_secret_key(&fingerprint, &updated)
+            .map_err(|e| format!("Failed to store secret key: {e}"))?;
+    }
+
+    // Cached passphrases for this key are no longer valid
+    if let Ok(mut cache) = state.passphrase_cache.lock() {
+        cache.clear_all();
+    }
+
+    Ok(())
+}
+
+/// Bind another key in the keyring to an own key as additional decryption
+/// subkey (ADSK).
+#[tauri::command]
+pub fn add_key_adsk(
+    state: State<'_, AppState>,
+    fingerprint: String,
+    adsk_fingerprint: String,
+    passphrase: Option<String>,
+) -> Result<KeyDetailedInfo, String> {
+    update_own_key(
+        &state,
+        &fingerprint,
+        &adsk_fingerprint,
+        |secret_key, adsk| {
+            state
+                .engine
+                .add_adsk(secret_key, passphrase.as_deref().map(str::as_bytes), adsk)
+                .map_err(|e| format!("Failed to add ADSK: {e}"))
+        },
+    )?;
+
+    inspect_key_detailed(state, fingerprint)
+}
+
+/// Allow another key in the keyring to revoke an own key.
+#[tauri::command]
+pub fn add_key_revoker(
+    state: State<'_, AppState>,
+    fingerprint: String,
+    revoker_fingerprint: String,
+    passphrase: Option<String>,
+) -> Result<KeyDetailedInfo, String> {
+    update_own_key(
+        &state,
+        &fingerprint,
+        &revoker_fingerprint,
+        |secret_key, revoker| {
+            state
+                .engine
+                .add_designated_revoker(
+                    secret_key,
+                    passphrase.as_deref().map(str::as_bytes),
+                    revoker,
+                )
+                .map_err(|e| format!("Failed to add designated revoker: {e}"))
+        },
+    )?;
+
+    inspect_key_detailed(state, fingerprint)
+}
+
+/// Rewrite an own secret key with `update`, given the public key of
+/// `other_fingerprint`, and store both the new secret and public parts.
+fn update_own_key(
+    state: &AppState,
+    fingerprint: &str,
+    other_fingerprint: &str,
+    update: impl FnOnce(&[u8], &[u8]) -> Result<Vec<u8>, String>,
+) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let other = keyring
+        .get_key(other_fingerprint)
+        .map_err(|e| format!("Failed to look up key: {e}"))?
+        .ok_or_else(|| format!("Key not found: {other_fingerprint}"))?;
+
+    let is_opsec = state.opsec_mode.load(Ordering::SeqCst);
+    let mut opsec_keys = state
+        .opsec_secret_keys
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    let secret_key = match opsec_keys.get(fingerprint) {
+        Some(k) if is_opsec => (**k).clone(),
+        _ => keyring
+            .get_secret_key(fingerprint)
+            .map_err(|e| format!("Failed to retrieve secret key: {e}"))?
+            .expose_secret()
+            .clone(),
+    };
+
+    let updated = update(&secret_key, &other.pgp_data)?;
+    let public_key = state
+        .engine
+        .public_key(&updated)
+        .map_err(|e| format!("Failed to extract public key: {e}"))?;
+
+    if is_opsec && opsec_keys.contains_key(fingerprint) {
+        opsec_keys.insert(fingerprint.to_string(), zeroize::Zeroizing::new(updated));
+    } else {
+        keyring
+            .update_secret_key(fingerprint, &updated)
+            .map_err(|e| format!("Failed to store secret key: {e}"))?;
+    }
+    keyring
+        .update_pgp_data(fingerprint, &public_key)
+        .map_err(|e| format!("Failed to store public key: {e}"))?;
+
+    Ok(())
+}
+
 /// Export a public key as a QR code SVG.
 #[tauri::command]
 pub fn export_key_qr(state: State<'_, AppState>, fingerprint: String) -> Result<String, String> {
@@ -509,6 +1039,12 @@ pub async fn wkd_lookup(
         trust_level: 0,
         is_own_key: false,
         is_revoked: cert_info.is_revoked,
+        primary_key_offline: false,
+        petname: None,
+        notes: None,
+        tags: Vec::new(),
+        last_used_at: None,
+        use_count: 0,
     }))
 }
 
@@ -543,6 +1079,7 @@ pub async fn wkd_fetch_and_import(
         is_own_key: false,
         is_revoked: cert_info.is_revoked,
         pgp_data: key_bytes,
+        metadata: Default::default(),
     };
 
     let keyring = state
@@ -562,7 +1099,7 @@ pub async fn wkd_fetch_and_import(
     }
 
     keyring
-        .import_public_key(record.clone())
+        .import_public_key(record.clone(), ImportSource::Wkd)
         .map_err(|e| e.to_string())?;
 
     Ok(KeyInfo::from(record))
@@ -739,7 +1276,7 @@ pub async fn fetch_and_import_key(
             Ok(key_data) => {
                 verify_fetched_key(&state, &key_data, &fingerprint)?;
                 let key_text = String::from_utf8_lossy(&key_data).into_owned();
-                return import_key(state, key_text);
+                return import_key(state, key_text, Some(ImportSource::Keyserver));
             }
             Err(e) => last_error = e,
         }
@@ -888,12 +1425,16 @@ pub fn import_backup(
                     is_own_key: true,
                     is_revoked: cert_info.is_revoked,
                     pgp_data: public_bytes,
+                    metadata: existing.metadata,
                 };
-                // Delete the public-only record and re-store with secret material
-                let _ = keyring.delete_key(&record.fingerprint);
-                keyring
-                    .store_generated_key(record.clone(), &secret_bytes)
-                    .map_err(|e| format!("Failed to upgrade key: {e}"))?;
+                import_own_key(
+                    &state,
+                    &keyring,
+                    record.clone(),
+                    &secret_bytes,
+                    ImportSource::Backup,
+                )
+                .map_err(|e| format!("Failed to upgrade key: {e}"))?;
                 // Update the previously-added KeyInfo in imported_keys
                 if let Some(prev) = imported_keys
                     .iter_mut()
@@ -918,15 +1459,21 @@ pub fn import_backup(
             is_own_key: is_own,
             is_revoked: cert_info.is_revoked,
             pgp_data: public_bytes,
+            metadata: Default::default(),
         };
 
         if is_own {
-            keyring
-                .store_generated_key(record.clone(), &secret_bytes)
-                .map_err(|e| format!("Failed to store key: {e}"))?;
+            import_own_key(
+                &state,
+                &keyring,
+                record.clone(),
+                &secret_bytes,
+                ImportSource::Backup,
+            )
+            .map_err(|e| format!("Failed to store key: {e}"))?;
         } else {
             keyring
-                .import_public_key(record.clone())
+                .import_public_key(record.clone(), ImportSource::Backup)
                 .map_err(|e| format!("Failed to import key: {e}"))?;
         }
 
@@ -963,6 +1510,58 @@ pub fn export_private_key(
     fingerprint: String,
     path: String,
 ) -> Result<String, String> {
+    let secret_key_bytes = own_secret_key_for_export(&state, &fingerprint)?;
+
+    // Armor the secret key
+    let armored = state
+        .engine
+        .armor_key(&secret_key_bytes)
+        .map_err(|e| format!("Failed to armor private key: {e}"))?;
+
+    std::fs::write(&path, armored.as_bytes()).map_err(|e| format!("Failed to write file: {e}"))?;
+    record_secret_export(&state, &fingerprint, "file")?;
+
+    Ok(format!("Private key exported to {path}"))
+}
+
+/// Export only the secret subkeys of an own key to a file path, leaving a
+/// stub in place of the primary secret key.
+#[tauri::command]
+pub fn export_secret_subkeys(
+    state: State<'_, AppState>,
+    fingerprint: String,
+    path: String,
+) -> Result<String, String> {
+    let secret_key_bytes = own_secret_key_for_export(&state, &fingerprint)?;
+
+    let exported = state
+        .engine
+        .export_secret_subkeys(&secret_key_bytes)
+        .map_err(|e| format!("Failed to export secret subkeys: {e}"))?;
+
+    std::fs::write(&path, &exported).map_err(|e| format!("Failed to write file: {e}"))?;
+    record_secret_export(&state, &fingerprint, "secret subkeys")?;
+
+    Ok(format!("Secret subkeys exported to {path}"))
+}
+
+/// Add a secret key export to the audit log.
+fn record_secret_export(
+    state: &AppState,
+    fingerprint: &str,
+    destination: &str,
+) -> Result<(), String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    keyring
+        .record_secret_export(fingerprint, destination)
+        .map_err(|e| format!("Failed to update audit log: {e}"))
+}
+
+/// Fetch the secret key of an own key for export, from RAM in OPSEC mode.
+fn own_secret_key_for_export(state: &AppState, fingerprint: &str) -> Result<Vec<u8>, String> {
     let keyring = state
         .keyring
         .lock()
@@ -970,7 +1569,7 @@ pub fn export_private_key(
 
     // Verify the key exists and is an own key
     let record = keyring
-        .get_key(&fingerprint)
+        .get_key(fingerprint)
         .map_err(|e| format!("Failed to look up key: {e}"))?
         .ok_or_else(|| format!("Key not found: {fingerprint}"))?;
 
@@ -979,31 +1578,21 @@ pub fn export_private_key(
     }
 
     // Check OPSEC mode — secret key might be in RAM
-    let secret_key_bytes = if state.opsec_mode.load(Ordering::SeqCst) {
+    if state.opsec_mode.load(Ordering::SeqCst) {
         let opsec_keys = state
             .opsec_secret_keys
             .lock()
             .map_err(|e| format!("Internal error: {e}"))?;
         opsec_keys
-            .get(&fingerprint)
+            .get(fingerprint)
             .map(|z| (**z).clone())
-            .ok_or_else(|| "Secret key not found in OPSEC session".to_string())?
+            .ok_or_else(|| "Secret key not found in OPSEC session".to_string())
     } else {
         let sk = keyring
-            .get_secret_key(&fingerprint)
+            .get_secret_key(fingerprint)
             .map_err(|e| format!("Failed to retrieve secret key: {e}"))?;
-        sk.expose_secret().clone()
-    };
-
-    // Armor the secret key
-    let armored = state
-        .engine
-        .armor_key(&secret_key_bytes)
-        .map_err(|e| format!("Failed to armor private key: {e}"))?;
-
-    std::fs::write(&path, armored.as_bytes()).map_err(|e| format!("Failed to write file: {e}"))?;
-
-    Ok(format!("Private key exported to {path}"))
+        Ok(sk.expose_secret().clone())
+    }
 }
 
 /// Publish a revocation certificate to all configured keyservers.
@@ -0,0 +1,104 @@
+//! Tauri commands for the master password and keyring lock.
+
+use keychainpgp_keys::Keyring;
+use keychainpgp_keys::credential::Protection;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::autolock::LOCKED_EVENT;
+use crate::state::AppState;
+
+/// Lock state reported to the frontend.
+#[derive(Debug, Serialize)]
+pub struct LockStatus {
+    /// Whether secret keys are protected by a master password.
+    pub has_master_password: bool,
+    /// Whether the master password must be entered before using secret keys.
+    pub locked: bool,
+}
+
+/// Fail with a readable error if the keyring is locked.
+///
+/// Commands that try every own key in turn would otherwise skip locked keys
+/// silently and report a misleading "no key could decrypt" error.
+pub fn ensure_unlocked(keyring: &Keyring) -> Result<(), String> {
+    if keyring.is_locked() {
+        return Err(keychainpgp_keys::Error::Locked.to_string());
+    }
+    Ok(())
+}
+
+/// Report whether a master password is set and whether the keyring is locked.
+#[tauri::command]
+pub fn get_lock_status(state: State<'_, AppState>) -> Result<LockStatus, String> {
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    let protection = keyring
+        .secret_key_protection()
+        .map_err(|e| format!("Failed to read key protection: {e}"))?;
+    Ok(LockStatus {
+        has_master_password: protection == Some(Protection::MasterPassword),
+        locked: keyring.is_locked(),
+    })
+}
+
+/// Lock the keyring now.
+#[tauri::command]
+pub fn lock_keyring(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    if state.lock_keyring() {
+        tracing::info!("keyring locked by user");
+        let _ = app.emit(LOCKED_EVENT, ());
+    }
+    Ok(())
+}
+
+/// Unlock the keyring with the master password.
+#[tauri::command]
+pub fn unlock_keyring(state: State<'_, AppState>, password: String) -> Result<(), String> {
+    let password = zeroize::Zeroizing::new(password);
+    state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?
+        .unlock(password.as_bytes())
+        .map_err(|e| e.to_string())?;
+    state.record_activity();
+    tracing::info!("keyring unlocked");
+    Ok(())
+}
+
+/// Set or change the master password.
+///
+/// When one is already set, `current` must be given unless the keyring is
+/// unlocked.
+#[tauri::command]
+pub fn set_master_password(
+    state: State<'_, AppState>,
+    current: Option<String>,
+    new_password: String,
+) -> Result<(), String> {
+    let current = current.map(zeroize::Zeroizing::new);
+    let new_password = zeroize::Zeroizing::new(new_password);
+    let keyring = state
+        .keyring
+        .lock()
+        .map_err(|e| format!("Internal error: {e}"))?;
+    if let Some(current) = current.filter(|c| !c.is_empty()) {
+        keyring
+            .unlock(current.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    keyring
+        .set_master_password(new_password.as_bytes())
+        .map_err(|e| format!("Failed to set master password: {e}"))?;
+    tracing::info!("master password set");
+    Ok(())
+}
+
+/// Postpone the idle auto-lock.
+#[tauri::command]
+pub fn record_activity(state: State<'_, AppState>) {
+    state.record_activity();
+}
@@ -3,7 +3,9 @@ pub mod clipboard;
 #[cfg(mobile)]
 pub mod clipboard_mobile;
 pub mod crypto;
+pub mod groups;
 pub mod keys;
+pub mod lock;
 pub mod opsec;
 pub mod settings;
 pub mod sync;
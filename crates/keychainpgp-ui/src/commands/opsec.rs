@@ -8,7 +8,9 @@ use tauri::{AppHandle, State};
 
 use crate::state::AppState;
 
-/// Enable OPSEC mode: change window title, set flag.
+/// Enable OPSEC mode: change window title, set flag, wipe the audit log and
+/// stop recording to it, stop sharing keys with other tools, and keep public
+/// keys in memory only.
 #[tauri::command]
 pub fn enable_opsec_mode(
     #[allow(unused_variables)] app: AppHandle,
@@ -17,6 +19,21 @@ pub fn enable_opsec_mode(
 ) -> Result<bool, String> {
     state.opsec_mode.store(true, Ordering::SeqCst);
 
+    {
+        let mut keyring = state
+            .keyring
+            .lock()
+            .map_err(|e| format!("Internal error: {e}"))?;
+        keyring.set_audit_enabled(false);
+        keyring.set_sharing_enabled(false);
+        keyring
+            .keep_keys_in_memory(true)
+            .map_err(|e| format!("Failed to keep keys in memory: {e}"))?;
+        keyring
+            .clear_audit_log()
+            .map_err(|e| format!("Failed to wipe audit log: {e}"))?;
+    }
+
     #[cfg(desktop)]
     {
         let title = title
@@ -34,13 +51,27 @@ pub fn enable_opsec_mode(
     Ok(false)
 }
 
-/// Disable OPSEC mode: restore window title, clear RAM keys.
+/// Disable OPSEC mode: restore window title, clear RAM keys, drop the public
+/// keys of the session, show recipients again, resume the audit log and key
+/// sharing.
 #[tauri::command]
 pub fn disable_opsec_mode(
     #[allow(unused_variables)] app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     state.opsec_mode.store(false, Ordering::SeqCst);
+    state.engine.set_hidden_recipients(false);
+    {
+        let mut keyring = state
+            .keyring
+            .lock()
+            .map_err(|e| format!("Internal error: {e}"))?;
+        keyring
+            .keep_keys_in_memory(false)
+            .map_err(|e| format!("Failed to restore the keyring: {e}"))?;
+        keyring.set_audit_enabled(true);
+        keyring.set_sharing_enabled(true);
+    }
 
     // Zeroize and clear any RAM-only keys (force access even if mutex is poisoned)
     let mut keys = state
@@ -79,6 +110,15 @@ pub fn panic_wipe(app: AppHandle, state: State<'_, AppState>) -> Result<(), Stri
         .unwrap_or_else(|e| e.into_inner())
         .clear_all();
 
+    // Wipe the audit log of which keys were used and imported
+    {
+        let mut keyring = state.keyring.lock().unwrap_or_else(|e| e.into_inner());
+        keyring.set_audit_enabled(false);
+        if let Err(e) = keyring.clear_audit_log() {
+            tracing::warn!("failed to wipe audit log: {e}");
+        }
+    }
+
     // Clear clipboard (desktop only)
     #[cfg(desktop)]
     {
@@ -63,6 +63,15 @@ pub struct Settings {
     /// OPSEC: view timeout in seconds for decrypted text (0 = no timeout).
     #[serde(default = "default_opsec_view_timeout")]
     pub opsec_view_timeout_secs: u64,
+    /// OPSEC: hide recipient key IDs in encrypted messages.
+    #[serde(default = "default_true")]
+    pub opsec_hidden_recipients: bool,
+    /// Lock the keyring after this many idle minutes (0 = never).
+    #[serde(default = "default_auto_lock_minutes")]
+    pub auto_lock_minutes: u64,
+    /// Lock the keyring when the system resumes from suspend.
+    #[serde(default = "default_true")]
+    pub lock_on_suspend: bool,
 }
 
 fn default_true() -> bool {
@@ -89,6 +98,9 @@ fn default_opsec_title() -> String {
 fn default_opsec_view_timeout() -> u64 {
     30
 }
+fn default_auto_lock_minutes() -> u64 {
+    15
+}
 
 impl Default for Settings {
     fn default() -> Self {
@@ -112,6 +124,9 @@ impl Default for Settings {
             opsec_mode: false,
             opsec_window_title: "Notes".into(),
             opsec_view_timeout_secs: 30,
+            opsec_hidden_recipients: true,
+            auto_lock_minutes: 15,
+            lock_on_suspend: true,
         }
     }
 }
@@ -166,9 +181,18 @@ pub fn update_settings(
     state
         .engine
         .set_include_armor_headers(settings.include_armor_headers);
+    state
+        .engine
+        .set_hidden_recipients(settings.opsec_mode && settings.opsec_hidden_recipients);
     state
         .close_to_tray
         .store(settings.close_to_tray, Ordering::Relaxed);
+    state
+        .auto_lock_secs
+        .store(settings.auto_lock_minutes * 60, Ordering::Relaxed);
+    state
+        .lock_on_suspend
+        .store(settings.lock_on_suspend, Ordering::Relaxed);
 
     // Sync passphrase cache TTL
     if let Ok(mut cache) = state.passphrase_cache.lock() {
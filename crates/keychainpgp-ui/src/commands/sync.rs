@@ -4,6 +4,7 @@ use serde::Serialize;
 use tauri::State;
 
 use keychainpgp_core::CryptoEngine;
+use keychainpgp_keys::ImportSource;
 use secrecy::ExposeSecret;
 
 use crate::state::AppState;
@@ -32,6 +33,7 @@ pub fn export_key_bundle(
     let all_keys = keyring
         .list_keys()
         .map_err(|e| format!("Failed to list keys: {e}"))?;
+    super::lock::ensure_unlocked(&keyring)?;
 
     // Build bundle of all keys (own keys with secret material + contact public keys)
     let mut entries = Vec::new();
@@ -57,9 +59,14 @@ pub fn export_key_bundle(
         return Err("No keys to export. Generate or import a key first.".into());
     }
 
+    let groups = keyring
+        .list_groups()
+        .map_err(|e| format!("Failed to list groups: {e}"))?;
+
     let bundle = keychainpgp_keys::sync::KeyBundle {
         version: 2,
         keys: entries,
+        groups,
     };
 
     // Generate random passphrase
@@ -106,6 +113,12 @@ pub fn export_key_bundle(
     let mut qr_parts = vec![pass_svg];
     qr_parts.extend(data_qr_parts?);
 
+    for entry in bundle.keys.iter().filter(|e| e.secret_key.is_some()) {
+        keyring
+            .record_secret_export(&entry.fingerprint, "sync bundle")
+            .map_err(|e| format!("Failed to update audit log: {e}"))?;
+    }
+
     let file_data = keychainpgp_keys::sync::base64_encode(&encrypted);
 
     Ok(SyncBundle {
@@ -155,15 +168,9 @@ pub fn import_key_bundle(
         let existing = keyring.get_key(&entry.fingerprint).ok().flatten();
 
         if let Some(ref existing_key) = existing {
-            if existing_key.is_own_key {
-                // Already have this key with secret material — skip
-                continue;
-            }
-            if entry.secret_key.is_some() {
-                // Upgrade: existing public-only -> own key with secret material
-                let _ = keyring.delete_key(&existing_key.fingerprint);
-            } else {
-                // Already have this public key — skip
+            // Skip keys we already have, unless the bundle upgrades a
+            // public-only key with its secret material (merged on import)
+            if existing_key.is_own_key || entry.secret_key.is_none() {
                 continue;
             }
         }
@@ -185,21 +192,26 @@ pub fn import_key_bundle(
             is_own_key: entry.secret_key.is_some(),
             is_revoked: cert_info.is_revoked,
             pgp_data: entry.public_key.clone(),
+            metadata: Default::default(),
         };
 
         if let Some(ref secret_key) = entry.secret_key {
-            keyring
-                .store_generated_key(record, secret_key)
+            super::keys::import_own_key(&state, &keyring, record, secret_key, ImportSource::Sync)
                 .map_err(|e| format!("Failed to import key: {e}"))?;
         } else {
             keyring
-                .import_public_key(record)
+                .import_public_key(record, ImportSource::Sync)
                 .map_err(|e| format!("Failed to import key: {e}"))?;
         }
 
         imported += 1;
     }
 
+    // Groups last, so members imported above are kept
+    keyring
+        .import_groups(&bundle.groups)
+        .map_err(|e| format!("Failed to import groups: {e}"))?;
+
     Ok(imported)
 }
 
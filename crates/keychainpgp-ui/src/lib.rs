@@ -3,6 +3,7 @@
 //! This module contains the app builder and setup logic shared between
 //! the desktop binary (`main.rs`) and the mobile library entry point.
 
+mod autolock;
 mod commands;
 mod passphrase_cache;
 mod state;
@@ -36,6 +37,7 @@ fn create_builder() -> tauri::Builder<tauri::Wry> {
         .invoke_handler(tauri::generate_handler![
             // Shared crypto commands
             commands::crypto::encrypt_text,
+            commands::crypto::check_recipients,
             commands::crypto::decrypt_text,
             commands::crypto::sign_text,
             commands::crypto::verify_text,
@@ -50,14 +52,29 @@ fn create_builder() -> tauri::Builder<tauri::Wry> {
             commands::clipboard::clear_clipboard,
             // Shared key commands
             commands::keys::generate_key_pair,
+            commands::keys::generate_recovery_phrase,
+            commands::keys::restore_key_from_mnemonic,
             commands::keys::list_keys,
             commands::keys::import_key,
             commands::keys::export_key,
             commands::keys::export_private_key,
+            commands::keys::export_secret_subkeys,
             commands::keys::publish_revocation_cert,
             commands::keys::delete_key,
+            commands::keys::list_trash,
+            commands::keys::restore_key,
+            commands::keys::purge_key,
+            commands::keys::empty_trash,
+            commands::keys::get_trash_retention,
+            commands::keys::set_trash_retention,
             commands::keys::search_keys,
+            commands::keys::update_key_metadata,
+            commands::keys::list_key_tags,
+            commands::keys::get_audit_log,
             commands::keys::inspect_key,
+            commands::keys::change_key_passphrase,
+            commands::keys::add_key_adsk,
+            commands::keys::add_key_revoker,
             commands::keys::set_key_trust,
             commands::keys::inspect_key_detailed,
             commands::keys::export_key_qr,
@@ -82,6 +99,17 @@ fn create_builder() -> tauri::Builder<tauri::Wry> {
             commands::opsec::disable_opsec_mode,
             commands::opsec::panic_wipe,
             commands::opsec::get_opsec_status,
+            // Lock commands
+            commands::lock::get_lock_status,
+            commands::lock::lock_keyring,
+            commands::lock::unlock_keyring,
+            commands::lock::set_master_password,
+            commands::lock::record_activity,
+            commands::groups::list_groups,
+            commands::groups::save_group,
+            commands::groups::rename_group,
+            commands::groups::delete_group,
+            commands::groups::expand_group,
         ])
 }
 
@@ -95,20 +123,36 @@ fn create_builder() -> tauri::Builder<tauri::Wry> {
         .invoke_handler(tauri::generate_handler![
             // Shared crypto commands
             commands::crypto::encrypt_text,
+            commands::crypto::check_recipients,
             commands::crypto::decrypt_text,
             commands::crypto::sign_text,
             commands::crypto::verify_text,
             commands::crypto::clear_passphrase_cache,
             // Shared key commands
             commands::keys::generate_key_pair,
+            commands::keys::generate_recovery_phrase,
+            commands::keys::restore_key_from_mnemonic,
             commands::keys::list_keys,
             commands::keys::import_key,
             commands::keys::export_key,
             commands::keys::export_private_key,
+            commands::keys::export_secret_subkeys,
             commands::keys::publish_revocation_cert,
             commands::keys::delete_key,
+            commands::keys::list_trash,
+            commands::keys::restore_key,
+            commands::keys::purge_key,
+            commands::keys::empty_trash,
+            commands::keys::get_trash_retention,
+            commands::keys::set_trash_retention,
             commands::keys::search_keys,
+            commands::keys::update_key_metadata,
+            commands::keys::list_key_tags,
+            commands::keys::get_audit_log,
             commands::keys::inspect_key,
+            commands::keys::change_key_passphrase,
+            commands::keys::add_key_adsk,
+            commands::keys::add_key_revoker,
             commands::keys::set_key_trust,
             commands::keys::inspect_key_detailed,
             commands::keys::export_key_qr,
@@ -137,6 +181,17 @@ fn create_builder() -> tauri::Builder<tauri::Wry> {
             commands::opsec::disable_opsec_mode,
             commands::opsec::panic_wipe,
             commands::opsec::get_opsec_status,
+            // Lock commands
+            commands::lock::get_lock_status,
+            commands::lock::lock_keyring,
+            commands::lock::unlock_keyring,
+            commands::lock::set_master_password,
+            commands::lock::record_activity,
+            commands::groups::list_groups,
+            commands::groups::save_group,
+            commands::groups::rename_group,
+            commands::groups::delete_group,
+            commands::groups::expand_group,
         ])
 }
 
@@ -208,9 +263,29 @@ pub fn run() {
                 app_state
                     .engine
                     .set_include_armor_headers(settings.include_armor_headers);
+                app_state
+                    .engine
+                    .set_hidden_recipients(settings.opsec_mode && settings.opsec_hidden_recipients);
                 if settings.opsec_mode {
                     app_state.opsec_mode.store(true, Ordering::SeqCst);
+                    // As `enable_opsec_mode` does: wipe the audit log, and
+                    // record, share and store nothing this session
+                    let mut keyring = app_state.keyring.lock().unwrap_or_else(|e| e.into_inner());
+                    keyring.set_audit_enabled(false);
+                    keyring.set_sharing_enabled(false);
+                    if let Err(e) = keyring.keep_keys_in_memory(true) {
+                        tracing::warn!("failed to keep keys in memory: {e}");
+                    }
+                    if let Err(e) = keyring.clear_audit_log() {
+                        tracing::warn!("failed to wipe audit log: {e}");
+                    }
                 }
+                app_state
+                    .auto_lock_secs
+                    .store(settings.auto_lock_minutes * 60, Ordering::Relaxed);
+                app_state
+                    .lock_on_suspend
+                    .store(settings.lock_on_suspend, Ordering::Relaxed);
                 #[cfg(desktop)]
                 {
                     app_state
@@ -224,6 +299,7 @@ pub fn run() {
             }
 
             app.manage(app_state);
+            autolock::spawn(app.handle().clone());
 
             // Apply OPSEC window title if active (desktop only)
             #[cfg(desktop)]
@@ -3,7 +3,8 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use keychainpgp_core::SequoiaEngine;
 use keychainpgp_keys::Keyring;
@@ -14,6 +15,9 @@ use crate::passphrase_cache::PassphraseCache;
 /// Default passphrase cache TTL in seconds (10 minutes).
 const DEFAULT_CACHE_TTL: u64 = 600;
 
+/// Default idle time in seconds before the keyring locks (15 minutes).
+const DEFAULT_AUTO_LOCK_SECS: u64 = 900;
+
 /// Detect portable mode by looking for a `.portable` marker file next to the executable.
 ///
 /// Returns `Some(data_dir)` if portable mode is detected, where `data_dir` is
@@ -45,6 +49,12 @@ pub struct AppState {
     pub portable: bool,
     /// In portable mode, the data directory next to the executable.
     pub portable_dir: Option<PathBuf>,
+    /// When the user last interacted with the app, for the idle auto-lock.
+    pub last_activity: Mutex<Instant>,
+    /// Idle time in seconds before the keyring locks (0 = never).
+    pub auto_lock_secs: AtomicU64,
+    /// Whether the keyring locks when the system resumes from suspend.
+    pub lock_on_suspend: AtomicBool,
 }
 
 impl AppState {
@@ -66,6 +76,9 @@ impl AppState {
             opsec_secret_keys: Mutex::new(HashMap::new()),
             portable: false,
             portable_dir: None,
+            last_activity: Mutex::new(Instant::now()),
+            auto_lock_secs: AtomicU64::new(DEFAULT_AUTO_LOCK_SECS),
+            lock_on_suspend: AtomicBool::new(true),
         })
     }
 
@@ -90,6 +103,56 @@ impl AppState {
             opsec_secret_keys: Mutex::new(HashMap::new()),
             portable: false,
             portable_dir: None,
+            last_activity: Mutex::new(Instant::now()),
+            auto_lock_secs: AtomicU64::new(DEFAULT_AUTO_LOCK_SECS),
+            lock_on_suspend: AtomicBool::new(true),
         })
     }
+
+    /// Note user activity, postponing the idle auto-lock.
+    pub fn record_activity(&self) {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    /// Whether the user has been idle longer than the auto-lock timeout.
+    pub fn idle_timeout_elapsed(&self) -> bool {
+        let secs = self.auto_lock_secs.load(Ordering::Relaxed);
+        secs > 0
+            && self
+                .last_activity
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .elapsed()
+                >= Duration::from_secs(secs)
+    }
+
+    /// Lock the keyring, wipe the OPSEC keys in memory and forget cached
+    /// passphrases.
+    ///
+    /// Returns `true` if the keyring went from unlocked to locked. Without a
+    /// master password there is nothing to lock and this returns `false`,
+    /// even if OPSEC keys were wiped.
+    pub fn lock_keyring(&self) -> bool {
+        let keyring = self.keyring.lock().unwrap_or_else(|e| e.into_inner());
+        let was_unlocked = matches!(
+            keyring.secret_key_protection(),
+            Ok(Some(
+                keychainpgp_keys::credential::Protection::MasterPassword
+            ))
+        ) && !keyring.is_locked();
+        keyring.lock();
+        drop(keyring);
+
+        // Zeroized on drop; they live in memory only, so they are gone for good
+        self.opsec_secret_keys
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        self.passphrase_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear_all();
+        was_unlocked
+    }
 }
@@ -35,6 +35,16 @@ struct KeyPairResult {
 struct VerifyResultJs {
     valid: bool,
     signer_fingerprint: Option<String>,
+    notations: Vec<NotationJs>,
+    expires_at: Option<String>,
+    signer_user_id: Option<String>,
+    intended_recipients: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct NotationJs {
+    name: String,
+    value: String,
 }
 
 /// Result of key inspection, returned as a JS object.
@@ -46,6 +56,7 @@ struct CertInfoJs {
     created_at: String,
     expires_at: Option<String>,
     has_secret_key: bool,
+    primary_key_offline: bool,
     subkeys: Vec<SubkeyInfoJs>,
 }
 
@@ -155,7 +166,9 @@ pub fn sign(data: &str, secret_key: &str, passphrase: Option<String>) -> Result<
 
 /// Verify a signed PGP message against a signer's public key.
 ///
-/// Returns a JS object: `{ valid: boolean, signer_fingerprint: string | null }`
+/// Returns a JS object: `{ valid: boolean, signer_fingerprint: string | null,
+/// notations: {name, value}[], expires_at: string | null,
+/// signer_user_id: string | null, intended_recipients: string[] }`
 #[wasm_bindgen(js_name = verify)]
 pub fn verify(signed_data: &str, signer_key: &str) -> Result<JsValue, JsError> {
     let engine = SequoiaEngine::new();
@@ -167,6 +180,17 @@ pub fn verify(signed_data: &str, signer_key: &str) -> Result<JsValue, JsError> {
     let js_result = VerifyResultJs {
         valid: result.valid,
         signer_fingerprint: result.signer_fingerprint,
+        notations: result
+            .notations
+            .into_iter()
+            .map(|n| NotationJs {
+                name: n.name,
+                value: n.value,
+            })
+            .collect(),
+        expires_at: result.expires_at,
+        signer_user_id: result.signer_user_id,
+        intended_recipients: result.intended_recipients,
     };
 
     serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()))
@@ -197,6 +221,7 @@ pub fn inspect_key(key_data: &str) -> Result<JsValue, JsError> {
         created_at: info.created_at,
         expires_at: info.expires_at,
         has_secret_key: info.has_secret_key,
+        primary_key_offline: info.primary_key_offline,
         subkeys: info
             .subkeys
             .into_iter()